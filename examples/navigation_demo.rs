@@ -2,6 +2,7 @@
 // Ejecutar con: cargo run --example navigation_demo
 
 use mechbot_3x::control::RobotState;
+use mechbot_3x::geometry::Pose2D;
 use mechbot_3x::navigation::{NavigationConfig, NavigationController, SensorData};
 
 #[tokio::main]
@@ -33,7 +34,7 @@ async fn main() {
             (1.5, 0.785),  // 1.5m a 45 grados
             (3.0, -0.785), // 3m a -45 grados
         ],
-        odometry: (0.0, 0.0, 0.0), // Sin movimiento
+        odometry: Pose2D::identity(), // Sin movimiento
         timestamp: 0.0,
     };
 
@@ -0,0 +1,575 @@
+//! Autenticación de la API REST y WebSocket: claves de API y JWT firmados con
+//! HMAC-SHA256. Cada credencial lleva un rol; la telemetría la lee cualquiera
+//! autenticado, pero mover el robot o cambiar la configuración exige más.
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use super::SharedState;
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+/// Intentos fallidos que se conservan para consultarlos
+const AUDIT_CAPACITY: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Solo lectura de estado y telemetría
+    Viewer,
+    /// Además mueve el robot: objetivos, acoplamiento
+    Operator,
+    /// Además cambia la configuración y consulta la auditoría
+    Admin,
+}
+
+impl Role {
+    pub fn allows(self, required: Role) -> bool {
+        self >= required
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+    /// Nombre del cliente, para los registros
+    pub name: String,
+    pub key: String,
+    pub role: Role,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuthConfig {
+    pub api_keys: Vec<ApiKey>,
+    /// Fichero TOML con más claves (`[[api_keys]]`), fuera del repositorio
+    pub api_keys_file: Option<PathBuf>,
+    /// Secreto HMAC de los JWT; sin él solo valen las claves de API
+    pub jwt_secret: Option<String>,
+    /// Validez de los tokens que emite `/api/v1/auth/token` (s)
+    pub token_ttl: u64,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            api_keys: Vec::new(),
+            api_keys_file: None,
+            jwt_secret: None,
+            token_ttl: 3600,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct KeyFile {
+    #[serde(default)]
+    api_keys: Vec<ApiKey>,
+}
+
+/// Quién hace la petición y con qué permisos
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Principal {
+    pub subject: String,
+    pub role: Role,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub role: Role,
+    /// Emisión y caducidad, en segundos Unix
+    pub iat: u64,
+    pub exp: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthError {
+    MissingCredentials,
+    InvalidApiKey,
+    InvalidToken(String),
+    Expired,
+    Forbidden {
+        role: Role,
+        required: Role,
+    },
+    /// No hay `jwt_secret` configurado
+    TokensDisabled,
+}
+
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthError::MissingCredentials => write!(f, "Faltan credenciales"),
+            AuthError::InvalidApiKey => write!(f, "Clave de API no válida"),
+            AuthError::InvalidToken(reason) => write!(f, "Token no válido: {}", reason),
+            AuthError::Expired => write!(f, "Token caducado"),
+            AuthError::Forbidden { role, required } => {
+                write!(f, "El rol {:?} no basta, hace falta {:?}", role, required)
+            }
+            AuthError::TokensDisabled => write!(f, "Los tokens JWT no están configurados"),
+        }
+    }
+}
+
+impl AuthError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AuthError::Forbidden { .. } => StatusCode::FORBIDDEN,
+            AuthError::TokensDisabled => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::UNAUTHORIZED,
+        }
+    }
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({ "status": "error", "message": self.to_string() });
+        (self.status(), Json(body)).into_response()
+    }
+}
+
+/// Valida credenciales y emite tokens
+#[derive(Clone)]
+pub struct Authenticator {
+    /// Si es `false` todas las peticiones pasan como administrador anónimo
+    required: bool,
+    keys: Vec<ApiKey>,
+    jwt_secret: Option<Vec<u8>>,
+    token_ttl: u64,
+}
+
+impl std::fmt::Debug for Authenticator {
+    // Sin claves ni secreto en los registros
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Authenticator")
+            .field("required", &self.required)
+            .field("keys", &self.keys.len())
+            .field("jwt", &self.jwt_secret.is_some())
+            .finish()
+    }
+}
+
+/// Sin configurar se exigen credenciales y, sin claves, se rechaza todo: la
+/// API nunca queda abierta por omisión
+impl Default for Authenticator {
+    fn default() -> Self {
+        let config = AuthConfig::default();
+        Self {
+            required: true,
+            keys: config.api_keys,
+            jwt_secret: None,
+            token_ttl: config.token_ttl,
+        }
+    }
+}
+
+impl Authenticator {
+    /// Exige credenciales con las claves y el secreto de `config`
+    pub fn new(config: &AuthConfig) -> anyhow::Result<Self> {
+        let mut keys = config.api_keys.clone();
+        if let Some(path) = &config.api_keys_file {
+            let content = std::fs::read_to_string(path)?;
+            let file: KeyFile = toml::from_str(&content)?;
+            keys.extend(file.api_keys);
+        }
+        if keys.is_empty() && config.jwt_secret.is_none() {
+            log::warn!("⚠️ Autenticación exigida sin claves ni secreto JWT: se rechazará todo");
+        }
+        Ok(Self {
+            required: true,
+            keys,
+            jwt_secret: config.jwt_secret.as_ref().map(|s| s.as_bytes().to_vec()),
+            token_ttl: config.token_ttl,
+        })
+    }
+
+    /// Todas las peticiones pasan como administrador anónimo. Solo para
+    /// desarrollo (`api_key_required = false`) y pruebas.
+    pub fn disabled() -> Self {
+        Self {
+            required: false,
+            ..Self::default()
+        }
+    }
+
+    pub fn is_required(&self) -> bool {
+        self.required
+    }
+
+    pub fn token_ttl(&self) -> u64 {
+        self.token_ttl
+    }
+
+    /// Credenciales de la cabecera `Authorization: Bearer <jwt>` o
+    /// `X-API-Key`; si no hay, de `?token=` o `?api_key=` en la URL, que es lo
+    /// único que puede enviar un navegador al abrir un WebSocket.
+    pub fn authenticate(
+        &self,
+        headers: &HeaderMap,
+        query: Option<&str>,
+        now: u64,
+    ) -> Result<Principal, AuthError> {
+        if !self.required {
+            return Ok(Principal {
+                subject: "anonymous".to_string(),
+                role: Role::Admin,
+            });
+        }
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+        if let Some(token) = header("authorization").and_then(|h| h.strip_prefix("Bearer ")) {
+            return self.authenticate_token(token.trim(), now);
+        }
+        if let Some(key) = header(API_KEY_HEADER) {
+            return self.authenticate_key(key);
+        }
+        let param = |name: &str| {
+            query?
+                .split('&')
+                .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+        };
+        if let Some(token) = param("token") {
+            return self.authenticate_token(token, now);
+        }
+        if let Some(key) = param("api_key") {
+            return self.authenticate_key(key);
+        }
+        Err(AuthError::MissingCredentials)
+    }
+
+    pub fn authenticate_key(&self, key: &str) -> Result<Principal, AuthError> {
+        self.keys
+            .iter()
+            .find(|candidate| constant_time_eq(candidate.key.as_bytes(), key.as_bytes()))
+            .map(|found| Principal {
+                subject: found.name.clone(),
+                role: found.role,
+            })
+            .ok_or(AuthError::InvalidApiKey)
+    }
+
+    pub fn authenticate_token(&self, token: &str, now: u64) -> Result<Principal, AuthError> {
+        let invalid = |reason: &str| AuthError::InvalidToken(reason.to_string());
+        let secret = self.jwt_secret.as_ref().ok_or(AuthError::TokensDisabled)?;
+        let (signed, signature) = token.rsplit_once('.').ok_or_else(|| invalid("formato"))?;
+        let (header, payload) = signed.split_once('.').ok_or_else(|| invalid("formato"))?;
+
+        let header: serde_json::Value = decode_part(header).ok_or_else(|| invalid("cabecera"))?;
+        // Solo HS256: no se acepta "none" ni otros algoritmos
+        if header.get("alg").and_then(|alg| alg.as_str()) != Some("HS256") {
+            return Err(invalid("algoritmo"));
+        }
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| invalid("firma"))?;
+        mac(secret, signed)
+            .verify_slice(&signature)
+            .map_err(|_| invalid("firma"))?;
+
+        let claims: Claims = decode_part(payload).ok_or_else(|| invalid("contenido"))?;
+        if claims.exp <= now {
+            return Err(AuthError::Expired);
+        }
+        Ok(Principal {
+            subject: claims.sub,
+            role: claims.role,
+        })
+    }
+
+    /// JWT con el rol de `principal`, válido `token_ttl` segundos
+    pub fn issue_token(&self, principal: &Principal, now: u64) -> Result<String, AuthError> {
+        let secret = self.jwt_secret.as_ref().ok_or(AuthError::TokensDisabled)?;
+        let claims = Claims {
+            sub: principal.subject.clone(),
+            role: principal.role,
+            iat: now,
+            exp: now + self.token_ttl,
+        };
+        let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"HS256","typ":"JWT"}"#);
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).unwrap_or_default());
+        let signed = format!("{}.{}", header, payload);
+        let signature = URL_SAFE_NO_PAD.encode(mac(secret, &signed).finalize().into_bytes());
+        Ok(format!("{}.{}", signed, signature))
+    }
+}
+
+fn mac(secret: &[u8], signed: &str) -> Hmac<Sha256> {
+    // HMAC admite claves de cualquier longitud
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("clave HMAC");
+    mac.update(signed.as_bytes());
+    mac
+}
+
+fn decode_part<T: serde::de::DeserializeOwned>(part: &str) -> Option<T> {
+    let bytes = URL_SAFE_NO_PAD.decode(part).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+/// Comparación que no revela por tiempo cuántos bytes coinciden
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+pub fn unix_now() -> u64 {
+    chrono::Utc::now().timestamp().max(0) as u64
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditEntry {
+    pub timestamp: String,
+    /// Dirección del cliente, si se conoce
+    pub remote: Option<String>,
+    /// Usuario autenticado, si lo hubo (intentos sin permiso)
+    pub subject: Option<String>,
+    /// Ruta o comando que se intentó
+    pub action: String,
+    pub reason: String,
+}
+
+/// Últimos intentos fallidos de autenticación o de permisos. Las copias
+/// comparten las entradas tras su propio cerrojo, así que un rechazo no
+/// bloquea el `AppState`.
+#[derive(Debug, Clone, Default)]
+pub struct AuditLog {
+    entries: Arc<Mutex<VecDeque<AuditEntry>>>,
+}
+
+impl AuditLog {
+    pub fn record(
+        &self,
+        remote: Option<String>,
+        subject: Option<&str>,
+        action: &str,
+        error: &AuthError,
+    ) {
+        log::warn!(
+            "🚫 Acceso denegado a {} ({}) desde {}: {}",
+            action,
+            subject.unwrap_or("sin identificar"),
+            remote.as_deref().unwrap_or("?"),
+            error
+        );
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() == AUDIT_CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back(AuditEntry {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            remote,
+            subject: subject.map(str::to_string),
+            action: action.to_string(),
+            reason: error.to_string(),
+        });
+    }
+
+    /// Copia de las entradas, de la más antigua a la más reciente
+    pub fn entries(&self) -> Vec<AuditEntry> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.iter().cloned().collect()
+    }
+}
+
+/// Middleware que exige al menos `required` y deja el `Principal` en las
+/// extensiones de la petición:
+/// `route_layer(middleware::from_fn_with_state((state, Role::Operator), authorize))`
+pub async fn authorize(
+    State((state, required)): State<(SharedState, Role)>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let (auth, audit) = {
+        let state = state.read().await;
+        (state.auth.clone(), state.audit.clone())
+    };
+    let result = auth
+        .authenticate(request.headers(), request.uri().query(), unix_now())
+        .and_then(|principal| {
+            if principal.role.allows(required) {
+                Ok(principal)
+            } else {
+                Err(AuthError::Forbidden {
+                    role: principal.role,
+                    required,
+                })
+            }
+        });
+    match result {
+        Ok(principal) => {
+            request.extensions_mut().insert(principal);
+            next.run(request).await
+        }
+        Err(e) => {
+            let action = format!("{} {}", request.method(), request.uri().path());
+            let subject = authenticated_subject(&auth, &request);
+            audit.record(remote_addr(&request), subject.as_deref(), &action, &e);
+            e.into_response()
+        }
+    }
+}
+
+/// Usuario de una petición rechazada por falta de permisos
+fn authenticated_subject(auth: &Authenticator, request: &Request) -> Option<String> {
+    auth.authenticate(request.headers(), request.uri().query(), unix_now())
+        .ok()
+        .map(|principal| principal.subject)
+}
+
+pub fn remote_addr(request: &Request) -> Option<String> {
+    request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|info| info.0.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn authenticator() -> Authenticator {
+        Authenticator::new(&AuthConfig {
+            api_keys: vec![ApiKey {
+                name: "panel".to_string(),
+                key: "clave-operador".to_string(),
+                role: Role::Operator,
+            }],
+            jwt_secret: Some("secreto".to_string()),
+            token_ttl: 60,
+            ..AuthConfig::default()
+        })
+        .unwrap()
+    }
+
+    fn operator() -> Principal {
+        Principal {
+            subject: "panel".to_string(),
+            role: Role::Operator,
+        }
+    }
+
+    #[test]
+    fn test_api_key_grants_its_role() {
+        let auth = authenticator();
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            auth.authenticate(&headers, None, 1000),
+            Err(AuthError::MissingCredentials)
+        );
+
+        headers.insert(API_KEY_HEADER, "clave-operador".parse().unwrap());
+        let principal = auth.authenticate(&headers, None, 1000).unwrap();
+        assert_eq!(principal, operator());
+        assert!(principal.role.allows(Role::Viewer) && !principal.role.allows(Role::Admin));
+        assert_eq!(auth.authenticate_key("otra"), Err(AuthError::InvalidApiKey));
+    }
+
+    #[test]
+    fn test_token_keeps_role_until_expiry() {
+        let auth = authenticator();
+        let token = auth.issue_token(&operator(), 1000).unwrap();
+        let query = format!("token={}", token);
+        assert_eq!(
+            auth.authenticate(&HeaderMap::new(), Some(&query), 1059),
+            Ok(operator())
+        );
+        assert_eq!(
+            auth.authenticate_token(&token, 1060),
+            Err(AuthError::Expired)
+        );
+    }
+
+    #[test]
+    fn test_token_from_other_secret_is_rejected() {
+        let token = authenticator().issue_token(&operator(), 1000).unwrap();
+        let other = Authenticator::new(&AuthConfig {
+            jwt_secret: Some("otro".to_string()),
+            ..AuthConfig::default()
+        })
+        .unwrap();
+        assert_eq!(
+            other.authenticate_token(&token, 1000),
+            Err(AuthError::InvalidToken("firma".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_tampered_token_is_rejected() {
+        let auth = authenticator();
+        let token = auth.issue_token(&operator(), 1000).unwrap();
+        // Se cambia el rol conservando la firma original
+        let admin = Principal {
+            role: Role::Admin,
+            ..operator()
+        };
+        let forged = auth.issue_token(&admin, 1000).unwrap();
+        let parts: Vec<&str> = token.split('.').collect();
+        let tampered = format!(
+            "{}.{}.{}",
+            parts[0],
+            forged.split('.').nth(1).unwrap(),
+            parts[2]
+        );
+        assert_eq!(
+            auth.authenticate_token(&tampered, 1000),
+            Err(AuthError::InvalidToken("firma".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_token_with_other_algorithm_is_rejected() {
+        let auth = authenticator();
+        let token = auth.issue_token(&operator(), 1000).unwrap();
+        let (_, rest) = token.split_once('.').unwrap();
+        for alg in ["none", "HS512"] {
+            let header = URL_SAFE_NO_PAD.encode(format!(r#"{{"alg":"{}","typ":"JWT"}}"#, alg));
+            assert_eq!(
+                auth.authenticate_token(&format!("{}.{}", header, rest), 1000),
+                Err(AuthError::InvalidToken("algoritmo".to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_default_requires_credentials_and_disabled_opens() {
+        assert_eq!(
+            Authenticator::default().authenticate(&HeaderMap::new(), None, 0),
+            Err(AuthError::MissingCredentials)
+        );
+        let open = Authenticator::disabled();
+        assert_eq!(
+            open.authenticate(&HeaderMap::new(), None, 0).unwrap().role,
+            Role::Admin
+        );
+    }
+
+    #[test]
+    fn test_audit_log_keeps_latest_entries() {
+        let audit = AuditLog::default();
+        for i in 0..AUDIT_CAPACITY + 5 {
+            audit.record(
+                None,
+                None,
+                &format!("/api/v1/{}", i),
+                &AuthError::MissingCredentials,
+            );
+        }
+        // Se descartan las más antiguas
+        let actions: Vec<String> = audit.entries().into_iter().map(|e| e.action).collect();
+        assert_eq!(actions.len(), AUDIT_CAPACITY);
+        assert_eq!(actions[0], "/api/v1/5");
+        assert_eq!(
+            actions[AUDIT_CAPACITY - 1],
+            format!("/api/v1/{}", AUDIT_CAPACITY + 4)
+        );
+    }
+}
@@ -0,0 +1,177 @@
+//! Codificación de las respuestas REST y de los mensajes del WebSocket: JSON,
+//! CBOR o MessagePack. REST la elige por la cabecera `Accept` y el WebSocket
+//! por subprotocolo.
+use axum::async_trait;
+use axum::extract::FromRequestParts;
+use axum::http::header::{ACCEPT, CONTENT_TYPE, VARY};
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::convert::Infallible;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    #[default]
+    Json,
+    Cbor,
+    MessagePack,
+}
+
+impl Encoding {
+    pub const ALL: [Encoding; 3] = [Encoding::Json, Encoding::Cbor, Encoding::MessagePack];
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Encoding::Json => "application/json",
+            Encoding::Cbor => "application/cbor",
+            Encoding::MessagePack => "application/msgpack",
+        }
+    }
+
+    /// Subprotocolo del WebSocket que la pide
+    pub fn subprotocol(self) -> &'static str {
+        match self {
+            Encoding::Json => "mechbot.json",
+            Encoding::Cbor => "mechbot.cbor",
+            Encoding::MessagePack => "mechbot.msgpack",
+        }
+    }
+
+    pub fn from_subprotocol(protocol: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|encoding| encoding.subprotocol() == protocol)
+    }
+
+    fn from_media_type(media_type: &str) -> Option<Self> {
+        match media_type {
+            "application/json" | "*/*" | "application/*" => Some(Encoding::Json),
+            "application/cbor" => Some(Encoding::Cbor),
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => {
+                Some(Encoding::MessagePack)
+            }
+            _ => None,
+        }
+    }
+
+    /// La codificación preferida en `Accept` (según `q`); JSON si no hay
+    /// ninguna que conozcamos
+    pub fn from_accept(headers: &HeaderMap) -> Self {
+        let mut best = None;
+        for value in headers.get_all(ACCEPT) {
+            let Ok(value) = value.to_str() else {
+                continue;
+            };
+            for entry in value.split(',') {
+                let mut parts = entry.split(';');
+                let media_type = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+                let quality = parts
+                    .filter_map(|param| param.trim().strip_prefix("q="))
+                    .find_map(|q| q.parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                let Some(encoding) = Self::from_media_type(&media_type) else {
+                    continue;
+                };
+                if quality > 0.0 && best.is_none_or(|(_, q)| quality > q) {
+                    best = Some((encoding, quality));
+                }
+            }
+        }
+        best.map(|(encoding, _)| encoding).unwrap_or_default()
+    }
+
+    pub fn is_binary(self) -> bool {
+        self != Encoding::Json
+    }
+
+    pub fn encode<T: Serialize + ?Sized>(self, value: &T) -> anyhow::Result<Vec<u8>> {
+        Ok(match self {
+            Encoding::Json => serde_json::to_vec(value)?,
+            Encoding::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(value, &mut bytes)?;
+                bytes
+            }
+            // Con nombres de campo, como en JSON
+            Encoding::MessagePack => rmp_serde::to_vec_named(value)?,
+        })
+    }
+
+    pub fn decode<T: DeserializeOwned>(self, bytes: &[u8]) -> anyhow::Result<T> {
+        Ok(match self {
+            Encoding::Json => serde_json::from_slice(bytes)?,
+            Encoding::Cbor => ciborium::from_reader(bytes)?,
+            Encoding::MessagePack => rmp_serde::from_slice(bytes)?,
+        })
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Encoding {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self::from_accept(&parts.headers))
+    }
+}
+
+/// Respuesta en la codificación que pidió el cliente
+#[derive(Debug, Clone)]
+pub struct Encoded<T>(pub Encoding, pub T);
+
+impl<T: Serialize> IntoResponse for Encoded<T> {
+    fn into_response(self) -> Response {
+        let Encoded(encoding, value) = self;
+        match encoding.encode(&value) {
+            Ok(body) => (
+                [(CONTENT_TYPE, encoding.content_type()), (VARY, "accept")],
+                body,
+            )
+                .into_response(),
+            Err(e) => {
+                log::error!("❌ No se pudo codificar la respuesta: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_accept_negotiation_and_round_trip() {
+        let accept = |value: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(ACCEPT, HeaderValue::from_static(value));
+            Encoding::from_accept(&headers)
+        };
+        assert_eq!(Encoding::from_accept(&HeaderMap::new()), Encoding::Json);
+        assert_eq!(accept("application/cbor"), Encoding::Cbor);
+        assert_eq!(
+            accept("application/json;q=0.5, application/x-msgpack"),
+            Encoding::MessagePack
+        );
+        assert_eq!(
+            accept("application/cbor;q=0.2, application/json;q=0.9"),
+            Encoding::Json
+        );
+        assert_eq!(accept("text/html, application/cbor;q=0"), Encoding::Json);
+        assert_eq!(
+            Encoding::from_subprotocol("mechbot.msgpack"),
+            Some(Encoding::MessagePack)
+        );
+
+        let value = serde_json::json!({"type": "topic", "sequence": 3, "data": [1.5, "a"]});
+        for encoding in Encoding::ALL {
+            let bytes = encoding.encode(&value).unwrap();
+            let decoded: serde_json::Value = encoding.decode(&bytes).unwrap();
+            assert_eq!(decoded, value, "{:?}", encoding);
+        }
+        assert!(Encoding::Cbor.encode(&value).unwrap().len() < value.to_string().len());
+    }
+}
@@ -0,0 +1,405 @@
+//! Ciclo de vida de los objetivos de navegación pedidos por la API. La API
+//! los crea y los cancela; la navegación recibe las órdenes por el canal de
+//! `ApiServer::navigation_goals` e informa del avance con un `GoalReporter`.
+use super::{NavigationGoal, PathData, Point, SharedState, Topic};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tokio::sync::broadcast;
+
+/// Objetivos terminados que se conservan para consultarlos
+const HISTORY: usize = 100;
+const EVENT_CAPACITY: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum GoalState {
+    /// Aceptado por la API, la navegación aún no ha empezado
+    Pending,
+    Active,
+    Succeeded,
+    /// La navegación no pudo llegar
+    Aborted,
+    /// Cancelado por el usuario o sustituido por un objetivo nuevo
+    Canceled,
+}
+
+impl GoalState {
+    pub fn is_terminal(self) -> bool {
+        matches!(self, Self::Succeeded | Self::Aborted | Self::Canceled)
+    }
+}
+
+/// Avance del objetivo en curso
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GoalFeedback {
+    /// Distancia que queda siguiendo la ruta (m)
+    pub distance_remaining: f64,
+    /// Tiempo estimado hasta llegar (s)
+    pub eta: Option<f64>,
+    /// Ruta pendiente
+    pub path: Vec<Point>,
+}
+
+impl GoalFeedback {
+    /// Avance desde `position` por la ruta pendiente a la velocidad `speed`
+    pub fn along_path(position: Point, path: Vec<Point>, speed: f64) -> Self {
+        let mut previous = position;
+        let distance_remaining = path
+            .iter()
+            .map(|point| {
+                let step = previous.distance_to(point);
+                previous = *point;
+                step
+            })
+            .sum::<f64>();
+        Self {
+            distance_remaining,
+            eta: (speed > 0.0).then(|| distance_remaining / speed),
+            path,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoalStatus {
+    pub id: u64,
+    pub target: Point,
+    pub speed: f64,
+    pub state: GoalState,
+    pub feedback: Option<GoalFeedback>,
+    /// Motivo de la cancelación o del fallo
+    pub message: Option<String>,
+    pub created: String,
+    pub updated: String,
+}
+
+/// Orden para la navegación
+#[derive(Debug, Clone, PartialEq)]
+pub enum GoalRequest {
+    /// Objetivo nuevo; sustituye al que esté en curso
+    Start(NavigationGoal),
+    Cancel {
+        id: u64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GoalError {
+    InvalidGoal,
+    NotFound(u64),
+    Finished {
+        id: u64,
+        state: GoalState,
+    },
+    /// Parada de emergencia activa
+    EmergencyStop,
+    /// No hay navegación que atienda el objetivo
+    Unavailable(String),
+}
+
+impl std::fmt::Display for GoalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GoalError::InvalidGoal => write!(f, "Objetivo o velocidad no válidos"),
+            GoalError::NotFound(id) => write!(f, "No existe el objetivo {}", id),
+            GoalError::Finished { id, state } => {
+                write!(f, "El objetivo {} ya terminó ({:?})", id, state)
+            }
+            GoalError::EmergencyStop => write!(f, "Parada de emergencia activa"),
+            GoalError::Unavailable(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// Objetivos creados y su estado. Cada cambio se publica a los suscriptores.
+#[derive(Debug, Clone)]
+pub struct GoalRegistry {
+    goals: BTreeMap<u64, GoalStatus>,
+    current: Option<u64>,
+    next_id: u64,
+    events: broadcast::Sender<GoalStatus>,
+}
+
+impl Default for GoalRegistry {
+    fn default() -> Self {
+        Self {
+            goals: BTreeMap::new(),
+            current: None,
+            next_id: 0,
+            events: broadcast::channel(EVENT_CAPACITY).0,
+        }
+    }
+}
+
+impl GoalRegistry {
+    /// Registra un objetivo pendiente; el que estuviera en curso se cancela
+    pub fn create(&mut self, target: Point, speed: f64) -> NavigationGoal {
+        self.next_id += 1;
+        let id = self.next_id;
+        if let Some(current) = self.current {
+            let reason = format!("Sustituido por el objetivo {}", id);
+            self.finish(current, GoalState::Canceled, Some(reason));
+        }
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let status = GoalStatus {
+            id,
+            target,
+            speed,
+            state: GoalState::Pending,
+            feedback: None,
+            message: None,
+            created: now.clone(),
+            updated: now,
+        };
+        let _ = self.events.send(status.clone());
+        self.goals.insert(id, status);
+        self.current = Some(id);
+        self.prune();
+        NavigationGoal { id, target, speed }
+    }
+
+    pub fn get(&self, id: u64) -> Option<&GoalStatus> {
+        self.goals.get(&id)
+    }
+
+    /// Objetivos conservados, del más antiguo al más reciente
+    pub fn list(&self) -> Vec<GoalStatus> {
+        self.goals.values().cloned().collect()
+    }
+
+    /// Objetivo pendiente o activo
+    pub fn current(&self) -> Option<&GoalStatus> {
+        self.current.and_then(|id| self.goals.get(&id))
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<GoalStatus> {
+        self.events.subscribe()
+    }
+
+    pub fn cancel(&mut self, id: u64) -> Result<GoalStatus, GoalError> {
+        let state = self.goals.get(&id).ok_or(GoalError::NotFound(id))?.state;
+        if state.is_terminal() {
+            return Err(GoalError::Finished { id, state });
+        }
+        let reason = "Cancelado por el usuario".to_string();
+        self.finish(id, GoalState::Canceled, Some(reason));
+        Ok(self.goals[&id].clone())
+    }
+
+    /// La navegación empezó a perseguir el objetivo
+    pub fn activate(&mut self, id: u64) -> bool {
+        self.update(id, |goal| goal.state = GoalState::Active)
+    }
+
+    pub fn feedback(&mut self, id: u64, feedback: GoalFeedback) -> bool {
+        self.update(id, |goal| {
+            goal.state = GoalState::Active;
+            goal.feedback = Some(feedback);
+        })
+    }
+
+    /// Cierra el objetivo; no hace nada si ya había terminado
+    pub fn finish(&mut self, id: u64, state: GoalState, message: Option<String>) -> bool {
+        if self.current == Some(id) {
+            self.current = None;
+        }
+        self.update(id, |goal| {
+            goal.state = state;
+            goal.message = message;
+        })
+    }
+
+    /// Aplica el cambio si el objetivo existe y sigue vivo. Los avisos tardíos
+    /// de un objetivo ya cancelado o sustituido se descartan.
+    fn update(&mut self, id: u64, change: impl FnOnce(&mut GoalStatus)) -> bool {
+        let Some(goal) = self.goals.get_mut(&id) else {
+            return false;
+        };
+        if goal.state.is_terminal() {
+            return false;
+        }
+        change(goal);
+        goal.updated = chrono::Utc::now().to_rfc3339();
+        let _ = self.events.send(goal.clone());
+        true
+    }
+
+    fn prune(&mut self) {
+        while self.goals.len() > HISTORY {
+            let oldest = self
+                .goals
+                .values()
+                .find(|goal| goal.state.is_terminal())
+                .map(|goal| goal.id);
+            match oldest {
+                Some(id) => self.goals.remove(&id),
+                None => break,
+            };
+        }
+    }
+}
+
+/// Crea un objetivo y lo envía a la navegación
+pub async fn send_goal(
+    state: &SharedState,
+    target: Point,
+    speed: f64,
+) -> Result<GoalStatus, GoalError> {
+    let valid = target.x.is_finite() && target.y.is_finite() && speed.is_finite();
+    if !valid || speed <= 0.0 {
+        return Err(GoalError::InvalidGoal);
+    }
+
+    let (sender, goal) = {
+        let mut state = state.write().await;
+        if state.emergency_stop {
+            return Err(GoalError::EmergencyStop);
+        }
+        let Some(sender) = state.navigation_goals.clone() else {
+            return Err(GoalError::Unavailable(
+                "No hay navegación conectada".to_string(),
+            ));
+        };
+        (sender, state.goals.create(target, speed))
+    };
+    log::info!(
+        "🎯 Objetivo {} hacia ({:.2}, {:.2}) a {} m/s",
+        goal.id,
+        target.x,
+        target.y,
+        speed
+    );
+
+    let id = goal.id;
+    let sent = sender.send(GoalRequest::Start(goal)).await;
+    let mut state = state.write().await;
+    if sent.is_err() {
+        let reason = "La navegación se ha detenido".to_string();
+        state
+            .goals
+            .finish(id, GoalState::Aborted, Some(reason.clone()));
+        return Err(GoalError::Unavailable(reason));
+    }
+    state.goals.get(id).cloned().ok_or(GoalError::NotFound(id))
+}
+
+/// Cancela un objetivo pendiente o activo y avisa a la navegación
+pub async fn cancel_goal(state: &SharedState, id: u64) -> Result<GoalStatus, GoalError> {
+    let (sender, status) = {
+        let mut state = state.write().await;
+        let status = state.goals.cancel(id)?;
+        (state.navigation_goals.clone(), status)
+    };
+    log::info!("✋ Objetivo {} cancelado", id);
+    if let Some(sender) = sender {
+        let _ = sender.send(GoalRequest::Cancel { id }).await;
+    }
+    Ok(status)
+}
+
+/// Extremo con el que la navegación informa del avance de los objetivos
+#[derive(Debug, Clone)]
+pub struct GoalReporter {
+    state: SharedState,
+}
+
+impl GoalReporter {
+    pub(super) fn new(state: SharedState) -> Self {
+        Self { state }
+    }
+
+    pub async fn activate(&self, id: u64) {
+        self.state.write().await.goals.activate(id);
+    }
+
+    pub async fn feedback(&self, id: u64, feedback: GoalFeedback) {
+        let path = PathData {
+            goal_id: id,
+            path: feedback.path.clone(),
+        };
+        let mut state = self.state.write().await;
+        if state.goals.feedback(id, feedback) {
+            state.telemetry.publish(Topic::Path, path);
+        }
+    }
+
+    pub async fn succeed(&self, id: u64) {
+        log::info!("🏁 Objetivo {} alcanzado", id);
+        let mut state = self.state.write().await;
+        state.goals.finish(id, GoalState::Succeeded, None);
+    }
+
+    pub async fn abort(&self, id: u64, reason: &str) {
+        log::warn!("⚠️ Objetivo {} abortado: {}", id, reason);
+        let mut state = self.state.write().await;
+        state
+            .goals
+            .finish(id, GoalState::Aborted, Some(reason.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_goal_lifecycle_with_preemption() {
+        let mut goals = GoalRegistry::default();
+        let mut events = goals.subscribe();
+
+        let first = goals.create(Point::new(1.0, 0.0), 0.5);
+        assert_eq!(goals.get(first.id).unwrap().state, GoalState::Pending);
+        assert!(goals.activate(first.id));
+        let feedback = GoalFeedback::along_path(
+            Point::new(0.0, 0.0),
+            vec![Point::new(0.0, 3.0), Point::new(4.0, 3.0)],
+            0.5,
+        );
+        assert_eq!(feedback.distance_remaining, 7.0);
+        assert_eq!(feedback.eta, Some(14.0));
+        assert!(goals.feedback(first.id, feedback));
+
+        // Un objetivo nuevo sustituye al activo
+        let second = goals.create(Point::new(2.0, 0.0), 0.5);
+        let first_status = goals.get(first.id).unwrap();
+        assert_eq!(first_status.state, GoalState::Canceled);
+        assert!(first_status.message.as_deref().unwrap().contains("2"));
+        assert_eq!(goals.current().unwrap().id, second.id);
+        // Avisos tardíos del objetivo sustituido no lo reviven
+        assert!(!goals.feedback(first.id, GoalFeedback::default()));
+        assert!(!goals.finish(first.id, GoalState::Succeeded, None));
+
+        assert_eq!(goals.cancel(second.id).unwrap().state, GoalState::Canceled);
+        assert_eq!(
+            goals.cancel(second.id),
+            Err(GoalError::Finished {
+                id: second.id,
+                state: GoalState::Canceled
+            })
+        );
+        assert_eq!(goals.cancel(99), Err(GoalError::NotFound(99)));
+        assert!(goals.current().is_none());
+
+        let states: Vec<_> = std::iter::from_fn(|| events.try_recv().ok())
+            .map(|status| (status.id, status.state))
+            .collect();
+        assert_eq!(
+            states,
+            [
+                (1, GoalState::Pending),
+                (1, GoalState::Active),
+                (1, GoalState::Active),
+                (1, GoalState::Canceled),
+                (2, GoalState::Pending),
+                (2, GoalState::Canceled),
+            ]
+        );
+
+        for _ in 0..HISTORY + 10 {
+            goals.create(Point::new(0.0, 0.0), 1.0);
+        }
+        assert_eq!(goals.list().len(), HISTORY);
+        assert!(goals.current().is_some());
+    }
+}
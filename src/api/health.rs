@@ -0,0 +1,374 @@
+//! Salud del robot por componentes para las sondas de Kubernetes.
+//! `/health/live` solo falla si el lazo de control se ha colgado (reiniciar
+//! lo arregla); `/health/ready` falla si sensores, localización o control no
+//! permiten operar.
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use super::AppState;
+use crate::sensors::{OperatingMode, SensorHealth};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HealthCheckConfig {
+    /// Confianza de la localización por debajo de la cual está degradada
+    pub min_confidence: f64,
+    /// Confianza por debajo de la cual el robot no está listo
+    pub failed_confidence: f64,
+    /// Silencio tolerado del lazo de control (ms)
+    pub heartbeat_timeout_ms: u64,
+}
+
+impl Default for HealthCheckConfig {
+    fn default() -> Self {
+        Self {
+            min_confidence: 0.5,
+            failed_confidence: 0.2,
+            heartbeat_timeout_ms: 1000,
+        }
+    }
+}
+
+/// De mejor a peor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Healthy,
+    Degraded,
+    Failed,
+}
+
+impl HealthStatus {
+    /// Igual que en JSON
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Healthy => "healthy",
+            Self::Degraded => "degraded",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComponentHealth {
+    pub status: HealthStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Medida en la que se basa (confianza, segundos desde el último latido)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+    /// Detalle por elemento, p. ej. cada sensor
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub components: BTreeMap<String, ComponentHealth>,
+}
+
+impl ComponentHealth {
+    pub fn new(status: HealthStatus, message: Option<String>) -> Self {
+        Self {
+            status,
+            message,
+            value: None,
+            components: BTreeMap::new(),
+        }
+    }
+
+    pub fn healthy() -> Self {
+        Self::new(HealthStatus::Healthy, None)
+    }
+
+    pub fn degraded(message: impl Into<String>) -> Self {
+        Self::new(HealthStatus::Degraded, Some(message.into()))
+    }
+
+    pub fn failed(message: impl Into<String>) -> Self {
+        Self::new(HealthStatus::Failed, Some(message.into()))
+    }
+
+    fn with_value(mut self, value: f64) -> Self {
+        self.value = Some(value);
+        self
+    }
+}
+
+impl From<&SensorHealth> for ComponentHealth {
+    fn from(health: &SensorHealth) -> Self {
+        match health {
+            SensorHealth::Healthy => Self::healthy(),
+            SensorHealth::Warning(message) => Self::degraded(message.clone()),
+            SensorHealth::Error(message) => Self::failed(message.clone()),
+            SensorHealth::Disconnected => Self::failed("Desconectado"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    /// El peor estado de los componentes
+    pub status: HealthStatus,
+    pub service: String,
+    pub timestamp: String,
+    pub uptime: u64,
+    pub components: BTreeMap<String, ComponentHealth>,
+}
+
+impl HealthReport {
+    fn new(state: &AppState, components: BTreeMap<String, ComponentHealth>) -> Self {
+        Self {
+            status: components
+                .values()
+                .map(|component| component.status)
+                .max()
+                .unwrap_or(HealthStatus::Healthy),
+            service: "mechbot-3x".to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            uptime: state.started.elapsed().as_secs(),
+            components,
+        }
+    }
+
+    pub fn is_failed(&self) -> bool {
+        self.status == HealthStatus::Failed
+    }
+}
+
+/// El proceso está vivo salvo que el control haya latido y luego se haya
+/// parado. Sin sensores o sin control conectado no se reinicia nada.
+pub fn liveness(state: &AppState, now: Instant) -> HealthReport {
+    let control = match control(state, now) {
+        hung if hung.status == HealthStatus::Failed && hung.value.is_some() => hung,
+        other => ComponentHealth {
+            status: HealthStatus::Healthy,
+            ..other
+        },
+    };
+    HealthReport::new(state, BTreeMap::from([("control".to_string(), control)]))
+}
+
+/// Si el robot puede aceptar órdenes
+pub fn readiness(state: &AppState, now: Instant) -> HealthReport {
+    let emergency_stop = if state.emergency_stop {
+        // Degradado y no caído: hace falta la API para rearmar
+        ComponentHealth::degraded("Parada de emergencia activa")
+    } else {
+        ComponentHealth::healthy()
+    };
+    let components = BTreeMap::from([
+        ("sensors".to_string(), sensors(state)),
+        ("localization".to_string(), localization(state)),
+        ("control".to_string(), control(state, now)),
+        ("emergency_stop".to_string(), emergency_stop),
+    ]);
+    HealthReport::new(state, components)
+}
+
+/// Cada sensor por separado; el conjunto según el modo de operación que
+/// decide el monitor, que sabe qué sensores son imprescindibles
+fn sensors(state: &AppState) -> ComponentHealth {
+    if state.sensor_health.is_empty() {
+        return ComponentHealth::failed("Ningún sensor conectado");
+    }
+    let components: BTreeMap<String, ComponentHealth> = state
+        .sensor_health
+        .iter()
+        .map(|(name, health)| (name.clone(), ComponentHealth::from(health)))
+        .collect();
+
+    let failed = components
+        .values()
+        .filter(|sensor| sensor.status == HealthStatus::Failed)
+        .count();
+    let mut sensors = match state.operating_mode {
+        _ if failed == components.len() => ComponentHealth::failed("Todos los sensores caídos"),
+        OperatingMode::Stopped => {
+            ComponentHealth::failed("Sensores imprescindibles caídos: robot detenido")
+        }
+        OperatingMode::Degraded { speed_factor } => ComponentHealth::degraded(format!(
+            "Modo degradado al {:.0} % de velocidad",
+            speed_factor * 100.0
+        )),
+        // Un sensor prescindible caído no impide operar
+        OperatingMode::Normal
+            if components
+                .values()
+                .any(|s| s.status > HealthStatus::Healthy) =>
+        {
+            ComponentHealth::degraded("Algún sensor con problemas")
+        }
+        OperatingMode::Normal => ComponentHealth::healthy(),
+    };
+    sensors.components = components;
+    sensors
+}
+
+fn localization(state: &AppState) -> ComponentHealth {
+    let config = &state.health_config;
+    let Some(confidence) = state.localization_confidence else {
+        return ComponentHealth::failed("Sin localización");
+    };
+    let health = if confidence < config.failed_confidence {
+        ComponentHealth::failed("Localización perdida")
+    } else if confidence < config.min_confidence {
+        ComponentHealth::degraded("Localización poco fiable")
+    } else {
+        ComponentHealth::healthy()
+    };
+    health.with_value(confidence)
+}
+
+/// `value` solo si el lazo ha latido alguna vez
+fn control(state: &AppState, now: Instant) -> ComponentHealth {
+    let Some(heartbeat) = &state.control_heartbeat else {
+        return ComponentHealth::failed("Lazo de control no conectado");
+    };
+    let Some(age) = heartbeat.age(now) else {
+        return ComponentHealth::failed("El lazo de control no ha arrancado");
+    };
+    let timeout = Duration::from_millis(state.health_config.heartbeat_timeout_ms);
+    let health = if age > timeout {
+        ComponentHealth::failed(format!(
+            "Sin latido del lazo de control desde hace {:.1} s",
+            age.as_secs_f64()
+        ))
+    } else {
+        ComponentHealth::healthy()
+    };
+    health.with_value(age.as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control::Heartbeat;
+
+    /// Todo en orden: lazo latiendo, buena localización y sensores sanos.
+    /// El latido es de ahora mismo, que se devuelve junto al estado
+    fn operational() -> (AppState, Instant) {
+        let heartbeat = Heartbeat::default();
+        let now = Instant::now();
+        heartbeat.beat_at(now);
+        let state = AppState {
+            control_heartbeat: Some(heartbeat),
+            localization_confidence: Some(0.9),
+            sensor_health: BTreeMap::from([
+                ("lidar".to_string(), SensorHealth::Healthy),
+                ("camera".to_string(), SensorHealth::Healthy),
+            ]),
+            ..AppState::default()
+        };
+        (state, now)
+    }
+
+    #[test]
+    fn test_fresh_state_is_alive_but_not_ready() {
+        let state = AppState::default();
+        let now = Instant::now();
+        let ready = readiness(&state, now);
+        assert_eq!(ready.status, HealthStatus::Failed);
+        assert_eq!(
+            ready.components["sensors"].message.as_deref(),
+            Some("Ningún sensor conectado")
+        );
+        assert_eq!(
+            ready.components["control"].message.as_deref(),
+            Some("Lazo de control no conectado")
+        );
+        assert_eq!(
+            ready.components["localization"].status,
+            HealthStatus::Failed
+        );
+        assert_eq!(liveness(&state, now).status, HealthStatus::Healthy);
+    }
+
+    #[test]
+    fn test_operational_robot_is_ready() {
+        let (state, now) = operational();
+        let ready = readiness(&state, now);
+        assert_eq!(ready.status, HealthStatus::Healthy);
+    }
+
+    #[test]
+    fn test_sensor_readiness_follows_operating_mode() {
+        let (mut state, now) = operational();
+        // Un sensor prescindible caído solo degrada
+        state
+            .sensor_health
+            .insert("camera".to_string(), SensorHealth::Disconnected);
+        let ready = readiness(&state, now);
+        assert_eq!(ready.status, HealthStatus::Degraded);
+        let sensors = &ready.components["sensors"];
+        assert_eq!(sensors.status, HealthStatus::Degraded);
+        assert_eq!(sensors.components["camera"].status, HealthStatus::Failed);
+
+        state.operating_mode = OperatingMode::Stopped;
+        assert_eq!(
+            readiness(&state, now).components["sensors"].status,
+            HealthStatus::Failed
+        );
+
+        state.operating_mode = OperatingMode::Normal;
+        state
+            .sensor_health
+            .insert("lidar".to_string(), SensorHealth::Error("sin datos".into()));
+        assert_eq!(
+            readiness(&state, now).components["sensors"]
+                .message
+                .as_deref(),
+            Some("Todos los sensores caídos")
+        );
+    }
+
+    #[test]
+    fn test_localization_readiness_follows_confidence() {
+        let (mut state, now) = operational();
+        state.localization_confidence = Some(0.3);
+        let localization = &readiness(&state, now).components["localization"];
+        assert_eq!(localization.status, HealthStatus::Degraded);
+        assert_eq!(localization.value, Some(0.3));
+
+        state.localization_confidence = Some(0.1);
+        assert_eq!(
+            readiness(&state, now).components["localization"].status,
+            HealthStatus::Failed
+        );
+    }
+
+    #[test]
+    fn test_emergency_stop_only_degrades_readiness() {
+        let (mut state, now) = operational();
+        state.emergency_stop = true;
+        let ready = readiness(&state, now);
+        assert_eq!(ready.status, HealthStatus::Degraded);
+        assert_eq!(
+            ready.components["emergency_stop"].status,
+            HealthStatus::Degraded
+        );
+    }
+
+    #[test]
+    fn test_hung_control_loop_fails_liveness() {
+        let (state, now) = operational();
+        let later = now + Duration::from_secs(2);
+        let ready = readiness(&state, later);
+        assert_eq!(ready.components["control"].status, HealthStatus::Failed);
+        assert_eq!(ready.components["control"].value, Some(2.0));
+        assert!(liveness(&state, later).is_failed());
+
+        // Un lazo conectado que aún no ha latido no se reinicia
+        let idle = AppState {
+            control_heartbeat: Some(Heartbeat::default()),
+            ..AppState::default()
+        };
+        assert!(!liveness(&idle, later).is_failed());
+    }
+
+    #[test]
+    fn test_report_json() {
+        let (mut state, now) = operational();
+        state.localization_confidence = Some(0.3);
+        let json = serde_json::to_value(readiness(&state, now)).unwrap();
+        assert_eq!(json["status"], "degraded");
+        assert_eq!(json["components"]["localization"]["value"], 0.3);
+        assert!(json["components"]["control"].get("message").is_none());
+    }
+}
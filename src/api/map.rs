@@ -0,0 +1,295 @@
+//! Mapa de ocupación para la API: celdas comprimidas con zlib o lz4 y
+//! actualizaciones por teselas que solo llevan las zonas que han cambiado.
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+
+use super::MapData;
+
+/// Lado de las teselas de las actualizaciones (celdas)
+pub const TILE_SIZE: u32 = 32;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compression {
+    #[default]
+    None,
+    Zlib,
+    /// Bloque lz4 precedido del tamaño original (u32 little-endian)
+    Lz4,
+}
+
+impl Compression {
+    pub fn compress(self, cells: &[u8]) -> Vec<u8> {
+        match self {
+            Compression::None => cells.to_vec(),
+            Compression::Zlib => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::fast());
+                // Escribir en memoria no falla
+                let _ = encoder.write_all(cells);
+                encoder.finish().unwrap_or_default()
+            }
+            Compression::Lz4 => lz4_flex::block::compress_prepend_size(cells),
+        }
+    }
+
+    pub fn decompress(self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        Ok(match self {
+            Compression::None => data.to_vec(),
+            Compression::Zlib => {
+                let mut cells = Vec::new();
+                ZlibDecoder::new(data).read_to_end(&mut cells)?;
+                cells
+            }
+            Compression::Lz4 => lz4_flex::block::decompress_size_prepended(data)?,
+        })
+    }
+}
+
+/// Mensaje del tema `map`: el mapa entero o las teselas que cambian respecto
+/// al mensaje anterior (`base_sequence`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MapUpdate {
+    Full(MapData),
+    Delta(MapDelta),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MapDelta {
+    pub width: u32,
+    pub height: u32,
+    pub compression: Compression,
+    pub tiles: Vec<MapTile>,
+}
+
+/// Rectángulo del mapa con sus celdas por filas
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MapTile {
+    /// Columna y fila de la primera celda
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    #[serde(with = "cells")]
+    pub data: Vec<u8>,
+}
+
+impl MapData {
+    /// Copia con las celdas comprimidas
+    pub fn compressed(&self, compression: Compression) -> MapData {
+        if self.compression != Compression::None {
+            return self.clone();
+        }
+        MapData {
+            compression,
+            data: compression.compress(&self.data),
+            ..self.clone()
+        }
+    }
+}
+
+/// Mapa nuevo con sus mensajes del tema `map`, preparado antes de tomar el
+/// cerrojo del estado
+#[derive(Debug, Clone)]
+pub struct MapSnapshot {
+    /// Sin comprimir, base del siguiente diff
+    pub map: MapData,
+    pub full: MapUpdate,
+    pub delta: Option<MapUpdate>,
+}
+
+impl MapSnapshot {
+    /// `previous` es el último mapa publicado, sin comprimir
+    pub fn new(previous: &MapData, map: MapData, compression: Compression) -> Self {
+        let delta = diff(previous, &map, compression).map(MapUpdate::Delta);
+        let full = MapUpdate::Full(map.compressed(compression));
+        Self { map, full, delta }
+    }
+}
+
+/// Teselas de `current` con alguna celda distinta de `previous`. `None` si
+/// cambian tamaño, resolución u origen y hay que enviar el mapa entero. Los
+/// dos mapas sin comprimir.
+pub fn diff(previous: &MapData, current: &MapData, compression: Compression) -> Option<MapDelta> {
+    let same_frame = previous.width == current.width
+        && previous.height == current.height
+        && previous.resolution == current.resolution
+        && previous.origin == current.origin
+        && previous.data.len() == current.data.len();
+    if !same_frame {
+        return None;
+    }
+
+    let width = current.width as usize;
+    let mut tiles = Vec::new();
+    for y in (0..current.height).step_by(TILE_SIZE as usize) {
+        for x in (0..current.width).step_by(TILE_SIZE as usize) {
+            let tile_width = TILE_SIZE.min(current.width - x);
+            let tile_height = TILE_SIZE.min(current.height - y);
+            let rows = (y..y + tile_height).map(|row| {
+                let start = row as usize * width + x as usize;
+                start..start + tile_width as usize
+            });
+            if rows
+                .clone()
+                .all(|row| previous.data[row.clone()] == current.data[row])
+            {
+                continue;
+            }
+            let cells: Vec<u8> = rows
+                .flat_map(|row| current.data[row].iter().copied())
+                .collect();
+            tiles.push(MapTile {
+                x,
+                y,
+                width: tile_width,
+                height: tile_height,
+                data: compression.compress(&cells),
+            });
+        }
+    }
+    Some(MapDelta {
+        width: current.width,
+        height: current.height,
+        compression,
+        tiles,
+    })
+}
+
+/// Celdas en base64 para JSON y como bytes en CBOR o MessagePack, en lugar
+/// de una lista de números
+pub(super) mod cells {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use serde::de::{self, SeqAccess, Visitor};
+    use serde::{Deserializer, Serializer};
+    use std::fmt;
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&STANDARD.encode(data))
+        } else {
+            serializer.serialize_bytes(data)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        struct Cells;
+
+        impl<'de> Visitor<'de> for Cells {
+            type Value = Vec<u8>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("celdas en base64, bytes o lista de números")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Vec<u8>, E> {
+                STANDARD.decode(v).map_err(E::custom)
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Vec<u8>, E> {
+                Ok(v.to_vec())
+            }
+
+            fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Vec<u8>, E> {
+                Ok(v)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+                let mut cells = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(cell) = seq.next_element()? {
+                    cells.push(cell);
+                }
+                Ok(cells)
+            }
+        }
+
+        deserializer.deserialize_any(Cells)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::Point;
+
+    fn map(width: u32, height: u32) -> MapData {
+        MapData {
+            resolution: 0.05,
+            width,
+            height,
+            origin: Point::new(0.0, 0.0),
+            compression: Compression::None,
+            data: vec![50; (width * height) as usize],
+        }
+    }
+
+    #[test]
+    fn test_delta_tiles_and_compression() {
+        let previous = map(70, 40);
+        let mut current = previous.clone();
+        // Una celda en la tesela (1, 0) y otra en la (2, 1), que es más pequeña
+        current.data[5 * 70 + 40] = 100;
+        current.data[35 * 70 + 69] = 0;
+
+        for compression in [Compression::None, Compression::Zlib, Compression::Lz4] {
+            let delta = diff(&previous, &current, compression).unwrap();
+            assert_eq!(delta.tiles.len(), 2);
+            let tile = &delta.tiles[1];
+            assert_eq!((tile.x, tile.y, tile.width, tile.height), (64, 32, 6, 8));
+            let cells = compression.decompress(&tile.data).unwrap();
+            assert_eq!(cells.len(), 6 * 8);
+            assert_eq!(cells[3 * 6 + 5], 0);
+            assert_eq!(cells.iter().filter(|&&cell| cell != 50).count(), 1);
+        }
+        assert!(diff(&previous, &previous, Compression::None)
+            .unwrap()
+            .tiles
+            .is_empty());
+        assert!(diff(&map(70, 41), &current, Compression::None).is_none());
+
+        // Un mapa uniforme grande se queda en casi nada
+        let big = map(1000, 1000);
+        let zlib = big.compressed(Compression::Zlib);
+        assert!(zlib.data.len() < 10_000);
+        assert_eq!(Compression::Zlib.decompress(&zlib.data).unwrap(), big.data);
+
+        // Celdas en base64 en JSON y como bytes en CBOR
+        let update = MapUpdate::Delta(diff(&previous, &current, Compression::Lz4).unwrap());
+        let json = serde_json::to_value(&update).unwrap();
+        assert_eq!(json["kind"], "delta");
+        assert!(json["tiles"][0]["data"].is_string());
+        assert_eq!(serde_json::from_value::<MapUpdate>(json).unwrap(), update);
+        let mut cbor = Vec::new();
+        ciborium::into_writer(&MapUpdate::Full(big.clone()), &mut cbor).unwrap();
+        assert!(cbor.len() < big.data.len() + 200);
+        let decoded: MapUpdate = ciborium::from_reader(cbor.as_slice()).unwrap();
+        assert_eq!(decoded, MapUpdate::Full(big));
+    }
+    #[test]
+    fn test_snapshot_keeps_raw_map_and_compressed_updates() {
+        let previous = map(70, 40);
+        let mut current = previous.clone();
+        current.data[0] = 100;
+
+        let snapshot = MapSnapshot::new(&previous, current.clone(), Compression::Zlib);
+        assert_eq!(snapshot.map, current);
+        let MapUpdate::Full(full) = &snapshot.full else {
+            panic!("se esperaba el mapa entero: {:?}", snapshot.full);
+        };
+        assert_eq!(full.compression, Compression::Zlib);
+        assert_eq!(
+            Compression::Zlib.decompress(&full.data).unwrap(),
+            current.data
+        );
+        let Some(MapUpdate::Delta(delta)) = &snapshot.delta else {
+            panic!("se esperaba un delta: {:?}", snapshot.delta);
+        };
+        assert_eq!(delta.tiles.len(), 1);
+
+        let resized = MapSnapshot::new(&previous, map(10, 10), Compression::None);
+        assert!(resized.delta.is_none());
+    }
+}
@@ -1,24 +1,267 @@
+pub mod auth;
+pub mod encoding;
+pub mod goals;
+pub mod health;
+pub mod map;
+pub mod protocol;
 pub mod rest;
+pub mod telemetry;
+pub mod tls;
 pub mod websocket;
 
+pub use crate::geometry::{Point2 as Point, Vector3};
+pub use auth::{AuthConfig, Authenticator, Principal, Role};
+pub use encoding::{Encoded, Encoding};
+pub use goals::{GoalFeedback, GoalRegistry, GoalReporter, GoalRequest, GoalState, GoalStatus};
+pub use health::{HealthCheckConfig, HealthReport, HealthStatus};
+pub use map::{Compression, MapSnapshot, MapUpdate};
+pub use protocol::{Command, Reply, RobotMode};
+pub use telemetry::{TelemetryHub, Topic};
+
+use crate::config::ApiConfig;
+use crate::control;
+use crate::geometry::Pose2D;
+use crate::mission::MissionAction;
+use crate::navigation::docking::DockingStatus;
+use crate::navigation::slam::OccupancyGrid;
+use crate::power::BatteryState;
+use crate::sensors::{
+    self, HealthEvent, HealthMonitor, OperatingMode, SensorBus, SensorHealth, SensorReading,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Instant;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{mpsc, watch, RwLock};
+use tokio::task::JoinHandle;
+use tokio::time::{interval, Duration};
+
+/// Estado compartido entre los servidores REST y WebSocket
+pub type SharedState = Arc<RwLock<AppState>>;
 
 #[derive(Debug, Clone)]
 pub struct ApiServer {
     pub port: u16,
     is_running: bool,
+    state: SharedState,
+    tls: Option<tls::TlsConfig>,
 }
 
 impl ApiServer {
+    /// Servidor sin claves: exige credenciales y rechaza todas las peticiones
+    /// hasta que se configure con `from_config`
     pub fn new(port: u16) -> Self {
         Self {
             port,
             is_running: false,
+            state: Arc::new(RwLock::new(AppState::default())),
+            tls: None,
         }
     }
 
+    /// Servidor con la autenticación y el TLS de `[api]`; falla si no se
+    /// puede leer el fichero de claves.
+    pub fn from_config(config: &ApiConfig) -> anyhow::Result<Self> {
+        let auth = if config.api_key_required {
+            Authenticator::new(&config.auth)?
+        } else {
+            log::warn!("⚠️ API sin autenticación: cualquiera actúa como administrador");
+            Authenticator::disabled()
+        };
+        let state = AppState {
+            auth: Arc::new(auth),
+            map_compression: config.map_compression,
+            health_config: config.health.clone(),
+            ..AppState::default()
+        };
+        Ok(Self {
+            port: config.rest_port.unwrap_or(8080),
+            is_running: false,
+            state: Arc::new(RwLock::new(state)),
+            tls: config.tls.clone(),
+        })
+    }
+
+    pub fn state(&self) -> SharedState {
+        self.state.clone()
+    }
+
+    /// Canal por el que llegan las acciones pedidas por la API (acoplar,
+    /// desacoplar...). Solo el último receptor creado las recibe.
+    pub async fn mission_actions(&self) -> mpsc::Receiver<MissionAction> {
+        let (sender, receiver) = mpsc::channel(16);
+        self.state.write().await.mission_actions = Some(sender);
+        receiver
+    }
+
+    /// Canal por el que llegan los objetivos creados y cancelados desde la
+    /// API. Solo el último receptor creado los recibe.
+    pub async fn navigation_goals(&self) -> mpsc::Receiver<GoalRequest> {
+        let (sender, receiver) = mpsc::channel(16);
+        self.state.write().await.navigation_goals = Some(sender);
+        receiver
+    }
+
+    /// Canal por el que llegan las órdenes directas (teleoperación, modo,
+    /// parámetros). Solo el último receptor creado las recibe.
+    pub async fn robot_commands(&self) -> mpsc::Receiver<RobotCommand> {
+        let (sender, receiver) = mpsc::channel(32);
+        self.state.write().await.robot_commands = Some(sender);
+        receiver
+    }
+
+    /// Parada de emergencia enclavada (`true` mientras esté activa). Va por
+    /// su propio canal para no esperar detrás de la teleoperación.
+    pub async fn emergency_stops(&self) -> watch::Receiver<bool> {
+        self.state.read().await.emergency_latch.subscribe()
+    }
+
+    /// Extremo para que la navegación publique el avance de los objetivos
+    pub fn goal_reporter(&self) -> GoalReporter {
+        GoalReporter::new(self.state.clone())
+    }
+
+    /// Publica en la API cada lectura del monitor de batería
+    pub fn track_battery(&self, battery: watch::Receiver<Option<BatteryState>>) -> JoinHandle<()> {
+        self.track(battery, AppState::apply_battery)
+    }
+
+    /// Publica en la API la pose estimada por la localización
+    pub fn track_pose(&self, pose: watch::Receiver<Option<control::RobotState>>) -> JoinHandle<()> {
+        self.track(pose, AppState::apply_pose)
+    }
+
+    /// Publica en la API el mapa de ocupación del SLAM. La conversión, el
+    /// diff y la compresión se hacen sin el cerrojo del estado.
+    pub fn track_map(&self, mut map: watch::Receiver<Option<OccupancyGrid>>) -> JoinHandle<()> {
+        let state = self.state.clone();
+        tokio::spawn(async move {
+            let (mut previous, compression) = {
+                let state = state.read().await;
+                (state.map_data.clone(), state.map_compression)
+            };
+            loop {
+                let latest = map.borrow_and_update().clone();
+                if let Some(grid) = latest {
+                    let snapshot = MapSnapshot::new(&previous, MapData::from(&grid), compression);
+                    previous = snapshot.map.clone();
+                    state.write().await.apply_map(snapshot);
+                }
+                if map.changed().await.is_err() {
+                    break;
+                }
+            }
+        })
+    }
+
+    /// Publica en la API el estado del acoplamiento a la base de carga
+    pub fn track_docking(&self, docking: watch::Receiver<Option<DockingStatus>>) -> JoinHandle<()> {
+        self.track(docking, AppState::apply_docking)
+    }
+
+    /// Publica en la API los objetos que detecta la visión
+    pub fn track_detections(
+        &self,
+        detections: watch::Receiver<Option<DetectionData>>,
+    ) -> JoinHandle<()> {
+        self.track(detections, AppState::apply_detections)
+    }
+
+    /// Publica en la API la confianza (0..1) de la localización, p. ej.
+    /// `NavigationController::localization_confidence`
+    pub fn track_localization(&self, confidence: watch::Receiver<Option<f64>>) -> JoinHandle<()> {
+        self.track(confidence, AppState::apply_localization)
+    }
+
+    /// Latido del lazo de control (`ControlSystem::heartbeat`) para las
+    /// comprobaciones de salud
+    pub async fn track_control(&self, heartbeat: control::Heartbeat) {
+        self.state.write().await.control_heartbeat = Some(heartbeat);
+    }
+
+    /// Sigue la salud de cada sensor y el modo de operación del monitor
+    pub fn track_sensor_health(&self, monitor: &HealthMonitor) -> JoinHandle<()> {
+        // Suscribirse antes de leer el estado para no perder cambios
+        let mut events = monitor.subscribe();
+        let statuses: BTreeMap<String, SensorHealth> = monitor
+            .statuses()
+            .into_iter()
+            .map(|(name, status)| (name, status.health))
+            .collect();
+        let mode = monitor.mode();
+        let state = self.state.clone();
+        tokio::spawn(async move {
+            {
+                let mut state = state.write().await;
+                state.sensor_health = statuses;
+                state.operating_mode = mode;
+            }
+            loop {
+                match events.recv().await {
+                    Ok(event) => state.write().await.apply_health_event(&event),
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!("⚠️ Perdidos {} eventos de salud de sensores", skipped)
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    /// Publica en la API las últimas lecturas de lidar, IMU y cámara del bus
+    pub fn track_sensors(&self, bus: &SensorBus) -> JoinHandle<()> {
+        let mut messages = bus.subscribe_all();
+        let state = self.state.clone();
+        tokio::spawn(async move {
+            loop {
+                match messages.recv().await {
+                    Ok(message) => state
+                        .write()
+                        .await
+                        .apply_reading(&message.reading, message.timestamp),
+                    // Solo interesa la última lectura
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    fn track<T: Clone + Send + Sync + 'static>(
+        &self,
+        mut receiver: watch::Receiver<Option<T>>,
+        apply: fn(&mut AppState, &T),
+    ) -> JoinHandle<()> {
+        let state = self.state.clone();
+        tokio::spawn(async move {
+            loop {
+                let latest = receiver.borrow_and_update().clone();
+                if let Some(latest) = latest {
+                    apply(&mut *state.write().await, &latest);
+                }
+                if receiver.changed().await.is_err() {
+                    break;
+                }
+            }
+        })
+    }
+
+    /// Publica el tema `diagnostics` una vez por segundo
+    fn publish_diagnostics(&self) -> JoinHandle<()> {
+        let state = self.state.clone();
+        tokio::spawn(async move {
+            let mut ticks = interval(Duration::from_secs(1));
+            loop {
+                ticks.tick().await;
+                let state = state.read().await;
+                state
+                    .telemetry
+                    .publish(Topic::Diagnostics, state.diagnostics());
+            }
+        })
+    }
+
     pub fn is_running(&self) -> bool {
         self.is_running
     }
@@ -30,22 +273,45 @@ impl ApiServer {
     pub async fn start(&mut self) -> anyhow::Result<()> {
         log::info!("🚀 Iniciando servidor API en puerto {}", self.port);
 
+        // Un mismo certificado para los dos servidores
+        let rustls = match &self.tls {
+            Some(config) => {
+                let rustls = tls::load(config).await?;
+                #[cfg(unix)]
+                tls::reload_on_sighup(rustls.clone(), config.clone())?;
+                Some(rustls)
+            }
+            None => None,
+        };
+
         // Iniciar servidor REST
-        let rest_handle = tokio::spawn(rest::start_rest_server(self.port));
+        let rest_handle = tokio::spawn(rest::start_rest_server(
+            self.port,
+            self.state(),
+            rustls.clone(),
+        ));
 
         // Iniciar servidor WebSocket
-        let websocket_handle = tokio::spawn(websocket::start_websocket_server(self.port + 1));
+        let websocket_handle = tokio::spawn(websocket::start_websocket_server(
+            self.port + 1,
+            self.state(),
+            rustls,
+        ));
+
+        let diagnostics = self.publish_diagnostics();
 
         self.is_running = true;
         log::info!("✅ Servidores API iniciados:");
-        log::info!("   - REST: http://localhost:{}", self.port);
-        log::info!("   - WebSocket: ws://localhost:{}", self.port + 1);
+        let secure = if self.tls.is_some() { "s" } else { "" };
+        log::info!("   - REST: http{}://localhost:{}", secure, self.port);
+        log::info!("   - WebSocket: ws{}://localhost:{}", secure, self.port + 1);
 
         // Mantener los servidores corriendo
         tokio::select! {
             _ = rest_handle => {},
             _ = websocket_handle => {},
         }
+        diagnostics.abort();
 
         Ok(())
     }
@@ -71,9 +337,8 @@ pub struct RobotState {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Position {
-    pub x: f64,
-    pub y: f64,
-    pub theta: f64,
+    #[serde(flatten)]
+    pub pose: Pose2D,
     pub timestamp: String,
 }
 
@@ -84,6 +349,34 @@ pub struct MoveCommand {
     pub speed: f64,
 }
 
+/// Orden directa al robot pedida por la API
+#[derive(Debug, Clone, PartialEq)]
+pub enum RobotCommand {
+    /// Velocidad de teleoperación (m/s, rad/s)
+    Velocity { linear: f64, angular: f64 },
+    SetMode(RobotMode),
+    SetParameter {
+        name: String,
+        value: serde_json::Value,
+    },
+}
+
+/// Objetivo de navegación pedido por la API
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NavigationGoal {
+    pub id: u64,
+    pub target: Point,
+    /// Velocidad máxima pedida (m/s)
+    pub speed: f64,
+}
+
+impl NavigationGoal {
+    /// Pose objetivo para `NavigationController::navigate_to_pose`
+    pub fn target_pose(&self) -> control::RobotState {
+        control::RobotState::new(self.target.x, self.target.y, 0.0)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SensorData {
     pub lidar: LidarData,
@@ -113,34 +406,248 @@ pub struct CameraData {
     pub objects_detected: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Point {
-    pub x: f64,
-    pub y: f64,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Vector3 {
-    pub x: f64,
-    pub y: f64,
-    pub z: f64,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MapData {
     pub resolution: f64,
     pub width: u32,
     pub height: u32,
     pub origin: Point,
+    #[serde(default)]
+    pub compression: Compression,
+    /// Probabilidad de ocupación (%) por filas; 50 = desconocido. En base64
+    /// en JSON
+    #[serde(with = "map::cells")]
     pub data: Vec<u8>,
 }
 
+/// Ruta pendiente del objetivo en curso, para el tema `path`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathData {
+    pub goal_id: u64,
+    pub path: Vec<Point>,
+}
+
+/// Objetos detectados en una imagen de la cámara
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DetectionData {
+    pub frame_id: String,
+    pub objects: Vec<DetectedObject>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DetectedObject {
+    pub label: String,
+    pub confidence: f64,
+    /// Caja en píxeles: x, y, ancho, alto
+    pub bbox: [f64; 4],
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub track_id: Option<u32>,
+}
+
+/// Resumen del robot para el tema `diagnostics`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostics {
+    pub status: RobotState,
+    pub battery: Option<BatteryState>,
+    pub docking: Option<DockingStatus>,
+    pub emergency_stop: bool,
+    pub current_goal: Option<u64>,
+    /// Última lectura de sensores
+    pub sensors_timestamp: String,
+    pub uptime: u64,
+}
+
+impl From<&sensors::LidarData> for LidarData {
+    fn from(scan: &sensors::LidarData) -> Self {
+        let valid: Vec<_> = scan
+            .points
+            .iter()
+            .filter(|point| point.distance.is_finite() && point.distance > 0.0)
+            .collect();
+        let distances = valid.iter().map(|point| point.distance);
+        Self {
+            points: valid
+                .iter()
+                .map(|point| Point::from_polar(point.distance, point.angle))
+                .collect(),
+            min_distance: distances.clone().reduce(f64::min).unwrap_or(0.0),
+            max_distance: distances.reduce(f64::max).unwrap_or(0.0),
+        }
+    }
+}
+
+impl From<&sensors::IMUData> for IMUData {
+    fn from(imu: &sensors::IMUData) -> Self {
+        Self {
+            acceleration: imu.acceleration,
+            gyroscope: imu.gyroscope,
+            magnetometer: imu.magnetometer,
+        }
+    }
+}
+
+impl From<&OccupancyGrid> for MapData {
+    fn from(grid: &OccupancyGrid) -> Self {
+        let (x, y) = grid.grid_to_world(0, 0);
+        Self {
+            resolution: grid.resolution(),
+            width: grid.width() as u32,
+            height: grid.height() as u32,
+            origin: Point::new(x, y),
+            compression: Compression::None,
+            data: grid
+                .cells()
+                .iter()
+                .map(|p| (p.clamp(0.0, 1.0) * 100.0).round() as u8)
+                .collect(),
+        }
+    }
+}
+
 // Estado compartido de la aplicación
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub robot_status: RobotStatus,
     pub last_sensor_data: SensorData,
     pub map_data: MapData,
+    /// Última lectura del monitor de batería; `None` si no hay monitor
+    pub battery: Option<BatteryState>,
+    pub docking: Option<DockingStatus>,
+    /// Ejecutor de misiones del robot; `None` si no hay ninguno conectado
+    pub mission_actions: Option<mpsc::Sender<MissionAction>>,
+    /// Navegación del robot; `None` si no hay ninguna conectada
+    pub navigation_goals: Option<mpsc::Sender<GoalRequest>>,
+    pub goals: GoalRegistry,
+    /// Control del robot; `None` si no hay ninguno conectado
+    pub robot_commands: Option<mpsc::Sender<RobotCommand>>,
+    /// Mientras esté activa se rechazan teleoperación y objetivos
+    pub emergency_stop: bool,
+    /// Copia de `emergency_stop` para el control (`ApiServer::emergency_stops`)
+    pub emergency_latch: watch::Sender<bool>,
+    pub started: Instant,
+    pub auth: Arc<Authenticator>,
+    /// Intentos de acceso rechazados; se registran sin bloquear el estado
+    pub audit: auth::AuditLog,
+    /// Últimos mensajes de cada tema del WebSocket
+    pub telemetry: TelemetryHub,
+    /// Compresión de las celdas del tema `map`
+    pub map_compression: Compression,
+    /// Salud de cada sensor vigilado; vacío sin monitor de salud
+    pub sensor_health: BTreeMap<String, SensorHealth>,
+    pub operating_mode: OperatingMode,
+    /// `None` hasta que la localización publique
+    pub localization_confidence: Option<f64>,
+    /// `None` si no hay control conectado
+    pub control_heartbeat: Option<control::Heartbeat>,
+    pub health_config: HealthCheckConfig,
+}
+
+impl AppState {
+    pub fn apply_battery(&mut self, battery: &BatteryState) {
+        self.robot_status.battery_level = battery.percentage();
+        self.battery = Some(battery.clone());
+    }
+
+    pub fn apply_localization(&mut self, confidence: &f64) {
+        self.localization_confidence = Some(*confidence);
+    }
+
+    pub fn apply_health_event(&mut self, event: &HealthEvent) {
+        match event {
+            HealthEvent::HealthChanged {
+                sensor, current, ..
+            } => {
+                self.sensor_health.insert(sensor.clone(), current.clone());
+            }
+            HealthEvent::ModeChanged { mode, .. } => self.operating_mode = *mode,
+            _ => {}
+        }
+    }
+
+    pub fn apply_pose(&mut self, pose: &control::RobotState) {
+        self.robot_status.position = Position {
+            pose: Pose2D::new(pose.x, pose.y, pose.theta),
+            timestamp: rfc3339(pose.timestamp),
+        };
+        self.telemetry
+            .publish(Topic::Pose, self.robot_status.position.clone());
+    }
+
+    /// Publica el mapa entero y, para quien tenga el anterior, solo las
+    /// teselas que cambian
+    /// Solo sustituye el mapa y publica: `MapSnapshot::new` ya lo ha preparado
+    pub fn apply_map(&mut self, snapshot: MapSnapshot) {
+        self.map_data = snapshot.map;
+        self.telemetry
+            .publish_with_delta(Topic::Map, snapshot.full, snapshot.delta);
+    }
+
+    pub fn apply_docking(&mut self, docking: &DockingStatus) {
+        self.docking = Some(docking.clone());
+    }
+
+    pub fn apply_detections(&mut self, detections: &DetectionData) {
+        self.last_sensor_data.camera.objects_detected = detections.objects.len() as u32;
+        self.telemetry.publish(Topic::Detections, detections.clone());
+    }
+
+    /// Lectura del bus con su marca de tiempo (segundos Unix)
+    pub fn apply_reading(&mut self, reading: &SensorReading, timestamp: f64) {
+        let sensors = &mut self.last_sensor_data;
+        match reading {
+            SensorReading::Lidar(scan) => {
+                sensors.lidar = LidarData::from(scan);
+                self.telemetry.publish(Topic::Scan, sensors.lidar.clone());
+            }
+            SensorReading::Imu(imu) => {
+                sensors.imu = IMUData::from(imu);
+                self.telemetry.publish(Topic::Imu, sensors.imu.clone());
+            }
+            SensorReading::Camera(frame) => {
+                sensors.camera.frame_id = frame.frame_id.clone();
+                sensors.camera.resolution = (frame.width, frame.height);
+            }
+            _ => return,
+        }
+        sensors.timestamp = rfc3339(timestamp);
+    }
+
+    /// Estado con el tiempo en marcha actualizado
+    pub fn status(&self) -> RobotStatus {
+        RobotStatus {
+            status: self.robot_state(),
+            uptime: self.started.elapsed().as_secs(),
+            ..self.robot_status.clone()
+        }
+    }
+
+    pub fn diagnostics(&self) -> Diagnostics {
+        Diagnostics {
+            status: self.robot_state(),
+            battery: self.battery.clone(),
+            docking: self.docking.clone(),
+            emergency_stop: self.emergency_stop,
+            current_goal: self.goals.current().map(|goal| goal.id),
+            sensors_timestamp: self.last_sensor_data.timestamp.clone(),
+            uptime: self.started.elapsed().as_secs(),
+        }
+    }
+
+    /// Estado con la salud que da `/health/ready`
+    fn robot_state(&self) -> RobotState {
+        let report = health::readiness(self, Instant::now());
+        RobotState {
+            health: report.status.as_str().to_string(),
+            ..self.robot_status.status.clone()
+        }
+    }
+}
+
+/// Segundos Unix en RFC 3339
+fn rfc3339(t: f64) -> String {
+    chrono::DateTime::from_timestamp_micros((t * 1e6) as i64)
+        .unwrap_or_default()
+        .to_rfc3339()
 }
 
 impl Default for AppState {
@@ -152,15 +659,13 @@ impl Default for AppState {
                 status: RobotState {
                     state: "ready".to_string(),
                     mode: "autonomous".to_string(),
-                    health: "excellent".to_string(),
+                    health: HealthStatus::Failed.as_str().to_string(),
                 },
                 position: Position {
-                    x: 0.0,
-                    y: 0.0,
-                    theta: 0.0,
+                    pose: Pose2D::identity(),
                     timestamp: chrono::Utc::now().to_rfc3339(),
                 },
-                battery_level: 95.5,
+                battery_level: 0.0,
                 uptime: 0,
             },
             last_sensor_data: SensorData {
@@ -198,8 +703,27 @@ impl Default for AppState {
                 width: 100,
                 height: 100,
                 origin: Point { x: -2.5, y: -2.5 },
+                compression: Compression::None,
                 data: vec![0; 100 * 100],
             },
+            battery: None,
+            docking: None,
+            mission_actions: None,
+            navigation_goals: None,
+            goals: GoalRegistry::default(),
+            robot_commands: None,
+            emergency_stop: false,
+            emergency_latch: watch::channel(false).0,
+            started: Instant::now(),
+            auth: Arc::new(Authenticator::default()),
+            audit: auth::AuditLog::default(),
+            telemetry: TelemetryHub::default(),
+            map_compression: Compression::None,
+            sensor_health: BTreeMap::new(),
+            operating_mode: OperatingMode::Normal,
+            localization_confidence: None,
+            control_heartbeat: None,
+            health_config: HealthCheckConfig::default(),
         }
     }
 }
@@ -221,9 +745,7 @@ mod tests {
                 health: "excellent".to_string(),
             },
             position: Position {
-                x: 1.0,
-                y: 2.0,
-                theta: 0.0,
+                pose: Pose2D::new(1.0, 2.0, 0.0),
                 timestamp: "2024-01-01T00:00:00Z".to_string(),
             },
             battery_level: 100.0,
@@ -234,6 +756,72 @@ mod tests {
         assert_eq!(status.battery_level, 100.0);
     }
 
+    #[tokio::test]
+    async fn test_battery_level_follows_power_monitor() {
+        let server = ApiServer::new(0);
+        let (sender, receiver) = watch::channel(None);
+        let task = server.track_battery(receiver);
+
+        let battery = BatteryState {
+            voltage: 11.4,
+            current: 1.2,
+            temperature: None,
+            state_of_charge: 0.42,
+            charging: false,
+            level: crate::power::PowerLevel::Normal,
+            timestamp: 0.0,
+        };
+        sender.send_replace(Some(battery.clone()));
+        drop(sender);
+        task.await.unwrap();
+
+        let state = server.state();
+        let state = state.read().await;
+        assert!((state.robot_status.battery_level - 42.0).abs() < 1e-9);
+        assert_eq!(state.battery, Some(battery));
+    }
+
+    #[tokio::test]
+    async fn test_status_sensors_and_map_follow_robot() {
+        let server = ApiServer::new(0);
+        let mut bus = SensorBus::new(16);
+        let mut imu = bus
+            .publisher("imu", crate::sensors::SensorKind::Imu)
+            .unwrap();
+        let sensors = server.track_sensors(&bus);
+        let (pose, pose_receiver) = watch::channel(None);
+        let (map, map_receiver) = watch::channel(None);
+        server.track_pose(pose_receiver);
+        server.track_map(map_receiver);
+
+        imu.publish(SensorReading::Imu(sensors::IMUData {
+            frame_id: "imu_link".to_string(),
+            acceleration: Vector3::new(0.1, 0.0, 9.8),
+            gyroscope: Vector3::default(),
+            magnetometer: Vector3::default(),
+            temperature: 25.0,
+            timestamp: 1.0,
+        }));
+        let mut robot = control::RobotState::new(3.0, -1.0, 0.5);
+        robot.timestamp = 1_700_000_000.0;
+        pose.send_replace(Some(robot));
+        let mut grid = OccupancyGrid::new(4, 2, 0.5);
+        grid.update_cell(1, 1, true);
+        map.send_replace(Some(grid));
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        sensors.abort();
+
+        let state = server.state();
+        let state = state.read().await;
+        assert_eq!(state.last_sensor_data.imu.acceleration.x, 0.1);
+        assert_eq!(state.robot_status.position.pose, Pose2D::new(3.0, -1.0, 0.5));
+        assert!(state.robot_status.position.timestamp.starts_with("2023-11-14"));
+        assert_eq!((state.map_data.width, state.map_data.height), (4, 2));
+        assert_eq!(state.map_data.origin, Point::new(-1.0, -0.5));
+        assert_eq!(state.map_data.data[5], 70);
+        assert_eq!(state.map_data.data[0], 50);
+    }
+
     #[tokio::test]
     async fn test_move_command_deserialization() {
         let json_data = r#"
@@ -0,0 +1,341 @@
+//! Protocolo de comandos del WebSocket. Cada mensaje del cliente lleva la
+//! versión, un `request_id` y el comando etiquetado por `type`; el servidor
+//! contesta siempre con un `ack` o un `error` con el mismo `request_id`.
+use schemars::schema::RootSchema;
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::auth::{AuthError, Role};
+use super::encoding::Encoding;
+use super::goals::GoalError;
+use super::telemetry::Topic;
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Identificador que elige el cliente para casar respuestas y peticiones
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum RequestId {
+    Number(u64),
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ClientMessage {
+    /// Versión del protocolo; si falta se asume la actual
+    #[serde(default = "current_version")]
+    pub version: u32,
+    pub request_id: RequestId,
+    #[serde(flatten)]
+    pub command: Command,
+}
+
+fn current_version() -> u32 {
+    PROTOCOL_VERSION
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Command {
+    /// Velocidad directa (m/s, rad/s), solo en modo manual. Si pasan 500 ms
+    /// sin recibirla, o se cierra la conexión, se manda velocidad cero.
+    Teleop {
+        linear: f64,
+        angular: f64,
+    },
+    SendGoal {
+        x: f64,
+        y: f64,
+        speed: f64,
+    },
+    CancelGoal {
+        goal_id: u64,
+    },
+    GetGoal {
+        goal_id: u64,
+    },
+    #[serde(alias = "change_mode")]
+    SetMode {
+        mode: RobotMode,
+    },
+    /// Detiene el robot y rechaza movimientos hasta `reset_emergency_stop`
+    EmergencyStop,
+    ResetEmergencyStop,
+    /// Cambia un parámetro del robot en marcha
+    SetParameter {
+        name: String,
+        value: Value,
+    },
+    /// Empieza a recibir `topic` a `rate` Hz como mucho (por defecto, la
+    /// del tema). Volver a suscribirse cambia la frecuencia.
+    Subscribe {
+        topic: Topic,
+        #[serde(default)]
+        rate: Option<f64>,
+    },
+    Unsubscribe {
+        topic: Topic,
+    },
+}
+
+impl Command {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::Teleop { .. } => "teleop",
+            Command::SendGoal { .. } => "send_goal",
+            Command::CancelGoal { .. } => "cancel_goal",
+            Command::GetGoal { .. } => "get_goal",
+            Command::SetMode { .. } => "set_mode",
+            Command::EmergencyStop => "emergency_stop",
+            Command::ResetEmergencyStop => "reset_emergency_stop",
+            Command::SetParameter { .. } => "set_parameter",
+            Command::Subscribe { .. } => "subscribe",
+            Command::Unsubscribe { .. } => "unsubscribe",
+        }
+    }
+
+    pub fn required_role(&self) -> Role {
+        match self {
+            // Detener el robot no se le niega a nadie conectado
+            Command::GetGoal { .. }
+            | Command::EmergencyStop
+            | Command::Subscribe { .. }
+            | Command::Unsubscribe { .. } => Role::Viewer,
+            Command::SetParameter { .. } => Role::Admin,
+            _ => Role::Operator,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RobotMode {
+    /// Se mueve con `teleop`
+    Manual,
+    /// Se mueve persiguiendo objetivos
+    Autonomous,
+}
+
+impl RobotMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RobotMode::Manual => "manual",
+            RobotMode::Autonomous => "autonomous",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Reply {
+    Ack {
+        request_id: RequestId,
+        /// Resultado del comando, si tiene (p. ej. el objetivo creado)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        result: Option<Value>,
+    },
+    Error {
+        /// Ausente si el mensaje no se pudo leer
+        request_id: Option<RequestId>,
+        code: ErrorCode,
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// El mensaje no es JSON (o CBOR o MessagePack, según el subprotocolo)
+    InvalidJson,
+    /// JSON que no es un comando conocido o le faltan campos
+    InvalidCommand,
+    UnsupportedVersion,
+    Forbidden,
+    InvalidArgument,
+    /// El robot no admite el comando ahora (parada de emergencia, modo...)
+    InvalidState,
+    NotFound,
+    Conflict,
+    /// No hay navegación o control conectados
+    Unavailable,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProtocolError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl ProtocolError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<GoalError> for ProtocolError {
+    fn from(e: GoalError) -> Self {
+        let code = match e {
+            GoalError::InvalidGoal => ErrorCode::InvalidArgument,
+            GoalError::NotFound(_) => ErrorCode::NotFound,
+            GoalError::Finished { .. } => ErrorCode::Conflict,
+            GoalError::EmergencyStop => ErrorCode::InvalidState,
+            GoalError::Unavailable(_) => ErrorCode::Unavailable,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<AuthError> for ProtocolError {
+    fn from(e: AuthError) -> Self {
+        Self::new(ErrorCode::Forbidden, e.to_string())
+    }
+}
+
+impl Reply {
+    pub fn ack(request_id: RequestId, result: Option<Value>) -> Self {
+        Reply::Ack { request_id, result }
+    }
+
+    pub fn error(request_id: Option<RequestId>, e: ProtocolError) -> Self {
+        Reply::Error {
+            request_id,
+            code: e.code,
+            message: e.message,
+        }
+    }
+}
+
+/// Lee un mensaje del cliente. Si falla, la respuesta de error lleva el
+/// `request_id` siempre que se haya podido leer.
+pub fn parse(text: &str) -> Result<ClientMessage, Reply> {
+    decode(Encoding::Json, text.as_bytes())
+}
+
+/// Como `parse`, para mensajes binarios en CBOR o MessagePack
+pub fn decode(encoding: Encoding, bytes: &[u8]) -> Result<ClientMessage, Reply> {
+    let value: Value = encoding.decode(bytes).map_err(|e| {
+        Reply::error(
+            None,
+            ProtocolError::new(ErrorCode::InvalidJson, e.to_string()),
+        )
+    })?;
+    let request_id = value
+        .get("request_id")
+        .and_then(|id| RequestId::deserialize(id).ok());
+    let message: ClientMessage = serde_json::from_value(value).map_err(|e| {
+        let e = ProtocolError::new(ErrorCode::InvalidCommand, e.to_string());
+        Reply::error(request_id.clone(), e)
+    })?;
+    if message.version != PROTOCOL_VERSION {
+        let e = ProtocolError::new(
+            ErrorCode::UnsupportedVersion,
+            format!(
+                "Versión {} no soportada, se espera la {}",
+                message.version, PROTOCOL_VERSION
+            ),
+        );
+        return Err(Reply::error(request_id, e));
+    }
+    Ok(message)
+}
+
+/// JSON Schema de los mensajes del cliente
+pub fn client_schema() -> RootSchema {
+    schema_for!(ClientMessage)
+}
+
+/// JSON Schema de las respuestas del servidor
+pub fn reply_schema() -> RootSchema {
+    schema_for!(Reply)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_code(reply: Reply) -> (Option<RequestId>, ErrorCode) {
+        match reply {
+            Reply::Error {
+                request_id, code, ..
+            } => (request_id, code),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        let message =
+            parse(r#"{"request_id": 7, "type": "teleop", "linear": 0.2, "angular": -0.1}"#)
+                .unwrap();
+        assert_eq!(message.request_id, RequestId::Number(7));
+        assert_eq!(
+            message.command,
+            Command::Teleop {
+                linear: 0.2,
+                angular: -0.1
+            }
+        );
+        let message = parse(r#"{"request_id": "a", "type": "change_mode", "mode": "manual"}"#);
+        assert_eq!(
+            message.unwrap().command,
+            Command::SetMode {
+                mode: RobotMode::Manual
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_messages_get_error_codes() {
+        assert_eq!(
+            error_code(parse("{no es json").unwrap_err()),
+            (None, ErrorCode::InvalidJson)
+        );
+        assert_eq!(
+            error_code(parse(r#"{"request_id": 3, "type": "fly"}"#).unwrap_err()),
+            (Some(RequestId::Number(3)), ErrorCode::InvalidCommand)
+        );
+        // Sin request_id no se puede responder con un ack
+        assert_eq!(
+            error_code(parse(r#"{"type": "emergency_stop"}"#).unwrap_err()),
+            (None, ErrorCode::InvalidCommand)
+        );
+    }
+
+    #[test]
+    fn test_unsupported_version_is_rejected() {
+        assert_eq!(
+            error_code(
+                parse(r#"{"version": 2, "request_id": 4, "type": "emergency_stop"}"#).unwrap_err()
+            ),
+            (Some(RequestId::Number(4)), ErrorCode::UnsupportedVersion)
+        );
+    }
+
+    #[test]
+    fn test_ack_format() {
+        let ack = serde_json::to_value(Reply::ack(RequestId::Number(7), None)).unwrap();
+        assert_eq!(ack, serde_json::json!({"type": "ack", "request_id": 7}));
+    }
+
+    #[test]
+    fn test_schemas_describe_every_command() {
+        let schema = serde_json::to_string(&client_schema()).unwrap();
+        for command in [
+            "teleop",
+            "send_goal",
+            "reset_emergency_stop",
+            "set_parameter",
+            "subscribe",
+            "diagnostics",
+        ] {
+            assert!(schema.contains(command), "{}", command);
+        }
+        assert!(serde_json::to_string(&reply_schema())
+            .unwrap()
+            .contains("invalid_state"));
+    }
+}
@@ -1,41 +1,175 @@
 use axum::{
-    extract::State,
-    http::StatusCode,
-    response::Json,
-    routing::{get, post},
+    extract::{MatchedPath, Path, Query, Request, State},
+    http::{header::CONTENT_TYPE, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Json, Response},
+    routing::{delete, get, post},
     Router,
 };
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use axum_server::tls_rustls::RustlsConfig;
+use serde::Deserialize;
+use std::time::Instant;
 
-use super::{AppState, MapData, MoveCommand, RobotStatus, SensorData};
+use super::auth::{self, AuditEntry, AuthError, Role, API_KEY_HEADER};
+use super::encoding::{Encoded, Encoding};
+use super::goals::{self, GoalError};
+use super::health::{self, HealthReport};
+use super::map::Compression;
+use super::protocol;
+use super::{GoalStatus, MoveCommand, Point, SharedState};
+use crate::metrics::{self, metrics};
+use crate::mission::MissionAction;
+use crate::navigation::docking::DockingStatus;
+use crate::power::BatteryState;
 
-type SharedState = Arc<RwLock<AppState>>;
+pub async fn start_rest_server(
+    port: u16,
+    state: SharedState,
+    tls: Option<RustlsConfig>,
+) -> anyhow::Result<()> {
+    let app = router(state);
 
-pub async fn start_rest_server(port: u16) -> anyhow::Result<()> {
-    let state = Arc::new(RwLock::new(AppState::default()));
+    let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", port)).await?;
+
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    log::info!(
+        "🌐 Servidor REST iniciado en {}://localhost:{}",
+        scheme,
+        port
+    );
+
+    super::tls::serve(listener, app, tls).await
+}
+
+pub fn router(state: SharedState) -> Router {
+    let require =
+        |role: Role| middleware::from_fn_with_state((state.clone(), role), auth::authorize);
 
-    let app = Router::new()
+    // Telemetría: cualquier usuario autenticado
+    let viewer = Router::new()
         .route("/api/v1/status", get(get_status))
-        .route("/api/v1/move", post(move_to_position))
+        .route("/api/v1/goals", get(list_goals))
+        .route("/api/v1/goals/:id", get(get_goal))
         .route("/api/v1/map", get(get_map))
         .route("/api/v1/sensors", get(get_sensors))
-        .route("/health", get(health_check))
-        .with_state(state);
+        .route("/api/v1/battery", get(get_battery))
+        .route("/api/v1/dock", get(get_docking))
+        .route("/api/v1/protocol/schema", get(get_protocol_schema))
+        .route_layer(require(Role::Viewer));
 
-    let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", port)).await?;
+    // Movimiento del robot
+    let operator = Router::new()
+        .route("/api/v1/move", post(move_to_position))
+        .route("/api/v1/goals", post(create_goal))
+        .route("/api/v1/goals/:id", delete(cancel_goal))
+        .route("/api/v1/dock", post(dock))
+        .route("/api/v1/undock", post(undock))
+        .route_layer(require(Role::Operator));
+
+    let admin = Router::new()
+        .route("/api/v1/auth/audit", get(get_audit))
+        .route_layer(require(Role::Admin));
+
+    Router::new()
+        .merge(viewer)
+        .merge(operator)
+        .merge(admin)
+        .route("/api/v1/auth/token", post(issue_token))
+        // Sondas de Kubernetes, sin autenticación
+        .route("/health", get(liveness))
+        .route("/health/live", get(liveness))
+        .route("/health/ready", get(readiness))
+        // Sin autenticación, para que Prometheus pueda leerlas
+        .route("/metrics", get(get_metrics))
+        .route_layer(middleware::from_fn(record_request))
+        .with_state(state)
+}
+
+// Cuenta y cronometra cada petición por ruta (la plantilla, no la URL, para
+// no crear una serie por objetivo)
+async fn record_request(request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_default();
+    let start = Instant::now();
 
-    log::info!("🌐 Servidor REST iniciado en http://localhost:{}", port);
+    let response = next.run(request).await;
 
-    axum::serve(listener, app).await?;
+    let metrics = metrics();
+    metrics
+        .api_request_duration
+        .with_label_values(&[&method, &route])
+        .observe(start.elapsed().as_secs_f64());
+    metrics
+        .api_requests
+        .with_label_values(&[&method, &route, response.status().as_str()])
+        .inc();
+    response
+}
+
+async fn get_metrics() -> impl IntoResponse {
+    ([(CONTENT_TYPE, metrics::CONTENT_TYPE)], metrics().encode())
+}
+
+// Cambia una clave de API por un JWT con su mismo rol
+async fn issue_token(State(state): State<SharedState>, request: Request) -> Response {
+    let auth = state.read().await.auth.clone();
+    let key = request
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok());
+    let now = auth::unix_now();
+    let result = key
+        .ok_or(AuthError::MissingCredentials)
+        .and_then(|key| auth.authenticate_key(key))
+        .and_then(|principal| {
+            auth.issue_token(&principal, now)
+                .map(|token| (principal, token))
+        });
+    match result {
+        Ok((principal, token)) => {
+            log::info!(
+                "🔑 Token emitido para {} ({:?})",
+                principal.subject,
+                principal.role
+            );
+            Json(serde_json::json!({
+                "token": token,
+                "token_type": "Bearer",
+                "role": principal.role,
+                "expires_in": auth.token_ttl(),
+            }))
+            .into_response()
+        }
+        Err(e) => {
+            let remote = auth::remote_addr(&request);
+            let audit = state.read().await.audit.clone();
+            audit.record(remote, None, "POST /api/v1/auth/token", &e);
+            e.into_response()
+        }
+    }
+}
+
+// JSON Schema del protocolo de comandos del WebSocket
+async fn get_protocol_schema() -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "version": protocol::PROTOCOL_VERSION,
+        "client": protocol::client_schema(),
+        "reply": protocol::reply_schema(),
+    }))
+}
 
-    Ok(())
+async fn get_audit(State(state): State<SharedState>) -> Json<Vec<AuditEntry>> {
+    let audit = state.read().await.audit.clone();
+    Json(audit.entries())
 }
 
 // Handler para el estado del robot
-async fn get_status(State(state): State<SharedState>) -> Json<RobotStatus> {
-    let state = state.read().await;
-    Json(state.robot_status.clone())
+async fn get_status(State(state): State<SharedState>, encoding: Encoding) -> Response {
+    Encoded(encoding, state.read().await.status()).into_response()
 }
 
 // Handler para mover el robot
@@ -50,46 +184,524 @@ async fn move_to_position(
         command.speed
     );
 
-    // Actualizar posición en el estado
-    {
-        let mut state = state.write().await;
-        state.robot_status.position.x = command.x;
-        state.robot_status.position.y = command.y;
-        state.robot_status.position.timestamp = chrono::Utc::now().to_rfc3339();
+    // El objetivo va a la navegación; la posición la actualiza la localización
+    let target = Point::new(command.x, command.y);
+    match goals::send_goal(&state, target, command.speed).await {
+        Ok(goal) => {
+            let response = serde_json::json!({
+                "status": "accepted",
+                "message": "Objetivo enviado a la navegación",
+                "goal_id": goal.id,
+                "target": goal.target,
+                "speed": goal.speed
+            });
+            (StatusCode::ACCEPTED, Json(response))
+        }
+        Err(e) => goal_error(e),
     }
+}
 
-    let response = serde_json::json!({
-        "status": "success",
-        "message": "Movimiento iniciado",
-        "target": {
-            "x": command.x,
-            "y": command.y
-        },
-        "speed": command.speed
-    });
+// Handlers del ciclo de vida de los objetivos
+async fn create_goal(
+    State(state): State<SharedState>,
+    Json(command): Json<MoveCommand>,
+) -> Result<(StatusCode, Json<GoalStatus>), (StatusCode, Json<serde_json::Value>)> {
+    let target = Point::new(command.x, command.y);
+    let goal = goals::send_goal(&state, target, command.speed)
+        .await
+        .map_err(goal_error)?;
+    Ok((StatusCode::ACCEPTED, Json(goal)))
+}
 
-    (StatusCode::OK, Json(response))
+async fn list_goals(State(state): State<SharedState>) -> Json<Vec<GoalStatus>> {
+    Json(state.read().await.goals.list())
 }
 
-// Handler para obtener el mapa
-async fn get_map(State(state): State<SharedState>) -> Json<MapData> {
+async fn get_goal(
+    State(state): State<SharedState>,
+    Path(id): Path<u64>,
+) -> Result<Json<GoalStatus>, (StatusCode, Json<serde_json::Value>)> {
     let state = state.read().await;
-    Json(state.map_data.clone())
+    state
+        .goals
+        .get(id)
+        .cloned()
+        .map(Json)
+        .ok_or_else(|| goal_error(GoalError::NotFound(id)))
+}
+
+async fn cancel_goal(
+    State(state): State<SharedState>,
+    Path(id): Path<u64>,
+) -> Result<Json<GoalStatus>, (StatusCode, Json<serde_json::Value>)> {
+    goals::cancel_goal(&state, id)
+        .await
+        .map(Json)
+        .map_err(goal_error)
+}
+
+fn goal_error(e: GoalError) -> (StatusCode, Json<serde_json::Value>) {
+    let status = match e {
+        GoalError::InvalidGoal => StatusCode::BAD_REQUEST,
+        GoalError::NotFound(_) => StatusCode::NOT_FOUND,
+        GoalError::Finished { .. } | GoalError::EmergencyStop => StatusCode::CONFLICT,
+        GoalError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    error(status, &e.to_string())
+}
+
+#[derive(Debug, Deserialize)]
+struct MapQuery {
+    #[serde(default)]
+    compression: Compression,
+}
+
+// Handler para obtener el mapa
+async fn get_map(
+    State(state): State<SharedState>,
+    encoding: Encoding,
+    Query(query): Query<MapQuery>,
+) -> Response {
+    let map = state.read().await.map_data.compressed(query.compression);
+    Encoded(encoding, map).into_response()
 }
 
 // Handler para datos de sensores
-async fn get_sensors(State(state): State<SharedState>) -> Json<SensorData> {
+async fn get_sensors(State(state): State<SharedState>, encoding: Encoding) -> Response {
     let state = state.read().await;
-    Json(state.last_sensor_data.clone())
+    Encoded(encoding, &state.last_sensor_data).into_response()
 }
 
-// Health check endpoint
-async fn health_check() -> (StatusCode, Json<serde_json::Value>) {
-    let response = serde_json::json!({
-        "status": "healthy",
-        "service": "mechbot-3x",
-        "timestamp": chrono::Utc::now().to_rfc3339()
-    });
+// Handler para el estado de la batería
+async fn get_battery(
+    State(state): State<SharedState>,
+    encoding: Encoding,
+) -> Result<Encoded<BatteryState>, (StatusCode, Json<serde_json::Value>)> {
+    let state = state.read().await;
+    state
+        .battery
+        .clone()
+        .map(|battery| Encoded(encoding, battery))
+        .ok_or_else(|| unavailable("Sin lecturas del monitor de batería"))
+}
+
+// Handler para el estado del acoplamiento
+async fn get_docking(
+    State(state): State<SharedState>,
+    encoding: Encoding,
+) -> Result<Encoded<DockingStatus>, (StatusCode, Json<serde_json::Value>)> {
+    let state = state.read().await;
+    state
+        .docking
+        .clone()
+        .map(|docking| Encoded(encoding, docking))
+        .ok_or_else(|| unavailable("Comportamiento de acoplamiento no disponible"))
+}
+
+// Handler para volver a la base de carga
+async fn dock(State(state): State<SharedState>) -> (StatusCode, Json<serde_json::Value>) {
+    send_mission_action(&state, MissionAction::Dock, "Volviendo a la base de carga").await
+}
+
+// Handler para separarse de la base de carga
+async fn undock(State(state): State<SharedState>) -> (StatusCode, Json<serde_json::Value>) {
+    send_mission_action(&state, MissionAction::Undock, "Desacoplando de la base").await
+}
+
+async fn send_mission_action(
+    state: &SharedState,
+    action: MissionAction,
+    message: &str,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let sender = state.read().await.mission_actions.clone();
+    let Some(sender) = sender else {
+        return unavailable("No hay ejecutor de misiones conectado");
+    };
+    log::info!("📥 Acción de misión desde la API: {:?}", action);
+    match sender.send(action).await {
+        Ok(()) => (
+            StatusCode::ACCEPTED,
+            Json(serde_json::json!({
+                "status": "accepted",
+                "message": message
+            })),
+        ),
+        Err(_) => unavailable("El ejecutor de misiones se ha detenido"),
+    }
+}
 
-    (StatusCode::OK, Json(response))
+fn unavailable(message: &str) -> (StatusCode, Json<serde_json::Value>) {
+    error(StatusCode::SERVICE_UNAVAILABLE, message)
+}
+
+fn error(status: StatusCode, message: &str) -> (StatusCode, Json<serde_json::Value>) {
+    (
+        status,
+        Json(serde_json::json!({
+            "status": "error",
+            "message": message
+        })),
+    )
+}
+
+// 503 solo si el lazo de control se ha colgado
+async fn liveness(State(state): State<SharedState>) -> (StatusCode, Json<HealthReport>) {
+    let report = health::liveness(&*state.read().await, Instant::now());
+    health_response(report)
+}
+
+// 200 también en estado degradado; 503 si algún componente ha fallado
+async fn readiness(State(state): State<SharedState>) -> (StatusCode, Json<HealthReport>) {
+    let report = health::readiness(&*state.read().await, Instant::now());
+    health_response(report)
+}
+
+fn health_response(report: HealthReport) -> (StatusCode, Json<HealthReport>) {
+    let status = if report.is_failed() {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+    (status, Json(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::auth::{ApiKey, AuthConfig};
+    use crate::api::{ApiServer, GoalRequest, GoalState};
+    use crate::config::ApiConfig;
+    use std::net::SocketAddr;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn command(speed: f64) -> Json<MoveCommand> {
+        Json(MoveCommand {
+            x: 1.0,
+            y: 2.0,
+            speed,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_move_dispatches_goal_to_navigation() {
+        let server = ApiServer::new(0);
+        let state = server.state();
+        let (status, _) = move_to_position(State(state.clone()), command(0.5)).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+        let mut requests = server.navigation_goals().await;
+        let (status, Json(body)) = move_to_position(State(state.clone()), command(0.5)).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(body["goal_id"], 1);
+        match requests.recv().await.unwrap() {
+            GoalRequest::Start(goal) => {
+                assert_eq!((goal.id, goal.target), (1, Point::new(1.0, 2.0)))
+            }
+            other => panic!("{:?}", other),
+        }
+        // La posición publicada no cambia hasta que la localización lo diga
+        assert_eq!(state.read().await.robot_status.position.pose.x, 0.0);
+        let (status, _) = move_to_position(State(state.clone()), command(0.0)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // Ciclo de vida: el nuevo objetivo sustituye al anterior
+        let (status, Json(second)) = create_goal(State(state.clone()), command(0.5))
+            .await
+            .unwrap();
+        assert_eq!(
+            (status, second.state),
+            (StatusCode::ACCEPTED, GoalState::Pending)
+        );
+        let Json(first) = get_goal(State(state.clone()), Path(1)).await.unwrap();
+        assert_eq!(first.state, GoalState::Canceled);
+
+        server.goal_reporter().activate(second.id).await;
+        let Json(canceled) = cancel_goal(State(state.clone()), Path(second.id))
+            .await
+            .unwrap();
+        assert_eq!(canceled.state, GoalState::Canceled);
+        requests.recv().await.unwrap();
+        assert_eq!(
+            requests.recv().await.unwrap(),
+            GoalRequest::Cancel { id: second.id }
+        );
+        let (status, _) = cancel_goal(State(state.clone()), Path(second.id))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::CONFLICT);
+        let (status, _) = get_goal(State(state.clone()), Path(42)).await.unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(list_goals(State(state)).await.0.len(), 2);
+    }
+
+    /// Petición HTTP/1.1 mínima; devuelve el código y el cuerpo
+    async fn request(addr: SocketAddr, line: &str, headers: &str) -> (u16, String) {
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let body = if line.starts_with("POST /api/v1/move") || line == "POST /api/v1/goals" {
+            r#"{"x": 1.0, "y": 2.0, "speed": 0.5}"#
+        } else {
+            ""
+        };
+        let request = format!(
+            "{} HTTP/1.1\r\nHost: robot\r\nConnection: close\r\n{}\
+             Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            line,
+            headers,
+            body.len(),
+            body
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        let code = response[9..12].parse().unwrap();
+        let body = response.split("\r\n\r\n").nth(1).unwrap_or("").to_string();
+        (code, body)
+    }
+
+    #[tokio::test]
+    async fn test_map_negotiates_encoding_and_compression() {
+        let server = ApiServer::new(0);
+        let state = server.state();
+        let query = |compression| Query(MapQuery { compression });
+
+        let response = get_map(
+            State(state.clone()),
+            Encoding::Json,
+            query(Compression::None),
+        )
+        .await;
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["compression"], "none");
+        assert!(json["data"].is_string());
+
+        let response = get_map(
+            State(state.clone()),
+            Encoding::Cbor,
+            query(Compression::Lz4),
+        )
+        .await;
+        assert_eq!(
+            response.headers()[axum::http::header::CONTENT_TYPE],
+            "application/cbor"
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let map: crate::api::MapData = Encoding::Cbor.decode(&body).unwrap();
+        assert_eq!(map.compression, Compression::Lz4);
+        assert!(body.len() < 1000);
+        let cells = Compression::Lz4.decompress(&map.data).unwrap();
+        assert_eq!(cells, state.read().await.map_data.data);
+    }
+
+    const VIEWER: &str = "X-API-Key: clave-panel\r\n";
+    const OPERATOR: &str = "X-API-Key: clave-mando\r\n";
+    const ADMIN: &str = "X-API-Key: clave-jefe\r\n";
+
+    /// API con credenciales obligatorias y una clave por rol
+    async fn serve_with_roles() -> (SocketAddr, SharedState) {
+        let key = |name: &str, role| ApiKey {
+            name: name.to_string(),
+            key: format!("clave-{}", name),
+            role,
+        };
+        let config = ApiConfig {
+            rest_port: Some(0),
+            websocket_port: None,
+            enable_cors: false,
+            api_key_required: true,
+            auth: AuthConfig {
+                api_keys: vec![
+                    key("panel", Role::Viewer),
+                    key("mando", Role::Operator),
+                    key("jefe", Role::Admin),
+                ],
+                jwt_secret: Some("secreto".to_string()),
+                ..AuthConfig::default()
+            },
+            tls: None,
+            map_compression: crate::api::Compression::Zlib,
+            health: Default::default(),
+        };
+        let state = ApiServer::from_config(&config).unwrap().state();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(crate::api::tls::serve(
+            listener,
+            router(state.clone()),
+            None,
+        ));
+        (addr, state)
+    }
+
+    async fn bearer(addr: SocketAddr, credentials: &str) -> String {
+        let (code, body) = request(addr, "POST /api/v1/auth/token", credentials).await;
+        assert_eq!(code, 200);
+        let token: serde_json::Value = serde_json::from_str(&body).unwrap();
+        format!(
+            "Authorization: Bearer {}\r\n",
+            token["token"].as_str().unwrap()
+        )
+    }
+
+    #[tokio::test]
+    async fn test_health_needs_no_credentials() {
+        let (addr, _) = serve_with_roles().await;
+        assert_eq!(request(addr, "GET /health", "").await.0, 200);
+        // Sin sensores, localización ni control no está listo
+        let (code, body) = request(addr, "GET /health/ready", "").await;
+        assert_eq!(code, 503);
+        let report: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(report["status"], "failed");
+        assert_eq!(report["components"]["emergency_stop"]["status"], "healthy");
+    }
+
+    #[tokio::test]
+    async fn test_missing_credentials_are_rejected() {
+        let (addr, _) = serve_with_roles().await;
+        assert_eq!(request(addr, "GET /api/v1/status", "").await.0, 401);
+        assert_eq!(request(addr, "POST /api/v1/move", "").await.0, 401);
+        assert_eq!(request(addr, "POST /api/v1/auth/token", "").await.0, 401);
+    }
+
+    #[tokio::test]
+    async fn test_viewer_cannot_move_robot() {
+        let (addr, _) = serve_with_roles().await;
+        assert_eq!(request(addr, "GET /api/v1/status", VIEWER).await.0, 200);
+        assert_eq!(request(addr, "POST /api/v1/move", VIEWER).await.0, 403);
+        assert_eq!(request(addr, "DELETE /api/v1/goals/1", VIEWER).await.0, 403);
+    }
+
+    #[tokio::test]
+    async fn test_operator_cannot_read_audit() {
+        let (addr, _) = serve_with_roles().await;
+        // Autorizado; sin navegación conectada
+        assert_eq!(request(addr, "POST /api/v1/move", OPERATOR).await.0, 503);
+        assert_eq!(
+            request(addr, "GET /api/v1/auth/audit", OPERATOR).await.0,
+            403
+        );
+        assert_eq!(request(addr, "GET /api/v1/auth/audit", ADMIN).await.0, 200);
+    }
+
+    #[tokio::test]
+    async fn test_token_keeps_role_of_its_issuer() {
+        let (addr, _) = serve_with_roles().await;
+        let operator = bearer(addr, OPERATOR).await;
+        assert_eq!(request(addr, "GET /api/v1/goals", &operator).await.0, 200);
+        assert_eq!(request(addr, "POST /api/v1/move", &operator).await.0, 503);
+
+        let viewer = bearer(addr, VIEWER).await;
+        assert_eq!(request(addr, "POST /api/v1/move", &viewer).await.0, 403);
+    }
+
+    #[tokio::test]
+    async fn test_denials_are_audited() {
+        let (addr, state) = serve_with_roles().await;
+        request(addr, "GET /api/v1/status", "").await;
+        request(addr, "POST /api/v1/move", VIEWER).await;
+        request(addr, "GET /api/v1/status", VIEWER).await;
+
+        let audit = state.read().await.audit.entries();
+        assert_eq!(audit.len(), 2);
+        assert_eq!(audit[0].subject, None);
+        assert_eq!(audit[1].subject.as_deref(), Some("panel"));
+        assert_eq!(audit[1].action, "POST /api/v1/move");
+        assert!(audit[1].remote.as_deref().unwrap().starts_with("127.0.0.1"));
+    }
+
+    #[tokio::test]
+    async fn test_denial_does_not_wait_for_state_readers() {
+        let (addr, state) = serve_with_roles().await;
+        // Con un lector del estado activo, auditar no puede pedir escritura
+        let _reader = state.read().await;
+        let reply = tokio::time::timeout(
+            std::time::Duration::from_secs(2),
+            request(addr, "POST /api/v1/move", VIEWER),
+        )
+        .await
+        .expect("el rechazo no debe esperar al estado");
+        assert_eq!(reply.0, 403);
+        assert_eq!(state.try_read().unwrap().audit.entries().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_count_requests_by_route() {
+        let (addr, _) = serve_with_roles().await;
+        request(addr, "POST /api/v1/move", VIEWER).await;
+        request(addr, "GET /api/v1/status", VIEWER).await;
+
+        // Sin autenticación, para Prometheus
+        let (code, metrics) = request(addr, "GET /metrics", "").await;
+        assert_eq!(code, 200);
+        assert!(metrics.contains(
+            r#"mechbot_api_requests_total{method="POST",route="/api/v1/move",status="403"}"#
+        ));
+        assert!(metrics.contains(
+            r#"mechbot_api_request_duration_seconds_count{method="GET",route="/api/v1/status"}"#
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_posted_goal_drives_navigation() {
+        use crate::control::{ControlInput, ControlSystem};
+        use crate::navigation::runtime::NavigationRuntime;
+        use crate::navigation::NavigationController;
+
+        let config = ApiConfig {
+            rest_port: Some(0),
+            websocket_port: None,
+            enable_cors: false,
+            api_key_required: false,
+            auth: AuthConfig::default(),
+            tls: None,
+            map_compression: crate::api::Compression::Zlib,
+            health: Default::default(),
+        };
+        let server = ApiServer::from_config(&config).unwrap();
+        let state = server.state();
+        let (velocity, mut base) = tokio::sync::mpsc::channel(16);
+        let mut runtime = NavigationRuntime::new(
+            NavigationController::default(),
+            ControlSystem::new(Default::default()),
+            velocity,
+        );
+        runtime.connect_api(&server).await;
+        let navigation = runtime.spawn();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(crate::api::tls::serve(
+            listener,
+            router(state.clone()),
+            None,
+        ));
+
+        let (code, body) = request(addr, "POST /api/v1/goals", "").await;
+        assert_eq!(code, 202, "{}", body);
+        let goal: GoalStatus = serde_json::from_str(&body).unwrap();
+
+        // La navegación planifica y manda velocidad hacia (1, 2)
+        let input = base.recv().await.unwrap();
+        assert!(input.linear_x > 0.0 && input.linear_x <= goal.speed);
+        let (_, body) = request(addr, &format!("GET /api/v1/goals/{}", goal.id), "").await;
+        let active: GoalStatus = serde_json::from_str(&body).unwrap();
+        assert_eq!(active.state, GoalState::Active);
+        assert!(active.feedback.unwrap().distance_remaining > 0.0);
+
+        let line = format!("DELETE /api/v1/goals/{}", goal.id);
+        assert_eq!(request(addr, &line, "").await.0, 200);
+        // Tras cancelar llega la orden de parar y no vuelve a moverse
+        while let Some(input) = base.recv().await {
+            if input == ControlInput::zero() {
+                break;
+            }
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+        assert!(base.try_recv().is_err());
+        navigation.abort();
+    }
 }
@@ -0,0 +1,418 @@
+//! Telemetría por temas del WebSocket. Cada tema guarda solo su último
+//! mensaje, serializado una vez por codificación para todos los clientes;
+//! cada conexión lo envía al ritmo que pidió y, si el cliente va lento, se
+//! salta los intermedios en lugar de acumularlos.
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+use super::encoding::Encoding;
+use super::protocol::{ErrorCode, ProtocolError};
+
+/// Frecuencia máxima que puede pedir un cliente (Hz)
+pub const MAX_RATE: f64 = 50.0;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Topic {
+    /// Pose estimada por la localización
+    Pose,
+    /// Puntos del lidar en el marco del robot
+    Scan,
+    Imu,
+    /// Ruta pendiente del objetivo en curso
+    Path,
+    /// Mapa de ocupación completo
+    Map,
+    /// Objetos detectados por la visión
+    Detections,
+    /// Estado, batería, parada de emergencia y objetivo en curso
+    Diagnostics,
+}
+
+impl Topic {
+    pub const ALL: [Topic; 7] = [
+        Topic::Pose,
+        Topic::Scan,
+        Topic::Imu,
+        Topic::Path,
+        Topic::Map,
+        Topic::Detections,
+        Topic::Diagnostics,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Topic::Pose => "pose",
+            Topic::Scan => "scan",
+            Topic::Imu => "imu",
+            Topic::Path => "path",
+            Topic::Map => "map",
+            Topic::Detections => "detections",
+            Topic::Diagnostics => "diagnostics",
+        }
+    }
+
+    /// Frecuencia si el cliente no pide otra (Hz)
+    pub fn default_rate(self) -> f64 {
+        match self {
+            Topic::Pose => 10.0,
+            Topic::Scan => 5.0,
+            Topic::Imu => 20.0,
+            Topic::Path => 2.0,
+            Topic::Map => 0.5,
+            Topic::Detections => 5.0,
+            Topic::Diagnostics => 1.0,
+        }
+    }
+}
+
+/// Último mensaje de un tema. Se serializa la primera vez que lo pide una
+/// conexión y una sola vez por codificación para todas.
+#[derive(Debug, Clone)]
+pub struct TopicMessage {
+    pub topic: Topic,
+    /// Crece con cada publicación del tema; los saltos son mensajes que el
+    /// cliente no llegó a recibir
+    pub sequence: u64,
+    full: Arc<Payload>,
+    /// Versión que solo lleva los cambios respecto a `sequence - 1`
+    delta: Option<Arc<Payload>>,
+}
+
+impl TopicMessage {
+    /// El mensaje completo en `encoding`
+    pub fn encoded(&self, encoding: Encoding) -> Option<Arc<[u8]>> {
+        self.full.encoded(encoding)
+    }
+
+    /// El cambio si quien lo recibe tiene el mensaje anterior; si no, el
+    /// mensaje completo
+    fn encoded_after(&self, last: Option<u64>, encoding: Encoding) -> Option<Arc<[u8]>> {
+        match &self.delta {
+            Some(delta) if last == Some(self.sequence - 1) => delta.encoded(encoding),
+            _ => self.full.encoded(encoding),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Envelope<T> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    topic: Topic,
+    sequence: u64,
+    /// En los cambios, el mensaje sobre el que se aplican
+    #[serde(skip_serializing_if = "Option::is_none")]
+    base_sequence: Option<u64>,
+    timestamp: String,
+    data: T,
+}
+
+impl<T> Envelope<T> {
+    fn new(
+        topic: Topic,
+        sequence: u64,
+        base_sequence: Option<u64>,
+        timestamp: &str,
+        data: T,
+    ) -> Self {
+        Self {
+            kind: "topic",
+            topic,
+            sequence,
+            base_sequence,
+            timestamp: timestamp.to_string(),
+            data,
+        }
+    }
+}
+
+trait Encode: Send + Sync {
+    fn encode(&self, encoding: Encoding) -> anyhow::Result<Vec<u8>>;
+}
+
+impl<T: Serialize + Send + Sync> Encode for Envelope<T> {
+    fn encode(&self, encoding: Encoding) -> anyhow::Result<Vec<u8>> {
+        encoding.encode(self)
+    }
+}
+
+struct Payload {
+    envelope: Box<dyn Encode>,
+    encoded: [OnceLock<Option<Arc<[u8]>>>; Encoding::ALL.len()],
+}
+
+impl Payload {
+    fn new<T: Serialize + Send + Sync + 'static>(envelope: Envelope<T>) -> Arc<Self> {
+        Arc::new(Self {
+            envelope: Box::new(envelope),
+            encoded: Default::default(),
+        })
+    }
+
+    fn encoded(&self, encoding: Encoding) -> Option<Arc<[u8]>> {
+        self.encoded[encoding as usize]
+            .get_or_init(|| match self.envelope.encode(encoding) {
+                Ok(bytes) => Some(bytes.into()),
+                Err(e) => {
+                    log::error!("❌ No se pudo serializar la telemetría: {}", e);
+                    None
+                }
+            })
+            .clone()
+    }
+}
+
+impl std::fmt::Debug for Payload {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Payload").finish_non_exhaustive()
+    }
+}
+
+type Latest = watch::Sender<Option<TopicMessage>>;
+
+/// Último mensaje de cada tema, compartido por todas las conexiones
+#[derive(Debug, Clone)]
+pub struct TelemetryHub {
+    topics: Arc<[Latest; Topic::ALL.len()]>,
+}
+
+impl Default for TelemetryHub {
+    fn default() -> Self {
+        Self {
+            topics: Arc::new(std::array::from_fn(|_| watch::channel(None).0)),
+        }
+    }
+}
+
+impl TelemetryHub {
+    /// Sustituye el último mensaje de `topic`
+    pub fn publish<T: Serialize + Send + Sync + 'static>(&self, topic: Topic, data: T) {
+        self.publish_with_delta(topic, data, None::<()>);
+    }
+
+    /// Como `publish`, con una versión más pequeña para quien ya tenga el
+    /// mensaje anterior (p. ej. solo las teselas del mapa que cambian)
+    pub fn publish_with_delta<T, D>(&self, topic: Topic, data: T, delta: Option<D>)
+    where
+        T: Serialize + Send + Sync + 'static,
+        D: Serialize + Send + Sync + 'static,
+    {
+        let timestamp = chrono::Utc::now().to_rfc3339();
+        self.topics[topic as usize].send_modify(|latest| {
+            let sequence = latest.as_ref().map_or(0, |message| message.sequence) + 1;
+            let envelope = Envelope::new(topic, sequence, None, &timestamp, data);
+            let delta = delta
+                .map(|delta| Envelope::new(topic, sequence, Some(sequence - 1), &timestamp, delta));
+            *latest = Some(TopicMessage {
+                topic,
+                sequence,
+                full: Payload::new(envelope),
+                delta: delta.map(Payload::new),
+            });
+        });
+    }
+
+    pub fn latest(&self, topic: Topic) -> Option<TopicMessage> {
+        self.topics[topic as usize].borrow().clone()
+    }
+
+    fn subscribe(&self, topic: Topic) -> watch::Receiver<Option<TopicMessage>> {
+        self.topics[topic as usize].subscribe()
+    }
+}
+
+/// Temas que recibe una conexión, a qué ritmo y en qué codificación
+#[derive(Debug, Default)]
+pub struct Subscriptions {
+    encoding: Encoding,
+    topics: BTreeMap<Topic, Subscription>,
+}
+
+#[derive(Debug)]
+struct Subscription {
+    latest: watch::Receiver<Option<TopicMessage>>,
+    period: Duration,
+    last_sent: Option<Instant>,
+    /// Último mensaje enviado, para saber si le vale un cambio
+    last_sequence: Option<u64>,
+}
+
+impl Subscriptions {
+    pub fn new(encoding: Encoding) -> Self {
+        Self {
+            encoding,
+            topics: BTreeMap::new(),
+        }
+    }
+
+    /// Suscribe (o cambia la frecuencia de) `topic` y devuelve la frecuencia
+    /// aplicada. El último mensaje del tema, si lo hay, sale enseguida.
+    pub fn subscribe(
+        &mut self,
+        hub: &TelemetryHub,
+        topic: Topic,
+        rate: Option<f64>,
+    ) -> Result<f64, ProtocolError> {
+        let rate = rate.unwrap_or(topic.default_rate());
+        if !(rate.is_finite() && rate > 0.0) {
+            return Err(ProtocolError::new(
+                ErrorCode::InvalidArgument,
+                "La frecuencia debe ser positiva",
+            ));
+        }
+        let rate = rate.min(MAX_RATE);
+        let mut latest = hub.subscribe(topic);
+        latest.mark_changed();
+        self.topics.insert(
+            topic,
+            Subscription {
+                latest,
+                period: Duration::from_secs_f64(1.0 / rate),
+                last_sent: None,
+                last_sequence: None,
+            },
+        );
+        Ok(rate)
+    }
+
+    /// Devuelve si la conexión estaba suscrita
+    pub fn unsubscribe(&mut self, topic: Topic) -> bool {
+        self.topics.remove(&topic).is_some()
+    }
+
+    pub fn topics(&self) -> impl Iterator<Item = Topic> + '_ {
+        self.topics.keys().copied()
+    }
+
+    /// Mensajes ya codificados de los temas con datos nuevos cuyo periodo se
+    /// ha cumplido en `now`. Solo sale el último de cada tema: los
+    /// anteriores ya no interesan.
+    pub fn due(&mut self, now: Instant) -> Vec<Arc<[u8]>> {
+        let mut due = Vec::new();
+        for subscription in self.topics.values_mut() {
+            let ready = subscription
+                .last_sent
+                .is_none_or(|sent| now.duration_since(sent) >= subscription.period);
+            if !ready || !subscription.latest.has_changed().unwrap_or(false) {
+                continue;
+            }
+            let Some(message) = subscription.latest.borrow_and_update().clone() else {
+                continue;
+            };
+            if let Some(bytes) = message.encoded_after(subscription.last_sequence, self.encoding) {
+                subscription.last_sent = Some(now);
+                subscription.last_sequence = Some(message.sequence);
+                due.push(bytes);
+            }
+        }
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn decode(bytes: &[u8]) -> Value {
+        serde_json::from_slice(bytes).unwrap()
+    }
+
+    #[test]
+    fn test_subscriptions_follow_rate_and_skip_stale() {
+        let hub = TelemetryHub::default();
+        let mut subscriptions = Subscriptions::default();
+        hub.publish(Topic::Map, "mapa");
+
+        let start = Instant::now();
+        assert_eq!(
+            subscriptions.subscribe(&hub, Topic::Pose, Some(4.0)),
+            Ok(4.0)
+        );
+        assert_eq!(
+            subscriptions.subscribe(&hub, Topic::Map, Some(1000.0)),
+            Ok(MAX_RATE)
+        );
+        assert_eq!(
+            subscriptions
+                .subscribe(&hub, Topic::Scan, Some(0.0))
+                .unwrap_err()
+                .code,
+            ErrorCode::InvalidArgument
+        );
+        assert_eq!(
+            subscriptions.topics().collect::<Vec<_>>(),
+            [Topic::Pose, Topic::Map]
+        );
+
+        // Al suscribirse llega el último mapa aunque sea anterior
+        let due = subscriptions.due(start);
+        assert_eq!(due.len(), 1);
+        let map = decode(&due[0]);
+        assert_eq!(
+            (
+                map["type"].as_str(),
+                map["topic"].as_str(),
+                map["data"].as_str()
+            ),
+            (Some("topic"), Some("map"), Some("mapa"))
+        );
+
+        // Un cliente lento solo recibe la última pose
+        for x in 0..3 {
+            hub.publish(Topic::Pose, x);
+        }
+        let due = subscriptions.due(start);
+        assert_eq!(due.len(), 1);
+        assert_eq!(decode(&due[0])["sequence"], 3);
+        assert_eq!(decode(&due[0])["data"], 2);
+
+        // Hasta que no pasan 250 ms no sale la siguiente
+        hub.publish(Topic::Pose, 3);
+        assert!(subscriptions
+            .due(start + Duration::from_millis(200))
+            .is_empty());
+        let due = subscriptions.due(start + Duration::from_millis(250));
+        assert_eq!(decode(&due[0])["sequence"], 4);
+        // Sin datos nuevos no se repite
+        assert!(subscriptions.due(start + Duration::from_secs(1)).is_empty());
+
+        assert!(subscriptions.unsubscribe(Topic::Pose));
+        assert!(!subscriptions.unsubscribe(Topic::Pose));
+        hub.publish(Topic::Pose, 4);
+        assert!(subscriptions.due(start + Duration::from_secs(2)).is_empty());
+        assert_eq!(hub.latest(Topic::Pose).unwrap().sequence, 5);
+    }
+
+    #[test]
+    fn test_deltas_only_after_previous_message() {
+        let hub = TelemetryHub::default();
+        let start = Instant::now();
+        let mut fast = Subscriptions::new(Encoding::Cbor);
+        let mut slow = Subscriptions::new(Encoding::Cbor);
+        fast.subscribe(&hub, Topic::Map, Some(MAX_RATE)).unwrap();
+        slow.subscribe(&hub, Topic::Map, Some(MAX_RATE)).unwrap();
+        let decode = |bytes: &[u8]| Encoding::Cbor.decode::<Value>(bytes).unwrap();
+
+        hub.publish_with_delta(Topic::Map, "entero 1", Some("cambio 1"));
+        assert_eq!(decode(&fast.due(start)[0])["data"], "entero 1");
+        hub.publish_with_delta(Topic::Map, "entero 2", Some("cambio 2"));
+        let delta = decode(&fast.due(start + Duration::from_secs(1))[0]);
+        assert_eq!(
+            (&delta["data"], &delta["base_sequence"]),
+            (&Value::from("cambio 2"), &Value::from(1))
+        );
+
+        // Quien no tiene el mensaje anterior recibe el mapa entero
+        let full = decode(&slow.due(start)[0]);
+        assert_eq!(full["data"], "entero 2");
+        assert!(full.get("base_sequence").is_none());
+    }
+}
@@ -0,0 +1,277 @@
+//! TLS de los servidores REST y WebSocket con rustls: certificado y clave en
+//! PEM, recarga con SIGHUP sin cortar conexiones, certificados de cliente
+//! opcionales (mTLS) y autofirmado para desarrollo.
+use anyhow::Context;
+use axum::Router;
+use axum_server::tls_rustls::RustlsConfig;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TlsConfig {
+    /// Cadena de certificados en PEM, el del servidor primero
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+    /// CA de las consolas de operador; si está, se piden certificados de cliente
+    #[serde(default)]
+    pub client_ca_path: Option<PathBuf>,
+    /// Con `client_ca_path`, admite también clientes sin certificado (que
+    /// siguen necesitando clave de API o JWT)
+    #[serde(default)]
+    pub client_auth_optional: bool,
+    /// Genera un certificado autofirmado si no existen los ficheros. Solo
+    /// para desarrollo: los clientes tendrán que confiar en él a mano
+    #[serde(default)]
+    pub self_signed: bool,
+    /// Nombres del certificado autofirmado
+    #[serde(default = "default_hosts")]
+    pub hosts: Vec<String>,
+}
+
+fn default_hosts() -> Vec<String> {
+    vec!["localhost".to_string(), "127.0.0.1".to_string()]
+}
+
+/// Configuración de rustls lista para servir; la comparten los dos
+/// servidores y se recarga en caliente
+pub async fn load(config: &TlsConfig) -> anyhow::Result<RustlsConfig> {
+    if config.self_signed && !(config.cert_path.exists() && config.key_path.exists()) {
+        generate_self_signed(&config.cert_path, &config.key_path, &config.hosts)?;
+    }
+    Ok(RustlsConfig::from_config(Arc::new(server_config(config)?)))
+}
+
+pub fn server_config(config: &TlsConfig) -> anyhow::Result<ServerConfig> {
+    let certs = read_certs(&config.cert_path)?;
+    let key = read_key(&config.key_path)?;
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()?;
+
+    let builder = match &config.client_ca_path {
+        Some(path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in read_certs(path)? {
+                roots.add(cert)?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider);
+            let verifier = if config.client_auth_optional {
+                verifier.allow_unauthenticated().build()?
+            } else {
+                verifier.build()?
+            };
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let mut server = builder.with_single_cert(certs, key)?;
+    // Sin h2: el WebSocket necesita la actualización de HTTP/1.1
+    server.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(server)
+}
+
+fn read_certs(path: &Path) -> anyhow::Result<Vec<CertificateDer<'static>>> {
+    let pem = std::fs::read(path).with_context(|| format!("leyendo {}", path.display()))?;
+    let certs = rustls_pemfile::certs(&mut pem.as_slice()).collect::<Result<Vec<_>, _>>()?;
+    anyhow::ensure!(
+        !certs.is_empty(),
+        "{} no contiene certificados",
+        path.display()
+    );
+    Ok(certs)
+}
+
+fn read_key(path: &Path) -> anyhow::Result<PrivateKeyDer<'static>> {
+    let pem = std::fs::read(path).with_context(|| format!("leyendo {}", path.display()))?;
+    rustls_pemfile::private_key(&mut pem.as_slice())?
+        .with_context(|| format!("{} no contiene una clave privada", path.display()))
+}
+
+/// Certificado autofirmado para `hosts`, con la clave en un fichero solo
+/// legible por el usuario
+pub fn generate_self_signed(
+    cert_path: &Path,
+    key_path: &Path,
+    hosts: &[String],
+) -> anyhow::Result<()> {
+    let generated = rcgen::generate_simple_self_signed(hosts.to_vec())?;
+    for path in [cert_path, key_path] {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+    std::fs::write(cert_path, generated.cert.pem())?;
+    std::fs::write(key_path, generated.key_pair.serialize_pem())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(key_path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    log::warn!(
+        "🔐 Certificado autofirmado generado en {} para {:?} (solo desarrollo)",
+        cert_path.display(),
+        hosts
+    );
+    Ok(())
+}
+
+/// Vuelve a leer certificado, clave y CA de clientes con cada SIGHUP. Las
+/// conexiones abiertas siguen con el certificado anterior; si los ficheros
+/// nuevos no son válidos se mantiene el que había.
+#[cfg(unix)]
+pub fn reload_on_sighup(
+    rustls: RustlsConfig,
+    config: TlsConfig,
+) -> anyhow::Result<tokio::task::JoinHandle<()>> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = signal(SignalKind::hangup())?;
+    Ok(tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            match server_config(&config) {
+                Ok(server) => {
+                    rustls.reload_from_config(Arc::new(server));
+                    log::info!("🔄 Certificado TLS recargado");
+                }
+                Err(e) => log::error!("❌ No se pudo recargar el certificado TLS: {:#}", e),
+            }
+        }
+    }))
+}
+
+/// Sirve `app` en `listener`, con TLS si hay configuración
+pub(super) async fn serve(
+    listener: tokio::net::TcpListener,
+    app: Router,
+    tls: Option<RustlsConfig>,
+) -> anyhow::Result<()> {
+    // La dirección del cliente queda en la auditoría
+    let service = app.into_make_service_with_connect_info::<SocketAddr>();
+    match tls {
+        Some(tls) => {
+            axum_server::from_tcp_rustls(listener.into_std()?, tls)
+                .serve(service)
+                .await?
+        }
+        None => axum::serve(listener, service).await?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn self_signed(dir: &Path) -> TlsConfig {
+        TlsConfig {
+            cert_path: dir.join("tls/cert.pem"),
+            key_path: dir.join("tls/key.pem"),
+            client_ca_path: None,
+            client_auth_optional: false,
+            self_signed: true,
+            hosts: default_hosts(),
+        }
+    }
+
+    async fn spawn_server(tls: RustlsConfig) -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().route("/health", axum::routing::get(|| async { "ok" }));
+        tokio::spawn(serve(listener, app, Some(tls)));
+        addr
+    }
+
+    /// Cliente que confía solo en `cert` y no presenta certificado propio
+    async fn get_health(addr: SocketAddr, cert: &[u8]) -> std::io::Result<String> {
+        let mut roots = RootCertStore::empty();
+        for cert in rustls_pemfile::certs(&mut &cert[..]) {
+            roots.add(cert.unwrap()).unwrap();
+        }
+        let client = rustls::ClientConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+        let connector = tokio_rustls::TlsConnector::from(Arc::new(client));
+        let stream = tokio::net::TcpStream::connect(addr).await?;
+        let name = rustls::pki_types::ServerName::try_from("localhost").unwrap();
+        let mut stream = connector.connect(name, stream).await?;
+        stream
+            .write_all(b"GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await?;
+        let mut response = String::new();
+        // Con TLS 1.3 el servidor rechaza al cliente después del saludo
+        stream.read_to_string(&mut response).await?;
+        Ok(response)
+    }
+
+    #[tokio::test]
+    async fn test_self_signed_certificate_serves_https() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = self_signed(dir.path());
+        let addr = spawn_server(load(&config).await.unwrap()).await;
+        let cert = std::fs::read(&config.cert_path).unwrap();
+
+        let response = get_health(addr, &cert).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    }
+
+    #[tokio::test]
+    async fn test_reload_replaces_certificate() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = self_signed(dir.path());
+        let rustls = load(&config).await.unwrap();
+        let addr = spawn_server(rustls.clone()).await;
+        let first = std::fs::read(&config.cert_path).unwrap();
+
+        generate_self_signed(&config.cert_path, &config.key_path, &config.hosts).unwrap();
+        rustls.reload_from_config(Arc::new(server_config(&config).unwrap()));
+        let second = std::fs::read(&config.cert_path).unwrap();
+        assert!(get_health(addr, &first).await.is_err());
+        let response = get_health(addr, &second).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"));
+    }
+
+    #[tokio::test]
+    async fn test_mtls_rejects_client_without_certificate() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = self_signed(dir.path());
+        load(&config).await.unwrap();
+        let cert = std::fs::read(&config.cert_path).unwrap();
+        let mtls = TlsConfig {
+            client_ca_path: Some(config.cert_path.clone()),
+            ..config
+        };
+        let tls = RustlsConfig::from_config(Arc::new(server_config(&mtls).unwrap()));
+        let addr = spawn_server(tls).await;
+
+        assert!(get_health(addr, &cert).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_optional_mtls_admits_client_without_certificate() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = self_signed(dir.path());
+        load(&config).await.unwrap();
+        let cert = std::fs::read(&config.cert_path).unwrap();
+        let mtls = TlsConfig {
+            client_ca_path: Some(config.cert_path.clone()),
+            client_auth_optional: true,
+            ..config
+        };
+        let tls = RustlsConfig::from_config(Arc::new(server_config(&mtls).unwrap()));
+        let addr = spawn_server(tls).await;
+
+        let response = get_health(addr, &cert).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"));
+    }
+}
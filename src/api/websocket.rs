@@ -1,71 +1,157 @@
 use axum::{
     extract::{
         ws::{Message, WebSocket},
-        State, WebSocketUpgrade,
+        ConnectInfo, State, WebSocketUpgrade,
     },
+    middleware,
     response::Response,
+    Extension,
 };
+use axum_server::tls_rustls::RustlsConfig;
+use futures::stream::SplitSink;
 use futures::{SinkExt, StreamExt};
-use serde_json::json;
-use std::sync::Arc;
-use tokio::sync::RwLock;
-use tokio::time::{interval, Duration};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::{interval, timeout, Duration, MissedTickBehavior};
 
-use super::AppState;
+use super::auth::{self, AuthError, Principal, Role};
+use super::encoding::Encoding;
+use super::goals::{self, GoalError};
+use super::protocol::{self, ClientMessage, Command, ErrorCode, ProtocolError, Reply, RobotMode};
+use super::telemetry::Subscriptions;
+use super::{Point, RobotCommand, SharedState};
 
-type SharedState = Arc<RwLock<AppState>>;
-
-pub async fn start_websocket_server(port: u16) -> anyhow::Result<()> {
-    let state = Arc::new(RwLock::new(AppState::default()));
+/// Cada cuánto se mira si toca enviar algún tema; limita la frecuencia máxima
+const PUBLISH_TICK: Duration = Duration::from_millis(20);
+const SEND_TIMEOUT: Duration = Duration::from_secs(5);
+/// Silencio tras el último `teleop` con el que se manda parar el robot
+const TELEOP_TIMEOUT: Duration = Duration::from_millis(500);
 
+pub async fn start_websocket_server(
+    port: u16,
+    state: SharedState,
+    tls: Option<RustlsConfig>,
+) -> anyhow::Result<()> {
     let app = axum::Router::new()
         .route("/telemetry", axum::routing::get(websocket_handler))
+        // Para conectarse basta con leer; cada comando comprueba su rol
+        .route_layer(middleware::from_fn_with_state(
+            (state.clone(), Role::Viewer),
+            auth::authorize,
+        ))
         .with_state(state);
 
     let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", port)).await?;
 
-    log::info!("📡 Servidor WebSocket iniciado en ws://localhost:{}", port);
+    let scheme = if tls.is_some() { "wss" } else { "ws" };
+    log::info!(
+        "📡 Servidor WebSocket iniciado en {}://localhost:{}",
+        scheme,
+        port
+    );
 
-    axum::serve(listener, app).await?;
+    super::tls::serve(listener, app, tls).await
+}
 
-    Ok(())
+async fn websocket_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<SharedState>,
+    Extension(principal): Extension<Principal>,
+    remote: Option<ConnectInfo<SocketAddr>>,
+) -> Response {
+    let client = Client {
+        principal,
+        remote: remote.map(|ConnectInfo(addr)| addr.to_string()),
+    };
+    // JSON si el cliente no pide otra codificación
+    let ws = ws.protocols(Encoding::ALL.map(Encoding::subprotocol));
+    ws.on_upgrade(|socket| {
+        let encoding = socket
+            .protocol()
+            .and_then(|protocol| protocol.to_str().ok())
+            .and_then(Encoding::from_subprotocol)
+            .unwrap_or_default();
+        handle_websocket_connection(socket, state, client, encoding)
+    })
 }
 
-async fn websocket_handler(ws: WebSocketUpgrade, State(state): State<SharedState>) -> Response {
-    ws.on_upgrade(|socket| handle_websocket_connection(socket, state))
+/// Usuario de la conexión, para los permisos de cada comando
+struct Client {
+    principal: Principal,
+    remote: Option<String>,
 }
 
-async fn handle_websocket_connection(socket: WebSocket, state: SharedState) {
+async fn handle_websocket_connection(
+    socket: WebSocket,
+    state: SharedState,
+    client: Client,
+    encoding: Encoding,
+) {
     let (mut sender, mut receiver) = socket.split();
+    let clients = &crate::metrics::metrics().websocket_clients;
+    clients.inc();
 
-    log::info!("🔌 Nueva conexión WebSocket establecida");
+    log::info!(
+        "🔌 Nueva conexión WebSocket establecida ({})",
+        encoding.subprotocol()
+    );
 
-    // Canal para enviar telemetría
-    let mut telemetry_interval = interval(Duration::from_millis(100)); // 10 Hz
+    // Solo se envían los temas suscritos, cada uno a su frecuencia
+    let mut subscriptions = Subscriptions::new(encoding);
+    let mut publish_tick = interval(PUBLISH_TICK);
+    publish_tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
 
-    // Manejar mensajes entrantes y enviar telemetría
-    let mut sequence_number = 0;
+    // Cambios de estado de los objetivos, de esta u otras conexiones
+    let mut goal_events = state.read().await.goals.subscribe();
+    let mut deadman = Deadman::default();
 
-    loop {
+    'connection: loop {
         tokio::select! {
-            // Enviar telemetría periódicamente
-            _ = telemetry_interval.tick() => {
-                sequence_number += 1;
-
-                let telemetry_data = generate_telemetry_data(&state, sequence_number).await;
-
-                if let Ok(json_data) = serde_json::to_string(&telemetry_data) {
-                    if sender.send(Message::Text(json_data)).await.is_err() {
-                        break;
+            now = publish_tick.tick() => {
+                deadman.check(&state, now.into_std()).await;
+                for message in subscriptions.due(now.into_std()) {
+                    if !send_message(&mut sender, frame(encoding, &message)).await {
+                        break 'connection;
                     }
                 }
             }
 
+            event = goal_events.recv() => {
+                let goal = match event {
+                    Ok(goal) => goal,
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                };
+                let event = json!({ "type": "goal_status", "goal": goal });
+                let event = encoding.encode(&event).unwrap_or_default();
+                if !send_message(&mut sender, frame(encoding, &event)).await {
+                    break;
+                }
+            }
+
             // Manejar mensajes entrantes
             message = receiver.next() => {
                 match message {
-                    Some(Ok(Message::Text(text))) => {
-                        handle_websocket_message(&text, &state).await;
+                    Some(Ok(message @ (Message::Text(_) | Message::Binary(_)))) => {
+                        let message = match message {
+                            Message::Text(text) => protocol::parse(&text),
+                            message => protocol::decode(encoding, &message.into_data()),
+                        };
+                        let teleop = matches!(
+                            message,
+                            Ok(ClientMessage { command: Command::Teleop { .. }, .. })
+                        );
+                        let reply =
+                            handle_websocket_message(message, &state, &client, &mut subscriptions)
+                                .await;
+                        if teleop && matches!(reply, Reply::Ack { .. }) {
+                            deadman.arm(std::time::Instant::now());
+                        }
+                        let reply = encoding.encode(&reply).unwrap_or_default();
+                        if !send_message(&mut sender, frame(encoding, &reply)).await {
+                            break;
+                        }
                     }
                     Some(Ok(Message::Close(_))) => {
                         break;
@@ -82,80 +168,455 @@ async fn handle_websocket_connection(socket: WebSocket, state: SharedState) {
         }
     }
 
+    // Una conexión que se cae a mitad de teleoperación no deja el robot andando
+    deadman.release(&state).await;
+    clients.dec();
     log::info!("🔌 Conexión WebSocket cerrada");
 }
 
-async fn generate_telemetry_data(state: &SharedState, sequence: u64) -> serde_json::Value {
-    let state = state.read().await;
-
-    // Simular datos de sensores en tiempo real
-    let simulated_position = json!({
-        "x": state.robot_status.position.x + (sequence as f64 * 0.01).sin() * 0.1,
-        "y": state.robot_status.position.y + (sequence as f64 * 0.02).cos() * 0.1,
-        "theta": (sequence as f64 * 0.05).sin() * 0.5
-    });
-
-    let simulated_sensors = json!({
-        "lidar": {
-            "points": (0..360).map(|i| {
-                json!({
-                    "angle": i,
-                    "distance": 2.0 + (i as f64 * 0.01745).sin().abs() * 1.5
-                })
-            }).collect::<Vec<_>>(),
-            "min_distance": 0.15,
-            "max_distance": 4.0
-        },
-        "imu": {
-            "acceleration": {
-                "x": (sequence as f64 * 0.1).sin() * 0.1,
-                "y": (sequence as f64 * 0.15).cos() * 0.1,
-                "z": 9.81
-            },
-            "gyroscope": {
-                "x": (sequence as f64 * 0.05).sin() * 0.01,
-                "y": (sequence as f64 * 0.07).cos() * 0.01,
-                "z": (sequence as f64 * 0.03).sin() * 0.02
+/// Hombre muerto de la teleoperación: si la conexión deja de mandar `teleop`
+/// durante `TELEOP_TIMEOUT`, manda velocidad cero una vez
+#[derive(Debug, Default)]
+struct Deadman {
+    deadline: Option<std::time::Instant>,
+}
+
+impl Deadman {
+    fn arm(&mut self, now: std::time::Instant) {
+        self.deadline = Some(now + TELEOP_TIMEOUT);
+    }
+
+    async fn check(&mut self, state: &SharedState, now: std::time::Instant) {
+        if self.deadline.is_some_and(|deadline| now >= deadline) {
+            log::warn!(
+                "🛑 Sin teleoperación en {:?}, se detiene el robot",
+                TELEOP_TIMEOUT
+            );
+            self.release(state).await;
+        }
+    }
+
+    async fn release(&mut self, state: &SharedState) {
+        if self.deadline.take().is_some() {
+            let stop = RobotCommand::Velocity {
+                linear: 0.0,
+                angular: 0.0,
+            };
+            if let Err(e) = send_command(state, stop).await {
+                log::error!("❌ No se pudo detener la teleoperación: {}", e.message);
             }
-        },
-        "camera": {
-            "objects_detected": (sequence % 5) as u32,
-            "resolution": [640, 480]
-        }
-    });
-
-    json!({
-        "sequence": sequence,
-        "timestamp": chrono::Utc::now().to_rfc3339(),
-        "position": simulated_position,
-        "sensors": simulated_sensors,
-        "battery_level": state.robot_status.battery_level - (sequence as f64 * 0.001),
-        "state": state.robot_status.status.state,
-        "uptime": sequence
-    })
+        }
+    }
+}
+
+/// Texto en JSON; binario en CBOR y MessagePack
+fn frame(encoding: Encoding, bytes: &[u8]) -> Message {
+    if encoding.is_binary() {
+        Message::Binary(bytes.to_vec())
+    } else {
+        Message::Text(String::from_utf8_lossy(bytes).into_owned())
+    }
 }
 
-async fn handle_websocket_message(message: &str, state: &SharedState) {
-    log::info!("📨 Mensaje WebSocket recibido: {}", message);
+/// Envía un mensaje al cliente. Mientras espera, los temas solo guardan su
+/// último mensaje, así que un cliente lento pierde los intermedios; si no
+/// acepta nada en `SEND_TIMEOUT` se da la conexión por perdida.
+async fn send_message(sender: &mut SplitSink<WebSocket, Message>, message: Message) -> bool {
+    match timeout(SEND_TIMEOUT, sender.send(message)).await {
+        Ok(result) => result.is_ok(),
+        Err(_) => {
+            log::warn!(
+                "🐢 Cliente WebSocket sin leer en {:?}, se cierra",
+                SEND_TIMEOUT
+            );
+            false
+        }
+    }
+}
 
-    // Aquí puedes procesar comandos específicos del WebSocket
-    if let Ok(command) = serde_json::from_str::<serde_json::Value>(message) {
-        if let Some(cmd_type) = command.get("type").and_then(|t| t.as_str()) {
-            match cmd_type {
-                "emergency_stop" => {
-                    log::warn!("🛑 Comando de parada de emergencia recibido");
-                    // Implementar lógica de parada de emergencia
-                }
-                "change_mode" => {
-                    if let Some(mode) = command.get("mode").and_then(|m| m.as_str()) {
-                        log::info!("🔄 Cambiando modo a: {}", mode);
-                        // Actualizar estado del robot
-                    }
+/// Atiende un mensaje del cliente. Todos tienen respuesta: `ack` con el
+/// resultado o `error`; los cambios de estado de los objetivos llegan después
+/// como `goal_status`.
+async fn handle_websocket_message(
+    message: Result<ClientMessage, Reply>,
+    state: &SharedState,
+    client: &Client,
+    subscriptions: &mut Subscriptions,
+) -> Reply {
+    let message = match message {
+        Ok(message) => message,
+        Err(reply) => return reply,
+    };
+    log::debug!("📨 Mensaje WebSocket recibido: {:?}", message);
+    let request_id = message.request_id;
+    let command = message.command;
+
+    let required = command.required_role();
+    if !client.principal.role.allows(required) {
+        let e = AuthError::Forbidden {
+            role: client.principal.role,
+            required,
+        };
+        let audit = state.read().await.audit.clone();
+        audit.record(
+            client.remote.clone(),
+            Some(&client.principal.subject),
+            &format!("ws {}", command.name()),
+            &e,
+        );
+        return Reply::error(Some(request_id), e.into());
+    }
+
+    match execute(command, state, subscriptions).await {
+        Ok(result) => Reply::ack(request_id, result),
+        Err(e) => Reply::error(Some(request_id), e),
+    }
+}
+
+async fn execute(
+    command: Command,
+    state: &SharedState,
+    subscriptions: &mut Subscriptions,
+) -> Result<Option<Value>, ProtocolError> {
+    match command {
+        Command::Teleop { linear, angular } => {
+            if !(linear.is_finite() && angular.is_finite()) {
+                return Err(ProtocolError::new(
+                    ErrorCode::InvalidArgument,
+                    "Velocidades no válidas",
+                ));
+            }
+            {
+                let state = state.read().await;
+                if state.emergency_stop {
+                    return Err(ProtocolError::from(GoalError::EmergencyStop));
                 }
-                _ => {
-                    log::debug!("❓ Comando WebSocket desconocido: {}", cmd_type);
+                if state.robot_status.status.mode != RobotMode::Manual.as_str() {
+                    return Err(ProtocolError::new(
+                        ErrorCode::InvalidState,
+                        "La teleoperación solo se admite en modo manual",
+                    ));
                 }
             }
+            send_command(state, RobotCommand::Velocity { linear, angular }).await?;
+            Ok(None)
+        }
+        Command::SendGoal { x, y, speed } => {
+            let goal = goals::send_goal(state, Point::new(x, y), speed).await?;
+            Ok(Some(json!(goal)))
+        }
+        Command::CancelGoal { goal_id } => {
+            let goal = goals::cancel_goal(state, goal_id).await?;
+            Ok(Some(json!(goal)))
+        }
+        Command::GetGoal { goal_id } => {
+            let state = state.read().await;
+            let goal = state
+                .goals
+                .get(goal_id)
+                .ok_or(GoalError::NotFound(goal_id))?;
+            Ok(Some(json!(goal)))
+        }
+        Command::SetMode { mode } => {
+            send_command(state, RobotCommand::SetMode(mode)).await?;
+            log::info!("🔄 Modo {}", mode.as_str());
+            let current = {
+                let mut state = state.write().await;
+                state.robot_status.status.mode = mode.as_str().to_string();
+                state.goals.current().map(|goal| goal.id)
+            };
+            // En manual no se persiguen objetivos
+            if let (RobotMode::Manual, Some(id)) = (mode, current) {
+                cancel_current_goal(state, id).await;
+            }
+            Ok(None)
+        }
+        Command::EmergencyStop => {
+            log::warn!("🛑 Parada de emergencia desde el WebSocket");
+            // La parada va por su propio canal: no espera detrás de la
+            // teleoperación y la API queda bloqueada aunque no haya control
+            let (current, forwarded) = {
+                let mut state = state.write().await;
+                state.emergency_stop = true;
+                state.robot_status.status.state = "emergency_stop".to_string();
+                state.emergency_latch.send_replace(true);
+                (
+                    state.goals.current().map(|goal| goal.id),
+                    state.emergency_latch.receiver_count() > 0,
+                )
+            };
+            if let Some(id) = current {
+                cancel_current_goal(state, id).await;
+            }
+            Ok(Some(json!({ "forwarded": forwarded })))
         }
+        Command::ResetEmergencyStop => {
+            let mut state = state.write().await;
+            if !state.emergency_stop {
+                return Err(ProtocolError::new(
+                    ErrorCode::InvalidState,
+                    "No hay parada de emergencia activa",
+                ));
+            }
+            if state.emergency_latch.receiver_count() == 0 {
+                return Err(ProtocolError::new(
+                    ErrorCode::Unavailable,
+                    "No hay control del robot conectado",
+                ));
+            }
+            state.emergency_latch.send_replace(false);
+            state.emergency_stop = false;
+            state.robot_status.status.state = "ready".to_string();
+            log::info!("✅ Parada de emergencia rearmada");
+            Ok(None)
+        }
+        Command::SetParameter { name, value } => {
+            if name.trim().is_empty() {
+                return Err(ProtocolError::new(
+                    ErrorCode::InvalidArgument,
+                    "Falta el nombre del parámetro",
+                ));
+            }
+            log::info!("⚙️ Parámetro {} = {}", name, value);
+            send_command(state, RobotCommand::SetParameter { name, value }).await?;
+            Ok(None)
+        }
+        Command::Subscribe { topic, rate } => {
+            let hub = state.read().await.telemetry.clone();
+            let rate = subscriptions.subscribe(&hub, topic, rate)?;
+            log::debug!("📡 Suscripción a {} a {} Hz", topic.as_str(), rate);
+            Ok(Some(json!({ "topic": topic, "rate": rate })))
+        }
+        Command::Unsubscribe { topic } => {
+            let subscribed = subscriptions.unsubscribe(topic);
+            Ok(Some(json!({ "subscribed": subscribed })))
+        }
+    }
+}
+
+/// El objetivo puede haber terminado mientras llegaba la orden: no es un error
+async fn cancel_current_goal(state: &SharedState, id: u64) {
+    if let Err(e) = goals::cancel_goal(state, id).await {
+        log::warn!("⚠️ No se pudo cancelar el objetivo {}: {}", id, e);
+    }
+}
+
+async fn send_command(state: &SharedState, command: RobotCommand) -> Result<(), ProtocolError> {
+    let sender = state.read().await.robot_commands.clone();
+    let Some(sender) = sender else {
+        return Err(ProtocolError::new(
+            ErrorCode::Unavailable,
+            "No hay control del robot conectado",
+        ));
+    };
+    sender.send(command).await.map_err(|_| {
+        ProtocolError::new(
+            ErrorCode::Unavailable,
+            "El control del robot se ha detenido",
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::ApiServer;
+
+    fn client(role: Role) -> Client {
+        Client {
+            principal: Principal {
+                subject: "consola".to_string(),
+                role,
+            },
+            remote: None,
+        }
+    }
+
+    async fn send(state: &SharedState, client: &Client, message: Value) -> Value {
+        let mut subscriptions = Subscriptions::default();
+        let message = protocol::parse(&message.to_string());
+        let reply = handle_websocket_message(message, state, client, &mut subscriptions).await;
+        serde_json::to_value(reply).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_commands_are_acknowledged_or_rejected() {
+        let server = ApiServer::new(0);
+        let state = server.state();
+        let operator = client(Role::Operator);
+        let teleop = json!({"request_id": 1, "type": "teleop", "linear": 0.3, "angular": 0.0});
+
+        let reply = send(&state, &operator, teleop.clone()).await;
+        assert_eq!(
+            (reply["type"].as_str(), reply["code"].as_str()),
+            (Some("error"), Some("invalid_state"))
+        );
+
+        let mut commands = server.robot_commands().await;
+        let manual = json!({"request_id": 2, "type": "set_mode", "mode": "manual"});
+        assert_eq!(
+            send(&state, &operator, manual).await,
+            json!({"type": "ack", "request_id": 2})
+        );
+        assert_eq!(
+            commands.recv().await,
+            Some(RobotCommand::SetMode(RobotMode::Manual))
+        );
+        assert_eq!(send(&state, &operator, teleop.clone()).await["type"], "ack");
+        assert_eq!(
+            commands.recv().await,
+            Some(RobotCommand::Velocity {
+                linear: 0.3,
+                angular: 0.0
+            })
+        );
+
+        // Cualquiera detiene el robot; rearmar exige operador
+        let mut stops = server.emergency_stops().await;
+        let viewer = client(Role::Viewer);
+        let stop = json!({"request_id": "s", "type": "emergency_stop"});
+        let reply = send(&state, &viewer, stop).await;
+        assert_eq!(reply["result"]["forwarded"], true);
+        assert!(*stops.borrow_and_update());
+        assert!(commands.try_recv().is_err());
+        assert_eq!(
+            send(&state, &operator, teleop.clone()).await["code"],
+            "invalid_state"
+        );
+        let goal = json!({"request_id": 3, "type": "send_goal", "x": 1.0, "y": 0.0, "speed": 0.5});
+        assert_eq!(send(&state, &operator, goal).await["code"], "invalid_state");
+
+        let reset = json!({"request_id": 4, "type": "reset_emergency_stop"});
+        assert_eq!(
+            send(&state, &viewer, reset.clone()).await["code"],
+            "forbidden"
+        );
+        assert_eq!(state.read().await.audit.entries().len(), 1);
+        assert_eq!(send(&state, &operator, reset).await["type"], "ack");
+        assert!(!*stops.borrow_and_update());
+        assert_eq!(send(&state, &operator, teleop).await["type"], "ack");
+
+        let parameter =
+            json!({"request_id": 5, "type": "set_parameter", "name": "max_speed", "value": 0.8});
+        assert_eq!(
+            send(&state, &operator, parameter.clone()).await["code"],
+            "forbidden"
+        );
+        assert_eq!(
+            send(&state, &client(Role::Admin), parameter).await["type"],
+            "ack"
+        );
+
+        let reply = send(
+            &state,
+            &operator,
+            json!({"request_id": 6, "type": "get_goal", "goal_id": 9}),
+        )
+        .await;
+        assert_eq!(
+            (reply["request_id"].as_u64(), reply["code"].as_str()),
+            (Some(6), Some("not_found"))
+        );
+
+        // Los temas se suscriben por conexión
+        let mut subscriptions = Subscriptions::default();
+        let subscribe = json!({"request_id": 7, "type": "subscribe", "topic": "scan"});
+        let reply = handle_websocket_message(
+            protocol::parse(&subscribe.to_string()),
+            &state,
+            &viewer,
+            &mut subscriptions,
+        )
+        .await;
+        assert_eq!(
+            serde_json::to_value(reply).unwrap()["result"],
+            json!({"topic": "scan", "rate": 5.0})
+        );
+        assert_eq!(
+            subscriptions.topics().collect::<Vec<_>>(),
+            [crate::api::Topic::Scan]
+        );
+        let unknown = json!({"request_id": 8, "type": "subscribe", "topic": "weather"});
+        assert_eq!(
+            send(&state, &viewer, unknown).await["code"],
+            "invalid_command"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_emergency_stop_does_not_wait_behind_teleop() {
+        let server = ApiServer::new(0);
+        let state = server.state();
+        let _navigation = server.navigation_goals().await;
+        let goal = goals::send_goal(&state, Point::new(1.0, 0.0), 0.5)
+            .await
+            .unwrap();
+        server.goal_reporter().activate(goal.id).await;
+
+        // Canal de órdenes lleno de teleoperación que nadie consume
+        let mut commands = server.robot_commands().await;
+        let sender = state.read().await.robot_commands.clone().unwrap();
+        while sender
+            .try_send(RobotCommand::Velocity {
+                linear: 0.2,
+                angular: 0.0,
+            })
+            .is_ok()
+        {}
+        let mut stops = server.emergency_stops().await;
+
+        let mut subscriptions = Subscriptions::default();
+        let reply = tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            execute(Command::EmergencyStop, &state, &mut subscriptions),
+        )
+        .await
+        .expect("la parada no debe esperar al canal de órdenes")
+        .unwrap()
+        .unwrap();
+        assert_eq!(reply["forwarded"], true);
+        assert!(*stops.borrow_and_update());
+        {
+            let state = state.read().await;
+            assert!(state.emergency_stop);
+            assert_eq!(
+                state.goals.get(goal.id).unwrap().state,
+                goals::GoalState::Canceled
+            );
+        }
+
+        // La teleoperación encolada sigue ahí; el control la descarta
+        assert!(matches!(
+            commands.try_recv(),
+            Ok(RobotCommand::Velocity { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_teleop_deadman_stops_robot_once() {
+        let server = ApiServer::new(0);
+        let state = server.state();
+        let mut commands = server.robot_commands().await;
+        let mut deadman = Deadman::default();
+        let start = std::time::Instant::now();
+
+        // Sin teleoperación previa no se manda nada
+        deadman.check(&state, start + TELEOP_TIMEOUT).await;
+        assert!(commands.try_recv().is_err());
+
+        deadman.arm(start);
+        deadman.check(&state, start + TELEOP_TIMEOUT / 2).await;
+        assert!(commands.try_recv().is_err());
+
+        deadman.check(&state, start + TELEOP_TIMEOUT).await;
+        assert_eq!(
+            commands.try_recv().ok(),
+            Some(RobotCommand::Velocity {
+                linear: 0.0,
+                angular: 0.0
+            })
+        );
+        deadman.check(&state, start + TELEOP_TIMEOUT * 2).await;
+        assert!(commands.try_recv().is_err());
     }
 }
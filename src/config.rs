@@ -1,3 +1,11 @@
+use crate::power::PowerConfig;
+use crate::api::auth::AuthConfig;
+use crate::api::health::HealthCheckConfig;
+use crate::api::tls::TlsConfig;
+use crate::api::map::Compression;
+use crate::recording::RecordingConfig;
+use crate::sensors::drivers::{CameraConfig, DriverConfig, IMUConfig, LidarConfig, SensorSpec};
+use crate::transforms::TransformsConfig;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -8,6 +16,12 @@ pub struct Config {
     pub navigation: NavigationConfig,
     pub api: ApiConfig,
     pub logging: LoggingConfig,
+    #[serde(default)]
+    pub power: PowerConfig,
+    #[serde(default)]
+    pub transforms: TransformsConfig,
+    #[serde(default)]
+    pub recording: RecordingConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,12 +33,51 @@ pub struct RobotConfig {
     pub max_acceleration: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SensorsConfig {
     pub lidar_port: Option<String>,
     pub lidar_baudrate: Option<u32>,
     pub camera_index: Option<u32>,
     pub imu_i2c_address: Option<u8>,
+    /// Lista explícita de sensores; si está vacía se usan los campos anteriores
+    #[serde(default)]
+    pub drivers: Vec<SensorSpec>,
+}
+
+impl SensorsConfig {
+    /// Sensores a instanciar según la configuración
+    pub fn specs(&self) -> Vec<SensorSpec> {
+        if !self.drivers.is_empty() {
+            return self.drivers.clone();
+        }
+
+        let mut specs = Vec::new();
+        if let Some(port) = &self.lidar_port {
+            let mut lidar = LidarConfig {
+                port: port.clone(),
+                ..Default::default()
+            };
+            if let Some(baudrate) = self.lidar_baudrate {
+                lidar.baudrate = baudrate;
+            }
+            specs.push(SensorSpec::new("lidar", DriverConfig::Lidar(lidar)));
+        }
+        if let Some(address) = self.imu_i2c_address {
+            let imu = IMUConfig {
+                i2c_address: address,
+                ..Default::default()
+            };
+            specs.push(SensorSpec::new("imu", DriverConfig::Imu(imu)));
+        }
+        if let Some(index) = self.camera_index {
+            let camera = CameraConfig {
+                device_path: format!("/dev/video{}", index),
+                ..Default::default()
+            };
+            specs.push(SensorSpec::new("camera", DriverConfig::Camera(camera)));
+        }
+        specs
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +94,17 @@ pub struct ApiConfig {
     pub websocket_port: Option<u16>,
     pub enable_cors: bool,
     pub api_key_required: bool,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    /// Sin esta sección los servidores van en claro
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    /// Compresión de las celdas del mapa en el WebSocket (none, zlib, lz4)
+    #[serde(default)]
+    pub map_compression: Compression,
+    /// Umbrales de `/health/ready`
+    #[serde(default)]
+    pub health: HealthCheckConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -66,6 +130,7 @@ impl Default for Config {
                 lidar_baudrate: Some(115200),
                 camera_index: Some(0),
                 imu_i2c_address: Some(0x68),
+                drivers: Vec::new(),
             },
             navigation: NavigationConfig {
                 max_speed: 2.0,
@@ -78,6 +143,10 @@ impl Default for Config {
                 websocket_port: Some(8081),
                 enable_cors: true,
                 api_key_required: true,
+                auth: AuthConfig::default(),
+                tls: None,
+                map_compression: Compression::None,
+                health: HealthCheckConfig::default(),
             },
             logging: LoggingConfig {
                 level: "info".to_string(),
@@ -85,6 +154,9 @@ impl Default for Config {
                 max_file_size: "10MB".to_string(),
                 rotate: true,
             },
+            power: PowerConfig::default(),
+            transforms: TransformsConfig::default(),
+            recording: RecordingConfig::default(),
         }
     }
 }
@@ -1,3 +1,4 @@
+use crate::geometry::Pose2D;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,16 +36,27 @@ impl RobotState {
         }
     }
 
+    /// Pose del robot sin velocidades
+    pub fn pose(&self) -> Pose2D {
+        Pose2D::new(self.x, self.y, self.theta)
+    }
+
     pub fn distance_to(&self, other: &RobotState) -> f64 {
-        ((self.x - other.x).powi(2) + (self.y - other.y).powi(2)).sqrt()
+        self.pose().distance_to(&other.pose())
     }
 
     pub fn heading_to(&self, other: &RobotState) -> f64 {
-        (other.y - self.y).atan2(other.x - self.x)
+        self.pose().heading_to(&other.pose())
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl From<Pose2D> for RobotState {
+    fn from(pose: Pose2D) -> Self {
+        Self::new(pose.x, pose.y, pose.theta)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ControlInput {
     pub linear_x: f64,  // Linear velocity in x [m/s]
     pub linear_y: f64,  // Linear velocity in y [m/s] (for holonomic robots)
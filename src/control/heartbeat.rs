@@ -0,0 +1,65 @@
+//! Latido del lazo de control, para saber desde fuera (API, salud) si sigue
+//! iterando sin tomar ningún cerrojo
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+pub struct Heartbeat {
+    origin: Instant,
+    /// Nanosegundos desde `origin` del último latido más uno; 0 = ninguno
+    last: Arc<AtomicU64>,
+}
+
+impl Default for Heartbeat {
+    fn default() -> Self {
+        Self {
+            origin: Instant::now(),
+            last: Arc::new(AtomicU64::new(0)),
+        }
+    }
+}
+
+impl Heartbeat {
+    pub fn beat(&self) {
+        self.beat_at(Instant::now());
+    }
+
+    pub fn beat_at(&self, now: Instant) {
+        let nanos = now.saturating_duration_since(self.origin).as_nanos() as u64;
+        self.last.store(nanos + 1, Ordering::Relaxed);
+    }
+
+    /// Tiempo desde el último latido; `None` si el lazo no ha latido nunca
+    pub fn age(&self, now: Instant) -> Option<Duration> {
+        match self.last.load(Ordering::Relaxed) {
+            0 => None,
+            nanos => {
+                let last = self.origin + Duration::from_nanos(nanos - 1);
+                Some(now.saturating_duration_since(last))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heartbeat_age_is_shared_between_clones() {
+        let heartbeat = Heartbeat::default();
+        let observer = heartbeat.clone();
+        let start = Instant::now();
+        assert_eq!(observer.age(start), None);
+
+        heartbeat.beat_at(start);
+        let age = observer.age(start + Duration::from_millis(300)).unwrap();
+        assert!((age.as_secs_f64() - 0.3).abs() < 1e-6);
+        // Un instante anterior al latido no da edades negativas
+        assert_eq!(
+            observer.age(start - Duration::from_millis(1)),
+            Some(Duration::ZERO)
+        );
+    }
+}
@@ -1,11 +1,16 @@
 pub mod base;
+pub mod heartbeat;
 pub mod mpc;
 pub mod pid;
 
 pub use base::{ControlInput, Controller, RobotState};
+pub use heartbeat::Heartbeat;
 pub use mpc::MPCController;
 pub use pid::PIDController;
 
+use crate::geometry::angle_difference;
+use crate::metrics::LoopTimer;
+use crate::sensors::{HealthEvent, OperatingMode};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -68,6 +73,9 @@ pub struct ControlSystem {
     pub pid_angular: PIDController,
     pub mpc: MPCController,
     pub config: ControlConfig,
+    loop_timer: LoopTimer,
+    heartbeat: Heartbeat,
+    operating_mode: OperatingMode,
 }
 
 impl ControlSystem {
@@ -89,6 +97,9 @@ impl ControlSystem {
             ),
             mpc: MPCController::new(config.mpc.horizon),
             config,
+            loop_timer: LoopTimer::default(),
+            heartbeat: Heartbeat::default(),
+            operating_mode: OperatingMode::Normal,
         }
     }
 
@@ -98,18 +109,17 @@ impl ControlSystem {
         target_pose: &RobotState,
         dt: f64,
     ) -> ControlInput {
+        // `dt` es el periodo nominal del lazo; lo que se desvíe es jitter
+        let now = std::time::Instant::now();
+        self.loop_timer.tick(now, dt);
+        self.heartbeat.beat_at(now);
+
         // Error de posición
         let dx = target_pose.x - _current_pose.x;
         let dy = target_pose.y - _current_pose.y;
 
         // Error de orientación (normalizado a [-pi, pi])
-        let mut dtheta = target_pose.theta - _current_pose.theta;
-        while dtheta > std::f64::consts::PI {
-            dtheta -= 2.0 * std::f64::consts::PI;
-        }
-        while dtheta < -std::f64::consts::PI {
-            dtheta += 2.0 * std::f64::consts::PI;
-        }
+        let dtheta = angle_difference(target_pose.theta, _current_pose.theta);
 
         // Control de velocidad lineal (basado en distancia)
         let distance_error = (dx * dx + dy * dy).sqrt();
@@ -118,7 +128,7 @@ impl ControlSystem {
         // Control de velocidad angular (basado en error de orientación)
         let angular_velocity = self.pid_angular.compute(dtheta, dt);
 
-        ControlInput {
+        self.limit(ControlInput {
             linear_x: linear_velocity
                 .clamp(-self.config.max_linear_speed, self.config.max_linear_speed),
             linear_y: 0.0,
@@ -126,7 +136,7 @@ impl ControlSystem {
                 -self.config.max_angular_speed,
                 self.config.max_angular_speed,
             ),
-        }
+        })
     }
 
     pub fn compute_mpc_control(
@@ -134,12 +144,85 @@ impl ControlSystem {
         current_state: &RobotState,
         reference_trajectory: &[RobotState],
     ) -> Result<ControlInput, String> {
+        self.heartbeat.beat();
         self.mpc
             .compute_control(current_state.clone(), reference_trajectory)
+            .map(|input| self.limit(input))
+    }
+
+    /// Aplica el modo de operación del monitor de salud a cualquier orden de
+    /// velocidad: escalada en modo degradado, cero si los sensores
+    /// imprescindibles han caído
+    pub fn limit(&self, input: ControlInput) -> ControlInput {
+        let factor = self.operating_mode.speed_factor();
+        ControlInput {
+            linear_x: input.linear_x * factor,
+            linear_y: input.linear_y * factor,
+            angular_z: input.angular_z * factor,
+        }
+    }
+
+    pub fn operating_mode(&self) -> OperatingMode {
+        self.operating_mode
+    }
+
+    pub fn handle_health_event(&mut self, event: &HealthEvent) {
+        if let HealthEvent::ModeChanged { mode, reason } = event {
+            if !mode.allows_motion() {
+                log::warn!("🛑 Control detenido: {}", reason);
+            }
+            self.operating_mode = *mode;
+        }
+    }
+
+    /// Latido que da el lazo en cada cálculo de control
+    pub fn heartbeat(&self) -> Heartbeat {
+        self.heartbeat.clone()
     }
 
     pub fn reset(&mut self) {
         self.pid_linear.reset();
         self.pid_angular.reset();
+        self.loop_timer = LoopTimer::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::{HealthConfig, HealthMonitor, SensorKind};
+    use std::time::{Duration, Instant};
+
+    #[tokio::test]
+    async fn test_silent_lidar_stops_motion() {
+        let mut control = ControlSystem::new(ControlConfig::default());
+        let mut monitor = HealthMonitor::new(HealthConfig::default());
+        let mut events = monitor.subscribe();
+        monitor.watch("lidar", SensorKind::Lidar, Duration::from_millis(100), None);
+
+        let current = RobotState::default();
+        let target = RobotState {
+            x: 2.0,
+            theta: 0.5,
+            ..RobotState::default()
+        };
+        assert!(control.compute_pid_control(&current, &target, 0.02).linear_x > 0.0);
+
+        // 2 s sin escaneos con un periodo nominal de 100 ms
+        monitor.check(Instant::now() + Duration::from_secs(2)).await;
+        while let Ok(event) = events.try_recv() {
+            control.handle_health_event(&event);
+        }
+        assert_eq!(control.operating_mode(), OperatingMode::Stopped);
+
+        let input = control.compute_pid_control(&current, &target, 0.02);
+        assert_eq!((input.linear_x, input.angular_z), (0.0, 0.0));
+
+        control.handle_health_event(&HealthEvent::ModeChanged {
+            mode: OperatingMode::Degraded { speed_factor: 0.5 },
+            reason: "IMU".to_string(),
+        });
+        let limited = control.limit(ControlInput::new(0.8, 1.0));
+        assert_eq!((limited.linear_x, limited.angular_z), (0.4, 0.5));
     }
 }
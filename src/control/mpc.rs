@@ -1,4 +1,5 @@
 use crate::control::base::{ControlInput, RobotState};
+use crate::geometry::angle_difference;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -71,13 +72,7 @@ impl MPCController {
         let distance_error = (dx * dx + dy * dy).sqrt();
 
         // Calculate orientation error (normalized)
-        let mut dtheta = target_state.theta - current_state.theta;
-        while dtheta > std::f64::consts::PI {
-            dtheta -= 2.0 * std::f64::consts::PI;
-        }
-        while dtheta < -std::f64::consts::PI {
-            dtheta += 2.0 * std::f64::consts::PI;
-        }
+        let dtheta = angle_difference(target_state.theta, current_state.theta);
 
         // Simple control law for demonstration
         // In practice, this would be replaced with a proper optimization
@@ -0,0 +1,176 @@
+//! Matrices de covarianza para posiciones y poses
+use super::Pose2D;
+use serde::{Deserialize, Serialize};
+use std::ops::{Index, IndexMut};
+
+/// Covarianza de una posición en el plano (m²); se serializa como la matriz
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Covariance2(pub [[f64; 2]; 2]);
+
+impl Covariance2 {
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    pub fn diagonal(xx: f64, yy: f64) -> Self {
+        Self([[xx, 0.0], [0.0, yy]])
+    }
+
+    /// Misma desviación típica en todas las direcciones
+    pub fn isotropic(std_dev: f64) -> Self {
+        Self::diagonal(std_dev * std_dev, std_dev * std_dev)
+    }
+
+    pub fn determinant(&self) -> f64 {
+        let [[a, b], [c, d]] = self.0;
+        a * d - b * c
+    }
+
+    pub fn trace(&self) -> f64 {
+        self.0[0][0] + self.0[1][1]
+    }
+
+    /// `None` si la matriz es singular
+    pub fn inverse(&self) -> Option<Covariance2> {
+        let det = self.determinant();
+        if det.abs() < 1e-12 || !det.is_finite() {
+            return None;
+        }
+        let [[a, b], [c, d]] = self.0;
+        Some(Self([[d / det, -b / det], [-c / det, a / det]]))
+    }
+
+    /// `R Σ Rᵀ`: la misma incertidumbre vista desde un marco girado `angle`
+    pub fn rotated(&self, angle: f64) -> Covariance2 {
+        let (sin, cos) = angle.sin_cos();
+        let [[a, b], [c, d]] = self.0;
+        // R Σ
+        let [[m00, m01], [m10, m11]] = [
+            [cos * a - sin * c, cos * b - sin * d],
+            [sin * a + cos * c, sin * b + cos * d],
+        ];
+        Self([
+            [m00 * cos - m01 * sin, m00 * sin + m01 * cos],
+            [m10 * cos - m11 * sin, m10 * sin + m11 * cos],
+        ])
+    }
+
+    /// Simétrica, finita y semidefinida positiva
+    pub fn is_valid(&self) -> bool {
+        let [[a, b], [c, d]] = self.0;
+        [a, b, c, d].iter().all(|v| v.is_finite())
+            && (b - c).abs() <= 1e-9 * (1.0 + b.abs())
+            && a >= 0.0
+            && d >= 0.0
+            && self.determinant() >= -1e-12
+    }
+}
+
+impl Index<usize> for Covariance2 {
+    type Output = [f64; 2];
+
+    fn index(&self, row: usize) -> &[f64; 2] {
+        &self.0[row]
+    }
+}
+
+impl IndexMut<usize> for Covariance2 {
+    fn index_mut(&mut self, row: usize) -> &mut [f64; 2] {
+        &mut self.0[row]
+    }
+}
+
+impl From<[[f64; 2]; 2]> for Covariance2 {
+    fn from(matrix: [[f64; 2]; 2]) -> Self {
+        Self(matrix)
+    }
+}
+
+/// Covarianza de una pose `(x, y, theta)` o de una posición 3D
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Covariance3(pub [[f64; 3]; 3]);
+
+impl Covariance3 {
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    pub fn diagonal(xx: f64, yy: f64, zz: f64) -> Self {
+        Self([[xx, 0.0, 0.0], [0.0, yy, 0.0], [0.0, 0.0, zz]])
+    }
+
+    pub fn trace(&self) -> f64 {
+        (0..3).map(|i| self.0[i][i]).sum()
+    }
+
+    /// Bloque de las dos primeras componentes (la posición en el plano)
+    pub fn position(&self) -> Covariance2 {
+        Covariance2([
+            [self.0[0][0], self.0[0][1]],
+            [self.0[1][0], self.0[1][1]],
+        ])
+    }
+
+    pub fn is_valid(&self) -> bool {
+        (0..3).all(|i| {
+            self.0[i][i] >= 0.0
+                && (0..3).all(|j| {
+                    self.0[i][j].is_finite()
+                        && (self.0[i][j] - self.0[j][i]).abs() <= 1e-9 * (1.0 + self.0[i][j].abs())
+                })
+        })
+    }
+}
+
+impl Index<usize> for Covariance3 {
+    type Output = [f64; 3];
+
+    fn index(&self, row: usize) -> &[f64; 3] {
+        &self.0[row]
+    }
+}
+
+impl IndexMut<usize> for Covariance3 {
+    fn index_mut(&mut self, row: usize) -> &mut [f64; 3] {
+        &mut self.0[row]
+    }
+}
+
+impl From<[[f64; 3]; 3]> for Covariance3 {
+    fn from(matrix: [[f64; 3]; 3]) -> Self {
+        Self(matrix)
+    }
+}
+
+/// Pose estimada con su incertidumbre en `(x, y, theta)`
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct PoseWithCovariance {
+    pub pose: Pose2D,
+    pub covariance: Covariance3,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_covariance_rotation_and_inverse() {
+        // Elipse alargada en x vista desde un marco girado 90°: alargada en y
+        let covariance = Covariance2::diagonal(4.0, 1.0);
+        let rotated = covariance.rotated(std::f64::consts::FRAC_PI_2);
+        assert!((rotated[0][0] - 1.0).abs() < 1e-9 && (rotated[1][1] - 4.0).abs() < 1e-9);
+        assert!((rotated.trace() - covariance.trace()).abs() < 1e-9);
+        assert!(rotated.is_valid());
+
+        let inverse = covariance.inverse().unwrap();
+        assert!((inverse[0][0] - 0.25).abs() < 1e-12);
+        assert!(Covariance2::zero().inverse().is_none());
+        assert!(!Covariance2([[1.0, 2.0], [0.0, 1.0]]).is_valid());
+
+        let pose = Covariance3::diagonal(0.1, 0.2, 0.05);
+        assert_eq!(pose.position(), Covariance2::diagonal(0.1, 0.2));
+        assert_eq!(serde_json::to_string(&pose.position()).unwrap(), "[[0.1,0.0],[0.0,0.2]]");
+    }
+}
@@ -0,0 +1,49 @@
+//! Tipos geométricos compartidos: puntos y poses en el plano (SE(2)),
+//! vectores y cuaterniones en 3D, ángulos y covarianzas
+pub mod covariance;
+pub mod pose;
+pub mod rotation;
+
+pub use covariance::{Covariance2, Covariance3, PoseWithCovariance};
+pub use pose::{Point2, Pose2D};
+pub use rotation::{Quaternion, Vector3};
+
+use std::f64::consts::PI;
+
+/// Lleva un ángulo al intervalo (-π, π]
+pub fn normalize_angle(angle: f64) -> f64 {
+    let wrapped = angle.sin().atan2(angle.cos());
+    // atan2 devuelve -π para el ángulo llano; se prefiere +π
+    if wrapped == -PI {
+        PI
+    } else {
+        wrapped
+    }
+}
+
+/// Diferencia `a - b` por el camino más corto
+pub fn angle_difference(a: f64, b: f64) -> f64 {
+    normalize_angle(a - b)
+}
+
+/// Interpola de `from` a `to` por el camino más corto
+pub fn interpolate_angle(from: f64, to: f64, t: f64) -> f64 {
+    normalize_angle(from + angle_difference(to, from) * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_angle_wrapping() {
+        assert!((normalize_angle(3.0 * PI) - PI).abs() < 1e-12);
+        assert!((normalize_angle(-PI) - PI).abs() < 1e-12);
+        assert!((normalize_angle(-5.0 * PI / 2.0) + PI / 2.0).abs() < 1e-12);
+        assert!((angle_difference(-3.0, 3.0) - (2.0 * PI - 6.0)).abs() < 1e-12);
+
+        // De 170° a -170° se pasa por 180°, no por 0°
+        let mid = interpolate_angle(170f64.to_radians(), -170f64.to_radians(), 0.5);
+        assert!((mid.abs() - PI).abs() < 1e-12);
+    }
+}
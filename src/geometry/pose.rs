@@ -0,0 +1,227 @@
+//! Puntos y poses en el plano
+use super::{angle_difference, normalize_angle};
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// Punto o vector en el plano (metros)
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Point2 {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point2 {
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+
+    pub fn origin() -> Self {
+        Self::default()
+    }
+
+    /// Punto a `distance` en la dirección `angle` desde el origen
+    pub fn from_polar(distance: f64, angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self::new(distance * cos, distance * sin)
+    }
+
+    pub fn norm(&self) -> f64 {
+        self.x.hypot(self.y)
+    }
+
+    /// Ángulo del vector desde el eje x
+    pub fn angle(&self) -> f64 {
+        self.y.atan2(self.x)
+    }
+
+    pub fn dot(&self, other: &Point2) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// Componente z del producto vectorial
+    pub fn cross(&self, other: &Point2) -> f64 {
+        self.x * other.y - self.y * other.x
+    }
+
+    pub fn distance_to(&self, other: &Point2) -> f64 {
+        (*other - *self).norm()
+    }
+
+    pub fn rotated(&self, angle: f64) -> Point2 {
+        let (sin, cos) = angle.sin_cos();
+        Point2::new(cos * self.x - sin * self.y, sin * self.x + cos * self.y)
+    }
+
+    pub fn lerp(&self, other: &Point2, t: f64) -> Point2 {
+        *self + (*other - *self) * t
+    }
+}
+
+impl Add for Point2 {
+    type Output = Point2;
+
+    fn add(self, other: Point2) -> Point2 {
+        Point2::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Sub for Point2 {
+    type Output = Point2;
+
+    fn sub(self, other: Point2) -> Point2 {
+        Point2::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl Mul<f64> for Point2 {
+    type Output = Point2;
+
+    fn mul(self, scale: f64) -> Point2 {
+        Point2::new(self.x * scale, self.y * scale)
+    }
+}
+
+impl Neg for Point2 {
+    type Output = Point2;
+
+    fn neg(self) -> Point2 {
+        Point2::new(-self.x, -self.y)
+    }
+}
+
+impl From<(f64, f64)> for Point2 {
+    fn from((x, y): (f64, f64)) -> Self {
+        Self::new(x, y)
+    }
+}
+
+impl From<Point2> for (f64, f64) {
+    fn from(point: Point2) -> Self {
+        (point.x, point.y)
+    }
+}
+
+/// Pose en el plano, elemento de SE(2). Como transformación lleva puntos del
+/// marco de la pose al marco en que está expresada: `p = T · p_local`.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Pose2D {
+    pub x: f64,     // metros
+    pub y: f64,     // metros
+    pub theta: f64, // radianes
+}
+
+impl Pose2D {
+    /// La orientación se normaliza a (-π, π]
+    pub fn new(x: f64, y: f64, theta: f64) -> Self {
+        Self {
+            x,
+            y,
+            theta: normalize_angle(theta),
+        }
+    }
+
+    pub fn identity() -> Self {
+        Self::default()
+    }
+
+    pub fn from_position(position: Point2, theta: f64) -> Self {
+        Self::new(position.x, position.y, theta)
+    }
+
+    pub fn position(&self) -> Point2 {
+        Point2::new(self.x, self.y)
+    }
+
+    /// `self · other`: la pose `other`, relativa a `self`, en el marco de `self`
+    pub fn compose(&self, other: &Pose2D) -> Pose2D {
+        Pose2D::from_position(
+            self.transform_point(other.position()),
+            self.theta + other.theta,
+        )
+    }
+
+    pub fn inverse(&self) -> Pose2D {
+        Pose2D::from_position(-self.position().rotated(-self.theta), -self.theta)
+    }
+
+    /// `other` expresada en el marco de `self`
+    pub fn relative(&self, other: &Pose2D) -> Pose2D {
+        self.inverse().compose(other)
+    }
+
+    /// Lleva un punto del marco de la pose al marco padre
+    pub fn transform_point(&self, point: Point2) -> Point2 {
+        self.position() + point.rotated(self.theta)
+    }
+
+    /// Interpolación lineal en posición y por el camino más corto en ángulo
+    pub fn interpolate(&self, other: &Pose2D, t: f64) -> Pose2D {
+        Pose2D::from_position(
+            self.position().lerp(&other.position(), t),
+            self.theta + angle_difference(other.theta, self.theta) * t,
+        )
+    }
+
+    pub fn distance_to(&self, other: &Pose2D) -> f64 {
+        self.position().distance_to(&other.position())
+    }
+
+    /// Rumbo desde esta pose hacia `other`
+    pub fn heading_to(&self, other: &Pose2D) -> f64 {
+        (other.position() - self.position()).angle()
+    }
+}
+
+impl Mul for Pose2D {
+    type Output = Pose2D;
+
+    fn mul(self, other: Pose2D) -> Pose2D {
+        self.compose(&other)
+    }
+}
+
+impl Mul<Point2> for Pose2D {
+    type Output = Point2;
+
+    fn mul(self, point: Point2) -> Point2 {
+        self.transform_point(point)
+    }
+}
+
+impl From<(f64, f64, f64)> for Pose2D {
+    fn from((x, y, theta): (f64, f64, f64)) -> Self {
+        Self::new(x, y, theta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_PI_2;
+
+    #[test]
+    fn test_pose_composition_and_inverse() {
+        let base = Pose2D::new(1.0, 2.0, FRAC_PI_2);
+        let laser = Pose2D::new(0.2, 0.0, 0.0);
+
+        // El LIDAR montado 20 cm delante queda 20 cm "arriba" en el mapa
+        let point = (base * laser) * Point2::new(1.0, 0.0);
+        assert!(point.distance_to(&Point2::new(1.0, 3.2)) < 1e-9);
+
+        let identity = base * base.inverse();
+        assert!(identity.position().norm() < 1e-9 && identity.theta.abs() < 1e-9);
+
+        let relative = base.relative(&Pose2D::new(1.0, 3.0, 0.0));
+        assert!(relative.distance_to(&Pose2D::new(1.0, 0.0, 0.0)) < 1e-9);
+        assert!((relative.theta + FRAC_PI_2).abs() < 1e-9);
+
+        // Interpolación por el lado corto de ±π
+        let mid = Pose2D::new(0.0, 0.0, 3.0).interpolate(&Pose2D::new(2.0, 0.0, -3.0), 0.5);
+        assert!((mid.x - 1.0).abs() < 1e-9);
+        assert!((mid.theta.abs() - std::f64::consts::PI).abs() < 1e-9);
+
+        let json = serde_json::to_string(&Point2::new(1.5, -2.0)).unwrap();
+        assert_eq!(json, r#"{"x":1.5,"y":-2.0}"#);
+    }
+}
@@ -0,0 +1,229 @@
+//! Vectores y rotaciones en 3D
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, Mul, Neg, Sub};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Vector3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Vector3 {
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z }
+    }
+
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    pub fn magnitude(&self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn dot(&self, other: &Vector3) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn cross(&self, other: &Vector3) -> Vector3 {
+        Vector3::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    /// Vector unitario; el nulo se devuelve tal cual
+    pub fn normalized(&self) -> Vector3 {
+        let magnitude = self.magnitude();
+        if magnitude > 0.0 {
+            *self * (1.0 / magnitude)
+        } else {
+            *self
+        }
+    }
+}
+
+impl Add for Vector3 {
+    type Output = Vector3;
+
+    fn add(self, other: Vector3) -> Vector3 {
+        Vector3::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl Sub for Vector3 {
+    type Output = Vector3;
+
+    fn sub(self, other: Vector3) -> Vector3 {
+        Vector3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl Mul<f64> for Vector3 {
+    type Output = Vector3;
+
+    fn mul(self, scale: f64) -> Vector3 {
+        Vector3::new(self.x * scale, self.y * scale, self.z * scale)
+    }
+}
+
+impl Neg for Vector3 {
+    type Output = Vector3;
+
+    fn neg(self) -> Vector3 {
+        Vector3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+/// Cuaternión unitario de rotación `w + xi + yj + zk`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Quaternion {
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Self {
+        Self { w, x, y, z }
+    }
+
+    pub fn identity() -> Self {
+        Self::new(1.0, 0.0, 0.0, 0.0)
+    }
+
+    /// Rotación de `angle` radianes alrededor de `axis`
+    pub fn from_axis_angle(axis: Vector3, angle: f64) -> Self {
+        let axis = axis.normalized();
+        let (sin, cos) = (angle / 2.0).sin_cos();
+        Self::new(cos, axis.x * sin, axis.y * sin, axis.z * sin)
+    }
+
+    /// Rotación de `yaw` alrededor del eje z, la única que existe en el plano
+    pub fn from_yaw(yaw: f64) -> Self {
+        Self::from_axis_angle(Vector3::new(0.0, 0.0, 1.0), yaw)
+    }
+
+    /// Ángulos de Euler en convención ZYX (yaw, luego pitch, luego roll)
+    pub fn from_euler(roll: f64, pitch: f64, yaw: f64) -> Self {
+        let (sr, cr) = (roll / 2.0).sin_cos();
+        let (sp, cp) = (pitch / 2.0).sin_cos();
+        let (sy, cy) = (yaw / 2.0).sin_cos();
+        Self::new(
+            cr * cp * cy + sr * sp * sy,
+            sr * cp * cy - cr * sp * sy,
+            cr * sp * cy + sr * cp * sy,
+            cr * cp * sy - sr * sp * cy,
+        )
+    }
+
+    /// `(roll, pitch, yaw)` en convención ZYX
+    pub fn to_euler(&self) -> (f64, f64, f64) {
+        let Quaternion { w, x, y, z } = *self;
+        let roll = (2.0 * (w * x + y * z)).atan2(1.0 - 2.0 * (x * x + y * y));
+        let pitch = (2.0 * (w * y - z * x)).clamp(-1.0, 1.0).asin();
+        (roll, pitch, self.yaw())
+    }
+
+    pub fn yaw(&self) -> f64 {
+        let Quaternion { w, x, y, z } = *self;
+        (2.0 * (w * z + x * y)).atan2(1.0 - 2.0 * (y * y + z * z))
+    }
+
+    pub fn norm(&self) -> f64 {
+        (self.w * self.w + self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
+    }
+
+    pub fn normalized(&self) -> Quaternion {
+        let norm = self.norm();
+        if norm > 0.0 {
+            Self::new(self.w / norm, self.x / norm, self.y / norm, self.z / norm)
+        } else {
+            Self::identity()
+        }
+    }
+
+    /// Rotación inversa (para cuaterniones unitarios)
+    pub fn conjugate(&self) -> Quaternion {
+        Self::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    pub fn rotate(&self, vector: Vector3) -> Vector3 {
+        let q = Vector3::new(self.x, self.y, self.z);
+        let t = q.cross(&vector) * 2.0;
+        vector + t * self.w + q.cross(&t)
+    }
+
+    /// Interpolación esférica por el camino más corto
+    pub fn slerp(&self, other: &Quaternion, t: f64) -> Quaternion {
+        let mut other = *other;
+        let mut dot = self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z;
+        if dot < 0.0 {
+            other = Self::new(-other.w, -other.x, -other.y, -other.z);
+            dot = -dot;
+        }
+
+        let (a, b) = if dot > 0.9995 {
+            // Casi paralelos: la interpolación lineal basta
+            (1.0 - t, t)
+        } else {
+            let omega = dot.acos();
+            let sin = omega.sin();
+            (((1.0 - t) * omega).sin() / sin, (t * omega).sin() / sin)
+        };
+        Self::new(
+            a * self.w + b * other.w,
+            a * self.x + b * other.x,
+            a * self.y + b * other.y,
+            a * self.z + b * other.z,
+        )
+        .normalized()
+    }
+}
+
+impl Mul for Quaternion {
+    type Output = Quaternion;
+
+    /// Composición: primero `other`, luego `self`
+    fn mul(self, other: Quaternion) -> Quaternion {
+        Quaternion::new(
+            self.w * other.w - self.x * other.x - self.y * other.y - self.z * other.z,
+            self.w * other.x + self.x * other.w + self.y * other.z - self.z * other.y,
+            self.w * other.y - self.x * other.z + self.y * other.w + self.z * other.x,
+            self.w * other.z + self.x * other.y - self.y * other.x + self.z * other.w,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_PI_2;
+
+    #[test]
+    fn test_quaternion_rotation_and_euler() {
+        let q = Quaternion::from_euler(0.1, -0.2, 2.5);
+        let (roll, pitch, yaw) = q.to_euler();
+        assert!((roll - 0.1).abs() < 1e-9);
+        assert!((pitch + 0.2).abs() < 1e-9);
+        assert!((yaw - 2.5).abs() < 1e-9);
+
+        // 90° en z lleva x a y
+        let rotated = Quaternion::from_yaw(FRAC_PI_2).rotate(Vector3::new(1.0, 0.0, 0.0));
+        assert!((rotated - Vector3::new(0.0, 1.0, 0.0)).magnitude() < 1e-9);
+
+        let composed = Quaternion::from_yaw(0.3) * Quaternion::from_yaw(0.4);
+        assert!((composed.yaw() - 0.7).abs() < 1e-9);
+        let half = Quaternion::identity().slerp(&Quaternion::from_yaw(1.0), 0.5);
+        assert!((half.yaw() - 0.5).abs() < 1e-9);
+    }
+}
@@ -1,8 +1,14 @@
 pub mod api;
 pub mod config; 
 pub mod control;
+pub mod geometry;
+pub mod metrics;
+pub mod mission;
 pub mod navigation;
+pub mod power;
+pub mod recording;
 pub mod sensors;
+pub mod transforms;
 pub mod vision;
 
 pub use config::Config;
@@ -0,0 +1,311 @@
+//! Métricas del robot en el formato de texto de Prometheus (`GET /metrics`).
+//! Un único registro global que instrumentan sensores, SLAM, planificador,
+//! control, batería y API; el proceso (CPU, memoria) se añade en Linux.
+use prometheus::core::Collector;
+use prometheus::{
+    Gauge, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts,
+    Registry, TextEncoder,
+};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use crate::power::BatteryState;
+
+/// Tipo MIME del formato de texto
+pub const CONTENT_TYPE: &str = prometheus::TEXT_FORMAT;
+
+/// Buckets de duración de 100 µs a ~3 s
+const DURATION_BUCKETS: &[f64] = &[
+    0.0001, 0.00025, 0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5,
+];
+
+pub struct Metrics {
+    registry: Registry,
+    pub sensor_messages: IntCounterVec,
+    pub sensor_errors: IntCounterVec,
+    pub sensor_read: HistogramVec,
+    pub sensor_latency: HistogramVec,
+    pub slam_update: Histogram,
+    pub slam_particle_ess: Gauge,
+    pub planner_duration: HistogramVec,
+    pub planner_failures: IntCounterVec,
+    pub control_jitter: Histogram,
+    pub battery_voltage: Gauge,
+    pub battery_current: Gauge,
+    pub battery_state_of_charge: Gauge,
+    pub battery_temperature: Gauge,
+    pub battery_charging: IntGauge,
+    pub battery_level: IntGauge,
+    pub api_requests: IntCounterVec,
+    pub api_request_duration: HistogramVec,
+    pub websocket_clients: IntGauge,
+}
+
+/// Registro global, creado en el primer uso
+pub fn metrics() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(Metrics::new)
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+        let durations = |name: &str, help: &str| {
+            HistogramOpts::new(name, help).buckets(DURATION_BUCKETS.to_vec())
+        };
+
+        let metrics = Self {
+            sensor_messages: register(
+                &registry,
+                IntCounterVec::new(
+                    Opts::new(
+                        "mechbot_sensor_messages_total",
+                        "Lecturas publicadas en el bus",
+                    ),
+                    &["sensor", "kind"],
+                ),
+            ),
+            sensor_errors: register(
+                &registry,
+                IntCounterVec::new(
+                    Opts::new(
+                        "mechbot_sensor_errors_total",
+                        "Errores de lectura de los drivers",
+                    ),
+                    &["sensor"],
+                ),
+            ),
+            sensor_read: register(
+                &registry,
+                HistogramVec::new(
+                    durations(
+                        "mechbot_sensor_read_seconds",
+                        "Duración de la lectura del driver",
+                    ),
+                    &["sensor"],
+                ),
+            ),
+            sensor_latency: register(
+                &registry,
+                HistogramVec::new(
+                    durations(
+                        "mechbot_sensor_latency_seconds",
+                        "Antigüedad de la lectura al publicarla en el bus",
+                    ),
+                    &["sensor"],
+                ),
+            ),
+            slam_update: register(
+                &registry,
+                Histogram::with_opts(durations(
+                    "mechbot_slam_update_seconds",
+                    "Duración de una actualización de SLAM",
+                )),
+            ),
+            slam_particle_ess: register(
+                &registry,
+                Gauge::new(
+                    "mechbot_slam_particle_ess",
+                    "Tamaño efectivo de la muestra del filtro de partículas antes de remuestrear",
+                ),
+            ),
+            planner_duration: register(
+                &registry,
+                HistogramVec::new(
+                    durations(
+                        "mechbot_planner_seconds",
+                        "Duración de la planificación de rutas",
+                    ),
+                    &["algorithm"],
+                ),
+            ),
+            planner_failures: register(
+                &registry,
+                IntCounterVec::new(
+                    Opts::new(
+                        "mechbot_planner_failures_total",
+                        "Planificaciones sin ruta o con error",
+                    ),
+                    &["algorithm"],
+                ),
+            ),
+            control_jitter: register(
+                &registry,
+                Histogram::with_opts(durations(
+                    "mechbot_control_loop_jitter_seconds",
+                    "Desviación del periodo real del lazo de control respecto al nominal",
+                )),
+            ),
+            battery_voltage: register(
+                &registry,
+                Gauge::new("mechbot_battery_voltage_volts", "Tensión de la batería"),
+            ),
+            battery_current: register(
+                &registry,
+                Gauge::new(
+                    "mechbot_battery_current_amperes",
+                    "Corriente de la batería (negativa cargando)",
+                ),
+            ),
+            battery_state_of_charge: register(
+                &registry,
+                Gauge::new("mechbot_battery_state_of_charge", "Estado de carga (0..1)"),
+            ),
+            battery_temperature: register(
+                &registry,
+                Gauge::new(
+                    "mechbot_battery_temperature_celsius",
+                    "Temperatura de la batería, si el sensor la mide",
+                ),
+            ),
+            battery_charging: register(
+                &registry,
+                IntGauge::new(
+                    "mechbot_battery_charging",
+                    "1 si la batería se está cargando",
+                ),
+            ),
+            battery_level: register(
+                &registry,
+                IntGauge::new(
+                    "mechbot_battery_level",
+                    "Nivel de batería: 0 normal, 1 bajo, 2 crítico",
+                ),
+            ),
+            api_requests: register(
+                &registry,
+                IntCounterVec::new(
+                    Opts::new("mechbot_api_requests_total", "Peticiones REST atendidas"),
+                    &["method", "route", "status"],
+                ),
+            ),
+            api_request_duration: register(
+                &registry,
+                HistogramVec::new(
+                    durations(
+                        "mechbot_api_request_duration_seconds",
+                        "Duración de las peticiones REST",
+                    ),
+                    &["method", "route"],
+                ),
+            ),
+            websocket_clients: register(
+                &registry,
+                IntGauge::new("mechbot_websocket_clients", "Conexiones WebSocket abiertas"),
+            ),
+            registry,
+        };
+
+        #[cfg(target_os = "linux")]
+        {
+            let process = prometheus::process_collector::ProcessCollector::for_self();
+            if let Err(e) = metrics.registry.register(Box::new(process)) {
+                log::warn!("⚠️ Sin métricas del proceso: {}", e);
+            }
+        }
+
+        metrics
+    }
+
+    pub fn record_battery(&self, battery: &BatteryState) {
+        self.battery_voltage.set(battery.voltage);
+        self.battery_current.set(battery.current);
+        self.battery_state_of_charge.set(battery.state_of_charge);
+        self.battery_temperature
+            .set(battery.temperature.unwrap_or(f64::NAN));
+        self.battery_charging.set(battery.charging as i64);
+        self.battery_level.set(battery.level as i64);
+    }
+
+    /// Todas las métricas en formato de texto
+    pub fn encode(&self) -> String {
+        TextEncoder::new()
+            .encode_to_string(&self.registry.gather())
+            .unwrap_or_else(|e| {
+                log::error!("❌ No se pudieron codificar las métricas: {}", e);
+                String::new()
+            })
+    }
+}
+
+/// Los nombres y etiquetas son constantes: si fallan es un error de programa
+fn register<C: Collector + Clone + 'static>(
+    registry: &Registry,
+    collector: prometheus::Result<C>,
+) -> C {
+    let collector = collector.expect("métrica mal definida");
+    registry
+        .register(Box::new(collector.clone()))
+        .expect("métrica registrada dos veces");
+    collector
+}
+
+/// Jitter del lazo de control: la diferencia entre el tiempo real entre dos
+/// llamadas a `tick` y el periodo que el lazo cree llevar
+#[derive(Debug, Clone, Default)]
+pub struct LoopTimer {
+    last: Option<Instant>,
+}
+
+impl LoopTimer {
+    pub fn tick(&mut self, now: Instant, period: f64) -> Option<f64> {
+        let jitter = self
+            .last
+            .map(|last| (now.duration_since(last).as_secs_f64() - period).abs());
+        self.last = Some(now);
+        if let Some(jitter) = jitter {
+            metrics().control_jitter.observe(jitter);
+        }
+        jitter
+    }
+}
+
+/// Segundos Unix actuales, como los timestamps de las lecturas
+pub fn unix_now() -> f64 {
+    chrono::Utc::now().timestamp_micros() as f64 / 1e6
+}
+
+/// Contador de lecturas publicadas por un sensor
+pub fn sensor_counter(sensor: &str, kind: &str) -> IntCounter {
+    metrics().sensor_messages.with_label_values(&[sensor, kind])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::power::PowerLevel;
+    use std::time::Duration;
+
+    #[test]
+    fn test_text_format_and_loop_jitter() {
+        let mut timer = LoopTimer::default();
+        let start = Instant::now();
+        assert_eq!(timer.tick(start, 0.02), None);
+        let jitter = timer.tick(start + Duration::from_millis(25), 0.02).unwrap();
+        assert!((jitter - 0.005).abs() < 1e-9);
+
+        metrics().record_battery(&BatteryState {
+            voltage: 12.3,
+            current: 1.5,
+            temperature: None,
+            state_of_charge: 0.8,
+            charging: false,
+            level: PowerLevel::Low,
+            timestamp: 0.0,
+        });
+        sensor_counter("lidar_test", "lidar").inc();
+
+        let text = metrics().encode();
+        assert!(
+            text.contains("mechbot_battery_voltage_volts 12.3"),
+            "{}",
+            text
+        );
+        assert!(text.contains("mechbot_battery_level 1"));
+        assert!(
+            text.contains(r#"mechbot_sensor_messages_total{kind="lidar",sensor="lidar_test"} 1"#)
+        );
+        assert!(text.contains("# TYPE mechbot_control_loop_jitter_seconds histogram"));
+        assert!(text.contains("mechbot_websocket_clients"));
+    }
+}
@@ -0,0 +1,313 @@
+//! Misiones: secuencias de acciones (ir a un punto, acoplar, desacoplar,
+//! esperar) ejecutadas paso a paso por el bucle de control
+use crate::control::{ControlInput, RobotState};
+use crate::navigation::docking::{DockDetection, DockingBehavior, DockingCommand, DockingState};
+use crate::navigation::geodesy::{LocalFrame, Waypoint};
+use crate::power::{BatteryState, PowerEvent};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum MissionAction {
+    NavigateTo {
+        waypoint: Waypoint,
+    },
+    /// Volver a la base y acoplar hasta confirmar la carga
+    Dock,
+    Undock,
+    Wait {
+        seconds: f64,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Mission {
+    pub name: String,
+    pub actions: Vec<MissionAction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum MissionState {
+    Idle,
+    Running { action: MissionAction },
+    Completed,
+    Failed { reason: String },
+}
+
+/// Lo observado en el ciclo actual
+#[derive(Debug, Clone, Copy)]
+pub struct MissionContext<'a> {
+    /// Pose en el marco del mapa
+    pub pose: &'a RobotState,
+    pub dock_detection: Option<&'a DockDetection>,
+    pub battery: Option<&'a BatteryState>,
+    pub local_frame: Option<&'a LocalFrame>,
+    pub dt: f64,
+}
+
+/// Lo que la misión pide al bucle de control
+#[derive(Debug, Clone)]
+pub enum MissionCommand {
+    /// Ir a la pose con el planificador de rutas
+    Navigate(RobotState),
+    Velocity(ControlInput),
+    Idle,
+}
+
+pub struct MissionExecutor {
+    queue: VecDeque<MissionAction>,
+    current: Option<(MissionAction, f64)>,
+    docking: DockingBehavior,
+    state: MissionState,
+    /// Distancia a la que se da por alcanzado un waypoint (m)
+    goal_tolerance: f64,
+}
+
+impl MissionExecutor {
+    pub fn new(docking: DockingBehavior) -> Self {
+        Self {
+            queue: VecDeque::new(),
+            current: None,
+            docking,
+            state: MissionState::Idle,
+            goal_tolerance: 0.2,
+        }
+    }
+
+    pub fn with_goal_tolerance(mut self, tolerance: f64) -> Self {
+        self.goal_tolerance = tolerance;
+        self
+    }
+
+    /// Sustituye lo pendiente por la misión
+    pub fn start(&mut self, mission: Mission) {
+        log::info!(
+            "🗺️ Misión '{}' con {} acciones",
+            mission.name,
+            mission.actions.len()
+        );
+        self.cancel();
+        self.queue.extend(mission.actions);
+    }
+
+    /// Añade una acción al final de la cola
+    pub fn push(&mut self, action: MissionAction) {
+        self.queue.push_back(action);
+    }
+
+    pub fn cancel(&mut self) {
+        self.queue.clear();
+        self.current = None;
+        if !matches!(
+            self.docking.state(),
+            DockingState::Docked | DockingState::Idle
+        ) {
+            self.docking.cancel();
+        }
+        self.state = MissionState::Idle;
+    }
+
+    /// Con batería baja se abandona la misión en curso para acoplar
+    pub fn handle_power_event(&mut self, event: &PowerEvent) {
+        let PowerEvent::ReturnToDock { .. } = event else {
+            return;
+        };
+        let docking = matches!(self.current, Some((MissionAction::Dock, _)));
+        if !docking && self.docking.state() != DockingState::Docked {
+            self.start(Mission {
+                name: "volver a la base".to_string(),
+                actions: vec![MissionAction::Dock],
+            });
+        }
+    }
+
+    pub fn state(&self) -> &MissionState {
+        &self.state
+    }
+
+    pub fn docking(&self) -> &DockingBehavior {
+        &self.docking
+    }
+
+    pub fn pending(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn step(&mut self, context: &MissionContext) -> MissionCommand {
+        if self.current.is_none() {
+            let Some(action) = self.queue.pop_front() else {
+                if matches!(self.state, MissionState::Running { .. }) {
+                    self.state = MissionState::Completed;
+                }
+                return MissionCommand::Idle;
+            };
+            if let Err(reason) = self.begin(&action) {
+                return self.fail(reason);
+            }
+            self.state = MissionState::Running {
+                action: action.clone(),
+            };
+            self.current = Some((action, 0.0));
+        }
+
+        let Some((action, elapsed)) = self.current.as_mut() else {
+            return MissionCommand::Idle;
+        };
+        *elapsed += context.dt;
+
+        let outcome = match action {
+            MissionAction::NavigateTo { waypoint } => match waypoint.resolve(context.local_frame) {
+                Ok(target) if context.pose.distance_to(&target) <= self.goal_tolerance => Ok(None),
+                Ok(target) => Ok(Some(MissionCommand::Navigate(target))),
+                Err(e) => Err(e),
+            },
+            MissionAction::Wait { seconds } => {
+                Ok((*elapsed < *seconds).then(|| MissionCommand::Velocity(ControlInput::zero())))
+            }
+            MissionAction::Dock | MissionAction::Undock => {
+                let command = self.docking.step(
+                    context.pose,
+                    context.dock_detection,
+                    context.battery,
+                    context.dt,
+                );
+                match (self.docking.state(), action) {
+                    (DockingState::Docked, MissionAction::Dock) => Ok(None),
+                    (DockingState::Idle, MissionAction::Undock) => Ok(None),
+                    (DockingState::Failed, _) => Err(self
+                        .docking
+                        .status()
+                        .message
+                        .unwrap_or_else(|| "Acoplamiento fallido".to_string())),
+                    _ => Ok(Some(match command {
+                        DockingCommand::Navigate(pose) => MissionCommand::Navigate(pose),
+                        DockingCommand::Velocity(input) => MissionCommand::Velocity(input),
+                        DockingCommand::Idle => MissionCommand::Idle,
+                    })),
+                }
+            }
+        };
+
+        match outcome {
+            Ok(Some(command)) => command,
+            // Acción terminada: se empieza la siguiente en el próximo ciclo
+            Ok(None) => {
+                self.current = None;
+                if self.queue.is_empty() {
+                    self.state = MissionState::Completed;
+                }
+                MissionCommand::Velocity(ControlInput::zero())
+            }
+            Err(reason) => self.fail(reason),
+        }
+    }
+
+    fn begin(&mut self, action: &MissionAction) -> Result<(), String> {
+        log::info!("▶️ Acción de misión: {:?}", action);
+        match action {
+            MissionAction::Dock => self.docking.dock(),
+            MissionAction::Undock => self.docking.undock()?,
+            MissionAction::NavigateTo { .. } | MissionAction::Wait { .. } => {
+                // Salir de la base antes de moverse
+                if self.docking.state() == DockingState::Docked {
+                    return Err("El robot está acoplado: desacoplar primero".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn fail(&mut self, reason: String) -> MissionCommand {
+        log::error!("❌ Misión abortada: {}", reason);
+        self.queue.clear();
+        self.current = None;
+        self.state = MissionState::Failed { reason };
+        MissionCommand::Velocity(ControlInput::zero())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::docking::DockingConfig;
+
+    #[test]
+    fn test_mission_from_json_runs_in_order() {
+        let mission: Mission = serde_json::from_str(
+            r#"{
+                "name": "patrulla",
+                "actions": [
+                    {"action": "navigate_to", "waypoint": {"x": 1.0, "y": 0.0}},
+                    {"action": "wait", "seconds": 0.3},
+                    {"action": "dock"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(mission.actions[2], MissionAction::Dock);
+
+        let docking =
+            DockingBehavior::new(DockingConfig::default(), RobotState::new(3.0, 0.0, 0.0));
+        let mut executor = MissionExecutor::new(docking);
+        executor.start(mission);
+
+        let context = |pose| MissionContext {
+            pose,
+            dock_detection: None,
+            battery: None,
+            local_frame: None,
+            dt: 0.1,
+        };
+
+        let far = RobotState::new(0.0, 0.0, 0.0);
+        let MissionCommand::Navigate(target) = executor.step(&context(&far)) else {
+            panic!("se esperaba navegación");
+        };
+        assert_eq!((target.x, target.y), (1.0, 0.0));
+
+        // Llegada al waypoint, espera de 0.3 s y arranque del acoplamiento
+        let there = RobotState::new(0.95, 0.0, 0.0);
+        executor.step(&context(&there));
+        for _ in 0..3 {
+            assert!(matches!(
+                executor.step(&context(&there)),
+                MissionCommand::Velocity(_)
+            ));
+        }
+        executor.step(&context(&there));
+        let MissionCommand::Navigate(staging) = executor.step(&context(&there)) else {
+            panic!("se esperaba la pose de espera");
+        };
+        assert!((staging.x - 2.0).abs() < 1e-9);
+        assert_eq!(executor.docking().state(), DockingState::Navigating);
+        assert_eq!(
+            executor.state(),
+            &MissionState::Running {
+                action: MissionAction::Dock
+            }
+        );
+
+        // Batería baja durante el acoplamiento: no se reinicia
+        executor.handle_power_event(&PowerEvent::ReturnToDock {
+            dock: Waypoint::Local {
+                x: 3.0,
+                y: 0.0,
+                theta: 0.0,
+            },
+            level: crate::power::PowerLevel::Low,
+            state_of_charge: 0.2,
+        });
+        assert_eq!(executor.pending(), 0);
+        assert_eq!(executor.docking().state(), DockingState::Navigating);
+
+        // Desacoplar sin estar acoplado aborta la misión
+        executor.start(Mission {
+            name: "salir".to_string(),
+            actions: vec![MissionAction::Undock],
+        });
+        executor.step(&context(&there));
+        assert!(matches!(executor.state(), MissionState::Failed { .. }));
+    }
+}
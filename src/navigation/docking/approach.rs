@@ -0,0 +1,127 @@
+//! Controlador de aproximación final a la base
+use crate::control::ControlInput;
+use crate::geometry::{normalize_angle, Pose2D};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApproachConfig {
+    pub k_rho: f64,
+    pub k_alpha: f64,
+    /// Negativa: corrige la orientación final
+    pub k_beta: f64,
+    pub max_linear_speed: f64,
+    pub max_angular_speed: f64,
+    /// Error de posición admitido al llegar a los contactos (m)
+    pub position_tolerance: f64,
+    /// Error de orientación admitido (rad)
+    pub heading_tolerance: f64,
+}
+
+impl Default for ApproachConfig {
+    fn default() -> Self {
+        Self {
+            k_rho: 0.6,
+            k_alpha: 1.8,
+            k_beta: -0.6,
+            max_linear_speed: 0.12,
+            max_angular_speed: 0.6,
+            position_tolerance: 0.015,
+            heading_tolerance: 3f64.to_radians(),
+        }
+    }
+}
+
+/// Ley de control en coordenadas polares (Astolfi) hacia una pose expresada
+/// en el marco del robot. Es estable para `k_rho > 0`, `k_beta < 0` y
+/// `k_alpha > k_rho`, y llega alineado sin maniobras de corrección.
+#[derive(Debug, Clone)]
+pub struct ApproachController {
+    config: ApproachConfig,
+}
+
+impl ApproachController {
+    pub fn new(config: ApproachConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn get_config(&self) -> &ApproachConfig {
+        &self.config
+    }
+
+    /// `true` si `target`, relativo al robot, está dentro de tolerancia
+    pub fn reached(&self, target: &Pose2D) -> bool {
+        target.position().norm() <= self.config.position_tolerance
+            && target.theta.abs() <= self.config.heading_tolerance
+    }
+
+    /// Velocidades para llevar el robot a `target`, expresado en su marco
+    pub fn compute(&self, target: &Pose2D) -> ControlInput {
+        if self.reached(target) {
+            return ControlInput::zero();
+        }
+
+        let Pose2D { x, y, theta } = *target;
+        let rho = x.hypot(y);
+        let config = &self.config;
+
+        // Ya en posición: solo queda girar
+        if rho <= config.position_tolerance {
+            let angular = (config.k_alpha * normalize_angle(theta))
+                .clamp(-config.max_angular_speed, config.max_angular_speed);
+            return ControlInput::new(0.0, angular);
+        }
+
+        let alpha = y.atan2(x);
+        let beta = normalize_angle(theta - alpha);
+        let linear = (config.k_rho * rho).min(config.max_linear_speed);
+        let angular = (config.k_alpha * alpha + config.k_beta * beta)
+            .clamp(-config.max_angular_speed, config.max_angular_speed);
+
+        // Con el objetivo muy desviado se gira antes de avanzar
+        let linear = if alpha.abs() > std::f64::consts::FRAC_PI_2 {
+            0.0
+        } else {
+            linear * alpha.cos()
+        };
+        ControlInput::new(linear, angular)
+    }
+}
+
+impl Default for ApproachController {
+    fn default() -> Self {
+        Self::new(ApproachConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_approach_converges_aligned() {
+        let controller = ApproachController::default();
+        // Objetivo 0.6 m delante, 0.15 m a la izquierda y girado 20°
+        let goal = Pose2D::new(0.6, 0.15, 20f64.to_radians());
+        let mut pose = Pose2D::identity();
+        let dt = 0.05;
+
+        for _ in 0..2000 {
+            // Objetivo en el marco del robot
+            let target = pose.relative(&goal);
+            if controller.reached(&target) {
+                break;
+            }
+            let command = controller.compute(&target);
+            assert!(command.linear_x <= 0.12 + 1e-9);
+            pose = pose * Pose2D::new(command.linear_x * dt, 0.0, command.angular_z * dt);
+        }
+
+        assert!(pose.distance_to(&goal) < 0.02, "{:?}", pose);
+        assert!(
+            (pose.theta - goal.theta).abs() < 4f64.to_radians(),
+            "{:?}",
+            pose
+        );
+    }
+}
@@ -0,0 +1,435 @@
+//! Detección de la base de carga: patrón en V o reflector en el lidar y
+//! marcador fiducial en la cámara
+use crate::geometry::{Point2, Pose2D};
+use crate::navigation::slam::features::{self, FeatureConfig, LineFeature};
+use crate::sensors::obstacles;
+use crate::sensors::{LidarData, MountingPose};
+use serde::{Deserialize, Serialize};
+
+/// Forma de la base tal y como la ve el lidar
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DockPattern {
+    /// Dos paneles en V abiertos hacia el robot; `angle` es el ángulo
+    /// interior (rad) y `arm_length` la longitud de cada panel (m)
+    VShape { angle: f64, arm_length: f64 },
+    /// Banda retrorreflectante de `width` metros; sus puntos llegan con
+    /// calidad (intensidad) igual o superior a `min_quality`
+    Reflector { width: f64, min_quality: u16 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CameraIntrinsics {
+    pub fx: f64,
+    pub fy: f64,
+    pub cx: f64,
+    pub cy: f64,
+}
+
+/// Marcador fiducial cuadrado pegado en el frontal de la base
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarkerConfig {
+    pub id: u32,
+    /// Lado del marcador (m)
+    pub size: f64,
+    pub intrinsics: CameraIntrinsics,
+    pub mounting: MountingPose,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DockDetectorConfig {
+    pub pattern: DockPattern,
+    /// Error admitido en longitudes y anchuras del patrón (m)
+    pub length_tolerance: f64,
+    /// Error admitido en el ángulo interior de la V (rad)
+    pub angle_tolerance: f64,
+    pub max_range: f64,
+    pub lidar_mounting: MountingPose,
+    pub marker: Option<MarkerConfig>,
+}
+
+impl Default for DockDetectorConfig {
+    fn default() -> Self {
+        Self {
+            pattern: DockPattern::VShape {
+                angle: 120f64.to_radians(),
+                arm_length: 0.25,
+            },
+            length_tolerance: 0.05,
+            angle_tolerance: 10f64.to_radians(),
+            max_range: 2.5,
+            lidar_mounting: MountingPose::default(),
+            marker: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DetectionSource {
+    Lidar,
+    Marker,
+}
+
+/// Base detectada en el marco del robot: `(x, y)` es el punto de referencia
+/// del patrón (vértice de la V, centro del reflector o del marcador) y
+/// `theta` la orientación que debe tener el robot para entrar en ella
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DockDetection {
+    pub x: f64,
+    pub y: f64,
+    pub theta: f64,
+    pub source: DetectionSource,
+}
+
+impl DockDetection {
+    pub fn pose(&self) -> Pose2D {
+        Pose2D::new(self.x, self.y, self.theta)
+    }
+}
+
+/// Esquinas de un marcador en la imagen (píxeles), en el orden superior
+/// izquierda, superior derecha, inferior derecha, inferior izquierda, tal y
+/// como las entrega un detector ArUco/AprilTag
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarkerObservation {
+    pub id: u32,
+    pub corners: [(f64, f64); 4],
+}
+
+#[derive(Debug, Clone)]
+pub struct DockDetector {
+    config: DockDetectorConfig,
+    features: FeatureConfig,
+}
+
+impl DockDetector {
+    pub fn new(config: DockDetectorConfig) -> Self {
+        // Los paneles son cortos: se relajan los mínimos pensados para paredes
+        let features = match config.pattern {
+            DockPattern::VShape { arm_length, .. } => FeatureConfig {
+                split_threshold: 0.02,
+                merge_threshold: 0.015,
+                min_points: 4,
+                min_length: (arm_length - config.length_tolerance).max(0.05),
+                ..Default::default()
+            },
+            DockPattern::Reflector { .. } => FeatureConfig::default(),
+        };
+        Self { config, features }
+    }
+
+    pub fn get_config(&self) -> &DockDetectorConfig {
+        &self.config
+    }
+
+    /// Busca el patrón en el escaneo; devuelve el más cercano
+    pub fn detect_lidar(&self, scan: &LidarData) -> Option<DockDetection> {
+        let detections = match self.config.pattern {
+            DockPattern::VShape { angle, arm_length } => {
+                self.find_v_shapes(scan, angle, arm_length)
+            }
+            DockPattern::Reflector { width, min_quality } => {
+                self.find_reflectors(scan, width, min_quality)
+            }
+        };
+
+        detections
+            .into_iter()
+            .filter(|pose| pose.position().norm() <= self.config.max_range)
+            .min_by(|a, b| a.position().norm().total_cmp(&b.position().norm()))
+            .map(|pose| to_detection(pose, self.config.lidar_mounting, DetectionSource::Lidar))
+    }
+
+    /// Pose de la base a partir del marcador: la altura aparente de cada
+    /// borde vertical da su distancia (modelo pinhole) y la columna su rumbo
+    pub fn detect_marker(&self, observation: &MarkerObservation) -> Option<DockDetection> {
+        let marker = self.config.marker.as_ref()?;
+        if observation.id != marker.id {
+            return None;
+        }
+
+        let [top_left, top_right, bottom_right, bottom_left] = observation.corners;
+        let edge = |top: (f64, f64), bottom: (f64, f64)| -> Option<Point2> {
+            let height = (bottom.1 - top.1).abs();
+            if height < 1.0 {
+                return None;
+            }
+            let depth = marker.intrinsics.fy * marker.size / height;
+            let column = (top.0 + bottom.0) / 2.0;
+            let lateral = (column - marker.intrinsics.cx) * depth / marker.intrinsics.fx;
+            // Cámara: z hacia delante y x a la derecha; robot: x delante, y izquierda
+            Some(Point2::new(depth, -lateral))
+        };
+        let left = edge(top_left, bottom_left)?;
+        let right = edge(top_right, bottom_right)?;
+
+        let center = left.lerp(&right, 0.5);
+        if center.norm() > self.config.max_range {
+            return None;
+        }
+        // El robot entra perpendicular al marcador, de izquierda a derecha
+        let edge = right - left;
+        let theta = edge.x.atan2(-edge.y);
+        Some(to_detection(
+            Pose2D::from_position(center, theta),
+            marker.mounting,
+            DetectionSource::Marker,
+        ))
+    }
+
+    fn find_v_shapes(&self, scan: &LidarData, angle: f64, arm_length: f64) -> Vec<Pose2D> {
+        let mut found = Vec::new();
+        for chain in features::extract_lines(scan, &self.features) {
+            for pair in chain.lines.windows(2) {
+                if let Some(pose) = self.v_shape(&pair[0], &pair[1], angle, arm_length) {
+                    found.push(pose);
+                }
+            }
+        }
+        found
+    }
+
+    fn v_shape(
+        &self,
+        first: &LineFeature,
+        second: &LineFeature,
+        angle: f64,
+        arm_length: f64,
+    ) -> Option<Pose2D> {
+        let vertex = intersection(first, second)?;
+        // Extremos libres de cada panel, lejos del vértice
+        let a = first.start - vertex;
+        let b = second.end - vertex;
+        let (length_a, length_b) = (a.norm(), b.norm());
+        let tolerance = self.config.length_tolerance;
+        if (length_a - arm_length).abs() > tolerance || (length_b - arm_length).abs() > tolerance {
+            return None;
+        }
+
+        let (ua, ub) = (a * (1.0 / length_a), b * (1.0 / length_b));
+        let interior = ua.dot(&ub).clamp(-1.0, 1.0).acos();
+        if (interior - angle).abs() > self.config.angle_tolerance {
+            return None;
+        }
+
+        // La bisectriz apunta hacia la abertura, que debe mirar al lidar
+        let opening = ua + ub;
+        if opening.dot(&-vertex) <= 0.0 {
+            return None;
+        }
+        Some(Pose2D::from_position(vertex, (-opening).angle()))
+    }
+
+    fn find_reflectors(&self, scan: &LidarData, width: f64, min_quality: u16) -> Vec<Pose2D> {
+        // Tramos de puntos brillantes consecutivos
+        let mut runs: Vec<Vec<Point2>> = Vec::new();
+        let mut current: Vec<Point2> = Vec::new();
+        for point in &scan.points {
+            let bright = point.quality >= min_quality && point.distance.is_finite();
+            let xy = Point2::from_polar(point.distance, point.angle);
+            let contiguous = current
+                .last()
+                .is_none_or(|last| last.distance_to(&xy) <= 0.1);
+            if bright && contiguous {
+                current.push(xy);
+                continue;
+            }
+            if !current.is_empty() {
+                runs.push(std::mem::take(&mut current));
+            }
+            if bright {
+                current.push(xy);
+            }
+        }
+        runs.push(current);
+
+        runs.iter()
+            .filter(|run| run.len() >= 3)
+            .filter_map(|run| obstacles::fit_line(run))
+            .filter_map(|(start, end, _)| {
+                let length = start.distance_to(&end);
+                if (length - width).abs() > self.config.length_tolerance {
+                    return None;
+                }
+                let center = start.lerp(&end, 0.5);
+                // Normal de la banda orientada hacia el lidar
+                let mut normal = (end - start).rotated(std::f64::consts::FRAC_PI_2);
+                if normal.dot(&center) > 0.0 {
+                    normal = -normal;
+                }
+                Some(Pose2D::from_position(center, (-normal).angle()))
+            })
+            .collect()
+    }
+}
+
+impl Default for DockDetector {
+    fn default() -> Self {
+        Self::new(DockDetectorConfig::default())
+    }
+}
+
+/// Intersección de dos rectas en forma normal de Hesse
+fn intersection(a: &LineFeature, b: &LineFeature) -> Option<Point2> {
+    let (sin_a, cos_a) = a.alpha.sin_cos();
+    let (sin_b, cos_b) = b.alpha.sin_cos();
+    let det = cos_a * sin_b - sin_a * cos_b;
+    if det.abs() < 1e-6 {
+        return None;
+    }
+    Some(Point2::new(
+        (a.rho * sin_b - b.rho * sin_a) / det,
+        (b.rho * cos_a - a.rho * cos_b) / det,
+    ))
+}
+
+/// Pasa una pose del marco del sensor al del robot
+fn to_detection(pose: Pose2D, mounting: MountingPose, source: DetectionSource) -> DockDetection {
+    let Pose2D { x, y, theta } = mounting * pose;
+    DockDetection {
+        x,
+        y,
+        theta,
+        source,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::sensors::LidarPoint;
+
+    /// Escaneo frontal de una V con el vértice en `vertex` y la abertura
+    /// orientada según `facing` (rad), delante de una pared en x = `wall`
+    pub(crate) fn v_scan(vertex: Point2, facing: f64, arm_length: f64, wall: f64) -> LidarData {
+        let half = 60f64.to_radians();
+        let arms = [facing + half, facing - half]
+            .map(|direction| (vertex, vertex + Point2::from_polar(arm_length, direction)));
+
+        let points = (0..=360)
+            .map(|i| {
+                let angle = (-90.0 + i as f64 * 0.5f64).to_radians();
+                let ray = Point2::from_polar(1.0, angle);
+                let mut distance = if ray.x > 1e-6 {
+                    wall / ray.x
+                } else {
+                    f64::INFINITY
+                };
+                for (a, b) in arms {
+                    if let Some(t) = ray_segment(ray, a, b) {
+                        distance = distance.min(t);
+                    }
+                }
+                LidarPoint {
+                    angle,
+                    distance,
+                    quality: 47,
+                    timestamp: 0.0,
+                }
+            })
+            .collect();
+
+        LidarData {
+            frame_id: "laser".to_string(),
+            points,
+            scan_time: 0.1,
+            min_angle: -std::f64::consts::FRAC_PI_2,
+            max_angle: std::f64::consts::FRAC_PI_2,
+            min_range: 0.15,
+            max_range: 12.0,
+        }
+    }
+
+    fn ray_segment(ray: Point2, a: Point2, b: Point2) -> Option<f64> {
+        let edge = b - a;
+        let det = edge.cross(&ray);
+        if det.abs() < 1e-12 {
+            return None;
+        }
+        let t = edge.cross(&a) / det;
+        let s = ray.cross(&a) / det;
+        (t > 0.0 && (0.0..=1.0).contains(&s)).then_some(t)
+    }
+
+    #[test]
+    fn test_v_shape_detection() {
+        // Base a 1.2 m, algo desplazada y girada 15° respecto al robot
+        let facing = std::f64::consts::PI + 15f64.to_radians();
+        let scan = v_scan(Point2::new(1.2, 0.2), facing, 0.25, 1.6);
+        let detection = DockDetector::default().detect_lidar(&scan).unwrap();
+
+        assert_eq!(detection.source, DetectionSource::Lidar);
+        assert!((detection.x - 1.2).abs() < 0.02, "{:?}", detection);
+        assert!((detection.y - 0.2).abs() < 0.02, "{:?}", detection);
+        assert!(
+            (detection.theta - 15f64.to_radians()).abs() < 0.05,
+            "{:?}",
+            detection
+        );
+
+        // Una pared lisa no es una base
+        let wall = v_scan(Point2::new(5.0, 5.0), 0.0, 0.25, 1.6);
+        assert!(DockDetector::default().detect_lidar(&wall).is_none());
+    }
+
+    #[test]
+    fn test_reflector_and_marker_detection() {
+        let detector = DockDetector::new(DockDetectorConfig {
+            pattern: DockPattern::Reflector {
+                width: 0.2,
+                min_quality: 200,
+            },
+            marker: Some(MarkerConfig {
+                id: 7,
+                size: 0.1,
+                intrinsics: CameraIntrinsics {
+                    fx: 600.0,
+                    fy: 600.0,
+                    cx: 320.0,
+                    cy: 240.0,
+                },
+                mounting: MountingPose {
+                    x: 0.1,
+                    y: 0.0,
+                    theta: 0.0,
+                },
+            }),
+            ..Default::default()
+        });
+
+        // Reflector de 0.2 m centrado a 1 m sobre una pared perpendicular
+        let mut scan = v_scan(Point2::new(5.0, 5.0), 0.0, 0.25, 1.0);
+        for point in &mut scan.points {
+            if (point.distance * point.angle.sin()).abs() <= 0.1 {
+                point.quality = 255;
+            }
+        }
+        let reflector = detector.detect_lidar(&scan).unwrap();
+        assert!((reflector.x - 1.0).abs() < 0.01 && reflector.y.abs() < 0.01);
+        assert!(reflector.theta.abs() < 0.02, "{:?}", reflector);
+
+        // Marcador de 10 cm visto de frente a 0.6 m de la cámara
+        let half = 600.0 * 0.05 / 0.6;
+        let observation = MarkerObservation {
+            id: 7,
+            corners: [
+                (320.0 - half, 240.0 - half),
+                (320.0 + half, 240.0 - half),
+                (320.0 + half, 240.0 + half),
+                (320.0 - half, 240.0 + half),
+            ],
+        };
+        let marker = detector.detect_marker(&observation).unwrap();
+        assert_eq!(marker.source, DetectionSource::Marker);
+        assert!((marker.x - 0.7).abs() < 1e-9 && marker.y.abs() < 1e-9);
+        assert!(marker.theta.abs() < 1e-9);
+
+        let other = MarkerObservation {
+            id: 3,
+            ..observation
+        };
+        assert!(detector.detect_marker(&other).is_none());
+    }
+}
@@ -0,0 +1,446 @@
+//! Acoplamiento autónomo a la base de carga: navegación hasta la pose de
+//! espera, búsqueda del patrón, aproximación final y confirmación de carga
+pub mod approach;
+pub mod detector;
+
+pub use approach::{ApproachConfig, ApproachController};
+pub use detector::{
+    DetectionSource, DockDetection, DockDetector, DockDetectorConfig, DockPattern,
+    MarkerObservation,
+};
+
+use crate::control::{ControlInput, RobotState};
+use crate::geometry::Pose2D;
+use crate::power::BatteryState;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DockingConfig {
+    pub detector: DockDetectorConfig,
+    pub approach: ApproachConfig,
+    /// Distancia frente a la base donde empieza la aproximación final (m)
+    pub staging_distance: f64,
+    pub staging_tolerance: f64,
+    /// Distancia del punto de referencia del patrón al centro del robot
+    /// cuando está acoplado (m)
+    pub contact_offset: f64,
+    /// Giro en el sitio mientras se busca el patrón (rad/s)
+    pub search_speed: f64,
+    pub search_timeout: f64,
+    /// Tiempo sin detecciones antes de volver a buscar (s)
+    pub lost_timeout: f64,
+    /// Espera en contacto hasta que el monitor de batería confirma la carga (s)
+    pub charge_timeout: f64,
+    pub max_attempts: u32,
+    /// Retroceso al desacoplar o antes de reintentar (m)
+    pub undock_distance: f64,
+    pub undock_speed: f64,
+}
+
+impl Default for DockingConfig {
+    fn default() -> Self {
+        Self {
+            detector: DockDetectorConfig::default(),
+            approach: ApproachConfig::default(),
+            staging_distance: 0.8,
+            staging_tolerance: 0.15,
+            contact_offset: 0.2,
+            search_speed: 0.3,
+            search_timeout: 25.0,
+            lost_timeout: 1.0,
+            charge_timeout: 5.0,
+            max_attempts: 3,
+            undock_distance: 0.4,
+            undock_speed: 0.1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DockingState {
+    Idle,
+    /// Navegando con el planificador hasta la pose de espera
+    Navigating,
+    Searching,
+    Approaching,
+    WaitingForCharge,
+    Docked,
+    Undocking,
+    Failed,
+}
+
+/// Resumen publicado por la API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DockingStatus {
+    pub state: DockingState,
+    pub attempts: u32,
+    pub dock: RobotState,
+    pub message: Option<String>,
+}
+
+/// Lo que el comportamiento pide al bucle de control
+#[derive(Debug, Clone)]
+pub enum DockingCommand {
+    /// Ir a la pose (marco del mapa) con el planificador de rutas
+    Navigate(RobotState),
+    /// Velocidades directas del controlador de aproximación
+    Velocity(ControlInput),
+    Idle,
+}
+
+pub struct DockingBehavior {
+    config: DockingConfig,
+    detector: DockDetector,
+    controller: ApproachController,
+    /// Pose de referencia del patrón en el mapa; `theta` mira hacia la base
+    dock: RobotState,
+    state: DockingState,
+    attempts: u32,
+    elapsed: f64,
+    since_detection: f64,
+    /// Pose de contacto en el mapa según la última detección
+    target: Option<RobotState>,
+    undock_start: Option<RobotState>,
+    retry_after_undock: bool,
+    message: Option<String>,
+}
+
+impl DockingBehavior {
+    pub fn new(config: DockingConfig, dock: RobotState) -> Self {
+        Self {
+            detector: DockDetector::new(config.detector.clone()),
+            controller: ApproachController::new(config.approach.clone()),
+            config,
+            dock,
+            state: DockingState::Idle,
+            attempts: 0,
+            elapsed: 0.0,
+            since_detection: 0.0,
+            target: None,
+            undock_start: None,
+            retry_after_undock: false,
+            message: None,
+        }
+    }
+
+    pub fn state(&self) -> DockingState {
+        self.state
+    }
+
+    pub fn detector(&self) -> &DockDetector {
+        &self.detector
+    }
+
+    /// Pose guardada de la base; se refina cada vez que se confirma la carga
+    pub fn dock_pose(&self) -> &RobotState {
+        &self.dock
+    }
+
+    pub fn set_dock_pose(&mut self, dock: RobotState) {
+        self.dock = dock;
+    }
+
+    pub fn status(&self) -> DockingStatus {
+        DockingStatus {
+            state: self.state,
+            attempts: self.attempts,
+            dock: self.dock.clone(),
+            message: self.message.clone(),
+        }
+    }
+
+    /// Pose frente a la base desde la que empieza la aproximación final
+    pub fn staging_pose(&self) -> RobotState {
+        let distance = self.config.staging_distance + self.config.contact_offset;
+        RobotState::new(
+            self.dock.x - distance * self.dock.theta.cos(),
+            self.dock.y - distance * self.dock.theta.sin(),
+            self.dock.theta,
+        )
+    }
+
+    /// Empieza a acoplar; no hace nada si ya está acoplado
+    pub fn dock(&mut self) {
+        if matches!(
+            self.state,
+            DockingState::Docked | DockingState::WaitingForCharge
+        ) {
+            return;
+        }
+        log::info!("🏠 Volviendo a la base de carga");
+        self.attempts = 1;
+        self.message = None;
+        self.enter(DockingState::Navigating);
+    }
+
+    /// Retrocede para separarse de la base
+    pub fn undock(&mut self) -> Result<(), String> {
+        match self.state {
+            DockingState::Docked | DockingState::WaitingForCharge | DockingState::Approaching => {
+                log::info!("🔌 Desacoplando de la base");
+                self.retry_after_undock = false;
+                self.undock_start = None;
+                self.enter(DockingState::Undocking);
+                Ok(())
+            }
+            state => Err(format!("No se puede desacoplar en estado {:?}", state)),
+        }
+    }
+
+    pub fn cancel(&mut self) {
+        self.enter(DockingState::Idle);
+    }
+
+    /// Avanza la máquina de estados. `pose` es la pose en el mapa, la
+    /// detección está en el marco del robot y `battery` es la última
+    /// lectura del monitor de batería.
+    pub fn step(
+        &mut self,
+        pose: &RobotState,
+        detection: Option<&DockDetection>,
+        battery: Option<&BatteryState>,
+        dt: f64,
+    ) -> DockingCommand {
+        self.elapsed += dt;
+        let charging = battery.is_some_and(|battery| battery.charging);
+
+        if let Some(detection) = detection {
+            self.since_detection = 0.0;
+            self.target = Some(self.contact_pose(pose, detection));
+        } else {
+            self.since_detection += dt;
+        }
+
+        match self.state {
+            DockingState::Idle | DockingState::Docked | DockingState::Failed => {
+                DockingCommand::Idle
+            }
+            DockingState::Navigating => {
+                let staging = self.staging_pose();
+                if pose.distance_to(&staging) <= self.config.staging_tolerance {
+                    self.enter(DockingState::Searching);
+                    return self.step(pose, detection, battery, 0.0);
+                }
+                DockingCommand::Navigate(staging)
+            }
+            DockingState::Searching => {
+                if detection.is_some() {
+                    log::info!("🎯 Base detectada, aproximación final");
+                    self.enter(DockingState::Approaching);
+                    return self.step(pose, None, battery, 0.0);
+                }
+                if self.elapsed > self.config.search_timeout {
+                    return self.fail("Base no detectada");
+                }
+                DockingCommand::Velocity(ControlInput::new(0.0, self.config.search_speed))
+            }
+            DockingState::Approaching => {
+                if charging {
+                    return self.confirm_docked(pose);
+                }
+                let Some(target) = self.target.clone() else {
+                    self.enter(DockingState::Searching);
+                    return DockingCommand::Velocity(ControlInput::zero());
+                };
+                if self.since_detection > self.config.lost_timeout
+                    && pose.distance_to(&target) > self.config.staging_distance / 2.0
+                {
+                    // Cerca de la base el patrón sale del campo de visión y se
+                    // sigue con odometría; lejos, se vuelve a buscar
+                    log::warn!("⚠️ Base perdida durante la aproximación");
+                    self.enter(DockingState::Searching);
+                    return DockingCommand::Velocity(ControlInput::zero());
+                }
+
+                let relative = pose.pose().relative(&target.pose());
+                if self.controller.reached(&relative) {
+                    self.enter(DockingState::WaitingForCharge);
+                    return DockingCommand::Velocity(ControlInput::zero());
+                }
+                DockingCommand::Velocity(self.controller.compute(&relative))
+            }
+            DockingState::WaitingForCharge => {
+                if charging {
+                    return self.confirm_docked(pose);
+                }
+                if self.elapsed > self.config.charge_timeout {
+                    return self.retry("Sin carga tras el contacto");
+                }
+                DockingCommand::Velocity(ControlInput::zero())
+            }
+            DockingState::Undocking => {
+                let start = self.undock_start.get_or_insert_with(|| pose.clone());
+                if pose.distance_to(start) >= self.config.undock_distance {
+                    let next = if self.retry_after_undock {
+                        DockingState::Searching
+                    } else {
+                        log::info!("✅ Desacoplado");
+                        DockingState::Idle
+                    };
+                    self.enter(next);
+                    return DockingCommand::Velocity(ControlInput::zero());
+                }
+                DockingCommand::Velocity(ControlInput::new(-self.config.undock_speed, 0.0))
+            }
+        }
+    }
+
+    /// Pose de contacto en el mapa: a `contact_offset` del patrón, mirándolo
+    fn contact_pose(&self, pose: &RobotState, detection: &DockDetection) -> RobotState {
+        let dock = pose.pose() * detection.pose();
+        RobotState::from(dock * Pose2D::new(-self.config.contact_offset, 0.0, 0.0))
+    }
+
+    fn confirm_docked(&mut self, pose: &RobotState) -> DockingCommand {
+        let offset = self.config.contact_offset;
+        // La pose acoplada fija la posición real de la base para la próxima vez
+        self.dock = RobotState::new(
+            pose.x + offset * pose.theta.cos(),
+            pose.y + offset * pose.theta.sin(),
+            pose.theta,
+        );
+        log::info!("🔌 Acoplado y cargando (intento {})", self.attempts);
+        self.enter(DockingState::Docked);
+        DockingCommand::Velocity(ControlInput::zero())
+    }
+
+    fn retry(&mut self, reason: &str) -> DockingCommand {
+        if self.attempts >= self.config.max_attempts {
+            return self.fail(reason);
+        }
+        log::warn!("🔁 {}: reintentando el acoplamiento", reason);
+        self.attempts += 1;
+        self.retry_after_undock = true;
+        self.undock_start = None;
+        self.enter(DockingState::Undocking);
+        DockingCommand::Velocity(ControlInput::zero())
+    }
+
+    fn fail(&mut self, reason: &str) -> DockingCommand {
+        log::error!("❌ Acoplamiento fallido: {}", reason);
+        self.message = Some(reason.to_string());
+        self.enter(DockingState::Failed);
+        DockingCommand::Velocity(ControlInput::zero())
+    }
+
+    fn enter(&mut self, state: DockingState) {
+        self.state = state;
+        self.elapsed = 0.0;
+        if state == DockingState::Searching {
+            self.target = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::power::PowerLevel;
+
+    fn battery(charging: bool) -> BatteryState {
+        BatteryState {
+            voltage: 11.1,
+            current: if charging { -2.0 } else { 0.5 },
+            temperature: None,
+            state_of_charge: 0.2,
+            charging,
+            level: PowerLevel::Low,
+            timestamp: 0.0,
+        }
+    }
+
+    /// Detección que vería el robot desde `pose` de una base en `dock`
+    fn observe(pose: &RobotState, dock: &RobotState) -> DockDetection {
+        let Pose2D { x, y, theta } = pose.pose().relative(&dock.pose());
+        DockDetection {
+            x,
+            y,
+            theta,
+            source: DetectionSource::Lidar,
+        }
+    }
+
+    #[test]
+    fn test_dock_charge_and_undock() {
+        let stored = RobotState::new(2.0, 1.0, 0.0);
+        let mut behavior = DockingBehavior::new(DockingConfig::default(), stored);
+        // La base real está algo desplazada de la guardada
+        let real_dock = RobotState::new(2.05, 0.96, 5f64.to_radians());
+        behavior.dock();
+
+        // El planificador lleva el robot a la pose de espera
+        let DockingCommand::Navigate(staging) =
+            behavior.step(&RobotState::new(0.0, 0.0, 0.0), None, None, 0.1)
+        else {
+            panic!("se esperaba navegación");
+        };
+        assert!((staging.x - 1.0).abs() < 1e-9 && (staging.y - 1.0).abs() < 1e-9);
+
+        let mut pose = RobotState::new(staging.x + 0.05, staging.y - 0.05, 0.1);
+        let dt = 0.05;
+        for _ in 0..2000 {
+            if behavior.state() == DockingState::WaitingForCharge {
+                break;
+            }
+            // El patrón se pierde en los últimos centímetros
+            let visible = pose.distance_to(&real_dock) > 0.35;
+            let detection = visible.then(|| observe(&pose, &real_dock));
+            let command = behavior.step(&pose, detection.as_ref(), Some(&battery(false)), dt);
+            let DockingCommand::Velocity(input) = command else {
+                panic!("comando inesperado {:?}", command);
+            };
+            pose.x += input.linear_x * pose.theta.cos() * dt;
+            pose.y += input.linear_x * pose.theta.sin() * dt;
+            pose.theta += input.angular_z * dt;
+        }
+        assert_eq!(behavior.state(), DockingState::WaitingForCharge);
+
+        // Contacto: el monitor de batería confirma la carga
+        behavior.step(&pose, None, Some(&battery(true)), dt);
+        assert_eq!(behavior.state(), DockingState::Docked);
+        let refined = behavior.dock_pose();
+        assert!(refined.distance_to(&real_dock) < 0.03, "{:?}", refined);
+
+        // Desacoplar: retroceso en línea recta
+        behavior.undock().unwrap();
+        let start = pose.clone();
+        while behavior.state() == DockingState::Undocking {
+            let DockingCommand::Velocity(input) = behavior.step(&pose, None, None, dt) else {
+                unreachable!()
+            };
+            assert!(input.linear_x <= 0.0);
+            pose.x += input.linear_x * pose.theta.cos() * dt;
+            pose.y += input.linear_x * pose.theta.sin() * dt;
+        }
+        assert_eq!(behavior.state(), DockingState::Idle);
+        assert!(pose.distance_to(&start) >= 0.4);
+        assert!(behavior.undock().is_err());
+    }
+
+    #[test]
+    fn test_gives_up_without_charge() {
+        let config = DockingConfig {
+            max_attempts: 2,
+            ..Default::default()
+        };
+        let mut behavior = DockingBehavior::new(config, RobotState::new(1.0, 0.0, 0.0));
+        behavior.dock();
+        behavior.state = DockingState::WaitingForCharge;
+
+        let mut pose = RobotState::new(0.8, 0.0, 0.0);
+        for _ in 0..10_000 {
+            if behavior.state() == DockingState::Failed {
+                break;
+            }
+            if let DockingCommand::Velocity(input) = behavior.step(&pose, None, None, 0.1) {
+                pose.x += input.linear_x * 0.1;
+            }
+            // Tras retroceder no se vuelve a ver la base
+        }
+        assert_eq!(behavior.state(), DockingState::Failed);
+        assert_eq!(behavior.status().attempts, 2);
+        assert!(behavior.status().message.is_some());
+    }
+}
@@ -0,0 +1,224 @@
+//! Conversión WGS84 ↔ marco local ENU (este-norte-arriba) anclado en un datum
+use crate::control::RobotState;
+use crate::geometry::normalize_angle;
+use crate::sensors::GpsData;
+use serde::{Deserialize, Serialize};
+
+/// Semieje mayor del elipsoide WGS84 (m)
+const WGS84_A: f64 = 6_378_137.0;
+/// Achatamiento del elipsoide WGS84
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+/// Primera excentricidad al cuadrado
+const WGS84_E2: f64 = WGS84_F * (2.0 - WGS84_F);
+
+/// Punto geodésico WGS84
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GeoPoint {
+    pub latitude: f64,  // grados
+    pub longitude: f64, // grados
+    #[serde(default)]
+    pub altitude: f64, // metros sobre el elipsoide
+}
+
+impl GeoPoint {
+    pub fn new(latitude: f64, longitude: f64, altitude: f64) -> Self {
+        Self {
+            latitude,
+            longitude,
+            altitude,
+        }
+    }
+
+    /// Coordenadas cartesianas geocéntricas (ECEF) en metros
+    pub fn to_ecef(&self) -> [f64; 3] {
+        let (sin_lat, cos_lat) = self.latitude.to_radians().sin_cos();
+        let (sin_lon, cos_lon) = self.longitude.to_radians().sin_cos();
+        let n = WGS84_A / (1.0 - WGS84_E2 * sin_lat * sin_lat).sqrt();
+        [
+            (n + self.altitude) * cos_lat * cos_lon,
+            (n + self.altitude) * cos_lat * sin_lon,
+            (n * (1.0 - WGS84_E2) + self.altitude) * sin_lat,
+        ]
+    }
+
+    /// Inversa de `to_ecef` por iteración de la latitud
+    pub fn from_ecef(ecef: [f64; 3]) -> Self {
+        let [x, y, z] = ecef;
+        let p = x.hypot(y);
+        let longitude = y.atan2(x);
+        let mut latitude = z.atan2(p * (1.0 - WGS84_E2));
+        let mut altitude = 0.0;
+
+        for _ in 0..5 {
+            let sin_lat = latitude.sin();
+            let n = WGS84_A / (1.0 - WGS84_E2 * sin_lat * sin_lat).sqrt();
+            altitude = p / latitude.cos() - n;
+            latitude = z.atan2(p * (1.0 - WGS84_E2 * n / (n + altitude)));
+        }
+
+        Self::new(latitude.to_degrees(), longitude.to_degrees(), altitude)
+    }
+}
+
+impl From<&GpsData> for GeoPoint {
+    fn from(gps: &GpsData) -> Self {
+        Self::new(gps.latitude, gps.longitude, gps.altitude)
+    }
+}
+
+/// Plano tangente local: x = este, y = norte, z = arriba, con origen en el
+/// datum. Es el marco del mapa cuando la navegación usa GNSS.
+#[derive(Debug, Clone)]
+pub struct LocalFrame {
+    datum: GeoPoint,
+    origin: [f64; 3],
+    /// Filas: ejes este, norte y arriba expresados en ECEF
+    rotation: [[f64; 3]; 3],
+}
+
+impl LocalFrame {
+    pub fn new(datum: GeoPoint) -> Self {
+        let (sin_lat, cos_lat) = datum.latitude.to_radians().sin_cos();
+        let (sin_lon, cos_lon) = datum.longitude.to_radians().sin_cos();
+        Self {
+            datum,
+            origin: datum.to_ecef(),
+            rotation: [
+                [-sin_lon, cos_lon, 0.0],
+                [-sin_lat * cos_lon, -sin_lat * sin_lon, cos_lat],
+                [cos_lat * cos_lon, cos_lat * sin_lon, sin_lat],
+            ],
+        }
+    }
+
+    pub fn datum(&self) -> GeoPoint {
+        self.datum
+    }
+
+    /// `[este, norte, arriba]` en metros
+    pub fn to_enu(&self, point: &GeoPoint) -> [f64; 3] {
+        let ecef = point.to_ecef();
+        let delta = [
+            ecef[0] - self.origin[0],
+            ecef[1] - self.origin[1],
+            ecef[2] - self.origin[2],
+        ];
+        self.rotation
+            .map(|axis| axis[0] * delta[0] + axis[1] * delta[1] + axis[2] * delta[2])
+    }
+
+    pub fn to_geodetic(&self, enu: [f64; 3]) -> GeoPoint {
+        // La rotación es ortogonal: su inversa es la traspuesta
+        let r = &self.rotation;
+        let ecef = [0, 1, 2]
+            .map(|i| self.origin[i] + r[0][i] * enu[0] + r[1][i] * enu[1] + r[2][i] * enu[2]);
+        GeoPoint::from_ecef(ecef)
+    }
+}
+
+/// Rumbo de brújula (grados desde el norte, horario) a orientación ENU
+/// (radianes desde el este, antihorario)
+pub fn course_to_yaw(course: f64) -> f64 {
+    normalize_angle(std::f64::consts::FRAC_PI_2 - course.to_radians())
+}
+
+/// Objetivo de navegación en el marco del mapa o en coordenadas geodésicas
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Waypoint {
+    Local {
+        x: f64,
+        y: f64,
+        #[serde(default)]
+        theta: f64,
+    },
+    Geodetic {
+        latitude: f64,
+        longitude: f64,
+        /// Rumbo de llegada en grados desde el norte
+        #[serde(default)]
+        heading: Option<f64>,
+    },
+}
+
+impl Waypoint {
+    /// Pose objetivo en el marco del mapa; los waypoints geodésicos
+    /// necesitan el marco local del datum
+    pub fn resolve(&self, frame: Option<&LocalFrame>) -> Result<RobotState, String> {
+        match *self {
+            Waypoint::Local { x, y, theta } => Ok(RobotState::new(x, y, theta)),
+            Waypoint::Geodetic {
+                latitude,
+                longitude,
+                heading,
+            } => {
+                if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+                    return Err(format!(
+                        "Coordenadas fuera de rango: {}, {}",
+                        latitude, longitude
+                    ));
+                }
+                let frame = frame
+                    .ok_or("Waypoint geodésico sin datum: no hay fix GNSS ni datum configurado")?;
+                // La altura no interviene en la navegación en el plano
+                let point = GeoPoint::new(latitude, longitude, frame.datum().altitude);
+                let [east, north, _] = frame.to_enu(&point);
+                Ok(RobotState::new(
+                    east,
+                    north,
+                    heading.map(course_to_yaw).unwrap_or(0.0),
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enu_round_trip_and_known_offsets() {
+        let datum = GeoPoint::new(40.416_775, -3.703_790, 650.0);
+        let frame = LocalFrame::new(datum);
+
+        let origin = frame.to_enu(&datum);
+        assert!(origin.iter().all(|v| v.abs() < 1e-6), "{:?}", origin);
+
+        // Un segundo de arco de latitud son ~30.8 m a 40°N
+        let north = frame.to_enu(&GeoPoint::new(40.416_775 + 1.0 / 3600.0, -3.703_790, 650.0));
+        assert!(north[0].abs() < 1e-3);
+        assert!((north[1] - 30.85).abs() < 0.1, "{:?}", north);
+
+        let point = GeoPoint::new(40.417_5, -3.702_5, 660.0);
+        let back = frame.to_geodetic(frame.to_enu(&point));
+        assert!((back.latitude - point.latitude).abs() < 1e-9);
+        assert!((back.longitude - point.longitude).abs() < 1e-9);
+        assert!((back.altitude - point.altitude).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_geodetic_waypoint_resolution() {
+        let waypoint: Waypoint =
+            serde_json::from_str(r#"{"latitude": 40.4168, "longitude": -3.7038, "heading": 90}"#)
+                .unwrap();
+        assert!(waypoint.resolve(None).is_err());
+
+        let frame = LocalFrame::new(GeoPoint::new(40.4168, -3.7048, 650.0));
+        let target = waypoint.resolve(Some(&frame)).unwrap();
+        // 0.001° de longitud a 40.4°N son ~84.8 m hacia el este
+        assert!((target.x - 84.8).abs() < 0.2, "{:?}", target);
+        assert!(target.y.abs() < 0.01);
+        assert!(target.theta.abs() < 1e-12);
+
+        let local: Waypoint = serde_json::from_str(r#"{"x": 1.0, "y": 2.0}"#).unwrap();
+        assert_eq!(
+            local,
+            Waypoint::Local {
+                x: 1.0,
+                y: 2.0,
+                theta: 0.0
+            }
+        );
+    }
+}
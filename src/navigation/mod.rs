@@ -1,7 +1,13 @@
+pub mod docking;
+pub mod geodesy;
 pub mod pathfinding;
+pub mod runtime;
 pub mod slam;
 
+pub use crate::geometry::Point2 as Point;
+
 use crate::control::{ControlInput, RobotState};
+use crate::geometry::{angle_difference, Pose2D};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -9,6 +15,11 @@ pub struct NavigationConfig {
     pub pathfinding: PathfindingConfig,
     pub slam: SLAMConfig,
     pub control: crate::control::ControlConfig,
+    /// Origen del marco ENU del mapa; sin él se ancla en el primer fix GNSS
+    #[serde(default)]
+    pub datum: Option<geodesy::GeoPoint>,
+    #[serde(default)]
+    pub docking: docking::DockingConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,6 +36,10 @@ pub struct SLAMConfig {
     pub map_size: (usize, usize),
     pub particle_count: usize,
     pub sensor_range: f64,
+    #[serde(default)]
+    pub algorithm: slam::SLAMAlgorithm,
+    #[serde(default)]
+    pub ekf: slam::EkfSlamConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,8 +64,12 @@ impl Default for NavigationConfig {
                 map_size: (1000, 1000), // 50x50 meters
                 particle_count: 1000,
                 sensor_range: 10.0, // 10 meters
+                algorithm: slam::SLAMAlgorithm::default(),
+                ekf: slam::EkfSlamConfig::default(),
             },
             control: crate::control::ControlConfig::default(),
+            datum: None,
+            docking: docking::DockingConfig::default(),
         }
     }
 }
@@ -60,6 +79,7 @@ pub struct NavigationController {
     slam_engine: slam::SLAMEngine,
     current_path: Option<Vec<RobotState>>,
     current_goal: Option<RobotState>,
+    local_frame: Option<geodesy::LocalFrame>,
     config: NavigationConfig,
 }
 
@@ -70,6 +90,7 @@ impl NavigationController {
             slam_engine: slam::SLAMEngine::new(config.slam.clone()),
             current_path: None,
             current_goal: None,
+            local_frame: config.datum.map(geodesy::LocalFrame::new),
             config,
         }
     }
@@ -161,7 +182,7 @@ src/navigation/mod.rs                Ok(path) => {
         let dy = lookahead_point.y - _current_pose.y;
         let target_heading = dy.atan2(dx);
 
-        let heading_error = target_heading - _current_pose.theta;
+        let heading_error = angle_difference(target_heading, _current_pose.theta);
 
         // Control simple para seguimiento de camino
         Ok(ControlInput::new(
@@ -246,12 +267,76 @@ src/navigation/mod.rs                Ok(path) => {
     pub fn get_pose_estimate(&self) -> RobotState {
         self.slam_engine.get_pose_estimate()
     }
+
+    /// Confianza (0..1) de la pose estimada
+    pub fn localization_confidence(&self) -> f64 {
+        self.slam_engine.localization_confidence()
+    }
+
+    /// Actualiza el SLAM sin navegar (robot parado o teleoperado)
+    pub async fn update_localization(&mut self, sensor_data: &SensorData) -> Result<(), String> {
+        let pose = self.get_pose_estimate();
+        self.slam_engine.update(pose, sensor_data).await
+    }
+
+    /// Toma del árbol de marcos el montaje del LIDAR sobre la base
+    pub fn apply_transforms(
+        &mut self,
+        transforms: &crate::transforms::TransformBuffer,
+    ) -> Result<(), String> {
+        use crate::transforms::frames::{BASE_LINK, LASER};
+
+        let laser = transforms.lookup(BASE_LINK, LASER, None)?;
+        self.slam_engine.set_laser_transform(laser.transform);
+        Ok(())
+    }
+
+    /// Incorpora un fix GNSS a la localización como posición absoluta en el
+    /// marco ENU. Devuelve `false` si no hay fix o el filtro lo descarta.
+    pub fn update_gnss(&mut self, gps: &crate::sensors::GpsData) -> bool {
+        if !gps.has_fix() {
+            return false;
+        }
+
+        let point = geodesy::GeoPoint::from(gps);
+        let frame = self.local_frame.get_or_insert_with(|| {
+            log::info!(
+                "🌍 Datum local fijado en {:.7}, {:.7}",
+                point.latitude,
+                point.longitude
+            );
+            geodesy::LocalFrame::new(point)
+        });
+        let [east, north, _] = frame.to_enu(&point);
+        self.slam_engine
+            .update_position(Point::new(east, north), gps.covariance.position())
+    }
+
+    pub fn get_local_frame(&self) -> Option<&geodesy::LocalFrame> {
+        self.local_frame.as_ref()
+    }
+
+    /// Convierte un waypoint (local o en latitud/longitud) a una pose del mapa
+    pub fn resolve_waypoint(&self, waypoint: &geodesy::Waypoint) -> Result<RobotState, String> {
+        waypoint.resolve(self.local_frame.as_ref())
+    }
+
+    pub async fn navigate_to_waypoint(
+        &mut self,
+        waypoint: &geodesy::Waypoint,
+        current_pose: RobotState,
+        sensor_data: &SensorData,
+    ) -> Result<ControlInput, String> {
+        let target_pose = self.resolve_waypoint(waypoint)?;
+        self.navigate_to_pose(target_pose, current_pose, sensor_data)
+            .await
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct SensorData {
     pub lidar_scan: Vec<(f64, f64)>, // (distance, angle)
-    pub odometry: (f64, f64, f64),   // (dx, dy, dtheta)
+    pub odometry: Pose2D,            // incremento en el marco del mapa
     pub timestamp: f64,
 }
 
@@ -357,12 +442,6 @@ impl std::fmt::Display for NavigationError {
     }
 }
 
-impl Point {
-    pub fn new(x: f64, y: f64) -> Self {
-        Self { x, y }
-    }
-}
-
 impl BoundingBox {
     pub fn area(&self) -> f64 {
         self.width * self.height
@@ -25,12 +25,23 @@ impl PathPlanner {
         goal: &RobotState,
         map: &super::slam::OccupancyGrid,
     ) -> Result<Vec<RobotState>, String> {
-        match self.config.algorithm {
+        let metrics = crate::metrics::metrics();
+        let algorithm = format!("{:?}", self.config.algorithm).to_lowercase();
+        let _timer = metrics
+            .planner_duration
+            .with_label_values(&[&algorithm])
+            .start_timer();
+
+        let result = match self.config.algorithm {
             PathfindingAlgorithm::AStar => self.a_star.plan_path(start, goal, map).await,
             PathfindingAlgorithm::RRT => self.rrt.plan_path(start, goal, map).await,
             PathfindingAlgorithm::RRTStar => self.rrt.plan_path_star(start, goal, map).await,
             PathfindingAlgorithm::Dijkstra => self.a_star.plan_dijkstra(start, goal, map).await,
+        };
+        if result.is_err() {
+            metrics.planner_failures.with_label_values(&[&algorithm]).inc();
         }
+        result
     }
 
     pub async fn plan_exploration(
@@ -0,0 +1,920 @@
+//! Bucle de navegación: atiende los objetivos, órdenes y misiones que llegan
+//! de la API, sigue la ruta con el `NavigationController` y manda a la base
+//! las velocidades ya limitadas por el control
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+
+use super::docking::{DockDetection, DockingState, DockingStatus};
+use super::{NavigationController, SensorData};
+use crate::api::{
+    ApiServer, GoalFeedback, GoalReporter, GoalRequest, NavigationGoal, Point, RobotCommand,
+};
+use crate::control::{ControlInput, ControlSystem, RobotState};
+use crate::geometry::{angle_difference, Pose2D};
+use crate::mission::{MissionAction, MissionCommand, MissionContext, MissionExecutor};
+use crate::power::{BatteryState, PowerEvent, PowerMonitor};
+use crate::sensors::{HealthEvent, HealthMonitor, SensorReading, SyncedFrame};
+
+/// Periodo del bucle
+const STEP_PERIOD: Duration = Duration::from_millis(100);
+
+pub struct NavigationRuntime {
+    controller: NavigationController,
+    control: ControlSystem,
+    missions: Option<MissionExecutor>,
+    velocity: mpsc::Sender<ControlInput>,
+    goals: Option<mpsc::Receiver<GoalRequest>>,
+    commands: Option<mpsc::Receiver<RobotCommand>>,
+    /// Parada de emergencia de la API, que se atiende antes que las órdenes
+    emergency_stops: Option<watch::Receiver<bool>>,
+    mission_actions: Option<mpsc::Receiver<MissionAction>>,
+    sensor_data: Option<mpsc::Receiver<SensorData>>,
+    frames: Option<mpsc::Receiver<SyncedFrame>>,
+    /// Odometría de la última tupla, para calcular el incremento
+    last_odometry: Option<Pose2D>,
+    /// Escaneo de la última tupla recibida en este ciclo, para buscar la base
+    latest_scan: Option<Arc<SensorReading>>,
+    battery: Option<watch::Receiver<Option<BatteryState>>>,
+    health: Option<broadcast::Receiver<HealthEvent>>,
+    power: Option<broadcast::Receiver<PowerEvent>>,
+    reporter: Option<GoalReporter>,
+    pose: watch::Sender<Option<RobotState>>,
+    localization: watch::Sender<Option<f64>>,
+    docking: watch::Sender<Option<DockingStatus>>,
+    active: Option<NavigationGoal>,
+    emergency_stop: bool,
+    /// Distancia a la que se da por alcanzado un objetivo (m)
+    goal_tolerance: f64,
+}
+
+impl NavigationRuntime {
+    /// Las velocidades para la base salen por `velocity`
+    pub fn new(
+        controller: NavigationController,
+        control: ControlSystem,
+        velocity: mpsc::Sender<ControlInput>,
+    ) -> Self {
+        Self {
+            controller,
+            control,
+            missions: None,
+            velocity,
+            goals: None,
+            commands: None,
+            emergency_stops: None,
+            mission_actions: None,
+            sensor_data: None,
+            frames: None,
+            last_odometry: None,
+            latest_scan: None,
+            battery: None,
+            health: None,
+            power: None,
+            reporter: None,
+            pose: watch::channel(None).0,
+            localization: watch::channel(None).0,
+            docking: watch::channel(None).0,
+            active: None,
+            emergency_stop: false,
+            goal_tolerance: 0.2,
+        }
+    }
+
+    pub fn with_goal_tolerance(mut self, tolerance: f64) -> Self {
+        self.goal_tolerance = tolerance;
+        self
+    }
+
+    /// Ejecuta las acciones de misión (acoplar, desacoplar...) de la API
+    pub fn with_missions(mut self, missions: MissionExecutor) -> Self {
+        self.missions = Some(missions);
+        self
+    }
+
+    /// Escaneos e incrementos de odometría para el SLAM
+    pub fn with_sensor_data(mut self, sensor_data: mpsc::Receiver<SensorData>) -> Self {
+        self.sensor_data = Some(sensor_data);
+        self
+    }
+
+    /// Tuplas lidar + odometría sincronizadas (`ApproximateTimeSync::spawn`)
+    /// como entrada del SLAM, en lugar de `with_sensor_data`
+    pub fn with_synced_frames(mut self, frames: mpsc::Receiver<SyncedFrame>) -> Self {
+        self.frames = Some(frames);
+        self
+    }
+
+    /// Última lectura del monitor de batería (`PowerMonitor::watch`), con la
+    /// que el acoplamiento confirma la carga
+    pub fn with_battery(mut self, battery: watch::Receiver<Option<BatteryState>>) -> Self {
+        self.battery = Some(battery);
+        self
+    }
+
+    /// Modo de operación del monitor de salud de sensores
+    pub fn with_health_events(mut self, events: broadcast::Receiver<HealthEvent>) -> Self {
+        self.health = Some(events);
+        self
+    }
+
+    /// Atiende los objetivos, órdenes y acciones de misión de la API e
+    /// informa del avance de los objetivos. Publica además la pose, la
+    /// confianza de la localización, el estado del acoplamiento y el latido
+    /// del bucle para `/health`.
+    pub async fn connect_api(&mut self, api: &ApiServer) {
+        self.goals = Some(api.navigation_goals().await);
+        self.commands = Some(api.robot_commands().await);
+        let mut emergency_stops = api.emergency_stops().await;
+        self.emergency_stop = *emergency_stops.borrow_and_update();
+        self.emergency_stops = Some(emergency_stops);
+        self.mission_actions = Some(api.mission_actions().await);
+        self.reporter = Some(api.goal_reporter());
+        api.track_pose(self.pose.subscribe());
+        api.track_localization(self.localization.subscribe());
+        api.track_docking(self.docking.subscribe());
+        api.track_control(self.control.heartbeat()).await;
+    }
+
+    /// Sigue el modo de operación del monitor y publica en la API la salud de
+    /// cada sensor
+    pub fn connect_health_monitor(&mut self, api: &ApiServer, monitor: &HealthMonitor) {
+        self.health = Some(monitor.subscribe());
+        api.track_sensor_health(monitor);
+    }
+
+    /// Sigue la batería para acoplar y vuelve a la base cuando el monitor lo
+    /// pide; publica además cada lectura en la API
+    pub fn connect_power_monitor(&mut self, api: &ApiServer, monitor: &PowerMonitor) {
+        self.battery = Some(monitor.watch());
+        self.power = Some(monitor.subscribe());
+        api.track_battery(monitor.watch());
+    }
+
+    pub fn controller(&self) -> &NavigationController {
+        &self.controller
+    }
+
+    pub fn active_goal(&self) -> Option<&NavigationGoal> {
+        self.active.as_ref()
+    }
+
+    /// Lanza el bucle a `STEP_PERIOD`
+    pub fn spawn(mut self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(STEP_PERIOD);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+            let mut last = Instant::now();
+
+            loop {
+                tokio::select! {
+                    // La parada va primero: nunca espera detrás de la
+                    // teleoperación encolada
+                    biased;
+                    engaged = next_change(&mut self.emergency_stops) => match engaged {
+                        Some(engaged) => self.set_emergency_stop(engaged).await,
+                        None => self.emergency_stops = None,
+                    },
+                    request = next(&mut self.goals) => match request {
+                        Some(request) => self.handle_goal(request).await,
+                        None => self.goals = None,
+                    },
+                    command = next(&mut self.commands) => match command {
+                        Some(command) => self.handle_command(command).await,
+                        None => self.commands = None,
+                    },
+                    action = next(&mut self.mission_actions) => match action {
+                        Some(action) => self.handle_mission_action(action).await,
+                        None => self.mission_actions = None,
+                    },
+                    event = next_event(&mut self.health, "salud de sensores") => match event {
+                        Some(event) => self.control.handle_health_event(&event),
+                        None => self.health = None,
+                    },
+                    event = next_event(&mut self.power, "batería") => match event {
+                        Some(event) => self.handle_power_event(event).await,
+                        None => self.power = None,
+                    },
+                    now = interval.tick() => {
+                        let now = now.into_std();
+                        self.step(now.duration_since(last).as_secs_f64()).await;
+                        last = now;
+                    }
+                }
+            }
+        })
+    }
+
+    pub async fn handle_goal(&mut self, request: GoalRequest) {
+        match request {
+            GoalRequest::Start(goal) => {
+                if self.emergency_stop {
+                    self.report_abort(goal.id, "Parada de emergencia activa")
+                        .await;
+                    return;
+                }
+                // Un objetivo de la API sustituye a la misión en curso
+                if let Some(missions) = &mut self.missions {
+                    missions.cancel();
+                }
+                if let Some(reporter) = &self.reporter {
+                    reporter.activate(goal.id).await;
+                }
+                self.active = Some(goal);
+            }
+            GoalRequest::Cancel { id } => {
+                if self.active.as_ref().is_some_and(|goal| goal.id == id) {
+                    self.active = None;
+                    self.stop().await;
+                }
+            }
+        }
+    }
+
+    pub async fn handle_command(&mut self, command: RobotCommand) {
+        match command {
+            RobotCommand::Velocity { linear, angular } => {
+                if !self.emergency_stop {
+                    let input = self.control.limit(ControlInput::new(linear, angular));
+                    self.send(input).await;
+                }
+            }
+            // La API ya cancela el objetivo al pasar a manual
+            RobotCommand::SetMode(_) => {}
+            RobotCommand::SetParameter { name, value } => match (name.as_str(), value.as_f64()) {
+                ("max_speed", Some(speed)) if speed > 0.0 => {
+                    self.control.config.max_linear_speed = speed;
+                }
+                _ => log::warn!("⚠️ Parámetro no admitido: {} = {}", name, value),
+            },
+        }
+    }
+
+    /// Activa o rearma la parada de emergencia. Al activarla detiene la base
+    /// y aborta el objetivo y la misión en curso.
+    pub async fn set_emergency_stop(&mut self, engaged: bool) {
+        let was_engaged = std::mem::replace(&mut self.emergency_stop, engaged);
+        if !engaged || was_engaged {
+            return;
+        }
+        self.stop().await;
+        if let Some(goal) = self.active.take() {
+            self.report_abort(goal.id, "Parada de emergencia").await;
+        }
+        if let Some(missions) = &mut self.missions {
+            missions.cancel();
+        }
+    }
+
+    pub async fn handle_mission_action(&mut self, action: MissionAction) {
+        let Some(missions) = &mut self.missions else {
+            log::warn!("⚠️ Sin ejecutor de misiones, se ignora {:?}", action);
+            return;
+        };
+        missions.push(action);
+        if let Some(goal) = self.active.take() {
+            self.report_abort(goal.id, "Sustituido por una acción de misión")
+                .await;
+        }
+    }
+
+    /// Con batería baja la vuelta a la base sustituye al objetivo en curso
+    pub async fn handle_power_event(&mut self, event: PowerEvent) {
+        let PowerEvent::ReturnToDock { .. } = event else {
+            return;
+        };
+        let Some(missions) = &mut self.missions else {
+            log::warn!("⚠️ Sin ejecutor de misiones, no se puede volver a la base");
+            return;
+        };
+        missions.handle_power_event(&event);
+        if let Some(goal) = self.active.take() {
+            self.report_abort(goal.id, "Batería baja: volviendo a la base")
+                .await;
+        }
+    }
+
+    /// Un ciclo del bucle, `dt` segundos después del anterior
+    pub async fn step(&mut self, dt: f64) {
+        self.control.heartbeat().beat();
+        let sensor_data = self.take_sensor_data();
+        let navigated = !self.emergency_stop && self.drive(dt, &sensor_data).await;
+        // Sin navegar el SLAM sigue localizando con lo recibido
+        if !navigated {
+            if let Err(e) = self.controller.update_localization(&sensor_data).await {
+                log::error!("❌ Error actualizando el SLAM: {}", e);
+            }
+        }
+        self.pose
+            .send_replace(Some(self.controller.get_pose_estimate()));
+        self.localization
+            .send_replace(Some(self.controller.localization_confidence()));
+    }
+
+    /// Persigue el objetivo o la misión en curso; `true` si se ha llamado a la
+    /// navegación, que ya actualiza el SLAM
+    async fn drive(&mut self, dt: f64, sensor_data: &SensorData) -> bool {
+        let pose = self.controller.get_pose_estimate();
+
+        if let Some(goal) = self.active.clone() {
+            let target = goal.target_pose();
+            if pose.distance_to(&target) <= self.goal_tolerance {
+                self.active = None;
+                self.stop().await;
+                if let Some(reporter) = &self.reporter {
+                    reporter.succeed(goal.id).await;
+                }
+                return false;
+            }
+            match self.navigate(target, &pose, sensor_data, goal.speed).await {
+                Ok(()) => {
+                    if let Some(reporter) = &self.reporter {
+                        let feedback = feedback(&self.controller, &pose, goal.speed);
+                        reporter.feedback(goal.id, feedback).await;
+                    }
+                }
+                Err(e) => {
+                    self.active = None;
+                    self.stop().await;
+                    self.report_abort(goal.id, &e).await;
+                }
+            }
+            return true;
+        }
+
+        let dock_detection = self.detect_dock();
+        let battery = self
+            .battery
+            .as_ref()
+            .and_then(|battery| battery.borrow().clone());
+        let Some(missions) = &mut self.missions else {
+            return false;
+        };
+        let local_frame = self.controller.get_local_frame().cloned();
+        let context = MissionContext {
+            pose: &pose,
+            dock_detection: dock_detection.as_ref(),
+            battery: battery.as_ref(),
+            local_frame: local_frame.as_ref(),
+            dt,
+        };
+        let command = missions.step(&context);
+        self.docking.send_replace(Some(missions.docking().status()));
+        match command {
+            MissionCommand::Navigate(target) => {
+                let speed = self.control.config.max_linear_speed;
+                if let Err(e) = self.navigate(target, &pose, sensor_data, speed).await {
+                    log::error!("❌ Navegación de la misión fallida: {}", e);
+                    self.stop().await;
+                }
+                true
+            }
+            MissionCommand::Velocity(input) => {
+                let input = self.control.limit(input);
+                self.send(input).await;
+                false
+            }
+            MissionCommand::Idle => false,
+        }
+    }
+
+    /// Busca la base en el escaneo recibido en este ciclo mientras se está
+    /// acoplando
+    fn detect_dock(&self) -> Option<DockDetection> {
+        let docking = self.missions.as_ref()?.docking();
+        if matches!(
+            docking.state(),
+            DockingState::Idle | DockingState::Docked | DockingState::Failed
+        ) {
+            return None;
+        }
+        let SensorReading::Lidar(scan) = self.latest_scan.as_deref()? else {
+            return None;
+        };
+        docking.detector().detect_lidar(scan)
+    }
+
+    async fn navigate(
+        &mut self,
+        target: RobotState,
+        pose: &RobotState,
+        sensor_data: &SensorData,
+        speed: f64,
+    ) -> Result<(), String> {
+        let mut input = self
+            .controller
+            .navigate_to_pose(target, pose.clone(), sensor_data)
+            .await?;
+        input.linear_x = input.linear_x.clamp(-speed, speed);
+        let input = self.control.limit(input);
+        self.send(input).await;
+        Ok(())
+    }
+
+    /// Último escaneo con la suma de los incrementos de odometría recibidos
+    /// desde el ciclo anterior, para no perder ni repetir movimiento
+    fn take_sensor_data(&mut self) -> SensorData {
+        let mut data = SensorData {
+            lidar_scan: Vec::new(),
+            odometry: Pose2D::identity(),
+            timestamp: 0.0,
+        };
+        if let Some(receiver) = &mut self.sensor_data {
+            while let Ok(next) = receiver.try_recv() {
+                data = accumulate(data, next);
+            }
+        }
+        self.latest_scan = None;
+        if let Some(receiver) = &mut self.frames {
+            while let Ok(frame) = receiver.try_recv() {
+                if let Some(next) = frame_data(&frame, &mut self.last_odometry) {
+                    data = accumulate(data, next);
+                    self.latest_scan = Some(frame.lidar.reading.clone());
+                }
+            }
+        }
+        data
+    }
+
+    async fn stop(&self) {
+        self.send(ControlInput::zero()).await;
+    }
+
+    async fn send(&self, input: ControlInput) {
+        if self.velocity.send(input).await.is_err() {
+            log::error!("❌ La base no recibe órdenes de velocidad");
+        }
+    }
+
+    async fn report_abort(&self, id: u64, reason: &str) {
+        if let Some(reporter) = &self.reporter {
+            reporter.abort(id, reason).await;
+        }
+    }
+}
+
+/// `next` con los incrementos de odometría de ambos sumados
+fn accumulate(data: SensorData, next: SensorData) -> SensorData {
+    let odometry = Pose2D::new(
+        data.odometry.x + next.odometry.x,
+        data.odometry.y + next.odometry.y,
+        data.odometry.theta + next.odometry.theta,
+    );
+    SensorData { odometry, ..next }
+}
+
+/// Escaneo e incremento de odometría desde la tupla anterior. La primera
+/// tupla solo fija la referencia de la odometría.
+fn frame_data(frame: &SyncedFrame, last_odometry: &mut Option<Pose2D>) -> Option<SensorData> {
+    let (SensorReading::Lidar(scan), SensorReading::Odometry(odometry)) =
+        (&*frame.lidar.reading, &*frame.odometry.reading)
+    else {
+        log::warn!("⚠️ Tupla sincronizada sin escaneo u odometría");
+        return None;
+    };
+    let current = odometry.pose();
+    let delta = match last_odometry.replace(current) {
+        Some(previous) => Pose2D::new(
+            current.x - previous.x,
+            current.y - previous.y,
+            angle_difference(current.theta, previous.theta),
+        ),
+        None => Pose2D::identity(),
+    };
+    Some(SensorData {
+        lidar_scan: scan
+            .points
+            .iter()
+            .filter(|point| point.distance.is_finite())
+            .map(|point| (point.distance, point.angle))
+            .collect(),
+        odometry: delta,
+        timestamp: frame.timestamp,
+    })
+}
+
+fn feedback(controller: &NavigationController, pose: &RobotState, speed: f64) -> GoalFeedback {
+    let path = controller
+        .get_current_path()
+        .map(|path| path.iter().map(|p| Point::new(p.x, p.y)).collect())
+        .unwrap_or_default();
+    GoalFeedback::along_path(Point::new(pose.x, pose.y), path, speed)
+}
+
+/// Espera el siguiente mensaje; sin canal, nunca
+async fn next<T>(receiver: &mut Option<mpsc::Receiver<T>>) -> Option<T> {
+    match receiver {
+        Some(receiver) => receiver.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Siguiente valor de un canal watch; `None` si el emisor ha desaparecido
+async fn next_change(receiver: &mut Option<watch::Receiver<bool>>) -> Option<bool> {
+    let Some(receiver) = receiver else {
+        return std::future::pending().await;
+    };
+    receiver.changed().await.ok()?;
+    let value = *receiver.borrow_and_update();
+    Some(value)
+}
+
+/// Siguiente evento de un canal broadcast; `what` solo para el aviso de
+/// eventos perdidos
+async fn next_event<T: Clone>(
+    receiver: &mut Option<broadcast::Receiver<T>>,
+    what: &str,
+) -> Option<T> {
+    let Some(receiver) = receiver else {
+        return std::future::pending().await;
+    };
+    loop {
+        match receiver.recv().await {
+            Ok(event) => return Some(event),
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                log::warn!("⚠️ Perdidos {} eventos de {}", skipped, what)
+            }
+            Err(broadcast::error::RecvError::Closed) => return None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{GoalState, RobotMode};
+    use crate::navigation::NavigationConfig;
+
+    #[tokio::test]
+    async fn test_reached_goal_succeeds_and_emergency_stop_aborts() {
+        let api = ApiServer::new(0);
+        let state = api.state();
+        let (velocity, mut base) = mpsc::channel(16);
+        let mut runtime = NavigationRuntime::new(
+            NavigationController::new(NavigationConfig::default()),
+            ControlSystem::new(Default::default()),
+            velocity,
+        );
+        runtime.connect_api(&api).await;
+
+        // Objetivo sobre la pose actual: alcanzado en el primer ciclo
+        let here = runtime.controller().get_pose_estimate();
+        let goal = crate::api::goals::send_goal(&state, Point::new(here.x, here.y), 0.5)
+            .await
+            .unwrap();
+        let request = runtime.goals.as_mut().unwrap().recv().await.unwrap();
+        runtime.handle_goal(request).await;
+        runtime.step(0.1).await;
+        assert!(runtime.active_goal().is_none());
+        assert_eq!(base.recv().await, Some(ControlInput::zero()));
+        assert_eq!(
+            state.read().await.goals.get(goal.id).unwrap().state,
+            GoalState::Succeeded
+        );
+
+        // Con la parada activa no se aceptan objetivos ni velocidades
+        runtime.set_emergency_stop(true).await;
+        assert_eq!(base.recv().await, Some(ControlInput::zero()));
+        runtime
+            .handle_command(RobotCommand::Velocity {
+                linear: 0.3,
+                angular: 0.0,
+            })
+            .await;
+        assert!(base.try_recv().is_err());
+
+        runtime.set_emergency_stop(false).await;
+        runtime
+            .handle_command(RobotCommand::SetMode(RobotMode::Manual))
+            .await;
+        runtime
+            .handle_command(RobotCommand::Velocity {
+                linear: 0.3,
+                angular: 0.0,
+            })
+            .await;
+        assert_eq!(base.recv().await, Some(ControlInput::new(0.3, 0.0)));
+    }
+
+    #[tokio::test]
+    async fn test_emergency_stop_overtakes_queued_teleop() {
+        let api = ApiServer::new(0);
+        let state = api.state();
+        let (velocity, mut base) = mpsc::channel(64);
+        let mut runtime = NavigationRuntime::new(
+            NavigationController::new(NavigationConfig::default()),
+            ControlSystem::new(Default::default()),
+            velocity,
+        );
+        runtime.connect_api(&api).await;
+
+        // Canal de órdenes lleno de teleoperación antes de la parada
+        let commands = state.read().await.robot_commands.clone().unwrap();
+        while commands
+            .try_send(RobotCommand::Velocity {
+                linear: 0.3,
+                angular: 0.0,
+            })
+            .is_ok()
+        {}
+        {
+            let mut state = state.write().await;
+            state.emergency_stop = true;
+            state.emergency_latch.send_replace(true);
+        }
+
+        let handle = runtime.spawn();
+        assert_eq!(base.recv().await, Some(ControlInput::zero()));
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while commands.capacity() < commands.max_capacity() {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .expect("el bucle debe vaciar el canal de órdenes");
+        assert!(base.try_recv().is_err());
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_step_feeds_api_health() {
+        let api = ApiServer::new(0);
+        let state = api.state();
+        let (velocity, _base) = mpsc::channel(16);
+        let mut runtime = NavigationRuntime::new(
+            NavigationController::new(NavigationConfig::default()),
+            ControlSystem::new(Default::default()),
+            velocity,
+        );
+        runtime.connect_api(&api).await;
+        let mut localization = runtime.localization.subscribe();
+        runtime.step(0.1).await;
+        localization.changed().await.unwrap();
+
+        // El seguimiento de la API aplica el cambio en su propia tarea
+        let published = async {
+            while state.read().await.localization_confidence.is_none() {
+                tokio::task::yield_now().await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(1), published)
+            .await
+            .unwrap();
+
+        let state = state.read().await;
+        let ready = crate::api::health::readiness(&state, Instant::now());
+        assert_eq!(
+            ready.components["control"].status,
+            crate::api::health::HealthStatus::Healthy
+        );
+        assert_eq!(
+            ready.components["localization"].value,
+            *localization.borrow()
+        );
+        // Sin sensores no está listo, y el estado de la API lo dice
+        assert_eq!(state.status().status.health, "failed");
+        assert_eq!(state.diagnostics().status.health, "failed");
+    }
+
+    fn frame(timestamp: f64, x: f64, theta: f64) -> SyncedFrame {
+        use crate::sensors::{LidarData, LidarPoint};
+
+        let scan = LidarData {
+            frame_id: "laser".to_string(),
+            points: vec![LidarPoint {
+                angle: 0.0,
+                distance: 2.0,
+                quality: 100,
+                timestamp,
+            }],
+            scan_time: 0.1,
+            min_angle: 0.0,
+            max_angle: 0.0,
+            min_range: 0.1,
+            max_range: 10.0,
+        };
+        frame_with_scan(timestamp, scan, x, theta)
+    }
+
+    fn frame_with_scan(
+        timestamp: f64,
+        scan: crate::sensors::LidarData,
+        x: f64,
+        theta: f64,
+    ) -> SyncedFrame {
+        use crate::sensors::{IMUData, OdometryData, SensorMessage, Vector3};
+
+        let message = |sensor: &str, reading| SensorMessage {
+            sensor: Arc::from(sensor),
+            sequence: 0,
+            timestamp,
+            reading: Arc::new(reading),
+        };
+        let odometry = OdometryData {
+            x,
+            theta,
+            timestamp,
+            ..Default::default()
+        };
+        SyncedFrame {
+            timestamp,
+            lidar: message("lidar", SensorReading::Lidar(scan)),
+            imu: message(
+                "imu",
+                SensorReading::Imu(IMUData {
+                    frame_id: "imu_link".to_string(),
+                    acceleration: Vector3::zero(),
+                    gyroscope: Vector3::zero(),
+                    magnetometer: Vector3::zero(),
+                    temperature: 25.0,
+                    timestamp,
+                }),
+            ),
+            odometry: message("odometry", SensorReading::Odometry(odometry)),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_synced_frames_feed_slam() {
+        let mut config = NavigationConfig::default();
+        config.slam.algorithm = crate::navigation::slam::SLAMAlgorithm::EKFSLAM;
+        let (velocity, _base) = mpsc::channel(4);
+        let (frames, receiver) = mpsc::channel(8);
+        let mut runtime = NavigationRuntime::new(
+            NavigationController::new(config),
+            ControlSystem::new(Default::default()),
+            velocity,
+        )
+        .with_synced_frames(receiver);
+
+        // La primera tupla fija la referencia; las demás suman su incremento
+        for (t, x, theta) in [(1.0, 1.0, 0.0), (1.1, 1.2, 0.1), (1.2, 1.5, 0.3)] {
+            frames.send(frame(t, x, theta)).await.unwrap();
+        }
+        let data = runtime.take_sensor_data();
+        assert!((data.odometry.x - 0.5).abs() < 1e-9);
+        assert!((data.odometry.theta - 0.3).abs() < 1e-9);
+        assert_eq!(
+            (data.lidar_scan.clone(), data.timestamp),
+            (vec![(2.0, 0.0)], 1.2)
+        );
+
+        // Sin objetivo el SLAM también consume las tuplas
+        frames.send(frame(1.3, 1.9, 0.3)).await.unwrap();
+        runtime.step(0.1).await;
+        let pose = runtime.controller().get_pose_estimate();
+        assert!((pose.x - 0.4).abs() < 0.05, "{:?}", pose);
+    }
+
+    #[tokio::test]
+    async fn test_rest_dock_request_docks_with_scan_and_battery() {
+        use crate::navigation::docking::detector::tests::v_scan;
+        use crate::navigation::docking::{DockingBehavior, DockingConfig};
+        use crate::power::PowerLevel;
+        use axum::body::Body;
+        use axum::http::{Request, StatusCode};
+        use tower::Service;
+
+        let api = ApiServer::new(0);
+        let state = api.state();
+        state.write().await.auth = Arc::new(crate::api::Authenticator::disabled());
+        let mut config = NavigationConfig::default();
+        config.slam.algorithm = crate::navigation::slam::SLAMAlgorithm::EKFSLAM;
+        let battery = |charging| BatteryState {
+            voltage: 11.1,
+            current: if charging { -2.0 } else { 0.5 },
+            temperature: None,
+            state_of_charge: 0.2,
+            charging,
+            level: PowerLevel::Low,
+            timestamp: 0.0,
+        };
+        let (pack, battery_state) = watch::channel(Some(battery(false)));
+        let (frames, receiver) = mpsc::channel(8);
+        let (velocity, mut base) = mpsc::channel(16);
+        // Base a 1 m delante: el robot arranca en la pose de espera
+        let docking =
+            DockingBehavior::new(DockingConfig::default(), RobotState::new(1.0, 0.0, 0.0));
+        let mut runtime = NavigationRuntime::new(
+            NavigationController::new(config),
+            ControlSystem::new(Default::default()),
+            velocity,
+        )
+        .with_missions(MissionExecutor::new(docking))
+        .with_synced_frames(receiver)
+        .with_battery(battery_state);
+        runtime.connect_api(&api).await;
+        let mut status = runtime.docking.subscribe();
+
+        let response = crate::api::rest::router(state)
+            .call(Request::post("/api/v1/dock").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let action = runtime
+            .mission_actions
+            .as_mut()
+            .unwrap()
+            .recv()
+            .await
+            .unwrap();
+        runtime.handle_mission_action(action).await;
+
+        // Sin escaneo no hay base a la vista
+        runtime.step(0.1).await;
+        let state = |status: &mut watch::Receiver<Option<DockingStatus>>| {
+            status.borrow_and_update().as_ref().unwrap().state
+        };
+        assert_eq!(state(&mut status), DockingState::Searching);
+        base.recv().await.unwrap();
+
+        // La V del escaneo sincronizado da la base
+        let scan = v_scan(
+            crate::geometry::Point2::new(1.0, 0.0),
+            std::f64::consts::PI,
+            0.25,
+            1.6,
+        );
+        frames
+            .send(frame_with_scan(1.0, scan.clone(), 0.0, 0.0))
+            .await
+            .unwrap();
+        runtime.step(0.1).await;
+        assert_eq!(state(&mut status), DockingState::Approaching);
+        assert!(base.recv().await.unwrap().linear_x > 0.0);
+
+        // El monitor de batería confirma la carga
+        pack.send_replace(Some(battery(true)));
+        frames
+            .send(frame_with_scan(1.1, scan, 0.0, 0.0))
+            .await
+            .unwrap();
+        runtime.step(0.1).await;
+        assert_eq!(state(&mut status), DockingState::Docked);
+        assert_eq!(
+            runtime.missions.as_ref().unwrap().state(),
+            &crate::mission::MissionState::Completed
+        );
+    }
+
+    #[tokio::test]
+    async fn test_low_battery_replaces_goal_with_dock_mission() {
+        use crate::navigation::docking::{DockingBehavior, DockingConfig};
+        use crate::power::{PowerConfig, PowerSensor};
+
+        let api = ApiServer::new(0);
+        let state = api.state();
+        let (velocity, _base) = mpsc::channel(16);
+        let docking =
+            DockingBehavior::new(DockingConfig::default(), RobotState::new(3.0, 0.0, 0.0));
+        let mut runtime = NavigationRuntime::new(
+            NavigationController::new(NavigationConfig::default()),
+            ControlSystem::new(Default::default()),
+            velocity,
+        )
+        .with_missions(MissionExecutor::new(docking));
+        runtime.connect_api(&api).await;
+        let config = PowerConfig {
+            sensor: PowerSensor::Simulated {
+                state_of_charge: 0.2,
+                load: 0.0,
+            },
+            ..Default::default()
+        };
+        let (mut monitor, _pack) = PowerMonitor::simulated(config, 0.2);
+        runtime.connect_power_monitor(&api, &monitor);
+
+        let goal = crate::api::goals::send_goal(&state, Point::new(5.0, 5.0), 0.5)
+            .await
+            .unwrap();
+        let request = runtime.goals.as_mut().unwrap().recv().await.unwrap();
+        runtime.handle_goal(request).await;
+
+        // La primera lectura ya está por debajo del umbral bajo
+        monitor.update(Instant::now()).unwrap();
+        while let Some(event) = next_event(&mut runtime.power, "batería").await {
+            let dock = matches!(event, PowerEvent::ReturnToDock { .. });
+            runtime.handle_power_event(event).await;
+            if dock {
+                break;
+            }
+        }
+        assert!(runtime.active_goal().is_none());
+        assert_eq!(
+            state.read().await.goals.get(goal.id).unwrap().state,
+            GoalState::Aborted
+        );
+
+        runtime.step(0.1).await;
+        let missions = runtime.missions.as_ref().unwrap();
+        assert_eq!(
+            missions.state(),
+            &crate::mission::MissionState::Running {
+                action: MissionAction::Dock
+            }
+        );
+        assert_eq!(missions.docking().state(), DockingState::Navigating);
+    }
+}
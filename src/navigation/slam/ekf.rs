@@ -0,0 +1,674 @@
+//! EKF-SLAM con landmarks de rectas y esquinas y covarianza conjunta
+use super::features::{self, Feature, FeatureConfig};
+use crate::geometry::{normalize_angle, Covariance2, Covariance3, Point2, Pose2D};
+use crate::sensors::LidarData;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// Tamaño de la pose `(x, y, θ)` al inicio del vector de estado
+const POSE_SIZE: usize = 3;
+/// Parámetros de cada landmark: `(x, y)` o `(ρ, α)`
+const LANDMARK_SIZE: usize = 2;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EkfSlamConfig {
+    pub features: FeatureConfig,
+    /// Desviación de la traslación por metro recorrido
+    pub translation_noise: f64,
+    /// Desviación del giro por radián girado y por metro recorrido
+    pub rotation_noise: f64,
+    /// Ruido de distancia y rumbo de una esquina observada (m, rad)
+    pub corner_range_noise: f64,
+    pub corner_bearing_noise: f64,
+    /// Ruido mínimo añadido a la covarianza de las rectas extraídas
+    pub line_rho_noise: f64,
+    pub line_alpha_noise: f64,
+    /// Umbral χ² (2 gdl) de Mahalanobis para aceptar una asociación
+    pub association_gate: f64,
+    /// Distancia de Mahalanobis a partir de la cual una observación es un
+    /// landmark nuevo; entre ambos umbrales se descarta por ambigua
+    pub new_landmark_gate: f64,
+    pub max_landmarks: usize,
+    /// Umbral χ² (2 gdl) para aceptar una posición absoluta
+    pub position_gate: f64,
+}
+
+impl Default for EkfSlamConfig {
+    fn default() -> Self {
+        Self {
+            features: FeatureConfig::default(),
+            translation_noise: 0.05,
+            rotation_noise: 0.05,
+            corner_range_noise: 0.05,
+            corner_bearing_noise: 0.02,
+            line_rho_noise: 0.02,
+            line_alpha_noise: 0.01,
+            association_gate: 9.21, // 99%
+            new_landmark_gate: 25.0,
+            max_landmarks: 200,
+            position_gate: 13.82, // 99.9%
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LandmarkKind {
+    /// Punto `(x, y)` en el marco del mapa
+    Corner,
+    /// Recta `(ρ, α)` en el marco del mapa
+    Line,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Landmark {
+    pub id: usize,
+    pub kind: LandmarkKind,
+    pub params: (f64, f64),
+    pub covariance: Covariance2,
+    pub observations: u32,
+}
+
+/// Resultado de incorporar un escaneo
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EkfUpdate {
+    pub matched: usize,
+    pub added: usize,
+    pub ambiguous: usize,
+}
+
+/// Observación de un landmark: valor medido, covarianza y tipo
+#[derive(Debug, Clone, Copy)]
+struct Observation {
+    kind: LandmarkKind,
+    z: [f64; 2],
+    noise: [[f64; 2]; 2],
+}
+
+/// Predicción de una observación con sus jacobianos respecto a la pose y
+/// al landmark
+struct Predicted {
+    z: [f64; 2],
+    pose_jacobian: [[f64; 3]; 2],
+    landmark_jacobian: [[f64; 2]; 2],
+}
+
+/// EKF-SLAM clásico: el estado contiene la pose del robot seguida de los
+/// parámetros de todos los landmarks, con una única covarianza conjunta
+#[derive(Debug, Clone)]
+pub struct EkfSlam {
+    config: EkfSlamConfig,
+    state: Vec<f64>,
+    /// Matriz `n × n` por filas
+    covariance: Vec<f64>,
+    kinds: Vec<LandmarkKind>,
+    observations: Vec<u32>,
+}
+
+impl EkfSlam {
+    pub fn new(config: EkfSlamConfig) -> Self {
+        Self::with_pose(config, Pose2D::identity())
+    }
+
+    pub fn with_pose(config: EkfSlamConfig, pose: Pose2D) -> Self {
+        Self {
+            config,
+            state: vec![pose.x, pose.y, normalize_angle(pose.theta)],
+            covariance: vec![0.0; POSE_SIZE * POSE_SIZE],
+            kinds: Vec::new(),
+            observations: Vec::new(),
+        }
+    }
+
+    pub fn config(&self) -> &EkfSlamConfig {
+        &self.config
+    }
+
+    pub fn pose(&self) -> Pose2D {
+        Pose2D::new(self.state[0], self.state[1], self.state[2])
+    }
+
+    pub fn pose_covariance(&self) -> Covariance3 {
+        let mut cov = Covariance3::zero();
+        for (i, row) in cov.0.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.p(i, j);
+            }
+        }
+        cov
+    }
+
+    pub fn landmark_count(&self) -> usize {
+        self.kinds.len()
+    }
+
+    pub fn landmarks(&self) -> Vec<Landmark> {
+        (0..self.kinds.len())
+            .map(|id| {
+                let l = Self::landmark_index(id);
+                Landmark {
+                    id,
+                    kind: self.kinds[id],
+                    params: (self.state[l], self.state[l + 1]),
+                    covariance: Covariance2([
+                        [self.p(l, l), self.p(l, l + 1)],
+                        [self.p(l + 1, l), self.p(l + 1, l + 1)],
+                    ]),
+                    observations: self.observations[id],
+                }
+            })
+            .collect()
+    }
+
+    /// Predicción con el incremento de odometría `(dx, dy, dθ)` expresado en
+    /// el marco del robot al inicio del movimiento
+    pub fn predict(&mut self, delta: Pose2D) {
+        let Pose2D {
+            x: dx,
+            y: dy,
+            theta: dtheta,
+        } = delta;
+        let theta = self.state[2];
+        let (s, c) = theta.sin_cos();
+
+        self.state[0] += dx * c - dy * s;
+        self.state[1] += dx * s + dy * c;
+        self.state[2] = normalize_angle(theta + dtheta);
+
+        // Jacobiano de la pose; los landmarks no se mueven
+        let g = [
+            [1.0, 0.0, -dx * s - dy * c],
+            [0.0, 1.0, dx * c - dy * s],
+            [0.0, 0.0, 1.0],
+        ];
+        let n = self.state.len();
+
+        // Filas de la pose: G · P[pose, :]
+        let rows: Vec<[f64; 3]> = (0..n)
+            .map(|j| {
+                let mut row = [0.0; 3];
+                for (i, value) in row.iter_mut().enumerate() {
+                    *value = (0..POSE_SIZE).map(|k| g[i][k] * self.p(k, j)).sum();
+                }
+                row
+            })
+            .collect();
+        for (j, row) in rows.iter().enumerate() {
+            for (i, value) in row.iter().enumerate() {
+                self.set(i, j, *value);
+                self.set(j, i, *value);
+            }
+        }
+        // Bloque de la pose: (G P Gᵀ)
+        let mut block = [[0.0; 3]; 3];
+        for (i, block_row) in block.iter_mut().enumerate() {
+            for (j, value) in block_row.iter_mut().enumerate() {
+                *value = (0..POSE_SIZE).map(|k| rows[k][i] * g[j][k]).sum();
+            }
+        }
+        for (i, block_row) in block.iter().enumerate() {
+            for (j, value) in block_row.iter().enumerate() {
+                self.set(i, j, *value);
+            }
+        }
+
+        // Ruido isótropo en traslación: invariante a la rotación del robot
+        let distance = dx.hypot(dy);
+        let sigma_t = self.config.translation_noise * distance;
+        let sigma_r = self.config.rotation_noise * (dtheta.abs() + distance);
+        self.add(0, 0, sigma_t * sigma_t);
+        self.add(1, 1, sigma_t * sigma_t);
+        self.add(2, 2, sigma_r * sigma_r);
+    }
+
+    /// Extrae rectas y esquinas del escaneo y corrige el filtro con ellas
+    pub fn update(&mut self, scan: &LidarData) -> EkfUpdate {
+        let features = features::extract_features(scan, &self.config.features);
+        self.update_features(&features)
+    }
+
+    /// Asocia cada observación con el landmark del mismo tipo de menor
+    /// distancia de Mahalanobis y aplica la corrección secuencialmente
+    pub fn update_features(&mut self, features: &[Feature]) -> EkfUpdate {
+        let mut result = EkfUpdate::default();
+
+        for feature in features {
+            let observation = self.observation(feature);
+
+            match self.associate(&observation) {
+                Some((id, distance)) if distance <= self.config.association_gate => {
+                    self.correct(id, &observation);
+                    self.observations[id] += 1;
+                    result.matched += 1;
+                }
+                Some((_, distance)) if distance <= self.config.new_landmark_gate => {
+                    result.ambiguous += 1;
+                }
+                _ if self.kinds.len() < self.config.max_landmarks => {
+                    self.augment(&observation);
+                    result.added += 1;
+                }
+                _ => result.ambiguous += 1,
+            }
+        }
+
+        log::debug!(
+            "EKF-SLAM: {} asociadas, {} nuevas, {} ambiguas ({} landmarks)",
+            result.matched,
+            result.added,
+            result.ambiguous,
+            self.kinds.len()
+        );
+        result
+    }
+
+    /// Corrección con una posición absoluta `(x, y)` en el marco del mapa,
+    /// p. ej. un fix GNSS ya convertido a ENU. Devuelve `false` si la
+    /// innovación supera `position_gate` (multitrayecto, saltos del receptor).
+    pub fn update_position(&mut self, position: Point2, covariance: Covariance2) -> bool {
+        let nu = [position.x - self.state[0], position.y - self.state[1]];
+
+        // H = [I₂ 0 …]: P·Hᵀ son las dos primeras columnas de P
+        let pht: Vec<[f64; 2]> = (0..self.state.len())
+            .map(|i| [self.p(i, 0), self.p(i, 1)])
+            .collect();
+        let mut s = covariance.0;
+        for (k, row) in s.iter_mut().enumerate() {
+            for (m, value) in row.iter_mut().enumerate() {
+                *value += self.p(k, m);
+            }
+        }
+
+        let distance = mahalanobis(nu, s);
+        if distance > self.config.position_gate {
+            log::debug!(
+                "EKF-SLAM: posición absoluta descartada (Mahalanobis {:.1})",
+                distance
+            );
+            return false;
+        }
+        self.apply_correction(nu, &pht, s);
+        true
+    }
+
+    /// Landmark más cercano en distancia de Mahalanobis al cuadrado
+    fn associate(&self, observation: &Observation) -> Option<(usize, f64)> {
+        (0..self.kinds.len())
+            .filter(|&id| self.kinds[id] == observation.kind)
+            .map(|id| {
+                let predicted = self.predict_observation(id);
+                let innovation = innovation(observation, &predicted);
+                let (_, s) = self.innovation_covariance(id, &predicted, observation);
+                (id, mahalanobis(innovation, s))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    fn observation(&self, feature: &Feature) -> Observation {
+        match feature {
+            Feature::Corner(corner) => {
+                let (range, bearing) = corner.range_bearing();
+                let (sr, sb) = (
+                    self.config.corner_range_noise,
+                    self.config.corner_bearing_noise,
+                );
+                Observation {
+                    kind: LandmarkKind::Corner,
+                    z: [range, bearing],
+                    noise: [[sr * sr, 0.0], [0.0, sb * sb]],
+                }
+            }
+            Feature::Line(line) => {
+                let (sr, sa) = (self.config.line_rho_noise, self.config.line_alpha_noise);
+                let mut noise = line.covariance;
+                noise[0][0] += sr * sr;
+                noise[1][1] += sa * sa;
+                Observation {
+                    kind: LandmarkKind::Line,
+                    z: [line.rho, line.alpha],
+                    noise,
+                }
+            }
+        }
+    }
+
+    fn predict_observation(&self, id: usize) -> Predicted {
+        let Pose2D { x, y, theta } = self.pose();
+        let l = Self::landmark_index(id);
+        let (a, b) = (self.state[l], self.state[l + 1]);
+
+        match self.kinds[id] {
+            LandmarkKind::Corner => {
+                let (dx, dy) = (a - x, b - y);
+                let q = (dx * dx + dy * dy).max(f64::EPSILON);
+                let r = q.sqrt();
+                Predicted {
+                    z: [r, normalize_angle(dy.atan2(dx) - theta)],
+                    pose_jacobian: [[-dx / r, -dy / r, 0.0], [dy / q, -dx / q, -1.0]],
+                    landmark_jacobian: [[dx / r, dy / r], [-dy / q, dx / q]],
+                }
+            }
+            LandmarkKind::Line => {
+                let (rho, alpha) = (a, b);
+                let (s, c) = alpha.sin_cos();
+                let mut z = [rho - (x * c + y * s), normalize_angle(alpha - theta)];
+                let mut pose_jacobian = [[-c, -s, 0.0], [0.0, 0.0, -1.0]];
+                let mut landmark_jacobian = [[1.0, x * s - y * c], [0.0, 1.0]];
+
+                // El robot está al otro lado de la recta: la normal se invierte
+                if z[0] < 0.0 {
+                    z = [-z[0], normalize_angle(z[1] + PI)];
+                    pose_jacobian[0].iter_mut().for_each(|v| *v = -*v);
+                    landmark_jacobian[0].iter_mut().for_each(|v| *v = -*v);
+                }
+                Predicted {
+                    z,
+                    pose_jacobian,
+                    landmark_jacobian,
+                }
+            }
+        }
+    }
+
+    /// Devuelve `P·Hᵀ` (n × 2) y `S = H·P·Hᵀ + R`
+    fn innovation_covariance(
+        &self,
+        id: usize,
+        predicted: &Predicted,
+        observation: &Observation,
+    ) -> (Vec<[f64; 2]>, [[f64; 2]; 2]) {
+        let l = Self::landmark_index(id);
+        let n = self.state.len();
+        let (hp, hl) = (&predicted.pose_jacobian, &predicted.landmark_jacobian);
+
+        let pht: Vec<[f64; 2]> = (0..n)
+            .map(|i| {
+                let mut row = [0.0; 2];
+                for (k, value) in row.iter_mut().enumerate() {
+                    *value = (0..POSE_SIZE).map(|c| self.p(i, c) * hp[k][c]).sum::<f64>()
+                        + (0..LANDMARK_SIZE)
+                            .map(|c| self.p(i, l + c) * hl[k][c])
+                            .sum::<f64>();
+                }
+                row
+            })
+            .collect();
+
+        let mut s = observation.noise;
+        for (k, row) in s.iter_mut().enumerate() {
+            for (m, value) in row.iter_mut().enumerate() {
+                *value += (0..POSE_SIZE).map(|c| hp[k][c] * pht[c][m]).sum::<f64>()
+                    + (0..LANDMARK_SIZE)
+                        .map(|c| hl[k][c] * pht[l + c][m])
+                        .sum::<f64>();
+            }
+        }
+
+        (pht, s)
+    }
+
+    fn correct(&mut self, id: usize, observation: &Observation) {
+        let predicted = self.predict_observation(id);
+        let nu = innovation(observation, &predicted);
+        let (pht, s) = self.innovation_covariance(id, &predicted, observation);
+        self.apply_correction(nu, &pht, s);
+    }
+
+    /// Actualización con `x ← x + K·ν` y `P ← P − K·S·Kᵀ = P − K·(P·Hᵀ)ᵀ`,
+    /// siendo `K = P·Hᵀ·S⁻¹`
+    fn apply_correction(&mut self, nu: [f64; 2], pht: &[[f64; 2]], s: [[f64; 2]; 2]) {
+        let Some(Covariance2(s_inv)) = Covariance2(s).inverse() else {
+            return;
+        };
+
+        let gain: Vec<[f64; 2]> = pht
+            .iter()
+            .map(|row| {
+                [
+                    row[0] * s_inv[0][0] + row[1] * s_inv[1][0],
+                    row[0] * s_inv[0][1] + row[1] * s_inv[1][1],
+                ]
+            })
+            .collect();
+
+        for (i, k) in gain.iter().enumerate() {
+            self.state[i] += k[0] * nu[0] + k[1] * nu[1];
+        }
+        self.state[2] = normalize_angle(self.state[2]);
+        for id in 0..self.kinds.len() {
+            if self.kinds[id] == LandmarkKind::Line {
+                let l = Self::landmark_index(id);
+                self.state[l + 1] = normalize_angle(self.state[l + 1]);
+            }
+        }
+
+        // Simetrizada para no acumular error numérico
+        let n = self.state.len();
+        for i in 0..n {
+            for j in i..n {
+                let value = self.p(i, j) - (gain[i][0] * pht[j][0] + gain[i][1] * pht[j][1]);
+                let other = self.p(j, i) - (gain[j][0] * pht[i][0] + gain[j][1] * pht[i][1]);
+                let value = 0.5 * (value + other);
+                self.set(i, j, value);
+                self.set(j, i, value);
+            }
+        }
+    }
+
+    /// Añade un landmark al estado inicializándolo desde la observación
+    fn augment(&mut self, observation: &Observation) {
+        let Pose2D { x, y, theta } = self.pose();
+        let [z0, z1] = observation.z;
+
+        let (params, gp, gz) = match observation.kind {
+            LandmarkKind::Corner => {
+                let (s, c) = (theta + z1).sin_cos();
+                (
+                    [x + z0 * c, y + z0 * s],
+                    [[1.0, 0.0, -z0 * s], [0.0, 1.0, z0 * c]],
+                    [[c, -z0 * s], [s, z0 * c]],
+                )
+            }
+            LandmarkKind::Line => {
+                let alpha = theta + z1;
+                let (s, c) = alpha.sin_cos();
+                let rho = z0 + x * c + y * s;
+                let d_alpha = -x * s + y * c;
+                let mut gp = [[c, s, d_alpha], [0.0, 0.0, 1.0]];
+                let mut gz = [[1.0, d_alpha], [0.0, 1.0]];
+                let mut params = [rho, normalize_angle(alpha)];
+                if rho < 0.0 {
+                    params = [-rho, normalize_angle(alpha + PI)];
+                    gp[0].iter_mut().for_each(|v| *v = -*v);
+                    gz[0].iter_mut().for_each(|v| *v = -*v);
+                }
+                (params, gp, gz)
+            }
+        };
+
+        let n = self.state.len();
+        let size = n + LANDMARK_SIZE;
+        let mut covariance = vec![0.0; size * size];
+        for i in 0..n {
+            covariance[i * size..i * size + n]
+                .copy_from_slice(&self.covariance[i * n..(i + 1) * n]);
+        }
+
+        // Correlación cruzada: Gp · P[pose, :]
+        for k in 0..LANDMARK_SIZE {
+            for j in 0..n {
+                let value: f64 = (0..POSE_SIZE).map(|c| gp[k][c] * self.p(c, j)).sum();
+                covariance[(n + k) * size + j] = value;
+                covariance[j * size + n + k] = value;
+            }
+        }
+
+        // Bloque del landmark: Gp·Ppp·Gpᵀ + Gz·R·Gzᵀ
+        for k in 0..LANDMARK_SIZE {
+            for m in 0..LANDMARK_SIZE {
+                let mut value = 0.0;
+                for a in 0..POSE_SIZE {
+                    for b in 0..POSE_SIZE {
+                        value += gp[k][a] * self.p(a, b) * gp[m][b];
+                    }
+                }
+                for a in 0..LANDMARK_SIZE {
+                    for b in 0..LANDMARK_SIZE {
+                        value += gz[k][a] * observation.noise[a][b] * gz[m][b];
+                    }
+                }
+                covariance[(n + k) * size + n + m] = value;
+            }
+        }
+
+        self.state.extend_from_slice(&params);
+        self.covariance = covariance;
+        self.kinds.push(observation.kind);
+        self.observations.push(1);
+    }
+
+    fn landmark_index(id: usize) -> usize {
+        POSE_SIZE + id * LANDMARK_SIZE
+    }
+
+    fn p(&self, i: usize, j: usize) -> f64 {
+        self.covariance[i * self.state.len() + j]
+    }
+
+    fn set(&mut self, i: usize, j: usize, value: f64) {
+        let n = self.state.len();
+        self.covariance[i * n + j] = value;
+    }
+
+    fn add(&mut self, i: usize, j: usize, value: f64) {
+        let n = self.state.len();
+        self.covariance[i * n + j] += value;
+    }
+}
+
+fn innovation(observation: &Observation, predicted: &Predicted) -> [f64; 2] {
+    let first = observation.z[0] - predicted.z[0];
+    let second = normalize_angle(observation.z[1] - predicted.z[1]);
+    [first, second]
+}
+
+fn mahalanobis(nu: [f64; 2], s: [[f64; 2]; 2]) -> f64 {
+    match Covariance2(s).inverse() {
+        Some(Covariance2(inv)) => {
+            nu[0] * (inv[0][0] * nu[0] + inv[0][1] * nu[1])
+                + nu[1] * (inv[1][0] * nu[0] + inv[1][1] * nu[1])
+        }
+        None => f64::INFINITY,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::slam::features::tests::room_scan;
+
+    #[test]
+    fn test_map_is_built_once_and_reobserved() {
+        let pose = Pose2D::new(1.0, 1.0, 0.0);
+        let mut slam = EkfSlam::with_pose(EkfSlamConfig::default(), pose);
+
+        let first = slam.update(&room_scan(4.0, 3.0, pose));
+        assert_eq!(first.matched, 0);
+        let landmarks = slam.landmark_count();
+        assert!(landmarks >= 7, "{} landmarks", landmarks);
+
+        // Mismo escaneo otra vez: todo se asocia, nada se añade
+        let second = slam.update(&room_scan(4.0, 3.0, pose));
+        assert_eq!(second.added, 0);
+        assert_eq!(second.matched, first.added);
+
+        let wall = slam
+            .landmarks()
+            .into_iter()
+            .find(|l| l.kind == LandmarkKind::Line && l.params.1.abs() < 0.05)
+            .expect("pared x = 4");
+        assert!((wall.params.0 - 4.0).abs() < 0.05, "{:?}", wall);
+        assert_eq!(wall.observations, 2);
+    }
+
+    #[test]
+    fn test_landmarks_correct_odometry_drift() {
+        let start = Pose2D::new(1.0, 1.0, 0.0);
+        let mut slam = EkfSlam::with_pose(EkfSlamConfig::default(), start);
+        slam.update(&room_scan(4.0, 3.0, start));
+
+        // El robot avanza 1.5 m en x con giro de 0.1 rad; la odometría
+        // sobrestima la traslación un 10% y el giro un 20%
+        let mut truth = start;
+        let mut dead_reckoning = truth;
+        for _ in 0..15 {
+            let step = Pose2D::new(0.1, 0.0, 0.1 / 15.0);
+            let noisy = Pose2D::new(step.x * 1.1, 0.0, step.theta * 1.2);
+            truth = truth * step;
+            dead_reckoning = dead_reckoning * noisy;
+            slam.predict(noisy);
+            slam.update(&room_scan(4.0, 3.0, truth));
+        }
+
+        let pose = slam.pose();
+        let error = pose.distance_to(&truth);
+        let drift = dead_reckoning.distance_to(&truth);
+        assert!(error < 0.05, "error {} (odometría {})", error, drift);
+        assert!(error < drift / 2.0);
+        assert!((pose.theta - truth.theta).abs() < 0.02);
+        assert!(slam.landmark_count() < 20, "{}", slam.landmark_count());
+    }
+
+    #[test]
+    fn test_mahalanobis_gate_creates_new_landmark() {
+        let mut slam = EkfSlam::new(EkfSlamConfig::default());
+        let corner = |x: f64, y: f64| {
+            Feature::Corner(features::CornerFeature {
+                x,
+                y,
+                angle: PI / 2.0,
+            })
+        };
+
+        slam.update_features(&[corner(2.0, 0.0)]);
+        let near = slam.update_features(&[corner(2.02, 0.01)]);
+        assert_eq!(near.matched, 1);
+
+        let far = slam.update_features(&[corner(3.0, 1.0)]);
+        assert_eq!(far.added, 1);
+        assert_eq!(slam.landmark_count(), 2);
+    }
+
+    #[test]
+    fn test_absolute_position_bounds_drift() {
+        let mut slam = EkfSlam::new(EkfSlamConfig::default());
+        let gnss = Covariance2::isotropic(0.1); // RTK
+
+        // Sin escaneos la deriva de la odometría solo la acota la posición
+        let mut truth = Pose2D::identity();
+        let mut dead_reckoning = truth;
+        for _ in 0..100 {
+            let step = Pose2D::new(0.1, 0.0, 0.005);
+            let noisy = Pose2D::new(step.x * 1.1, 0.0, step.theta);
+            truth = truth * step;
+            dead_reckoning = dead_reckoning * noisy;
+            slam.predict(noisy);
+            assert!(slam.update_position(truth.position(), gnss));
+        }
+
+        let x = slam.pose().x;
+        let error = slam.pose().distance_to(&truth);
+        let drift = dead_reckoning.distance_to(&truth);
+        assert!(
+            error < 0.3 && error < drift / 2.0,
+            "error {} (odometría {})",
+            error,
+            drift
+        );
+
+        // Un salto de 50 m es multitrayecto, no movimiento
+        let jump = truth.position() + Point2::new(50.0, 0.0);
+        assert!(!slam.update_position(jump, gnss));
+        assert!((slam.pose().x - x).abs() < 1e-12);
+    }
+}
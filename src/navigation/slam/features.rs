@@ -0,0 +1,402 @@
+//! Extracción de rectas (split-and-merge) y esquinas a partir de escaneos lidar
+use crate::geometry::Point2;
+use crate::sensors::obstacles::{self, SegmentationConfig};
+use crate::sensors::LidarData;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeatureConfig {
+    /// Separación del escaneo en clusters antes del split-and-merge
+    pub segmentation: SegmentationConfig,
+    /// Distancia máxima de un punto a la cuerda antes de partir (m)
+    pub split_threshold: f64,
+    /// Residuo máximo para fusionar dos segmentos vecinos (m)
+    pub merge_threshold: f64,
+    pub min_points: usize,
+    /// Longitud mínima de un segmento para usarlo como landmark (m)
+    pub min_length: f64,
+    /// Ángulo mínimo entre dos rectas para considerar la unión una esquina (rad)
+    pub corner_min_angle: f64,
+    /// Distancia máxima entre la intersección y los extremos de ambas rectas (m)
+    pub corner_max_gap: f64,
+}
+
+impl Default for FeatureConfig {
+    fn default() -> Self {
+        Self {
+            segmentation: SegmentationConfig::default(),
+            split_threshold: 0.05,
+            merge_threshold: 0.04,
+            min_points: 6,
+            min_length: 0.3,
+            corner_min_angle: 45f64.to_radians(),
+            corner_max_gap: 0.2,
+        }
+    }
+}
+
+/// Recta en forma normal de Hesse `x·cos(α) + y·sin(α) = ρ`, con `ρ ≥ 0`,
+/// en el marco del lidar
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LineFeature {
+    pub rho: f64,
+    pub alpha: f64,
+    /// Covarianza de `(ρ, α)`
+    pub covariance: [[f64; 2]; 2],
+    pub start: Point2,
+    pub end: Point2,
+    pub point_count: usize,
+}
+
+impl LineFeature {
+    pub fn length(&self) -> f64 {
+        self.start.distance_to(&self.end)
+    }
+
+    /// Dirección unitaria de `start` a `end`
+    pub fn direction(&self) -> Point2 {
+        let length = self.length().max(f64::EPSILON);
+        (self.end - self.start) * (1.0 / length)
+    }
+}
+
+/// Intersección de dos rectas consecutivas, en el marco del lidar
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CornerFeature {
+    pub x: f64,
+    pub y: f64,
+    /// Ángulo entre las dos rectas (rad, en `(0, π/2]`)
+    pub angle: f64,
+}
+
+impl CornerFeature {
+    /// Observación en distancia y rumbo
+    pub fn range_bearing(&self) -> (f64, f64) {
+        (self.x.hypot(self.y), self.y.atan2(self.x))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Feature {
+    Line(LineFeature),
+    Corner(CornerFeature),
+}
+
+/// Rectas consecutivas de un cluster. En un barrido de 360° sin huecos el
+/// cluster da la vuelta completa y la última recta enlaza con la primera.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LineChain {
+    pub lines: Vec<LineFeature>,
+    pub closed: bool,
+}
+
+/// Rectas del escaneo: clusters por umbral adaptativo, split recursivo
+/// sobre la cuerda y fusión de segmentos vecinos colineales
+pub fn extract_lines(scan: &LidarData, config: &FeatureConfig) -> Vec<LineChain> {
+    obstacles::segment(scan, &config.segmentation)
+        .iter()
+        .map(|cluster| chain(cluster, config))
+        .filter(|chain| !chain.lines.is_empty())
+        .collect()
+}
+
+/// Esquinas entre rectas consecutivas de una cadena
+pub fn detect_corners(chain: &LineChain, config: &FeatureConfig) -> Vec<CornerFeature> {
+    let lines = &chain.lines;
+    let mut corners: Vec<CornerFeature> = lines
+        .windows(2)
+        .filter_map(|pair| corner(&pair[0], &pair[1], config))
+        .collect();
+
+    if chain.closed && lines.len() > 2 {
+        corners.extend(corner(&lines[lines.len() - 1], &lines[0], config));
+    }
+    corners
+}
+
+/// Rectas y esquinas del escaneo
+pub fn extract_features(scan: &LidarData, config: &FeatureConfig) -> Vec<Feature> {
+    let mut features = Vec::new();
+    for chain in extract_lines(scan, config) {
+        features.extend(
+            detect_corners(&chain, config)
+                .into_iter()
+                .map(Feature::Corner),
+        );
+        features.extend(chain.lines.into_iter().map(Feature::Line));
+    }
+    features
+}
+
+fn chain(cluster: &[Point2], config: &FeatureConfig) -> LineChain {
+    let mut ranges = Vec::new();
+    split(cluster, 0, cluster.len() - 1, config, &mut ranges);
+    let mut segments: Vec<Vec<Point2>> = merge(cluster, ranges, config)
+        .into_iter()
+        .map(|(first, last)| cluster[first..=last].to_vec())
+        .collect();
+
+    let closed = cluster.len() >= 2 * config.min_points
+        && cluster[0].distance_to(&cluster[cluster.len() - 1]) <= config.corner_max_gap;
+
+    // El inicio del barrido suele caer a mitad de una pared: unir sus dos mitades
+    if closed && segments.len() > 1 {
+        let mut joined = segments[segments.len() - 1].clone();
+        joined.extend_from_slice(&segments[0]);
+        if is_collinear(&joined, config) {
+            segments[0] = joined;
+            segments.pop();
+        }
+    }
+
+    let lines = segments
+        .iter()
+        .filter(|points| points.len() >= config.min_points)
+        .filter_map(|points| fit(points, config.segmentation.range_sigma))
+        .filter(|line| line.length() >= config.min_length)
+        .collect();
+
+    LineChain { lines, closed }
+}
+
+fn split(
+    points: &[Point2],
+    first: usize,
+    last: usize,
+    config: &FeatureConfig,
+    ranges: &mut Vec<(usize, usize)>,
+) {
+    if last <= first + 1 {
+        ranges.push((first, last));
+        return;
+    }
+
+    let (a, b) = (points[first], points[last]);
+    let (index, farthest) = (first + 1..last)
+        .map(|i| (i, distance_to_chord(points[i], a, b)))
+        .fold((first, 0.0), |best, candidate| {
+            if candidate.1 > best.1 {
+                candidate
+            } else {
+                best
+            }
+        });
+
+    if farthest > config.split_threshold {
+        split(points, first, index, config, ranges);
+        split(points, index, last, config, ranges);
+    } else {
+        ranges.push((first, last));
+    }
+}
+
+fn merge(
+    points: &[Point2],
+    ranges: Vec<(usize, usize)>,
+    config: &FeatureConfig,
+) -> Vec<(usize, usize)> {
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(ranges.len());
+
+    for range in ranges {
+        if let Some(previous) = merged.last_mut() {
+            let joined = (previous.0, range.1);
+            if is_collinear(&points[joined.0..=joined.1], config) {
+                *previous = joined;
+                continue;
+            }
+        }
+        merged.push(range);
+    }
+
+    merged
+}
+
+fn is_collinear(points: &[Point2], config: &FeatureConfig) -> bool {
+    obstacles::fit_line(points)
+        .map(|(_, _, residual)| residual <= config.merge_threshold)
+        .unwrap_or(false)
+}
+
+/// Ajuste por mínimos cuadrados totales con la covarianza de `(ρ, α)`
+/// propagada desde el ruido de distancia `sigma`
+fn fit(points: &[Point2], sigma: f64) -> Option<LineFeature> {
+    let (mut start, mut end, _) = obstacles::fit_line(points)?;
+    // Extremos en el orden del barrido
+    if start.distance_to(&points[0]) > end.distance_to(&points[0]) {
+        std::mem::swap(&mut start, &mut end);
+    }
+    let n = points.len() as f64;
+    let direction = {
+        let length = start.distance_to(&end);
+        if length <= f64::EPSILON {
+            return None;
+        }
+        (end - start) * (1.0 / length)
+    };
+
+    let center = points.iter().fold(Point2::origin(), |sum, p| sum + *p) * (1.0 / n);
+
+    let mut normal = direction.rotated(PI / 2.0);
+    let mut rho = normal.dot(&center);
+    if rho < 0.0 {
+        rho = -rho;
+        normal = -normal;
+    }
+    let alpha = normal.angle();
+
+    // Posición de cada punto a lo largo de la recta respecto al centroide
+    let spread: f64 = points
+        .iter()
+        .map(|p| (*p - center).dot(&direction).powi(2))
+        .sum();
+    let var_alpha = sigma * sigma / spread.max(f64::EPSILON);
+    // ρ se mide en el centroide; un giro dα lo desplaza en t_c·dα
+    let t_center = -center.x * alpha.sin() + center.y * alpha.cos();
+    let var_rho = sigma * sigma / n + t_center * t_center * var_alpha;
+    let cov = t_center * var_alpha;
+
+    Some(LineFeature {
+        rho,
+        alpha,
+        covariance: [[var_rho, cov], [cov, var_alpha]],
+        start,
+        end,
+        point_count: points.len(),
+    })
+}
+
+fn corner(a: &LineFeature, b: &LineFeature, config: &FeatureConfig) -> Option<CornerFeature> {
+    let (da, db) = (a.direction(), b.direction());
+    let cross = da.cross(&db);
+    let mut angle = cross.atan2(da.dot(&db)).abs();
+    if angle > PI / 2.0 {
+        angle = PI - angle;
+    }
+    if angle < config.corner_min_angle {
+        return None;
+    }
+
+    // Intersección de a.start + s·da con b.start + u·db
+    let s = (b.start - a.start).cross(&db) / cross;
+    let point = a.start + da * s;
+
+    if point.distance_to(&a.end) > config.corner_max_gap
+        || point.distance_to(&b.start) > config.corner_max_gap
+    {
+        return None;
+    }
+
+    Some(CornerFeature {
+        x: point.x,
+        y: point.y,
+        angle,
+    })
+}
+
+fn distance_to_chord(p: Point2, a: Point2, b: Point2) -> f64 {
+    let length = a.distance_to(&b);
+    if length <= f64::EPSILON {
+        return p.distance_to(&a);
+    }
+    (b - a).cross(&(p - a)).abs() / length
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use crate::geometry::Pose2D;
+    use crate::sensors::LidarPoint;
+
+    /// Escaneo de 360° desde `pose` dentro de una habitación rectangular
+    /// `[0, width] × [0, height]`
+    pub(crate) fn room_scan(width: f64, height: f64, pose: Pose2D) -> LidarData {
+        let Pose2D { x, y, theta } = pose;
+        let points = (0..720)
+            .map(|i| {
+                let angle = -PI + i as f64 * PI / 360.0;
+                let (c, s) = ((theta + angle).cos(), (theta + angle).sin());
+                let tx = if c > 0.0 { (width - x) / c } else { -x / c };
+                let ty = if s > 0.0 { (height - y) / s } else { -y / s };
+                LidarPoint {
+                    angle,
+                    distance: tx.abs().min(ty.abs()),
+                    quality: 100,
+                    timestamp: 0.0,
+                }
+            })
+            .collect();
+
+        LidarData {
+            frame_id: "laser".to_string(),
+            points,
+            scan_time: 0.1,
+            min_angle: -PI,
+            max_angle: PI - PI / 360.0,
+            min_range: 0.1,
+            max_range: 12.0,
+        }
+    }
+
+    #[test]
+    fn test_room_yields_four_walls_and_corners() {
+        let config = FeatureConfig::default();
+        let scan = room_scan(4.0, 3.0, Pose2D::new(1.0, 1.0, 0.0));
+        let features = extract_features(&scan, &config);
+
+        let lines: Vec<&LineFeature> = features
+            .iter()
+            .filter_map(|f| match f {
+                Feature::Line(line) => Some(line),
+                _ => None,
+            })
+            .collect();
+        let corners: Vec<&CornerFeature> = features
+            .iter()
+            .filter_map(|f| match f {
+                Feature::Corner(corner) => Some(corner),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(lines.len(), 4, "{:?}", lines);
+        // Pared x = 3 vista desde (1, 1): ρ = 3, α = 0
+        assert!(lines
+            .iter()
+            .any(|l| (l.rho - 3.0).abs() < 0.02 && l.alpha.abs() < 0.02));
+        assert!(lines.iter().all(|l| l.covariance[0][0] > 0.0));
+
+        // El cierre del barrido de 360° hace que se detecten las 4 esquinas
+        assert!(corners.len() >= 3, "{:?}", corners);
+        assert!(corners
+            .iter()
+            .any(|c| (c.x - 3.0).abs() < 0.05 && (c.y - 2.0).abs() < 0.05));
+        assert!(corners.iter().all(|c| (c.angle - PI / 2.0).abs() < 0.05));
+    }
+
+    #[test]
+    fn test_split_separates_chord_deviation() {
+        let config = FeatureConfig::default();
+        // Una "L": 20 puntos en x y 20 en y
+        let mut points: Vec<Point2> = (0..20)
+            .map(|i| Point2::new(2.0 - i as f64 * 0.1, 1.0))
+            .collect();
+        points.extend((1..20).map(|i| Point2::new(0.1, 1.0 + i as f64 * 0.1)));
+
+        let mut ranges = Vec::new();
+        split(&points, 0, points.len() - 1, &config, &mut ranges);
+        let ranges = merge(&points, ranges, &config);
+
+        assert_eq!(ranges.len(), 2);
+        assert!(
+            (distance_to_chord(
+                Point2::new(1.0, 0.0),
+                Point2::new(-1.0, 0.0),
+                Point2::new(1.0, 2.0)
+            ) - 2f64.sqrt())
+            .abs()
+                < 1e-9
+        );
+    }
+}
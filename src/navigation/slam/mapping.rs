@@ -1,4 +1,9 @@
+use super::ekf::{EkfSlam, EkfSlamConfig};
+use crate::geometry::Pose2D;
 use crate::navigation::Point;
+use crate::sensors::{LidarData, LidarPoint};
+use crate::transforms::frames;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone)]
 pub struct OccupancyGrid {
@@ -18,14 +23,18 @@ impl OccupancyGrid {
         }
     }
     
-    pub fn update_from_lidar(&mut self, points: &[Point], robot_pose: (f64, f64, f64)) {
+    pub fn update_from_lidar(&mut self, points: &[Point], _robot_pose: &Pose2D) {
         // Actualizar grid con datos LIDAR
         println!("Actualizando grid de ocupación con {} puntos LIDAR", points.len());
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum SLAMAlgorithm {
+    /// Landmarks de rectas y esquinas; ligero en interiores estructurados
     EKFSLAM,
+    /// Filtro de partículas sobre grid de ocupación
+    #[default]
     FastSLAM,
     ORBSLAM,
 }
@@ -38,11 +47,49 @@ pub fn run_slam(algorithm: SLAMAlgorithm, sensor_data: &[f64]) -> Result<(), Str
     }
 }
 
-fn run_ekf_slam(_sensor_data: &[f64]) -> Result<(), String> {
-    println!("Ejecutando EKF-SLAM");
+/// `sensor_data` son pares `(distancia, ángulo)` de un escaneo lidar
+fn run_ekf_slam(sensor_data: &[f64]) -> Result<(), String> {
+    if !sensor_data.len().is_multiple_of(2) {
+        return Err("EKF-SLAM espera pares (distancia, ángulo)".to_string());
+    }
+
+    let pairs: Vec<(f64, f64)> = sensor_data.chunks(2).map(|p| (p[0], p[1])).collect();
+    let mut slam = EkfSlam::new(EkfSlamConfig::default());
+    let update = slam.update(&scan_from_pairs(&pairs, f64::INFINITY));
+    println!(
+        "EKF-SLAM: {} landmarks iniciales ({} ambiguos)",
+        slam.landmark_count(),
+        update.ambiguous
+    );
     Ok(())
 }
 
+/// Convierte pares `(distancia, ángulo)` en un escaneo lidar
+pub fn scan_from_pairs(pairs: &[(f64, f64)], max_range: f64) -> LidarData {
+    let points: Vec<LidarPoint> = pairs
+        .iter()
+        .filter(|(distance, _)| *distance <= max_range)
+        .map(|&(distance, angle)| LidarPoint {
+            angle,
+            distance,
+            quality: 100,
+            timestamp: 0.0,
+        })
+        .collect();
+
+    let min_angle = points.iter().map(|p| p.angle).fold(f64::INFINITY, f64::min);
+    let max_angle = points.iter().map(|p| p.angle).fold(f64::NEG_INFINITY, f64::max);
+    LidarData {
+        frame_id: frames::LASER.to_string(),
+        points,
+        scan_time: 0.0,
+        min_angle,
+        max_angle,
+        min_range: 0.0,
+        max_range,
+    }
+}
+
 fn run_fast_slam(_sensor_data: &[f64]) -> Result<(), String> {
     println!("Ejecutando FastSLAM");
     Ok(())
@@ -1,21 +1,52 @@
+pub mod ekf;
+pub mod features;
+pub mod mapping;
+
+pub use ekf::{EkfSlam, EkfSlamConfig, EkfUpdate, Landmark, LandmarkKind};
+pub use features::{CornerFeature, Feature, FeatureConfig, LineFeature};
+pub use mapping::SLAMAlgorithm;
+
 use super::SLAMConfig;
 use crate::control::RobotState;
+use crate::geometry::{normalize_angle, Covariance2, Point2, Pose2D};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// Desviación típica de la posición (m) con la que la confianza es 0.5
+const CONFIDENCE_SCALE: f64 = 0.5;
+
+/// Tamaño efectivo de la muestra, (Σw)² / Σw²: cuántas partículas aportan
+/// de verdad. `None` si todos los pesos son cero.
+fn effective_sample_size(weights: &[f64]) -> Option<f64> {
+    let total: f64 = weights.iter().sum();
+    let squares: f64 = weights.iter().map(|w| w * w).sum();
+    (squares > 0.0).then(|| total * total / squares)
+}
+
 #[derive(Debug, Clone)]
 pub struct SLAMEngine {
     mapper: OccupancyGridMapper,
     localizer: ParticleFilterLocalizer,
+    ekf: Option<EkfSlam>,
     config: SLAMConfig,
     pose_history: Vec<RobotState>,
 }
 
 impl SLAMEngine {
     pub fn new(config: SLAMConfig) -> Self {
+        let ekf = match config.algorithm {
+            SLAMAlgorithm::EKFSLAM => Some(EkfSlam::new(config.ekf.clone())),
+            SLAMAlgorithm::FastSLAM => None,
+            SLAMAlgorithm::ORBSLAM => {
+                log::warn!("⚠️ ORB-SLAM no disponible, usando filtro de partículas");
+                None
+            }
+        };
+
         Self {
             mapper: OccupancyGridMapper::new(config.map_size, config.map_resolution),
             localizer: ParticleFilterLocalizer::new(config.particle_count),
+            ekf,
             config,
             pose_history: Vec::new(),
         }
@@ -26,15 +57,45 @@ impl SLAMEngine {
         odometry_pose: RobotState,
         sensor_data: &super::SensorData,
     ) -> Result<(), String> {
-        // Paso de predicción del filtro de partículas
-        self.localizer.predict(&sensor_data.odometry);
-
-        // Paso de corrección usando datos LIDAR
-        let weights = self.calculate_particle_weights(&sensor_data.lidar_scan);
-        self.localizer.resample(&weights);
+        let _timer = crate::metrics::metrics().slam_update.start_timer();
+        let estimated_pose = match self.ekf.as_mut() {
+            Some(ekf) => {
+                // La odometría llega en el marco del mapa; el EKF la espera
+                // en el marco del robot
+                let delta = sensor_data.odometry;
+                let local = delta.position().rotated(-ekf.pose().theta);
+                ekf.predict(Pose2D::from_position(local, delta.theta));
+
+                // Los landmarks se miden desde el centro del robot, no del LIDAR
+                let offset = self.mapper.sensor_offset;
+                let pairs: Vec<(f64, f64)> = sensor_data
+                    .lidar_scan
+                    .iter()
+                    .map(|&(distance, angle)| {
+                        let point = offset * Point2::from_polar(distance, angle);
+                        (point.norm(), point.angle())
+                    })
+                    .collect();
+                let mut scan = mapping::scan_from_pairs(&pairs, self.config.sensor_range);
+                scan.frame_id = crate::transforms::frames::BASE_LINK.to_string();
+                ekf.update(&scan);
+
+                RobotState::from(ekf.pose())
+            }
+            None => {
+                // Paso de predicción del filtro de partículas
+                self.localizer.predict(&sensor_data.odometry);
+
+                // Paso de corrección usando datos LIDAR
+                let weights = self.calculate_particle_weights(&sensor_data.lidar_scan);
+                if let Some(ess) = effective_sample_size(&weights) {
+                    crate::metrics::metrics().slam_particle_ess.set(ess);
+                }
+                self.localizer.resample(&weights);
 
-        // Obtener estimación de pose
-        let estimated_pose = self.localizer.get_estimated_pose();
+                self.localizer.get_estimated_pose()
+            }
+        };
 
         // Actualizar mapa con la pose estimada y datos LIDAR
         self.mapper
@@ -60,15 +121,16 @@ impl SLAMEngine {
         lidar_scan: &[(f64, f64)],
     ) -> f64 {
         let mut likelihood = 1.0;
+        let sensor = self.mapper.sensor_pose(particle);
 
         for &(distance, angle) in lidar_scan {
-            let global_angle = particle.theta + angle;
-            let expected_distance = self.mapper.ray_cast(particle, global_angle);
+            let global_angle = sensor.theta + angle;
+            let expected_distance = self.mapper.grid.ray_cast(&sensor, global_angle);
 
             if expected_distance.is_finite() {
                 // Modelo de sensor: probabilidad gaussiana
                 let error = (distance - expected_distance).abs();
-                let sensor_std_dev = 0.1; // 10cm de desviación estándar
+                let sensor_std_dev: f64 = 0.1; // 10cm de desviación estándar
                 let probability = (-error.powi(2) / (2.0 * sensor_std_dev.powi(2))).exp();
                 likelihood *= probability;
             }
@@ -81,8 +143,46 @@ impl SLAMEngine {
         &self.mapper.grid
     }
 
+    /// Montaje del LIDAR respecto a `base_link` (transformación base_link → laser)
+    pub fn set_laser_transform(&mut self, transform: Pose2D) {
+        self.mapper.sensor_offset = transform;
+    }
+
     pub fn get_pose_estimate(&self) -> RobotState {
-        self.localizer.get_estimated_pose()
+        match &self.ekf {
+            Some(ekf) => RobotState::from(ekf.pose()),
+            None => self.localizer.get_estimated_pose(),
+        }
+    }
+
+    /// Confianza de la localización (0..1) según la dispersión de la
+    /// posición: 0.5 con `CONFIDENCE_SCALE` metros de desviación típica
+    pub fn localization_confidence(&self) -> f64 {
+        let variance = match &self.ekf {
+            Some(ekf) => {
+                let cov = ekf.pose_covariance().0;
+                cov[0][0] + cov[1][1]
+            }
+            None => self.localizer.position_variance(),
+        };
+        1.0 / (1.0 + variance.max(0.0).sqrt() / CONFIDENCE_SCALE)
+    }
+
+    /// Corrección con una posición absoluta en el marco del mapa (GNSS en
+    /// ENU); devuelve `false` si el filtro la descarta
+    pub fn update_position(&mut self, position: Point2, covariance: Covariance2) -> bool {
+        match self.ekf.as_mut() {
+            Some(ekf) => ekf.update_position(position, covariance),
+            None => self.localizer.correct_position(position, covariance),
+        }
+    }
+
+    /// Mapa de landmarks cuando el motor usa EKF-SLAM
+    pub fn get_landmarks(&self) -> Vec<Landmark> {
+        self.ekf
+            .as_ref()
+            .map(EkfSlam::landmarks)
+            .unwrap_or_default()
     }
 
     pub fn is_occupied(&self, x: f64, y: f64) -> bool {
@@ -98,6 +198,8 @@ impl SLAMEngine {
 pub struct OccupancyGridMapper {
     pub grid: OccupancyGrid,
     resolution: f64,
+    /// Pose del sensor en el marco del robot
+    pub sensor_offset: Pose2D,
 }
 
 impl OccupancyGridMapper {
@@ -105,23 +207,32 @@ impl OccupancyGridMapper {
         Self {
             grid: OccupancyGrid::new(size.0, size.1, resolution),
             resolution,
+            sensor_offset: Pose2D::identity(),
         }
     }
 
+    /// Pose del sensor en el mapa para una pose del robot
+    pub fn sensor_pose(&self, robot_pose: &RobotState) -> RobotState {
+        RobotState::from(robot_pose.pose() * self.sensor_offset)
+    }
+
     pub fn update_map(&mut self, robot_pose: &RobotState, lidar_scan: &[(f64, f64)]) {
+        // Los rayos salen del sensor, no del centro del robot
+        let sensor = self.sensor_pose(robot_pose);
+
         for &(distance, angle) in lidar_scan {
             if distance > self.grid.max_range {
                 continue;
             }
 
-            let global_angle = robot_pose.theta + angle;
+            let global_angle = sensor.theta + angle;
 
             // Coordenadas globales del punto de impacto del rayo
-            let hit_x = robot_pose.x + distance * global_angle.cos();
-            let hit_y = robot_pose.y + distance * global_angle.sin();
+            let hit_x = sensor.x + distance * global_angle.cos();
+            let hit_y = sensor.y + distance * global_angle.sin();
 
             // Actualizar celdas a lo largo del rayo como libres
-            self.update_ray(robot_pose, hit_x, hit_y, false);
+            self.update_ray(&sensor, hit_x, hit_y, false);
 
             // Actualizar celda de impacto como ocupada
             if let Some((cell_x, cell_y)) = self.grid.world_to_grid(hit_x, hit_y) {
@@ -188,7 +299,7 @@ impl OccupancyGridMapper {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OccupancyGrid {
     width: usize,
     height: usize,
@@ -229,6 +340,23 @@ impl OccupancyGrid {
             .unwrap_or(0.5) // Desconocido si está fuera de los límites
     }
 
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn resolution(&self) -> f64 {
+        self.resolution
+    }
+
+    /// Probabilidades de ocupación por filas, desde la celda (0, 0)
+    pub fn cells(&self) -> &[f64] {
+        &self.data
+    }
+
     pub fn is_occupied(&self, world_x: f64, world_y: f64, safety_margin: f64) -> bool {
         if let Some((x, y)) = self.world_to_grid(world_x, world_y) {
             // Verificar celdas dentro del margen de seguridad
@@ -327,18 +455,17 @@ impl ParticleFilterLocalizer {
         }
     }
 
-    pub fn predict(&mut self, odometry: &(f64, f64, f64)) {
+    pub fn predict(&mut self, odometry: &Pose2D) {
         for particle in &mut self.particles {
             // Aplicar odometría con ruado
-            let (dx, dy, dtheta) = odometry;
             let noise_std = 0.05; // 5cm de ruado en posición, 0.1 rad en orientación
 
-            particle.x += dx + rand::random::<f64>() * noise_std;
-            particle.y += dy + rand::random::<f64>() * noise_std;
-            particle.theta += dtheta + rand::random::<f64>() * 0.1;
+            particle.x += odometry.x + rand::random::<f64>() * noise_std;
+            particle.y += odometry.y + rand::random::<f64>() * noise_std;
+            particle.theta += odometry.theta + rand::random::<f64>() * 0.1;
 
             // Normalizar orientación
-            particle.theta = particle.theta % (2.0 * std::f64::consts::PI);
+            particle.theta = normalize_angle(particle.theta);
         }
     }
 
@@ -373,6 +500,35 @@ impl ParticleFilterLocalizer {
         self.weights = vec![uniform_weight; self.particles.len()];
     }
 
+    /// Pondera las partículas con la verosimilitud gaussiana de la posición
+    /// medida y remuestrea
+    pub fn correct_position(&mut self, position: Point2, covariance: Covariance2) -> bool {
+        if covariance.determinant() <= 0.0 {
+            return false;
+        }
+        let Some(inverse) = covariance.inverse() else {
+            return false;
+        };
+
+        let weights: Vec<f64> = self
+            .particles
+            .iter()
+            .map(|particle| {
+                let (dx, dy) = (position.x - particle.x, position.y - particle.y);
+                let distance = dx * (inverse[0][0] * dx + inverse[0][1] * dy)
+                    + dy * (inverse[1][0] * dx + inverse[1][1] * dy);
+                (-0.5 * distance).exp()
+            })
+            .collect();
+
+        // Ninguna partícula explica la medida: mejor ignorarla que colapsar
+        if weights.iter().sum::<f64>() == 0.0 {
+            return false;
+        }
+        self.resample(&weights);
+        true
+    }
+
     pub fn get_estimated_pose(&self) -> RobotState {
         // Promedio de todas las partículas
         let sum_x: f64 = self.particles.iter().map(|p| p.x).sum();
@@ -390,6 +546,17 @@ impl ParticleFilterLocalizer {
         )
     }
 
+    /// Varianza de la posición de las partículas (m²), suma de la de x e y
+    pub fn position_variance(&self) -> f64 {
+        let mean = self.get_estimated_pose();
+        let n = self.particles.len().max(1) as f64;
+        self.particles
+            .iter()
+            .map(|p| (p.x - mean.x).powi(2) + (p.y - mean.y).powi(2))
+            .sum::<f64>()
+            / n
+    }
+
     pub fn get_particles(&self) -> &Vec<RobotState> {
         &self.particles
     }
@@ -402,3 +569,26 @@ struct MapBounds {
     width: f64,
     height: f64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_map_projects_from_laser_mounting() {
+        let mut mapper = OccupancyGridMapper::new((100, 100), 0.05);
+        // LIDAR montado 0.5 m delante del centro del robot
+        mapper.sensor_offset = Pose2D::new(0.5, 0.0, 0.0);
+
+        let pose = RobotState::new(0.0, 0.0, std::f64::consts::FRAC_PI_2);
+        mapper.update_map(&pose, &[(1.0, 0.0)]);
+
+        let (x, y) = mapper.grid.world_to_grid(0.0, 1.5).unwrap();
+        assert!(mapper.grid.get(x, y) > 0.65);
+        let (x, y) = mapper.grid.world_to_grid(0.0, 1.0).unwrap();
+        assert!(mapper.grid.get(x, y) < 0.5);
+        // Sin el montaje el impacto caería en (0, 1)
+        let (x, y) = mapper.grid.world_to_grid(0.0, 0.2).unwrap();
+        assert_eq!(mapper.grid.get(x, y), 0.5);
+    }
+}
@@ -0,0 +1,158 @@
+//! Monitor de corriente y tensión INA219 sobre I2C con resistencia shunt
+use super::BatteryMonitor;
+use crate::sensors::drivers::i2c::I2cBus;
+use anyhow::Result;
+
+/// INA219 en el lado alto de la batería: la corriente es positiva cuando la
+/// batería se descarga (de IN+ hacia IN-)
+#[derive(Debug)]
+pub struct Ina219<B: I2cBus> {
+    bus: B,
+    address: u8,
+    shunt_resistance: f64,
+    current_lsb: f64,
+    calibration: u16,
+    configured: bool,
+}
+
+impl<B: I2cBus> Ina219<B> {
+    pub const DEFAULT_ADDRESS: u8 = 0x40;
+
+    const REG_CONFIG: u8 = 0x00;
+    const REG_SHUNT_VOLTAGE: u8 = 0x01;
+    const REG_BUS_VOLTAGE: u8 = 0x02;
+    const REG_CURRENT: u8 = 0x04;
+    const REG_CALIBRATION: u8 = 0x05;
+    /// Rango de bus de 32 V, PGA /8 (±320 mV), ADC de 12 bits y modo continuo
+    const CONFIG: u16 = 0x399F;
+
+    /// `max_current` fija la resolución del registro de corriente
+    pub fn new(bus: B, address: u8, shunt_resistance: f64, max_current: f64) -> Self {
+        let current_lsb = max_current / 32768.0;
+        let calibration = (0.04096 / (current_lsb * shunt_resistance)).trunc() as u16;
+        Self {
+            bus,
+            address,
+            shunt_resistance,
+            current_lsb,
+            calibration,
+            configured: false,
+        }
+    }
+
+    pub fn calibration(&self) -> u16 {
+        self.calibration
+    }
+
+    /// Escribe la configuración y la calibración (se pierden con un reset)
+    pub fn configure(&mut self) -> Result<()> {
+        self.write_u16(Self::REG_CONFIG, Self::CONFIG)?;
+        self.write_u16(Self::REG_CALIBRATION, self.calibration)?;
+        self.configured = true;
+        Ok(())
+    }
+
+    /// Caída de tensión en el shunt (V)
+    pub fn shunt_voltage(&mut self) -> Result<f64> {
+        Ok(self.read_u16(Self::REG_SHUNT_VOLTAGE)? as i16 as f64 * 10e-6)
+    }
+
+    /// Tensión en IN- respecto a GND (V)
+    pub fn bus_voltage(&mut self) -> Result<f64> {
+        let raw = self.read_u16(Self::REG_BUS_VOLTAGE)?;
+        // Bit 0: desbordamiento de la multiplicación interna
+        if raw & 0x01 != 0 {
+            return Err(anyhow::anyhow!(
+                "INA219 0x{:02X}: desbordamiento matemático",
+                self.address
+            ));
+        }
+        Ok((raw >> 3) as f64 * 0.004)
+    }
+
+    fn ensure_configured(&mut self) -> Result<()> {
+        if !self.configured {
+            self.configure()?;
+        }
+        Ok(())
+    }
+
+    fn write_u16(&mut self, register: u8, value: u16) -> Result<()> {
+        let [high, low] = value.to_be_bytes();
+        self.bus.write(self.address, &[register, high, low])
+    }
+
+    fn read_u16(&mut self, register: u8) -> Result<u16> {
+        self.ensure_configured()?;
+        let mut raw = [0u8; 2];
+        self.bus.read_registers(self.address, register, &mut raw)?;
+        Ok(u16::from_be_bytes(raw))
+    }
+}
+
+impl<B: I2cBus> BatteryMonitor for Ina219<B> {
+    fn voltage(&mut self) -> Result<f64> {
+        // En el lado alto la batería ve la tensión del bus más la del shunt
+        Ok(self.bus_voltage()? + self.shunt_voltage()?)
+    }
+
+    fn current(&mut self) -> Result<f64> {
+        let raw = self.read_u16(Self::REG_CURRENT)? as i16;
+        if raw == 0 {
+            // Un reset por caída de tensión borra la calibración y el registro
+            // de corriente se queda a cero: se recalcula desde el shunt
+            let shunt = self.shunt_voltage()?;
+            if shunt.abs() > 100e-6 {
+                log::warn!("🔋 INA219 sin calibración, reconfigurando");
+                self.configured = false;
+                return Ok(shunt / self.shunt_resistance);
+            }
+        }
+        Ok(raw as f64 * self.current_lsb)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::drivers::i2c::MockI2cBus;
+
+    #[test]
+    fn test_ina219_calibration_and_readings() {
+        let bus = MockI2cBus::new();
+        bus.add_device(0x40);
+
+        // 3.2 A de fondo de escala con un shunt de 0.1 Ω
+        let mut ina = Ina219::new(bus.clone(), Ina219::<MockI2cBus>::DEFAULT_ADDRESS, 0.1, 3.2);
+        assert_eq!(ina.calibration(), 4194);
+
+        // 11.8 V en el bus y 150 mV en el shunt: 1.5 A de descarga, que el
+        // chip escala con la calibración en el registro de corriente
+        bus.push_stream(
+            0x40,
+            0x02,
+            &(((11.8f64 / 0.004).round() as u16) << 3).to_be_bytes(),
+        );
+        bus.push_stream(0x40, 0x01, &15000i16.to_be_bytes());
+        bus.push_stream(0x40, 0x04, &((15000i32 * 4194 / 4096) as i16).to_be_bytes());
+
+        let voltage = ina.voltage().unwrap();
+        assert!((voltage - 11.95).abs() < 1e-6, "{}", voltage);
+        let current = ina.current().unwrap();
+        assert!((current - 1.5).abs() < 1e-3, "{}", current);
+
+        assert_eq!(
+            bus.writes(),
+            vec![
+                (0x40, 0x00, 0x39),
+                (0x40, 0x01, 0x9F),
+                (0x40, 0x05, 0x10),
+                (0x40, 0x06, 0x62),
+            ]
+        );
+
+        // Carga: la corriente cambia de signo
+        bus.push_stream(0x40, 0x04, &(-5120i16).to_be_bytes());
+        assert!((ina.current().unwrap() + 0.5).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,462 @@
+//! Monitorización de la batería: tensión, corriente, estado de carga y
+//! avisos de batería baja
+pub mod ina219;
+pub mod simulated;
+pub mod soc;
+
+pub use ina219::Ina219;
+pub use simulated::SimulatedPack;
+pub use soc::{OcvCurve, SocEstimator};
+
+use crate::navigation::geodesy::Waypoint;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, watch, Mutex};
+use tokio::task::JoinHandle;
+
+/// Fuente de medidas de la batería. La corriente es positiva en descarga.
+pub trait BatteryMonitor: Send + std::fmt::Debug {
+    /// Tensión en bornes del pack (V)
+    fn voltage(&mut self) -> Result<f64>;
+
+    /// Corriente (A): positiva al descargar, negativa al cargar
+    fn current(&mut self) -> Result<f64>;
+
+    /// Temperatura del pack (°C) si el monitor tiene sonda
+    fn temperature(&mut self) -> Result<Option<f64>> {
+        Ok(None)
+    }
+}
+
+/// Dispositivo que mide la batería
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerSensor {
+    /// INA219 en el lado alto con shunt de `shunt_resistance` ohmios
+    Ina219 {
+        i2c_bus: String,
+        address: u8,
+        shunt_resistance: f64,
+        max_current: f64,
+    },
+    /// Pack en memoria con un consumo constante (A)
+    Simulated { state_of_charge: f64, load: f64 },
+}
+
+/// Características del pack
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BatteryConfig {
+    /// Celdas en serie (3S = 11.1 V nominal)
+    pub cells: u32,
+    pub capacity_ah: f64,
+    /// Resistencia interna del pack (Ω)
+    pub internal_resistance: f64,
+    /// Por debajo de esta corriente (A) se considera la batería en reposo
+    pub rest_current: f64,
+    /// Tiempo en reposo (s) antes de corregir con la curva OCV
+    pub rest_time: f64,
+    /// Tensión de celda por debajo de la cual la batería es crítica
+    pub min_cell_voltage: f64,
+    pub ocv_curve: OcvCurve,
+}
+
+impl Default for BatteryConfig {
+    fn default() -> Self {
+        Self {
+            cells: 3,
+            capacity_ah: 5.0,
+            internal_resistance: 0.05,
+            rest_current: 0.2,
+            rest_time: 30.0,
+            min_cell_voltage: 3.3,
+            ocv_curve: OcvCurve::lithium_ion(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PowerConfig {
+    pub sensor: PowerSensor,
+    pub battery: BatteryConfig,
+    pub sample_rate: u32,
+    /// Estado de carga (0..1) por debajo del cual se vuelve a la base
+    pub low_threshold: f64,
+    pub critical_threshold: f64,
+    /// Margen para salir de un nivel y no oscilar en el umbral
+    pub hysteresis: f64,
+    /// Corriente de carga mínima (A) para considerar que la batería carga
+    pub charge_current: f64,
+    /// Posición de la base de carga
+    pub dock: Waypoint,
+}
+
+impl Default for PowerConfig {
+    fn default() -> Self {
+        Self {
+            sensor: PowerSensor::Ina219 {
+                i2c_bus: "/dev/i2c-1".to_string(),
+                address: 0x40,
+                shunt_resistance: 0.1,
+                max_current: 3.2,
+            },
+            battery: BatteryConfig::default(),
+            sample_rate: 2,
+            low_threshold: 0.25,
+            critical_threshold: 0.10,
+            hysteresis: 0.05,
+            charge_current: 0.05,
+            dock: Waypoint::Local {
+                x: 0.0,
+                y: 0.0,
+                theta: 0.0,
+            },
+        }
+    }
+}
+
+/// Nivel de batería, de mejor a peor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerLevel {
+    Normal,
+    Low,
+    Critical,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatteryState {
+    pub voltage: f64,
+    pub current: f64,
+    pub temperature: Option<f64>,
+    pub state_of_charge: f64, // 0..1
+    pub charging: bool,
+    pub level: PowerLevel,
+    pub timestamp: f64, // segundos
+}
+
+impl BatteryState {
+    pub fn percentage(&self) -> f64 {
+        self.state_of_charge * 100.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PowerEvent {
+    LevelChanged {
+        level: PowerLevel,
+        state_of_charge: f64,
+    },
+    /// Batería baja sin cargador: hay que volver a la base
+    ReturnToDock {
+        dock: Waypoint,
+        level: PowerLevel,
+        state_of_charge: f64,
+    },
+    ChargingStarted {
+        state_of_charge: f64,
+    },
+    ChargingStopped {
+        state_of_charge: f64,
+    },
+}
+
+pub struct PowerMonitor {
+    config: PowerConfig,
+    device: Box<dyn BatteryMonitor>,
+    estimator: SocEstimator,
+    level: PowerLevel,
+    charging: bool,
+    dock_requested: bool,
+    last_update: Option<Instant>,
+    events: broadcast::Sender<PowerEvent>,
+    state: watch::Sender<Option<BatteryState>>,
+}
+
+impl PowerMonitor {
+    pub fn new(config: PowerConfig, device: Box<dyn BatteryMonitor>) -> Self {
+        let (events, _) = broadcast::channel(64);
+        let (state, _) = watch::channel(None);
+        Self {
+            estimator: SocEstimator::new(&config.battery),
+            config,
+            device,
+            level: PowerLevel::Normal,
+            charging: false,
+            dock_requested: false,
+            last_update: None,
+            events,
+            state,
+        }
+    }
+
+    /// Abre el monitor indicado en la configuración
+    pub fn open(config: PowerConfig) -> Result<Self> {
+        let device: Box<dyn BatteryMonitor> = match &config.sensor {
+            PowerSensor::Simulated {
+                state_of_charge,
+                load,
+            } => {
+                let pack = SimulatedPack::new(&config.battery, *state_of_charge);
+                pack.set_load(*load);
+                Box::new(pack)
+            }
+            #[cfg(target_os = "linux")]
+            PowerSensor::Ina219 {
+                i2c_bus,
+                address,
+                shunt_resistance,
+                max_current,
+            } => {
+                let bus = crate::sensors::drivers::i2c::LinuxI2cBus::open(i2c_bus)?;
+                let mut ina = Ina219::new(bus, *address, *shunt_resistance, *max_current);
+                ina.configure()?;
+                Box::new(ina)
+            }
+            #[cfg(not(target_os = "linux"))]
+            sensor => {
+                return Err(anyhow::anyhow!(
+                    "{:?} no soportado en esta plataforma",
+                    sensor
+                ))
+            }
+        };
+        log::info!("🔋 Monitor de batería: {:?}", config.sensor);
+        Ok(Self::new(config, device))
+    }
+
+    /// Monitor sobre un pack simulado; el handle permite cambiar carga y consumo
+    pub fn simulated(config: PowerConfig, state_of_charge: f64) -> (Self, SimulatedPack) {
+        let pack = SimulatedPack::new(&config.battery, state_of_charge);
+        (Self::new(config, Box::new(pack.clone())), pack)
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<PowerEvent> {
+        self.events.subscribe()
+    }
+
+    /// Último estado medido; `None` hasta la primera lectura
+    pub fn watch(&self) -> watch::Receiver<Option<BatteryState>> {
+        self.state.subscribe()
+    }
+
+    pub fn level(&self) -> PowerLevel {
+        self.level
+    }
+
+    pub fn latest(&self) -> Option<BatteryState> {
+        self.state.borrow().clone()
+    }
+
+    pub fn get_config(&self) -> &PowerConfig {
+        &self.config
+    }
+
+    /// Lee el monitor, actualiza el estado de carga y emite los avisos
+    pub fn update(&mut self, now: Instant) -> Result<BatteryState> {
+        let voltage = self.device.voltage()?;
+        let current = self.device.current()?;
+        let temperature = self.device.temperature()?;
+
+        let dt = self
+            .last_update
+            .map(|last| now.saturating_duration_since(last).as_secs_f64())
+            .unwrap_or(0.0);
+        self.last_update = Some(now);
+
+        let state_of_charge = self.estimator.update(voltage, current, dt);
+        let cell_voltage = voltage / self.config.battery.cells.max(1) as f64;
+        let charging = current < -self.config.charge_current;
+
+        if charging != self.charging {
+            self.charging = charging;
+            let event = if charging {
+                log::info!("🔌 Carga iniciada ({:.0} %)", state_of_charge * 100.0);
+                PowerEvent::ChargingStarted { state_of_charge }
+            } else {
+                log::info!("🔋 Carga detenida ({:.0} %)", state_of_charge * 100.0);
+                PowerEvent::ChargingStopped { state_of_charge }
+            };
+            let _ = self.events.send(event);
+        }
+
+        // La caída bajo carga no cuenta como subtensión mientras se carga
+        let undervoltage = !charging && cell_voltage < self.config.battery.min_cell_voltage;
+        let level = self.classify(state_of_charge, undervoltage);
+        if level != self.level {
+            self.level = level;
+            if level > PowerLevel::Normal {
+                log::warn!(
+                    "🪫 Batería {:?}: {:.0} % ({:.2} V)",
+                    level,
+                    state_of_charge * 100.0,
+                    voltage
+                );
+            } else {
+                log::info!("🔋 Batería recuperada: {:.0} %", state_of_charge * 100.0);
+            }
+            let _ = self.events.send(PowerEvent::LevelChanged {
+                level,
+                state_of_charge,
+            });
+        }
+
+        // Se pide volver a la base una vez por descarga, y de nuevo si el
+        // robot se separa del cargador sin haber recuperado la carga
+        let needs_dock = level > PowerLevel::Normal && !charging;
+        if needs_dock && !self.dock_requested {
+            log::warn!("🏠 Batería baja: volviendo a la base de carga");
+            let _ = self.events.send(PowerEvent::ReturnToDock {
+                dock: self.config.dock.clone(),
+                level,
+                state_of_charge,
+            });
+        }
+        self.dock_requested = needs_dock;
+
+        let state = BatteryState {
+            voltage,
+            current,
+            temperature,
+            state_of_charge,
+            charging,
+            level,
+            timestamp: chrono::Utc::now().timestamp_millis() as f64 / 1000.0,
+        };
+        crate::metrics::metrics().record_battery(&state);
+        self.state.send_replace(Some(state.clone()));
+        Ok(state)
+    }
+
+    /// Nivel con histéresis: se entra al cruzar el umbral y se sale al
+    /// superarlo en `hysteresis`
+    fn classify(&self, soc: f64, undervoltage: bool) -> PowerLevel {
+        let config = &self.config;
+        let critical = undervoltage
+            || soc <= config.critical_threshold
+            || (self.level == PowerLevel::Critical
+                && soc <= config.critical_threshold + config.hysteresis);
+        let low = soc <= config.low_threshold
+            || (self.level >= PowerLevel::Low && soc <= config.low_threshold + config.hysteresis);
+
+        if critical {
+            PowerLevel::Critical
+        } else if low {
+            PowerLevel::Low
+        } else {
+            PowerLevel::Normal
+        }
+    }
+
+    /// Muestrea el monitor a `sample_rate` en segundo plano
+    pub fn spawn(monitor: Arc<Mutex<PowerMonitor>>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let rate = monitor.lock().await.config.sample_rate.max(1);
+            let mut interval = tokio::time::interval(Duration::from_secs_f64(1.0 / rate as f64));
+
+            loop {
+                interval.tick().await;
+                if let Err(e) = monitor.lock().await.update(Instant::now()) {
+                    log::error!("❌ Error leyendo la batería: {}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn simulated_config() -> PowerConfig {
+        PowerConfig {
+            sensor: PowerSensor::Simulated {
+                state_of_charge: 0.3,
+                load: 0.0,
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_low_battery_requests_dock_once() {
+        let (mut monitor, pack) = PowerMonitor::simulated(simulated_config(), 0.3);
+        let mut events = monitor.subscribe();
+        let start = Instant::now();
+
+        let state = monitor.update(start).unwrap();
+        assert!((state.state_of_charge - 0.3).abs() < 0.01, "{:?}", state);
+        assert_eq!(state.level, PowerLevel::Normal);
+
+        // Descarga hasta el umbral bajo
+        pack.set_state_of_charge(0.24);
+        pack.set_load(2.0);
+        monitor.estimator.reset(0.24);
+        monitor.update(start + Duration::from_secs(1)).unwrap();
+        assert_eq!(monitor.level(), PowerLevel::Low);
+        monitor.update(start + Duration::from_secs(2)).unwrap();
+
+        let received: Vec<PowerEvent> = std::iter::from_fn(|| events.try_recv().ok()).collect();
+        assert_eq!(received.len(), 2, "{:?}", received);
+        assert!(matches!(
+            received[0],
+            PowerEvent::LevelChanged {
+                level: PowerLevel::Low,
+                ..
+            }
+        ));
+        assert!(matches!(
+            received[1],
+            PowerEvent::ReturnToDock {
+                level: PowerLevel::Low,
+                ..
+            }
+        ));
+
+        // En la base: la carga no sale de Low hasta superar la histéresis
+        pack.set_load(0.0);
+        pack.set_charging(2.5);
+        let state = monitor.update(start + Duration::from_secs(3)).unwrap();
+        assert!(state.charging && state.current < 0.0);
+        monitor.estimator.reset(0.27);
+        assert_eq!(
+            monitor
+                .update(start + Duration::from_secs(4))
+                .unwrap()
+                .level,
+            PowerLevel::Low
+        );
+        monitor.estimator.reset(0.31);
+        assert_eq!(
+            monitor
+                .update(start + Duration::from_secs(5))
+                .unwrap()
+                .level,
+            PowerLevel::Normal
+        );
+
+        let received: Vec<PowerEvent> = std::iter::from_fn(|| events.try_recv().ok()).collect();
+        assert!(matches!(received[0], PowerEvent::ChargingStarted { .. }));
+        assert!(received
+            .iter()
+            .all(|event| !matches!(event, PowerEvent::ReturnToDock { .. })));
+        assert_eq!(monitor.latest().unwrap().level, PowerLevel::Normal);
+    }
+
+    #[test]
+    fn test_undervoltage_is_critical() {
+        let (mut monitor, pack) = PowerMonitor::simulated(simulated_config(), 0.5);
+        monitor.update(Instant::now()).unwrap();
+        assert_eq!(monitor.level(), PowerLevel::Normal);
+
+        // Un pico de consumo hunde la tensión de celda por debajo del mínimo
+        pack.set_load(40.0);
+        assert_eq!(
+            monitor.update(Instant::now()).unwrap().level,
+            PowerLevel::Critical
+        );
+    }
+}
@@ -0,0 +1,125 @@
+//! Batería simulada para pruebas y simulación sin hardware
+use super::{BatteryConfig, BatteryMonitor, OcvCurve};
+use anyhow::Result;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Pack simulado: integra la corriente en tiempo real y responde con la OCV
+/// de la curva menos la caída en la resistencia interna.
+///
+/// Los clones comparten el estado, de modo que un test o la simulación de
+/// la base de carga pueden cambiar la carga mientras el monitor lee otro.
+#[derive(Debug, Clone)]
+pub struct SimulatedPack {
+    state: Arc<Mutex<PackState>>,
+}
+
+#[derive(Debug)]
+struct PackState {
+    curve: OcvCurve,
+    cells: u32,
+    capacity_ah: f64,
+    internal_resistance: f64,
+    soc: f64,
+    load: f64,
+    charge: f64,
+    temperature: f64,
+    /// Multiplicador del tiempo real para acelerar descargas en simulación
+    time_scale: f64,
+    last: Instant,
+}
+
+impl PackState {
+    fn current(&self) -> f64 {
+        self.load - self.charge
+    }
+
+    fn advance(&mut self) {
+        let now = Instant::now();
+        let dt = now.duration_since(self.last).as_secs_f64() * self.time_scale;
+        self.last = now;
+        self.soc = (self.soc - self.current() * dt / 3600.0 / self.capacity_ah).clamp(0.0, 1.0);
+    }
+}
+
+impl SimulatedPack {
+    pub fn new(config: &BatteryConfig, state_of_charge: f64) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(PackState {
+                curve: config.ocv_curve.clone(),
+                cells: config.cells.max(1),
+                capacity_ah: config.capacity_ah.max(1e-3),
+                internal_resistance: config.internal_resistance,
+                soc: state_of_charge.clamp(0.0, 1.0),
+                load: 0.0,
+                charge: 0.0,
+                temperature: 25.0,
+                time_scale: 1.0,
+                last: Instant::now(),
+            })),
+        }
+    }
+
+    /// Consumo de los motores y la electrónica (A)
+    pub fn set_load(&self, current: f64) {
+        let mut state = self.lock();
+        state.advance();
+        state.load = current.max(0.0);
+    }
+
+    /// Corriente que entrega el cargador (A); 0 al desconectarlo
+    pub fn set_charging(&self, current: f64) {
+        let mut state = self.lock();
+        state.advance();
+        state.charge = current.max(0.0);
+    }
+
+    pub fn set_state_of_charge(&self, soc: f64) {
+        let mut state = self.lock();
+        state.advance();
+        state.soc = soc.clamp(0.0, 1.0);
+    }
+
+    pub fn set_time_scale(&self, scale: f64) {
+        let mut state = self.lock();
+        state.advance();
+        state.time_scale = scale.max(0.0);
+    }
+
+    pub fn state_of_charge(&self) -> f64 {
+        let mut state = self.lock();
+        state.advance();
+        state.soc
+    }
+
+    pub fn is_charging(&self) -> bool {
+        self.lock().charge > 0.0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, PackState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl BatteryMonitor for SimulatedPack {
+    fn voltage(&mut self) -> Result<f64> {
+        let mut state = self.lock();
+        state.advance();
+        let open_circuit = state.curve.voltage(state.soc) * state.cells as f64;
+        Ok(open_circuit - state.current() * state.internal_resistance)
+    }
+
+    fn current(&mut self) -> Result<f64> {
+        let mut state = self.lock();
+        state.advance();
+        Ok(state.current())
+    }
+
+    fn temperature(&mut self) -> Result<Option<f64>> {
+        let state = self.lock();
+        // Calentamiento aproximado por efecto Joule
+        Ok(Some(
+            state.temperature + state.current().powi(2) * state.internal_resistance,
+        ))
+    }
+}
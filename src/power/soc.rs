@@ -0,0 +1,194 @@
+//! Estimación del estado de carga: curva OCV y recuento de culombios
+use serde::{Deserialize, Serialize};
+
+/// Tensión en circuito abierto por celda frente al estado de carga.
+/// Los puntos van ordenados por estado de carga creciente.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OcvCurve {
+    /// `(estado de carga 0..1, tensión por celda en V)`
+    pub points: Vec<(f64, f64)>,
+}
+
+impl OcvCurve {
+    /// Curva típica de una celda Li-ion/LiPo (3.0 V vacía, 4.2 V llena)
+    pub fn lithium_ion() -> Self {
+        Self {
+            points: vec![
+                (0.00, 3.00),
+                (0.05, 3.30),
+                (0.10, 3.50),
+                (0.20, 3.60),
+                (0.30, 3.68),
+                (0.40, 3.74),
+                (0.50, 3.80),
+                (0.60, 3.87),
+                (0.70, 3.95),
+                (0.80, 4.02),
+                (0.90, 4.10),
+                (1.00, 4.20),
+            ],
+        }
+    }
+
+    /// Estado de carga para una tensión de celda en reposo
+    pub fn soc(&self, voltage: f64) -> f64 {
+        interpolate(self.points.iter().map(|&(soc, v)| (v, soc)), voltage)
+    }
+
+    /// Tensión de celda en reposo para un estado de carga
+    pub fn voltage(&self, soc: f64) -> f64 {
+        interpolate(self.points.iter().copied(), soc)
+    }
+}
+
+impl Default for OcvCurve {
+    fn default() -> Self {
+        Self::lithium_ion()
+    }
+}
+
+/// Interpolación lineal por tramos, saturada en los extremos
+fn interpolate(points: impl Iterator<Item = (f64, f64)>, x: f64) -> f64 {
+    let points: Vec<(f64, f64)> = points.collect();
+    let (Some(first), Some(last)) = (points.first(), points.last()) else {
+        return 0.0;
+    };
+    if x <= first.0 {
+        return first.1;
+    }
+    if x >= last.0 {
+        return last.1;
+    }
+    points
+        .windows(2)
+        .find(|w| x <= w[1].0)
+        .map(|w| {
+            let (x0, y0) = w[0];
+            let (x1, y1) = w[1];
+            y0 + (y1 - y0) * (x - x0) / (x1 - x0)
+        })
+        .unwrap_or(last.1)
+}
+
+/// Recuento de culombios corregido con la curva OCV.
+///
+/// La integración de corriente es precisa a corto plazo pero acumula el error
+/// del sensor; en reposo la tensión medida se acerca a la OCV y se usa para
+/// arrastrar la estimación hacia ella.
+#[derive(Debug, Clone)]
+pub struct SocEstimator {
+    curve: OcvCurve,
+    cells: u32,
+    capacity_ah: f64,
+    internal_resistance: f64,
+    rest_current: f64,
+    rest_time: f64,
+    /// Fracción del error frente a la OCV corregida en cada muestra en reposo
+    ocv_gain: f64,
+    soc: Option<f64>,
+    resting: f64,
+}
+
+impl SocEstimator {
+    pub fn new(config: &super::BatteryConfig) -> Self {
+        Self {
+            curve: config.ocv_curve.clone(),
+            cells: config.cells.max(1),
+            capacity_ah: config.capacity_ah.max(1e-3),
+            internal_resistance: config.internal_resistance,
+            rest_current: config.rest_current,
+            rest_time: config.rest_time,
+            ocv_gain: 0.05,
+            soc: None,
+            resting: 0.0,
+        }
+    }
+
+    pub fn state_of_charge(&self) -> Option<f64> {
+        self.soc
+    }
+
+    /// Fuerza el estado de carga (p. ej. al terminar una carga completa)
+    pub fn reset(&mut self, soc: f64) {
+        self.soc = Some(soc.clamp(0.0, 1.0));
+    }
+
+    /// Estado de carga deducido de la tensión, compensando la caída en la
+    /// resistencia interna
+    pub fn ocv_soc(&self, voltage: f64, current: f64) -> f64 {
+        let open_circuit = voltage + current * self.internal_resistance;
+        self.curve.soc(open_circuit / self.cells as f64)
+    }
+
+    /// Integra una muestra; `current` es positiva en descarga (A) y `dt` en s
+    pub fn update(&mut self, voltage: f64, current: f64, dt: f64) -> f64 {
+        let ocv_soc = self.ocv_soc(voltage, current);
+
+        let soc = match self.soc {
+            // Arranque: la única referencia es la tensión
+            None => ocv_soc,
+            Some(soc) => {
+                let mut soc = soc - current * dt / 3600.0 / self.capacity_ah;
+
+                if current.abs() < self.rest_current {
+                    self.resting += dt;
+                } else {
+                    self.resting = 0.0;
+                }
+                if self.resting >= self.rest_time {
+                    soc += self.ocv_gain * (ocv_soc - soc);
+                }
+                soc
+            }
+        };
+
+        let soc = soc.clamp(0.0, 1.0);
+        self.soc = Some(soc);
+        soc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::power::BatteryConfig;
+
+    #[test]
+    fn test_ocv_curve_is_invertible() {
+        let curve = OcvCurve::lithium_ion();
+        assert_eq!(curve.soc(2.5), 0.0);
+        assert_eq!(curve.soc(4.3), 1.0);
+        for soc in [0.03, 0.25, 0.5, 0.77, 0.95] {
+            assert!((curve.soc(curve.voltage(soc)) - soc).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_coulomb_counting_and_rest_correction() {
+        let config = BatteryConfig::default();
+        let cells = config.cells as f64;
+        let curve = config.ocv_curve.clone();
+        let mut estimator = SocEstimator::new(&config);
+
+        // Arranque al 80 % en vacío
+        let soc = estimator.update(cells * curve.voltage(0.8), 0.0, 0.0);
+        assert!((soc - 0.8).abs() < 1e-9);
+
+        // Una hora a C/10: se consume el 10 % aunque la tensión caiga por la
+        // resistencia interna
+        let sag = cells * curve.voltage(0.75) - 0.5 * config.internal_resistance;
+        for _ in 0..3600 {
+            estimator.update(sag, config.capacity_ah / 10.0, 1.0);
+        }
+        let soc = estimator.state_of_charge().unwrap();
+        assert!((soc - 0.7).abs() < 1e-6, "{}", soc);
+
+        // En reposo la OCV (65 %) corrige la deriva del recuento
+        let rest = cells * curve.voltage(0.65);
+        for _ in 0..600 {
+            estimator.update(rest, 0.0, 1.0);
+        }
+        let soc = estimator.state_of_charge().unwrap();
+        assert!((soc - 0.65).abs() < 0.005, "{}", soc);
+    }
+}
@@ -0,0 +1,515 @@
+//! Formato binario de los registros
+//!
+//! ```text
+//! fichero  MAGIC | bloque* | posición del índice: u64 | MAGIC
+//! bloque   tipo: u8 | longitud: u64 | cuerpo
+//!   CHANNEL  JSON de ChannelInfo; va antes del primer bloque que lo usa
+//!   CHUNK    inicio: f64 | fin: f64 | mensajes: u32 | crc32: u32 | mensaje*
+//!            mensaje = canal: u16 | secuencia: u64 | tiempo: f64 | longitud: u32 | CBOR
+//!   INDEX    JSON de LogIndex
+//! ```
+//!
+//! Todo en little-endian. Los mensajes van en CBOR, que a diferencia de JSON
+//! conserva los `NaN` e infinitos de las lecturas. Si el proceso muere antes de escribir el índice,
+//! el lector lo reconstruye recorriendo los bloques y descarta el último si
+//! quedó a medias.
+use super::{ChannelKind, LogPayload, LogRecord, RecordSource};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+pub const MAGIC: &[u8; 8] = b"MBLOG\x00\x00\x01";
+
+const OP_CHANNEL: u8 = 1;
+const OP_CHUNK: u8 = 2;
+const OP_INDEX: u8 = 3;
+
+const BLOCK_HEADER: u64 = 9;
+const CHUNK_HEADER: usize = 24;
+/// Canal, secuencia, tiempo y longitud de cada mensaje del bloque
+const MESSAGE_HEADER: usize = 22;
+const TAIL: u64 = 16;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelInfo {
+    pub id: u16,
+    pub name: String,
+    pub kind: ChannelKind,
+}
+
+/// Entrada del índice: dónde empieza un bloque y qué intervalo cubre
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChunkInfo {
+    pub offset: u64,
+    pub start: f64,
+    pub end: f64,
+    pub records: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LogIndex {
+    pub channels: Vec<ChannelInfo>,
+    pub chunks: Vec<ChunkInfo>,
+}
+
+impl LogIndex {
+    pub fn records(&self) -> u64 {
+        self.chunks.iter().map(|chunk| chunk.records as u64).sum()
+    }
+
+    pub fn time_range(&self) -> Option<(f64, f64)> {
+        time_span(&self.chunks)
+    }
+}
+
+/// Primer y último instante cubiertos por los bloques
+pub fn time_span(chunks: &[ChunkInfo]) -> Option<(f64, f64)> {
+    let start = chunks.iter().map(|c| c.start).min_by(f64::total_cmp)?;
+    let end = chunks.iter().map(|c| c.end).max_by(f64::total_cmp)?;
+    Some((start, end))
+}
+
+pub struct LogWriter<W: Write + Seek> {
+    output: W,
+    chunk_size: usize,
+    index: LogIndex,
+    channels: HashMap<Arc<str>, (u16, ChannelKind)>,
+    chunk: Vec<u8>,
+    chunk_start: f64,
+    chunk_end: f64,
+    chunk_records: u32,
+}
+
+impl LogWriter<BufWriter<File>> {
+    pub fn create(path: &Path, chunk_size: usize) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = File::create(path)
+            .with_context(|| format!("No se pudo crear el registro {}", path.display()))?;
+        Self::new(BufWriter::new(file), chunk_size)
+    }
+}
+
+impl<W: Write + Seek> LogWriter<W> {
+    pub fn new(mut output: W, chunk_size: usize) -> Result<Self> {
+        output.write_all(MAGIC)?;
+        Ok(Self {
+            output,
+            chunk_size: chunk_size.max(1),
+            index: LogIndex::default(),
+            channels: HashMap::new(),
+            chunk: Vec::new(),
+            chunk_start: f64::INFINITY,
+            chunk_end: f64::NEG_INFINITY,
+            chunk_records: 0,
+        })
+    }
+
+    pub fn write(&mut self, record: &LogRecord) -> Result<()> {
+        let channel = self.channel_id(&record.channel, record.payload.kind())?;
+        let mut payload = Vec::new();
+        ciborium::into_writer(&record.payload, &mut payload)?;
+
+        self.chunk.extend_from_slice(&channel.to_le_bytes());
+        self.chunk.extend_from_slice(&record.sequence.to_le_bytes());
+        self.chunk
+            .extend_from_slice(&record.timestamp.to_le_bytes());
+        self.chunk
+            .extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        self.chunk.extend_from_slice(&payload);
+        self.chunk_start = self.chunk_start.min(record.timestamp);
+        self.chunk_end = self.chunk_end.max(record.timestamp);
+        self.chunk_records += 1;
+
+        if self.chunk.len() >= self.chunk_size {
+            self.flush_chunk()?;
+        }
+        Ok(())
+    }
+
+    /// Escribe el bloque en curso aunque no haya llegado a `chunk_size`
+    pub fn flush_chunk(&mut self) -> Result<()> {
+        if self.chunk_records == 0 {
+            return Ok(());
+        }
+
+        let offset = self.output.stream_position()?;
+        let mut body = Vec::with_capacity(CHUNK_HEADER + self.chunk.len());
+        body.extend_from_slice(&self.chunk_start.to_le_bytes());
+        body.extend_from_slice(&self.chunk_end.to_le_bytes());
+        body.extend_from_slice(&self.chunk_records.to_le_bytes());
+        body.extend_from_slice(&crc32(&self.chunk).to_le_bytes());
+        body.append(&mut self.chunk);
+        self.write_block(OP_CHUNK, &body)?;
+        self.output.flush()?;
+
+        self.index.chunks.push(ChunkInfo {
+            offset,
+            start: self.chunk_start,
+            end: self.chunk_end,
+            records: self.chunk_records,
+        });
+        self.chunk_start = f64::INFINITY;
+        self.chunk_end = f64::NEG_INFINITY;
+        self.chunk_records = 0;
+        Ok(())
+    }
+
+    pub fn index(&self) -> &LogIndex {
+        &self.index
+    }
+
+    /// Cierra el último bloque y escribe el índice
+    pub fn finish(mut self) -> Result<(W, LogIndex)> {
+        self.flush_chunk()?;
+        let offset = self.output.stream_position()?;
+        let index = serde_json::to_vec(&self.index)?;
+        self.write_block(OP_INDEX, &index)?;
+        self.output.write_all(&offset.to_le_bytes())?;
+        self.output.write_all(MAGIC)?;
+        self.output.flush()?;
+        Ok((self.output, self.index))
+    }
+
+    fn channel_id(&mut self, name: &Arc<str>, kind: ChannelKind) -> Result<u16> {
+        if let Some(&(id, existing)) = self.channels.get(name) {
+            if existing != kind {
+                bail!("Canal '{}' ya grabado con tipo {:?}", name, existing);
+            }
+            return Ok(id);
+        }
+
+        let id = u16::try_from(self.channels.len()).map_err(|_| anyhow!("Demasiados canales"))?;
+        let info = ChannelInfo {
+            id,
+            name: name.to_string(),
+            kind,
+        };
+        self.write_block(OP_CHANNEL, &serde_json::to_vec(&info)?)?;
+        self.index.channels.push(info);
+        self.channels.insert(name.clone(), (id, kind));
+        Ok(id)
+    }
+
+    fn write_block(&mut self, op: u8, body: &[u8]) -> Result<()> {
+        self.output.write_all(&[op])?;
+        self.output.write_all(&(body.len() as u64).to_le_bytes())?;
+        self.output.write_all(body)?;
+        Ok(())
+    }
+}
+
+pub struct LogReader<R: Read + Seek> {
+    input: R,
+    index: LogIndex,
+    names: HashMap<u16, Arc<str>>,
+}
+
+impl LogReader<BufReader<File>> {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("No se pudo abrir el registro {}", path.display()))?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R: Read + Seek> LogReader<R> {
+    pub fn new(mut input: R) -> Result<Self> {
+        let mut magic = [0u8; 8];
+        input.seek(SeekFrom::Start(0))?;
+        input
+            .read_exact(&mut magic)
+            .context("Registro vacío o truncado")?;
+        if &magic != MAGIC {
+            bail!("No es un registro de MechBot");
+        }
+
+        let length = input.seek(SeekFrom::End(0))?;
+        let index = match read_indexed(&mut input, length)? {
+            Some(index) => index,
+            None => {
+                log::warn!("⚠️ Registro sin índice; reconstruyéndolo");
+                rebuild_index(&mut input, length)?
+            }
+        };
+
+        let names = index
+            .channels
+            .iter()
+            .map(|channel| (channel.id, Arc::from(channel.name.as_str())))
+            .collect();
+        Ok(Self {
+            input,
+            index,
+            names,
+        })
+    }
+
+    pub fn index(&self) -> &LogIndex {
+        &self.index
+    }
+}
+
+impl<R: Read + Seek> RecordSource for LogReader<R> {
+    fn channels(&self) -> &[ChannelInfo] {
+        &self.index.channels
+    }
+
+    fn chunks(&self) -> &[ChunkInfo] {
+        &self.index.chunks
+    }
+
+    /// Mensajes del bloque `chunk` en el orden en que se grabaron
+    fn read_chunk(&mut self, chunk: usize) -> Result<Vec<LogRecord>> {
+        let info = self
+            .index
+            .chunks
+            .get(chunk)
+            .ok_or_else(|| anyhow!("Bloque {} fuera del registro", chunk))?;
+        let (count, offset) = (info.records, info.offset);
+
+        self.input.seek(SeekFrom::Start(offset))?;
+        let (op, body) = read_block(&mut self.input)?;
+        if op != OP_CHUNK || body.len() < CHUNK_HEADER {
+            bail!("Bloque {} corrupto", chunk);
+        }
+        let data = &body[CHUNK_HEADER..];
+        if crc32(data) != u32::from_le_bytes(body[20..24].try_into()?) {
+            bail!("CRC incorrecto en el bloque {}", chunk);
+        }
+
+        let mut cursor = data;
+        // El número de mensajes viene del fichero: no se reservan más de los
+        // que caben en el bloque
+        let mut records = Vec::with_capacity((count as usize).min(data.len() / MESSAGE_HEADER));
+        while !cursor.is_empty() {
+            let channel = u16::from_le_bytes(take(&mut cursor)?);
+            let sequence = u64::from_le_bytes(take(&mut cursor)?);
+            let timestamp = f64::from_le_bytes(take(&mut cursor)?);
+            let length = u32::from_le_bytes(take(&mut cursor)?) as usize;
+            if cursor.len() < length {
+                bail!("Mensaje truncado en el bloque {}", chunk);
+            }
+            let (payload, rest) = cursor.split_at(length);
+            cursor = rest;
+
+            let channel = self
+                .names
+                .get(&channel)
+                .cloned()
+                .ok_or_else(|| anyhow!("Canal {} sin declarar", channel))?;
+            let payload: LogPayload = ciborium::from_reader(payload)
+                .with_context(|| format!("Mensaje ilegible en '{}'", channel))?;
+            records.push(LogRecord {
+                channel,
+                sequence,
+                timestamp,
+                payload,
+            });
+        }
+        Ok(records)
+    }
+}
+
+/// Índice al final del fichero, si se cerró correctamente
+fn read_indexed<R: Read + Seek>(input: &mut R, length: u64) -> Result<Option<LogIndex>> {
+    if length < MAGIC.len() as u64 + BLOCK_HEADER + TAIL {
+        return Ok(None);
+    }
+    let mut tail = [0u8; TAIL as usize];
+    input.seek(SeekFrom::Start(length - TAIL))?;
+    input.read_exact(&mut tail)?;
+    if &tail[8..] != MAGIC {
+        return Ok(None);
+    }
+
+    let offset = u64::from_le_bytes(tail[..8].try_into()?);
+    if offset >= length - TAIL {
+        return Ok(None);
+    }
+    input.seek(SeekFrom::Start(offset))?;
+    match read_block(input)? {
+        (OP_INDEX, body) => Ok(Some(serde_json::from_slice(&body)?)),
+        _ => Ok(None),
+    }
+}
+
+fn rebuild_index<R: Read + Seek>(input: &mut R, length: u64) -> Result<LogIndex> {
+    let mut index = LogIndex::default();
+    let mut offset = MAGIC.len() as u64;
+
+    while offset + BLOCK_HEADER <= length {
+        input.seek(SeekFrom::Start(offset))?;
+        let mut header = [0u8; BLOCK_HEADER as usize];
+        input.read_exact(&mut header)?;
+        let size = u64::from_le_bytes(header[1..].try_into()?);
+        let end = offset + BLOCK_HEADER + size;
+        if end > length {
+            log::warn!("⚠️ Último bloque incompleto en {}; se descarta", offset);
+            break;
+        }
+
+        match header[0] {
+            OP_CHANNEL => {
+                let body = read_body(input, size)?;
+                index.channels.push(serde_json::from_slice(&body)?);
+            }
+            OP_CHUNK if size >= CHUNK_HEADER as u64 => {
+                let mut body = [0u8; 20];
+                input.read_exact(&mut body)?;
+                index.chunks.push(ChunkInfo {
+                    offset,
+                    start: f64::from_le_bytes(body[..8].try_into()?),
+                    end: f64::from_le_bytes(body[8..16].try_into()?),
+                    records: u32::from_le_bytes(body[16..20].try_into()?),
+                });
+            }
+            OP_INDEX => break,
+            op => bail!("Bloque desconocido {} en {}", op, offset),
+        }
+        offset = end;
+    }
+    Ok(index)
+}
+
+fn read_block<R: Read>(input: &mut R) -> Result<(u8, Vec<u8>)> {
+    let mut header = [0u8; BLOCK_HEADER as usize];
+    input.read_exact(&mut header)?;
+    let size = u64::from_le_bytes(header[1..].try_into()?);
+    Ok((header[0], read_body(input, size)?))
+}
+
+/// Cuerpo de `size` bytes. El tamaño viene del fichero: se lee con `take`
+/// para que uno truncado o manipulado no reserve más de lo que contiene.
+fn read_body<R: Read>(input: &mut R, size: u64) -> Result<Vec<u8>> {
+    let mut body = Vec::new();
+    input.take(size).read_to_end(&mut body)?;
+    if body.len() as u64 != size {
+        bail!("Bloque truncado: {} de {} bytes", body.len(), size);
+    }
+    Ok(body)
+}
+
+fn take<const N: usize>(cursor: &mut &[u8]) -> Result<[u8; N]> {
+    if cursor.len() < N {
+        bail!("Mensaje truncado");
+    }
+    let (head, rest) = cursor.split_at(N);
+    *cursor = rest;
+    Ok(head.try_into()?)
+}
+
+/// CRC-32 (IEEE 802.3)
+pub(super) fn crc32(data: &[u8]) -> u32 {
+    let mut crc = flate2::Crc::new();
+    crc.update(data);
+    crc.sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control::ControlInput;
+    use crate::sensors::{OdometryData, SensorReading};
+    use std::io::Cursor;
+
+    fn odometry(t: f64) -> LogRecord {
+        LogRecord {
+            channel: Arc::from("odom"),
+            sequence: t as u64,
+            timestamp: t,
+            payload: LogPayload::Sensor(SensorReading::Odometry(OdometryData {
+                x: t,
+                // Los valores no finitos sobreviven a la grabación
+                angular_velocity: f64::NAN,
+                timestamp: t,
+                ..Default::default()
+            })),
+        }
+    }
+
+    #[test]
+    fn test_chunked_log_round_trip_and_recovery() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+
+        let mut writer = LogWriter::new(Cursor::new(Vec::new()), 200).unwrap();
+        for i in 0..10 {
+            writer.write(&odometry(i as f64)).unwrap();
+        }
+        let command = LogRecord {
+            channel: Arc::from(super::super::COMMAND_CHANNEL),
+            sequence: 1,
+            timestamp: 9.5,
+            payload: LogPayload::Command(ControlInput::new(0.3, 0.1)),
+        };
+        writer.write(&command).unwrap();
+        let mut wrong = odometry(10.0);
+        wrong.payload = command.payload.clone();
+        assert!(writer.write(&wrong).is_err());
+
+        let (cursor, index) = writer.finish().unwrap();
+        let bytes = cursor.into_inner();
+        assert!(index.chunks.len() > 1);
+        assert_eq!(index.records(), 11);
+
+        let mut reader = LogReader::new(Cursor::new(bytes.clone())).unwrap();
+        assert_eq!(reader.index(), &index);
+        assert_eq!(reader.index().time_range(), Some((0.0, 9.5)));
+        let chunk = reader.chunk_at(4.5).unwrap();
+        let records = reader.read_chunk(chunk).unwrap();
+        assert!(records.iter().any(|r| r.timestamp >= 4.5));
+        let LogPayload::Sensor(SensorReading::Odometry(first)) = &records[0].payload else {
+            panic!("se esperaba odometría");
+        };
+        assert!(first.angular_velocity.is_nan());
+
+        // Sin índice ni cola, y con el último bloque a medias
+        let cut = index.chunks.last().unwrap().offset as usize + 12;
+        let reader = LogReader::new(Cursor::new(bytes[..cut].to_vec())).unwrap();
+        assert_eq!(
+            reader.index().chunks,
+            index.chunks[..index.chunks.len() - 1]
+        );
+        assert_eq!(reader.channels(), index.channels);
+
+        // Un byte cambiado se detecta
+        let mut corrupted = bytes;
+        let position = index.chunks[0].offset as usize + BLOCK_HEADER as usize + 40;
+        corrupted[position] ^= 0xFF;
+        let mut reader = LogReader::new(Cursor::new(corrupted)).unwrap();
+        assert!(reader.read_chunk(0).is_err());
+    }
+
+    #[test]
+    fn test_sizes_from_file_are_bounded() {
+        let mut writer = LogWriter::new(Cursor::new(Vec::new()), 200).unwrap();
+        for i in 0..3 {
+            writer.write(&odometry(i as f64)).unwrap();
+        }
+        let (cursor, index) = writer.finish().unwrap();
+        let bytes = cursor.into_inner();
+        let tail = bytes.len() - TAIL as usize;
+        let index_offset = u64::from_le_bytes(bytes[tail..tail + 8].try_into().unwrap()) as usize;
+
+        // Índice que dice ocupar 1 TB en un fichero de pocos bytes
+        let mut huge = bytes.clone();
+        huge[index_offset + 1..index_offset + 9].copy_from_slice(&(1u64 << 40).to_le_bytes());
+        assert!(LogReader::new(Cursor::new(huge)).is_err());
+
+        // Sin índice y con un número de mensajes imposible en la cabecera
+        let mut count = bytes[..index_offset].to_vec();
+        let records = index.chunks[0].offset as usize + BLOCK_HEADER as usize + 16;
+        count[records..records + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let mut reader = LogReader::new(Cursor::new(count)).unwrap();
+        assert_eq!(reader.index().chunks[0].records, u32::MAX);
+        assert_eq!(
+            reader.read_chunk(0).unwrap().len(),
+            index.chunks[0].records as usize
+        );
+    }
+}
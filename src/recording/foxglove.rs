@@ -0,0 +1,257 @@
+//! Esquemas de Foxglove (<https://docs.foxglove.dev/docs/sdk/schemas>) que se
+//! leen de ficheros MCAP grabados por otras herramientas, convertidos a los
+//! tipos del robot
+use super::mcap::parse;
+use super::{ChannelKind, LogPayload};
+use crate::control::RobotState;
+use crate::geometry::{Covariance3, Quaternion};
+use crate::sensors::{GpsData, GpsFix, LidarData, LidarPoint, SensorKind, SensorReading};
+use anyhow::Result;
+use serde::Deserialize;
+
+/// Esquema de Foxglove que se sabe convertir
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FoxgloveSchema {
+    LaserScan,
+    PoseInFrame,
+    LocationFix,
+}
+
+impl FoxgloveSchema {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "foxglove.LaserScan" => Some(Self::LaserScan),
+            "foxglove.PoseInFrame" => Some(Self::PoseInFrame),
+            "foxglove.LocationFix" => Some(Self::LocationFix),
+            _ => None,
+        }
+    }
+
+    pub fn kind(self) -> ChannelKind {
+        match self {
+            Self::LaserScan => ChannelKind::Sensor(SensorKind::Lidar),
+            Self::PoseInFrame => ChannelKind::Pose,
+            Self::LocationFix => ChannelKind::Sensor(SensorKind::Gps),
+        }
+    }
+
+    pub(super) fn decode(self, data: &[u8]) -> Result<LogPayload> {
+        Ok(match self {
+            Self::LaserScan => {
+                LogPayload::Sensor(SensorReading::Lidar(parse::<LaserScan>(data)?.into()))
+            }
+            Self::PoseInFrame => LogPayload::Pose(parse::<PoseInFrame>(data)?.into()),
+            Self::LocationFix => {
+                LogPayload::Sensor(SensorReading::Gps(parse::<LocationFix>(data)?.into()))
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+struct Time {
+    sec: u32,
+    nsec: u32,
+}
+
+impl Time {
+    fn seconds(self) -> f64 {
+        self.sec as f64 + self.nsec as f64 * 1e-9
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+struct Position {
+    x: f64,
+    y: f64,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+struct Orientation {
+    x: f64,
+    y: f64,
+    z: f64,
+    w: f64,
+}
+
+impl Default for Orientation {
+    fn default() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            w: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+struct Pose {
+    position: Position,
+    orientation: Orientation,
+}
+
+impl Pose {
+    fn yaw(&self) -> f64 {
+        let Orientation { x, y, z, w } = self.orientation;
+        Quaternion::new(w, x, y, z).yaw()
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct LaserScan {
+    timestamp: Time,
+    frame_id: String,
+    pose: Pose,
+    start_angle: f64,
+    end_angle: f64,
+    ranges: Vec<f64>,
+    intensities: Vec<f64>,
+}
+
+/// Los rumbos se giran con la orientación de `pose`; su traslación, que
+/// `LidarData` no recoge, se descarta
+impl From<LaserScan> for LidarData {
+    fn from(scan: LaserScan) -> Self {
+        let timestamp = scan.timestamp.seconds();
+        let step = match scan.ranges.len() {
+            0 | 1 => 0.0,
+            n => (scan.end_angle - scan.start_angle) / (n - 1) as f64,
+        };
+        let yaw = scan.pose.yaw();
+        let points = scan
+            .ranges
+            .iter()
+            .enumerate()
+            .map(|(i, &distance)| LidarPoint {
+                angle: yaw + scan.start_angle + i as f64 * step,
+                distance,
+                quality: scan
+                    .intensities
+                    .get(i)
+                    .map_or(0, |&intensity| intensity.clamp(0.0, u16::MAX as f64) as u16),
+                timestamp,
+            })
+            .collect();
+        let max_range = scan
+            .ranges
+            .iter()
+            .copied()
+            .filter(|range| range.is_finite())
+            .fold(0.0, f64::max);
+
+        LidarData {
+            frame_id: scan.frame_id,
+            points,
+            scan_time: 0.0,
+            min_angle: yaw + scan.start_angle.min(scan.end_angle),
+            max_angle: yaw + scan.start_angle.max(scan.end_angle),
+            min_range: 0.0,
+            max_range,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct PoseInFrame {
+    timestamp: Time,
+    pose: Pose,
+}
+
+impl From<PoseInFrame> for RobotState {
+    fn from(message: PoseInFrame) -> Self {
+        let position = message.pose.position;
+        let mut state = RobotState::new(position.x, position.y, message.pose.yaw());
+        state.timestamp = message.timestamp.seconds();
+        state
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct LocationFix {
+    timestamp: Time,
+    frame_id: String,
+    latitude: f64,
+    longitude: f64,
+    altitude: f64,
+    /// Este-norte-arriba por filas (m²)
+    position_covariance: Vec<f64>,
+}
+
+/// Foxglove no da el tipo de solución ni la calidad: una posición finita se
+/// toma como 3D y lo desconocido queda en `NaN`
+impl From<LocationFix> for GpsData {
+    fn from(fix: LocationFix) -> Self {
+        let mut covariance = Covariance3::zero();
+        if let Ok(rows) = <[f64; 9]>::try_from(fix.position_covariance.as_slice()) {
+            for (i, value) in rows.into_iter().enumerate() {
+                covariance[i / 3][i % 3] = value;
+            }
+        }
+        let located = fix.latitude.is_finite() && fix.longitude.is_finite();
+
+        GpsData {
+            frame_id: fix.frame_id,
+            latitude: fix.latitude,
+            longitude: fix.longitude,
+            altitude: fix.altitude,
+            fix: if located {
+                GpsFix::Fix3D
+            } else {
+                GpsFix::NoFix
+            },
+            satellites: 0,
+            hdop: f64::NAN,
+            vdop: f64::NAN,
+            speed: f64::NAN,
+            course: f64::NAN,
+            covariance,
+            timestamp: fix.timestamp.seconds(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_pose_and_location_fix_conversion() {
+        let scan: LidarData = parse::<LaserScan>(
+            br#"{"pose":{"orientation":{"x":0,"y":0,"z":1,"w":0}},
+            "start_angle":0.5,"end_angle":-0.5,"ranges":[2.0,2.0],"intensities":[-1,70000]}"#,
+        )
+        .unwrap()
+        .into();
+        // Lidar montado al revés y barriendo en sentido horario
+        let pi = std::f64::consts::PI;
+        assert!((scan.points[0].angle - (pi + 0.5)).abs() < 1e-9);
+        assert!((scan.points[1].angle - (pi - 0.5)).abs() < 1e-9);
+        assert!((scan.min_angle - (pi - 0.5)).abs() < 1e-9);
+        assert_eq!(
+            (scan.points[0].quality, scan.points[1].quality),
+            (0, u16::MAX)
+        );
+
+        let fix: GpsData = parse::<LocationFix>(
+            br#"{"latitude":40.4,"longitude":-3.7,"altitude":650,
+            "position_covariance":[4,0,0,0,9,0,0,0,16]}"#,
+        )
+        .unwrap()
+        .into();
+        assert!(fix.has_fix());
+        assert_eq!(fix.covariance[1][1], 9.0);
+        let lost: GpsData = parse::<LocationFix>(br#"{"latitude":null,"longitude":null}"#)
+            .unwrap()
+            .into();
+        assert!(!lost.has_fix());
+        assert_eq!(lost.covariance, Covariance3::zero());
+    }
+}
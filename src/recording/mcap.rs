@@ -0,0 +1,1221 @@
+//! Exportación e importación en MCAP (<https://mcap.dev>), el formato que
+//! abre Foxglove Studio
+//!
+//! Cada tópico lleva un esquema JSON Schema (`mechbot.LidarData`,
+//! `mechbot.RobotState`...) y los mensajes van en JSON, así que el visor los
+//! muestra sin saber nada del robot. Al escribir no se usan bloques
+//! comprimidos; al leer se aceptan bloques sin comprimir, `lz4` y `zstd`.
+//!
+//! Además de los esquemas propios se leen en JSON `foxglove.LaserScan`,
+//! `foxglove.PoseInFrame` y `foxglove.LocationFix`, convertidos a los tipos
+//! del robot. Los tópicos con otros esquemas o codificaciones se ignoran con
+//! un aviso.
+//!
+//! JSON no admite `NaN` ni infinitos: se escriben como `null` y al leer un
+//! `null` donde se espera un número se recupera como `NaN`.
+use super::format::{crc32, ChannelInfo, ChunkInfo, LogReader};
+use super::foxglove::FoxgloveSchema;
+use super::{ChannelKind, LogPayload, LogRecord, RecordSource};
+use crate::navigation::slam::OccupancyGrid;
+use crate::sensors::{SensorKind, SensorReading};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::de::{DeserializeOwned, Deserializer, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+pub const MAGIC: &[u8; 8] = b"\x89MCAP0\r\n";
+
+const OP_HEADER: u8 = 0x01;
+const OP_FOOTER: u8 = 0x02;
+const OP_SCHEMA: u8 = 0x03;
+const OP_CHANNEL: u8 = 0x04;
+const OP_MESSAGE: u8 = 0x05;
+const OP_CHUNK: u8 = 0x06;
+const OP_STATISTICS: u8 = 0x0B;
+const OP_DATA_END: u8 = 0x0F;
+
+const RECORD_HEADER: u64 = 9;
+/// Canal, secuencia y tiempos de un mensaje antes de los datos
+const MESSAGE_HEADER: usize = 22;
+const SCHEMA_ENCODING: &str = "jsonschema";
+const MESSAGE_ENCODING: &str = "json";
+/// Mensajes sueltos (fuera de bloques MCAP) que se leen de una vez
+const MESSAGES_PER_BLOCK: u32 = 1024;
+/// Reserva máxima al descomprimir un bloque, en veces su tamaño comprimido
+const MAX_EXPANSION: usize = 16;
+
+/// Tipo de los mensajes de un tópico MCAP
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum McapKind {
+    Log(ChannelKind),
+    Grid,
+}
+
+impl McapKind {
+    const ALL: [McapKind; 9] = [
+        McapKind::Log(ChannelKind::Sensor(SensorKind::Lidar)),
+        McapKind::Log(ChannelKind::Sensor(SensorKind::Imu)),
+        McapKind::Log(ChannelKind::Sensor(SensorKind::Camera)),
+        McapKind::Log(ChannelKind::Sensor(SensorKind::Odometry)),
+        McapKind::Log(ChannelKind::Sensor(SensorKind::Range)),
+        McapKind::Log(ChannelKind::Sensor(SensorKind::Gps)),
+        McapKind::Log(ChannelKind::Command),
+        McapKind::Log(ChannelKind::Pose),
+        McapKind::Grid,
+    ];
+
+    pub fn schema_name(self) -> &'static str {
+        match self {
+            Self::Log(ChannelKind::Sensor(SensorKind::Lidar)) => "mechbot.LidarData",
+            Self::Log(ChannelKind::Sensor(SensorKind::Imu)) => "mechbot.IMUData",
+            Self::Log(ChannelKind::Sensor(SensorKind::Camera)) => "mechbot.CameraData",
+            Self::Log(ChannelKind::Sensor(SensorKind::Odometry)) => "mechbot.OdometryData",
+            Self::Log(ChannelKind::Sensor(SensorKind::Range)) => "mechbot.RangeData",
+            Self::Log(ChannelKind::Sensor(SensorKind::Gps)) => "mechbot.GpsData",
+            Self::Log(ChannelKind::Command) => "mechbot.ControlInput",
+            Self::Log(ChannelKind::Pose) => "mechbot.RobotState",
+            Self::Grid => "mechbot.OccupancyGrid",
+        }
+    }
+
+    pub fn from_schema_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.schema_name() == name)
+    }
+
+    /// JSON Schema de los mensajes, tal como los serializa serde
+    fn schema(self) -> Value {
+        let number = || json!({ "type": ["number", "null"] });
+        let integer = || json!({ "type": "integer" });
+        let string = || json!({ "type": "string" });
+        let vector = || object(json!({ "x": number(), "y": number(), "z": number() }));
+        let pose = || object(json!({ "x": number(), "y": number(), "theta": number() }));
+        let array = |items: Value| json!({ "type": "array", "items": items });
+
+        let properties = match self {
+            Self::Log(ChannelKind::Sensor(SensorKind::Lidar)) => json!({
+                "frame_id": string(),
+                "points": array(object(json!({
+                    "angle": number(),
+                    "distance": number(),
+                    "quality": integer(),
+                    "timestamp": number(),
+                }))),
+                "scan_time": number(),
+                "min_angle": number(),
+                "max_angle": number(),
+                "min_range": number(),
+                "max_range": number(),
+            }),
+            Self::Log(ChannelKind::Sensor(SensorKind::Imu)) => json!({
+                "frame_id": string(),
+                "acceleration": vector(),
+                "gyroscope": vector(),
+                "magnetometer": vector(),
+                "temperature": number(),
+                "timestamp": number(),
+            }),
+            Self::Log(ChannelKind::Sensor(SensorKind::Camera)) => json!({
+                "frame_id": string(),
+                "sequence": integer(),
+                "width": integer(),
+                "height": integer(),
+                "channels": integer(),
+                "data": array(integer()),
+                "timestamp": number(),
+            }),
+            Self::Log(ChannelKind::Sensor(SensorKind::Odometry)) => json!({
+                "frame_id": string(),
+                "x": number(),
+                "y": number(),
+                "theta": number(),
+                "linear_velocity": number(),
+                "angular_velocity": number(),
+                "timestamp": number(),
+            }),
+            Self::Log(ChannelKind::Sensor(SensorKind::Range)) => json!({
+                "frame_id": string(),
+                "radiation": { "enum": ["ultrasound", "infrared"] },
+                "range": number(),
+                "min_range": number(),
+                "max_range": number(),
+                "field_of_view": number(),
+                "mounting": pose(),
+                "timestamp": number(),
+            }),
+            Self::Log(ChannelKind::Sensor(SensorKind::Gps)) => json!({
+                "frame_id": string(),
+                "latitude": number(),
+                "longitude": number(),
+                "altitude": number(),
+                "fix": { "enum": ["no_fix", "fix2_d", "fix3_d", "dgps", "rtk_float", "rtk_fixed"] },
+                "satellites": integer(),
+                "hdop": number(),
+                "vdop": number(),
+                "speed": number(),
+                "course": number(),
+                "covariance": array(array(number())),
+                "timestamp": number(),
+            }),
+            Self::Log(ChannelKind::Command) => json!({
+                "linear_x": number(),
+                "linear_y": number(),
+                "angular_z": number(),
+            }),
+            Self::Log(ChannelKind::Pose) => json!({
+                "x": number(),
+                "y": number(),
+                "theta": number(),
+                "linear_velocity": number(),
+                "angular_velocity": number(),
+                "timestamp": number(),
+            }),
+            Self::Grid => json!({
+                "width": integer(),
+                "height": integer(),
+                "resolution": number(),
+                "data": array(number()),
+                "origin_x": number(),
+                "origin_y": number(),
+                "max_range": number(),
+            }),
+        };
+        let mut schema = object(properties);
+        schema["title"] = json!(self.schema_name());
+        schema
+    }
+}
+
+fn object(properties: Value) -> Value {
+    json!({ "type": "object", "properties": properties })
+}
+
+/// Contenido de un mensaje MCAP
+#[derive(Debug, Clone)]
+pub enum McapPayload {
+    Log(LogPayload),
+    Grid(OccupancyGrid),
+}
+
+#[derive(Debug, Clone)]
+pub struct McapMessage {
+    pub topic: Arc<str>,
+    pub sequence: u32,
+    pub timestamp: f64,
+    pub payload: McapPayload,
+}
+
+pub struct McapWriter<W: Write + Seek> {
+    output: W,
+    schemas: HashMap<McapKind, u16>,
+    channels: HashMap<Arc<str>, (u16, McapKind)>,
+    /// Registros de esquemas y canales, que se repiten en el resumen final
+    summary: Vec<(u8, Vec<u8>)>,
+    counts: BTreeMap<u16, u64>,
+    start: u64,
+    end: u64,
+}
+
+impl McapWriter<BufWriter<File>> {
+    pub fn create(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = File::create(path)
+            .with_context(|| format!("No se pudo crear el fichero MCAP {}", path.display()))?;
+        Self::new(BufWriter::new(file))
+    }
+}
+
+impl<W: Write + Seek> McapWriter<W> {
+    pub fn new(mut output: W) -> Result<Self> {
+        output.write_all(MAGIC)?;
+        let header = Body::default()
+            .string("")
+            .string(concat!("mechbot-3x ", env!("CARGO_PKG_VERSION")));
+        write_record(&mut output, OP_HEADER, &header.0)?;
+        Ok(Self {
+            output,
+            schemas: HashMap::new(),
+            channels: HashMap::new(),
+            summary: Vec::new(),
+            counts: BTreeMap::new(),
+            start: u64::MAX,
+            end: 0,
+        })
+    }
+
+    pub fn write(&mut self, record: &LogRecord) -> Result<()> {
+        let channel = self.channel_id(&record.channel, McapKind::Log(record.payload.kind()))?;
+        let data = encode(&record.payload)?;
+        self.write_message(channel, record.sequence as u32, record.timestamp, &data)
+    }
+
+    /// Mapa de ocupación en `topic`, con marca de tiempo en segundos Unix
+    pub fn write_grid(&mut self, topic: &str, timestamp: f64, grid: &OccupancyGrid) -> Result<()> {
+        let channel = self.channel_id(&Arc::from(topic), McapKind::Grid)?;
+        let sequence = self.counts.get(&channel).copied().unwrap_or(0) + 1;
+        let data = serde_json::to_vec(grid)?;
+        self.write_message(channel, sequence as u32, timestamp, &data)
+    }
+
+    /// Escribe el resumen y el pie; devuelve el destino y los mensajes escritos
+    pub fn finish(mut self) -> Result<(W, u64)> {
+        write_record(&mut self.output, OP_DATA_END, &0u32.to_le_bytes())?;
+
+        let summary_start = self.output.stream_position()?;
+        for (op, body) in &self.summary {
+            write_record(&mut self.output, *op, body)?;
+        }
+        let messages = self.counts.values().sum();
+        let mut counts = Body::default();
+        for (channel, count) in &self.counts {
+            counts = counts.u16(*channel).u64(*count);
+        }
+        let statistics = Body::default()
+            .u64(messages)
+            .u16(self.schemas.len() as u16)
+            .u32(self.channels.len() as u32)
+            .u32(0)
+            .u32(0)
+            .u32(0)
+            .u64(if messages > 0 { self.start } else { 0 })
+            .u64(self.end)
+            .prefixed(&counts.0);
+        write_record(&mut self.output, OP_STATISTICS, &statistics.0)?;
+
+        // Sin tabla de offsets del resumen ni CRC (0 = no comprobar)
+        let footer = Body::default().u64(summary_start).u64(0).u32(0);
+        write_record(&mut self.output, OP_FOOTER, &footer.0)?;
+        self.output.write_all(MAGIC)?;
+        self.output.flush()?;
+        Ok((self.output, messages))
+    }
+
+    fn write_message(
+        &mut self,
+        channel: u16,
+        sequence: u32,
+        timestamp: f64,
+        data: &[u8],
+    ) -> Result<()> {
+        let time = to_nanos(timestamp);
+        let body = Body::default()
+            .u16(channel)
+            .u32(sequence)
+            .u64(time)
+            .u64(time)
+            .raw(data);
+        write_record(&mut self.output, OP_MESSAGE, &body.0)?;
+        *self.counts.entry(channel).or_default() += 1;
+        self.start = self.start.min(time);
+        self.end = self.end.max(time);
+        Ok(())
+    }
+
+    fn channel_id(&mut self, topic: &Arc<str>, kind: McapKind) -> Result<u16> {
+        if let Some(&(id, existing)) = self.channels.get(topic) {
+            if existing != kind {
+                bail!(
+                    "Tópico '{}' ya escrito como {}",
+                    topic,
+                    existing.schema_name()
+                );
+            }
+            return Ok(id);
+        }
+
+        let schema = match self.schemas.get(&kind) {
+            Some(&id) => id,
+            None => {
+                // El esquema 0 significa «sin esquema»
+                let id = self.schemas.len() as u16 + 1;
+                let body = Body::default()
+                    .u16(id)
+                    .string(kind.schema_name())
+                    .string(SCHEMA_ENCODING)
+                    .prefixed(&serde_json::to_vec(&kind.schema())?);
+                self.emit(OP_SCHEMA, body.0)?;
+                self.schemas.insert(kind, id);
+                id
+            }
+        };
+
+        let id = u16::try_from(self.channels.len()).map_err(|_| anyhow!("Demasiados tópicos"))?;
+        let body = Body::default()
+            .u16(id)
+            .u16(schema)
+            .string(topic)
+            .string(MESSAGE_ENCODING)
+            .u32(0);
+        self.emit(OP_CHANNEL, body.0)?;
+        self.channels.insert(topic.clone(), (id, kind));
+        Ok(id)
+    }
+
+    fn emit(&mut self, op: u8, body: Vec<u8>) -> Result<()> {
+        write_record(&mut self.output, op, &body)?;
+        self.summary.push((op, body));
+        Ok(())
+    }
+}
+
+/// Esquema de un tópico al leer: uno propio o uno de Foxglove que se
+/// convierte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Schema {
+    Mechbot(McapKind),
+    Foxglove(FoxgloveSchema),
+}
+
+impl Schema {
+    fn from_name(name: &str) -> Option<Self> {
+        McapKind::from_schema_name(name)
+            .map(Self::Mechbot)
+            .or_else(|| FoxgloveSchema::from_name(name).map(Self::Foxglove))
+    }
+
+    fn kind(self) -> McapKind {
+        match self {
+            Self::Mechbot(kind) => kind,
+            Self::Foxglove(schema) => McapKind::Log(schema.kind()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Topic {
+    name: Arc<str>,
+    schema: Option<Schema>,
+}
+
+/// Lector de ficheros MCAP. Al abrirlo recorre el fichero una vez para
+/// localizar esquemas, canales y mensajes; después se lee por bloques, que son
+/// los `Chunk` del fichero o tandas de mensajes sueltos.
+pub struct McapReader<R: Read + Seek> {
+    input: R,
+    schemas: HashMap<u16, Option<Schema>>,
+    topics: HashMap<u16, Topic>,
+    channels: Vec<ChannelInfo>,
+    blocks: Vec<ChunkInfo>,
+    /// Fin (exclusivo) de cada bloque en el fichero
+    ends: Vec<u64>,
+}
+
+impl McapReader<BufReader<File>> {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("No se pudo abrir el fichero MCAP {}", path.display()))?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R: Read + Seek> McapReader<R> {
+    pub fn new(mut input: R) -> Result<Self> {
+        let mut magic = [0u8; 8];
+        input.seek(SeekFrom::Start(0))?;
+        input
+            .read_exact(&mut magic)
+            .context("Fichero MCAP vacío o truncado")?;
+        if &magic != MAGIC {
+            bail!("No es un fichero MCAP");
+        }
+
+        let length = input.seek(SeekFrom::End(0))?;
+        let mut reader = Self {
+            input,
+            schemas: HashMap::new(),
+            topics: HashMap::new(),
+            channels: Vec::new(),
+            blocks: Vec::new(),
+            ends: Vec::new(),
+        };
+        reader.scan(length)?;
+        Ok(reader)
+    }
+
+    /// Tópicos legibles con su tipo, incluidos los mapas
+    pub fn topics(&self) -> Vec<(Arc<str>, McapKind)> {
+        let mut topics: Vec<_> = self
+            .topics
+            .values()
+            .filter_map(|topic| Some((topic.name.clone(), topic.schema?.kind())))
+            .collect();
+        topics.sort_by(|a, b| a.0.cmp(&b.0));
+        topics
+    }
+
+    /// Mensajes legibles del bloque `block` ordenados por tiempo
+    pub fn messages(&mut self, block: usize) -> Result<Vec<McapMessage>> {
+        let (offset, end) = match (self.blocks.get(block), self.ends.get(block)) {
+            (Some(info), Some(&end)) => (info.offset, end),
+            _ => bail!("Bloque {} fuera del fichero MCAP", block),
+        };
+        let mut data = vec![0u8; usize::try_from(end - offset)?];
+        self.input.seek(SeekFrom::Start(offset))?;
+        self.input.read_exact(&mut data)?;
+
+        let mut messages = Vec::new();
+        for (op, body) in records(&data)? {
+            match op {
+                OP_MESSAGE => messages.extend(self.decode_message(body)?),
+                OP_CHUNK => {
+                    let chunk = chunk_records(body)?;
+                    for (op, body) in records(&chunk)? {
+                        if op == OP_MESSAGE {
+                            messages.extend(self.decode_message(body)?);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        messages.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+        Ok(messages)
+    }
+
+    fn scan(&mut self, length: u64) -> Result<()> {
+        let mut blocks: Vec<(ChunkInfo, u64)> = Vec::new();
+        // Tanda de mensajes sueltos en curso
+        let mut run: Option<(ChunkInfo, u64)> = None;
+        let mut offset = MAGIC.len() as u64;
+
+        while offset + RECORD_HEADER <= length {
+            self.input.seek(SeekFrom::Start(offset))?;
+            let mut header = [0u8; RECORD_HEADER as usize];
+            self.input.read_exact(&mut header)?;
+            let op = header[0];
+            let size = u64::from_le_bytes(header[1..].try_into()?);
+            let end = offset + RECORD_HEADER + size;
+            if end > length {
+                log::warn!("⚠️ Registro MCAP incompleto en {}; se descarta", offset);
+                break;
+            }
+            match op {
+                OP_SCHEMA | OP_CHANNEL => {
+                    let body = self.read_body(size)?;
+                    self.register(op, &body)?;
+                }
+                OP_MESSAGE => {
+                    if size < MESSAGE_HEADER as u64 {
+                        bail!("Mensaje MCAP corrupto en {}", offset);
+                    }
+                    let mut message = [0u8; MESSAGE_HEADER];
+                    self.input.read_exact(&mut message)?;
+                    let time = from_nanos(u64::from_le_bytes(message[6..14].try_into()?));
+
+                    let (info, run_end) = run.get_or_insert_with(|| {
+                        let info = ChunkInfo {
+                            offset,
+                            start: time,
+                            end: time,
+                            records: 0,
+                        };
+                        (info, end)
+                    });
+                    info.start = info.start.min(time);
+                    info.end = info.end.max(time);
+                    info.records += 1;
+                    *run_end = end;
+                    if info.records >= MESSAGES_PER_BLOCK {
+                        blocks.extend(run.take());
+                    }
+                }
+                OP_CHUNK => {
+                    // Los esquemas y canales entre mensajes sueltos no cortan
+                    // la tanda; el bloque MCAP sí
+                    blocks.extend(run.take());
+                    let body = self.read_body(size)?;
+                    let mut info = ChunkInfo {
+                        offset,
+                        start: f64::INFINITY,
+                        end: f64::NEG_INFINITY,
+                        records: 0,
+                    };
+                    let chunk = chunk_records(&body)
+                        .with_context(|| format!("Bloque MCAP ilegible en {}", offset))?;
+                    for (op, body) in records(&chunk)? {
+                        match op {
+                            OP_SCHEMA | OP_CHANNEL => self.register(op, body)?,
+                            OP_MESSAGE => {
+                                let (_, _, time, _) = message(body)?;
+                                info.start = info.start.min(time);
+                                info.end = info.end.max(time);
+                                info.records += 1;
+                            }
+                            _ => {}
+                        }
+                    }
+                    if info.records > 0 {
+                        blocks.push((info, end));
+                    }
+                }
+                // Lo que sigue (resumen) repite lo ya leído
+                OP_DATA_END | OP_FOOTER => break,
+                _ => {}
+            }
+            offset = end;
+        }
+        blocks.extend(run);
+
+        blocks.sort_by(|a, b| a.0.start.total_cmp(&b.0.start));
+        (self.blocks, self.ends) = blocks.into_iter().unzip();
+        Ok(())
+    }
+
+    fn read_body(&mut self, size: u64) -> Result<Vec<u8>> {
+        let mut body = vec![0u8; usize::try_from(size)?];
+        self.input.read_exact(&mut body)?;
+        Ok(body)
+    }
+
+    fn register(&mut self, op: u8, body: &[u8]) -> Result<()> {
+        let mut fields = Fields(body);
+        let id = fields.u16()?;
+        if op == OP_SCHEMA {
+            let name = fields.string()?;
+            self.schemas.insert(id, Schema::from_name(name));
+            return Ok(());
+        }
+        if self.topics.contains_key(&id) {
+            return Ok(());
+        }
+
+        let schema = fields.u16()?;
+        let name: Arc<str> = Arc::from(fields.string()?);
+        let encoding = fields.string()?;
+        let schema = match self.schemas.get(&schema) {
+            Some(&schema) if encoding == MESSAGE_ENCODING => schema,
+            _ => None,
+        };
+        match schema.map(Schema::kind) {
+            Some(McapKind::Log(kind)) => self.channels.push(ChannelInfo {
+                id,
+                name: name.to_string(),
+                kind,
+            }),
+            Some(McapKind::Grid) => {}
+            None => log::warn!(
+                "⚠️ Tópico MCAP '{}' con esquema desconocido; se ignora",
+                name
+            ),
+        }
+        self.topics.insert(id, Topic { name, schema });
+        Ok(())
+    }
+
+    fn decode_message(&self, body: &[u8]) -> Result<Option<McapMessage>> {
+        let (channel, sequence, timestamp, data) = message(body)?;
+        let Some(Topic {
+            name,
+            schema: Some(schema),
+        }) = self.topics.get(&channel)
+        else {
+            return Ok(None);
+        };
+        let payload = match schema {
+            Schema::Mechbot(kind) => decode(*kind, data),
+            Schema::Foxglove(schema) => schema.decode(data).map(McapPayload::Log),
+        }
+        .with_context(|| format!("Mensaje ilegible en '{}'", name))?;
+        Ok(Some(McapMessage {
+            topic: name.clone(),
+            sequence,
+            timestamp,
+            payload,
+        }))
+    }
+}
+
+impl<R: Read + Seek> RecordSource for McapReader<R> {
+    fn channels(&self) -> &[ChannelInfo] {
+        &self.channels
+    }
+
+    fn chunks(&self) -> &[ChunkInfo] {
+        &self.blocks
+    }
+
+    /// Mensajes de sensores, comandos y poses del bloque; los mapas se omiten
+    fn read_chunk(&mut self, chunk: usize) -> Result<Vec<LogRecord>> {
+        Ok(self
+            .messages(chunk)?
+            .into_iter()
+            .filter_map(|message| match message.payload {
+                McapPayload::Log(payload) => Some(LogRecord {
+                    channel: message.topic,
+                    sequence: message.sequence as u64,
+                    timestamp: message.timestamp,
+                    payload,
+                }),
+                McapPayload::Grid(_) => None,
+            })
+            .collect())
+    }
+}
+
+/// Convierte un registro `.mblog` a MCAP; devuelve los mensajes escritos
+pub fn export(log: &Path, output: &Path) -> Result<u64> {
+    let mut reader = LogReader::open(log)?;
+    let mut writer = McapWriter::create(output)?;
+    for chunk in 0..reader.chunks().len() {
+        for record in reader.read_chunk(chunk)? {
+            writer.write(&record)?;
+        }
+    }
+    let (_, messages) = writer.finish()?;
+    log::info!(
+        "📦 {} exportado a {}: {} mensajes",
+        log.display(),
+        output.display(),
+        messages
+    );
+    Ok(messages)
+}
+
+fn encode(payload: &LogPayload) -> serde_json::Result<Vec<u8>> {
+    match payload {
+        LogPayload::Sensor(SensorReading::Lidar(data)) => serde_json::to_vec(data),
+        LogPayload::Sensor(SensorReading::Imu(data)) => serde_json::to_vec(data),
+        LogPayload::Sensor(SensorReading::Camera(data)) => serde_json::to_vec(data),
+        LogPayload::Sensor(SensorReading::Odometry(data)) => serde_json::to_vec(data),
+        LogPayload::Sensor(SensorReading::Range(data)) => serde_json::to_vec(data),
+        LogPayload::Sensor(SensorReading::Gps(data)) => serde_json::to_vec(data),
+        LogPayload::Command(command) => serde_json::to_vec(command),
+        LogPayload::Pose(pose) => serde_json::to_vec(pose),
+    }
+}
+
+/// Mensaje JSON con `null` donde haya números no finitos
+pub(super) fn parse<T: DeserializeOwned>(data: &[u8]) -> serde_json::Result<T> {
+    T::deserialize(Lenient(serde_json::from_slice(data)?))
+}
+
+fn decode(kind: McapKind, data: &[u8]) -> Result<McapPayload> {
+    let reading = match kind {
+        McapKind::Grid => return Ok(McapPayload::Grid(parse(data)?)),
+        McapKind::Log(ChannelKind::Command) => LogPayload::Command(parse(data)?),
+        McapKind::Log(ChannelKind::Pose) => LogPayload::Pose(parse(data)?),
+        McapKind::Log(ChannelKind::Sensor(kind)) => LogPayload::Sensor(match kind {
+            SensorKind::Lidar => SensorReading::Lidar(parse(data)?),
+            SensorKind::Imu => SensorReading::Imu(parse(data)?),
+            SensorKind::Camera => SensorReading::Camera(parse(data)?),
+            SensorKind::Odometry => SensorReading::Odometry(parse(data)?),
+            SensorKind::Range => SensorReading::Range(parse(data)?),
+            SensorKind::Gps => SensorReading::Gps(parse(data)?),
+        }),
+    };
+    Ok(McapPayload::Log(reading))
+}
+
+/// JSON que acepta `null` donde se espera un número y lo lee como `NaN`
+struct Lenient(Value);
+
+impl<'de> IntoDeserializer<'de, serde_json::Error> for Lenient {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for Lenient {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> serde_json::Result<V::Value> {
+        match self.0 {
+            Value::Array(items) => {
+                let mut items = SeqDeserializer::new(items.into_iter().map(Lenient));
+                let value = visitor.visit_seq(&mut items)?;
+                items.end()?;
+                Ok(value)
+            }
+            Value::Object(fields) => {
+                let mut fields =
+                    MapDeserializer::new(fields.into_iter().map(|(k, v)| (k, Lenient(v))));
+                let value = visitor.visit_map(&mut fields)?;
+                fields.end()?;
+                Ok(value)
+            }
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> serde_json::Result<V::Value> {
+        match self.0 {
+            Value::Null => visitor.visit_f64(f64::NAN),
+            other => other.deserialize_f64(visitor),
+        }
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> serde_json::Result<V::Value> {
+        match self.0 {
+            Value::Null => visitor.visit_f32(f32::NAN),
+            other => other.deserialize_f32(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> serde_json::Result<V::Value> {
+        match self.0 {
+            Value::Null => visitor.visit_none(),
+            other => visitor.visit_some(Lenient(other)),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> serde_json::Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> serde_json::Result<V::Value> {
+        self.0.deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 char str string bytes byte_buf
+        unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+fn to_nanos(t: f64) -> u64 {
+    (t * 1e9).round() as u64
+}
+
+fn from_nanos(ns: u64) -> f64 {
+    ns as f64 * 1e-9
+}
+
+fn write_record<W: Write>(output: &mut W, op: u8, body: &[u8]) -> Result<()> {
+    output.write_all(&[op])?;
+    output.write_all(&(body.len() as u64).to_le_bytes())?;
+    output.write_all(body)?;
+    Ok(())
+}
+
+/// Registros consecutivos `tipo | longitud | cuerpo`
+fn records(mut data: &[u8]) -> Result<Vec<(u8, &[u8])>> {
+    let mut records = Vec::new();
+    while !data.is_empty() {
+        let mut fields = Fields(data);
+        let op = fields.bytes(1)?[0];
+        let size = usize::try_from(fields.u64()?)?;
+        records.push((op, fields.bytes(size)?));
+        data = fields.0;
+    }
+    Ok(records)
+}
+
+/// Canal, secuencia, tiempo de registro y datos de un mensaje
+fn message(body: &[u8]) -> Result<(u16, u32, f64, &[u8])> {
+    let mut fields = Fields(body);
+    let channel = fields.u16()?;
+    let sequence = fields.u32()?;
+    let log_time = from_nanos(fields.u64()?);
+    let _publish_time = fields.u64()?;
+    Ok((channel, sequence, log_time, fields.0))
+}
+
+/// Registros contenidos en un `Chunk`, ya descomprimidos
+fn chunk_records(body: &[u8]) -> Result<Vec<u8>> {
+    let mut fields = Fields(body);
+    let _start = fields.u64()?;
+    let _end = fields.u64()?;
+    let size = usize::try_from(fields.u64()?)?;
+    let crc = fields.u32()?;
+    let compression = fields.string()?;
+    let length = usize::try_from(fields.u64()?)?;
+    let data = fields.bytes(length)?;
+
+    let records = match compression {
+        "" => data.to_vec(),
+        "lz4" => decompress(lz4_flex::frame::FrameDecoder::new(data), size, data.len())?,
+        "zstd" => {
+            let decoder = ruzstd::decoding::StreamingDecoder::new(data)
+                .map_err(|e| anyhow!("zstd: {}", e))?;
+            decompress(decoder, size, data.len())?
+        }
+        other => bail!("Compresión MCAP '{}' no soportada", other),
+    };
+    if records.len() != size || (crc != 0 && crc32(&records) != crc) {
+        bail!("Bloque MCAP corrupto");
+    }
+    Ok(records)
+}
+
+/// Descomprime un bloque que dice ocupar `size` bytes. El tamaño viene del
+/// fichero: no se reserva más de `MAX_EXPANSION` veces lo comprimido ni se lee
+/// más de `size + 1`, así que un fichero corrupto no agota la memoria antes
+/// de la comprobación de longitud.
+fn decompress(decoder: impl Read, size: usize, compressed: usize) -> Result<Vec<u8>> {
+    let mut records = Vec::with_capacity(size.min(compressed.saturating_mul(MAX_EXPANSION)));
+    decoder.take(size as u64 + 1).read_to_end(&mut records)?;
+    Ok(records)
+}
+
+/// Campos little-endian de un registro
+struct Fields<'a>(&'a [u8]);
+
+impl<'a> Fields<'a> {
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.0.len() < n {
+            bail!("Registro MCAP truncado");
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into()?))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into()?))
+    }
+
+    fn string(&mut self) -> Result<&'a str> {
+        let length = self.u32()? as usize;
+        Ok(std::str::from_utf8(self.bytes(length)?)?)
+    }
+}
+
+/// Cuerpo de un registro en construcción
+#[derive(Default)]
+struct Body(Vec<u8>);
+
+impl Body {
+    fn u16(mut self, value: u16) -> Self {
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    fn u32(mut self, value: u32) -> Self {
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    fn u64(mut self, value: u64) -> Self {
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    fn string(self, value: &str) -> Self {
+        self.prefixed(value.as_bytes())
+    }
+
+    /// Bytes precedidos de su longitud como u32
+    fn prefixed(self, data: &[u8]) -> Self {
+        self.u32(data.len() as u32).raw(data)
+    }
+
+    fn raw(mut self, data: &[u8]) -> Self {
+        self.0.extend_from_slice(data);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control::{ControlInput, RobotState};
+    use crate::recording::{PlaybackMode, Player, COMMAND_CHANNEL, POSE_CHANNEL};
+    use crate::sensors::{OdometryData, RangeData, RangeRadiation, SensorBus};
+    use std::io::Cursor;
+
+    fn odometry(i: u64) -> LogRecord {
+        let t = 1_700_000_000.0 + i as f64 * 0.1;
+        LogRecord {
+            channel: Arc::from("odom"),
+            sequence: i + 1,
+            timestamp: t,
+            payload: LogPayload::Sensor(SensorReading::Odometry(OdometryData {
+                x: i as f64,
+                theta: if i == 1 { f64::NAN } else { 0.5 },
+                timestamp: t,
+                ..Default::default()
+            })),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mcap_round_trip_and_replay() {
+        let mut writer = McapWriter::new(Cursor::new(Vec::new())).unwrap();
+        for i in 0..5 {
+            writer.write(&odometry(i)).unwrap();
+        }
+        writer
+            .write(&LogRecord {
+                channel: Arc::from("sonar"),
+                sequence: 1,
+                timestamp: 1_700_000_000.05,
+                payload: LogPayload::Sensor(SensorReading::Range(RangeData {
+                    frame_id: "sonar".into(),
+                    radiation: RangeRadiation::Ultrasound,
+                    range: f64::INFINITY,
+                    min_range: 0.02,
+                    max_range: 4.0,
+                    field_of_view: 0.5,
+                    mounting: Default::default(),
+                    timestamp: 1_700_000_000.05,
+                })),
+            })
+            .unwrap();
+        writer
+            .write(&LogRecord {
+                channel: Arc::from(COMMAND_CHANNEL),
+                sequence: 1,
+                timestamp: 1_700_000_000.15,
+                payload: LogPayload::Command(ControlInput::new(0.3, 0.1)),
+            })
+            .unwrap();
+        writer
+            .write(&LogRecord {
+                channel: Arc::from(POSE_CHANNEL),
+                sequence: 1,
+                timestamp: 1_700_000_000.2,
+                payload: LogPayload::Pose(RobotState::new(1.0, 2.0, 0.3)),
+            })
+            .unwrap();
+        writer
+            .write_grid("map", 1_700_000_000.3, &OccupancyGrid::new(4, 3, 0.05))
+            .unwrap();
+        assert!(writer
+            .write_grid("odom", 0.0, &OccupancyGrid::new(1, 1, 1.0))
+            .is_err());
+        let (cursor, messages) = writer.finish().unwrap();
+        assert_eq!(messages, 9);
+
+        let bytes = cursor.into_inner();
+        assert_eq!(&bytes[..8], MAGIC);
+        assert_eq!(&bytes[bytes.len() - 8..], MAGIC);
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.contains("mechbot.OdometryData") && text.contains("jsonschema"));
+
+        let mut reader = McapReader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(reader.channels().len(), 4);
+        assert!(reader
+            .topics()
+            .contains(&(Arc::from("map"), McapKind::Grid)));
+        let messages = reader.messages(0).unwrap();
+        assert_eq!(messages.len(), 9);
+        assert!(messages
+            .windows(2)
+            .all(|pair| pair[0].timestamp <= pair[1].timestamp));
+        match &messages[2].payload {
+            McapPayload::Log(LogPayload::Sensor(SensorReading::Odometry(odometry))) => {
+                assert_eq!(odometry.x, 1.0);
+                assert!(odometry.theta.is_nan());
+            }
+            other => panic!("{:?}", other),
+        }
+        match &messages[1].payload {
+            McapPayload::Log(LogPayload::Sensor(SensorReading::Range(range))) => {
+                assert!(!range.is_detection());
+                assert_eq!(range.radiation, RangeRadiation::Ultrasound);
+            }
+            other => panic!("{:?}", other),
+        }
+        let grid = messages
+            .iter()
+            .find_map(|message| match &message.payload {
+                McapPayload::Grid(grid) => Some(grid),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(grid.get(3, 2), 0.5);
+
+        let mut bus = SensorBus::new(16);
+        let mut player = Player::new(reader);
+        player.attach(&mut bus).unwrap();
+        let mut poses = player.subscribe_poses();
+        assert_eq!(player.run(PlaybackMode::AsFastAsPossible).await.unwrap(), 8);
+        assert_eq!(bus.history("odom", 10).len(), 5);
+        assert_eq!(bus.history("sonar", 10).len(), 1);
+        assert_eq!(poses.recv().await.unwrap().y, 2.0);
+    }
+
+    #[test]
+    fn test_reads_compressed_chunks() {
+        let mut inner = Vec::new();
+        let schema = Body::default()
+            .u16(1)
+            .string("mechbot.ControlInput")
+            .string(SCHEMA_ENCODING)
+            .prefixed(b"{}");
+        write_record(&mut inner, OP_SCHEMA, &schema.0).unwrap();
+        let channel = Body::default()
+            .u16(7)
+            .u16(1)
+            .string("/cmd")
+            .string(MESSAGE_ENCODING)
+            .u32(0);
+        write_record(&mut inner, OP_CHANNEL, &channel.0).unwrap();
+        for (i, linear) in [(2u64, "0.2"), (1, "null")] {
+            let data = format!(r#"{{"linear_x":{},"linear_y":0,"angular_z":0}}"#, linear);
+            let message = Body::default()
+                .u16(7)
+                .u32(i as u32)
+                .u64(i * 1_000_000_000)
+                .u64(0)
+                .raw(data.as_bytes());
+            write_record(&mut inner, OP_MESSAGE, &message.0).unwrap();
+        }
+
+        let mut compressed = lz4_flex::frame::FrameEncoder::new(Vec::new());
+        compressed.write_all(&inner).unwrap();
+        let compressed = compressed.finish().unwrap();
+        let chunk = Body::default()
+            .u64(1_000_000_000)
+            .u64(2_000_000_000)
+            .u64(inner.len() as u64)
+            .u32(crc32(&inner))
+            .string("lz4")
+            .u64(compressed.len() as u64)
+            .raw(&compressed);
+        let mut file = MAGIC.to_vec();
+        write_record(&mut file, OP_CHUNK, &chunk.0).unwrap();
+        write_record(&mut file, OP_DATA_END, &0u32.to_le_bytes()).unwrap();
+
+        let mut reader = McapReader::new(Cursor::new(file)).unwrap();
+        assert_eq!(reader.chunks().len(), 1);
+        assert_eq!(reader.time_range(), Some((1.0, 2.0)));
+        let records = reader.read_chunk(0).unwrap();
+        assert_eq!(&*records[0].channel, "/cmd");
+        assert_eq!(records[0].sequence, 1);
+        match (&records[0].payload, &records[1].payload) {
+            (LogPayload::Command(first), LogPayload::Command(second)) => {
+                assert!(first.linear_x.is_nan());
+                assert_eq!(second.linear_x, 0.2);
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_reads_foxglove_schemas() {
+        let mut file = MAGIC.to_vec();
+        let schemas = [
+            "foxglove.LaserScan",
+            "foxglove.PoseInFrame",
+            "foxglove.Grid",
+        ];
+        for (id, (name, topic)) in schemas.iter().zip(["/scan", "/pose", "/grid"]).enumerate() {
+            let id = id as u16 + 1;
+            let schema = Body::default()
+                .u16(id)
+                .string(name)
+                .string(SCHEMA_ENCODING)
+                .prefixed(b"{}");
+            write_record(&mut file, OP_SCHEMA, &schema.0).unwrap();
+            let channel = Body::default()
+                .u16(id)
+                .u16(id)
+                .string(topic)
+                .string(MESSAGE_ENCODING)
+                .u32(0);
+            write_record(&mut file, OP_CHANNEL, &channel.0).unwrap();
+        }
+        let messages = [
+            (
+                1u16,
+                r#"{"timestamp":{"sec":1,"nsec":0},"frame_id":"laser","start_angle":-0.5,
+                "end_angle":0.5,"ranges":[1.0,null,3.0],"intensities":[10,20,30]}"#,
+            ),
+            (
+                2,
+                r#"{"timestamp":{"sec":2,"nsec":500000000},"frame_id":"map",
+                "pose":{"position":{"x":1.5,"y":-2.0,"z":0.0},
+                "orientation":{"x":0.0,"y":0.0,"z":0.7071067811865476,"w":0.7071067811865476}}}"#,
+            ),
+            (3, r#"{"cell_size":{"x":0.1,"y":0.1}}"#),
+        ];
+        for (i, (channel, data)) in messages.into_iter().enumerate() {
+            let message = Body::default()
+                .u16(channel)
+                .u32(i as u32)
+                .u64((i as u64 + 1) * 1_000_000_000)
+                .u64(0)
+                .raw(data.as_bytes());
+            write_record(&mut file, OP_MESSAGE, &message.0).unwrap();
+        }
+        write_record(&mut file, OP_DATA_END, &0u32.to_le_bytes()).unwrap();
+
+        let mut reader = McapReader::new(Cursor::new(file)).unwrap();
+        // Los esquemas de Foxglove sin conversión siguen ignorándose
+        assert_eq!(
+            reader.topics(),
+            [
+                (Arc::from("/pose"), McapKind::Log(ChannelKind::Pose)),
+                (
+                    Arc::from("/scan"),
+                    McapKind::Log(ChannelKind::Sensor(SensorKind::Lidar))
+                ),
+            ]
+        );
+        let records = reader.read_chunk(0).unwrap();
+        assert_eq!(records.len(), 2);
+        match &records[0].payload {
+            LogPayload::Sensor(SensorReading::Lidar(scan)) => {
+                assert_eq!(scan.frame_id, "laser");
+                assert_eq!(scan.points.len(), 3);
+                assert!((scan.points[2].angle - 0.5).abs() < 1e-9);
+                assert!(scan.points[1].distance.is_nan());
+                assert_eq!(scan.points[2].quality, 30);
+                assert_eq!(scan.max_range, 3.0);
+            }
+            other => panic!("{:?}", other),
+        }
+        match &records[1].payload {
+            LogPayload::Pose(pose) => {
+                assert_eq!((pose.x, pose.y, pose.timestamp), (1.5, -2.0, 2.5));
+                assert!((pose.theta - std::f64::consts::FRAC_PI_2).abs() < 1e-9);
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_corrupt_chunk_header_is_rejected_without_allocating() {
+        let inner = vec![0u8; 64];
+        let mut compressed = lz4_flex::frame::FrameEncoder::new(Vec::new());
+        compressed.write_all(&inner).unwrap();
+        let compressed = compressed.finish().unwrap();
+        let chunk = |size: u64| {
+            Body::default()
+                .u64(0)
+                .u64(0)
+                .u64(size)
+                .u32(0)
+                .string("lz4")
+                .u64(compressed.len() as u64)
+                .raw(&compressed)
+                .0
+        };
+
+        // Un tamaño descomprimido absurdo no reserva esa memoria
+        let error = chunk_records(&chunk(1 << 60)).unwrap_err();
+        assert_eq!(error.to_string(), "Bloque MCAP corrupto");
+        // Ni se descomprime más de lo que dice la cabecera
+        assert!(chunk_records(&chunk(10)).is_err());
+        assert_eq!(chunk_records(&chunk(64)).unwrap(), inner);
+    }
+}
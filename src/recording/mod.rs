@@ -0,0 +1,117 @@
+//! Grabación de todos los flujos del robot (sensores, comandos y poses
+//! estimadas) en un registro binario por bloques con índice, y reproducción
+//! determinista por los mismos canales para depurar fuera del robot. Los
+//! registros se exportan a MCAP para verlos en Foxglove Studio.
+pub mod format;
+mod foxglove;
+pub mod mcap;
+pub mod player;
+pub mod recorder;
+
+pub use format::{ChannelInfo, ChunkInfo, LogIndex, LogReader, LogWriter};
+pub use mcap::{McapKind, McapMessage, McapPayload, McapReader, McapWriter};
+pub use player::{PlaybackMode, Player};
+pub use recorder::{LogSummary, Recorder, RecorderHandle};
+
+use crate::control::{ControlInput, RobotState};
+use crate::sensors::{SensorKind, SensorReading};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Canal en que se publican los comandos de velocidad
+pub const COMMAND_CHANNEL: &str = "cmd_vel";
+/// Canal en que se publica la pose estimada por la localización
+pub const POSE_CHANNEL: &str = "pose";
+
+/// Tipo de los mensajes de un canal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelKind {
+    Sensor(SensorKind),
+    Command,
+    Pose,
+}
+
+/// Contenido de un mensaje grabado
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogPayload {
+    Sensor(SensorReading),
+    Command(ControlInput),
+    Pose(RobotState),
+}
+
+impl LogPayload {
+    pub fn kind(&self) -> ChannelKind {
+        match self {
+            Self::Sensor(reading) => ChannelKind::Sensor(reading.kind()),
+            Self::Command(_) => ChannelKind::Command,
+            Self::Pose(_) => ChannelKind::Pose,
+        }
+    }
+}
+
+/// Mensaje de un canal con su marca de tiempo (segundos Unix)
+#[derive(Debug, Clone)]
+pub struct LogRecord {
+    pub channel: Arc<str>,
+    pub sequence: u64,
+    pub timestamp: f64,
+    pub payload: LogPayload,
+}
+
+/// Origen de mensajes grabados, que se recorre por bloques
+pub trait RecordSource {
+    fn channels(&self) -> &[ChannelInfo];
+
+    fn chunks(&self) -> &[ChunkInfo];
+
+    /// Mensajes del bloque `chunk` en el orden en que se grabaron
+    fn read_chunk(&mut self, chunk: usize) -> Result<Vec<LogRecord>>;
+
+    fn time_range(&self) -> Option<(f64, f64)> {
+        format::time_span(self.chunks())
+    }
+
+    /// Primer bloque que contiene mensajes en `t` o posteriores
+    fn chunk_at(&self, t: f64) -> Option<usize> {
+        self.chunks().iter().position(|chunk| chunk.end >= t)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecordingConfig {
+    /// Carpeta donde se crean los registros
+    pub directory: PathBuf,
+    /// Tamaño (bytes) a partir del cual se cierra un bloque
+    pub chunk_size: usize,
+    /// Mensajes en cola hacia el disco antes de descartar
+    pub queue_capacity: usize,
+    /// Cada cuánto (s) se escribe el bloque en curso aunque no esté lleno
+    pub flush_interval: f64,
+    /// Canales que no se graban (p. ej. la cámara, que ocupa mucho)
+    pub exclude: Vec<String>,
+}
+
+impl Default for RecordingConfig {
+    fn default() -> Self {
+        Self {
+            directory: PathBuf::from("logs"),
+            chunk_size: 1 << 20,
+            queue_capacity: 1024,
+            flush_interval: 1.0,
+            exclude: Vec::new(),
+        }
+    }
+}
+
+impl RecordingConfig {
+    /// Ruta de un registro nuevo con la fecha y hora actuales en el nombre
+    pub fn new_log_path(&self) -> PathBuf {
+        let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+        self.directory.join(format!("mechbot-{}.mblog", stamp))
+    }
+}
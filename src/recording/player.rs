@@ -0,0 +1,244 @@
+//! Reproducción de registros por los mismos canales en que se grabaron: los
+//! sensores vuelven al bus con su nombre y tipo, y los comandos y poses a
+//! canales propios del reproductor
+use super::format::{ChannelInfo, LogReader};
+use super::{ChannelKind, LogPayload, LogRecord, RecordSource};
+use crate::control::{ControlInput, RobotState};
+use crate::sensors::bus::SensorPublisher;
+use crate::sensors::SensorBus;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+const CHANNEL_CAPACITY: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlaybackMode {
+    /// Respeta los intervalos grabados acelerados por el factor (1.0 = tiempo real)
+    Rate(f64),
+    /// Sin esperas entre mensajes
+    AsFastAsPossible,
+}
+
+pub struct Player<S: RecordSource> {
+    source: S,
+    next_chunk: usize,
+    pending: VecDeque<LogRecord>,
+    publishers: HashMap<Arc<str>, SensorPublisher>,
+    commands: broadcast::Sender<ControlInput>,
+    poses: broadcast::Sender<RobotState>,
+}
+
+impl Player<LogReader<BufReader<File>>> {
+    pub fn open(path: &Path) -> Result<Self> {
+        Ok(Self::new(LogReader::open(path)?))
+    }
+}
+
+impl<S: RecordSource> Player<S> {
+    pub fn new(source: S) -> Self {
+        let (commands, _) = broadcast::channel(CHANNEL_CAPACITY);
+        let (poses, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            source,
+            next_chunk: 0,
+            pending: VecDeque::new(),
+            publishers: HashMap::new(),
+            commands,
+            poses,
+        }
+    }
+
+    pub fn channels(&self) -> &[ChannelInfo] {
+        self.source.channels()
+    }
+
+    pub fn time_range(&self) -> Option<(f64, f64)> {
+        self.source.time_range()
+    }
+
+    /// Crea en el bus un tópico por cada sensor grabado. El bus no debería
+    /// tener drivers reales con los mismos nombres.
+    pub fn attach(&mut self, bus: &mut SensorBus) -> Result<()> {
+        for channel in self.source.channels() {
+            if let ChannelKind::Sensor(kind) = channel.kind {
+                let publisher = bus.publisher(&channel.name, kind)?;
+                self.publishers
+                    .insert(Arc::from(channel.name.as_str()), publisher);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn subscribe_commands(&self) -> broadcast::Receiver<ControlInput> {
+        self.commands.subscribe()
+    }
+
+    pub fn subscribe_poses(&self) -> broadcast::Receiver<RobotState> {
+        self.poses.subscribe()
+    }
+
+    /// Salta al primer mensaje grabado en `t` o después
+    pub fn seek(&mut self, t: f64) -> Result<()> {
+        self.pending.clear();
+        let Some(chunk) = self.source.chunk_at(t) else {
+            self.next_chunk = self.source.chunks().len();
+            return Ok(());
+        };
+        self.next_chunk = chunk + 1;
+        self.pending = self
+            .source
+            .read_chunk(chunk)?
+            .into_iter()
+            .filter(|record| record.timestamp >= t)
+            .collect();
+        Ok(())
+    }
+
+    /// Publica el siguiente mensaje y lo devuelve; `None` al final del registro.
+    /// Paso a paso la reproducción es determinista aunque el consumidor sea lento.
+    pub fn step(&mut self) -> Result<Option<LogRecord>> {
+        let Some(record) = self.next_record()? else {
+            return Ok(None);
+        };
+        self.publish(&record);
+        Ok(Some(record))
+    }
+
+    /// Reproduce hasta el final y devuelve el número de mensajes publicados
+    pub async fn run(&mut self, mode: PlaybackMode) -> Result<u64> {
+        if let PlaybackMode::Rate(rate) = mode {
+            if !(rate > 0.0 && rate.is_finite()) {
+                bail!("Factor de reproducción no válido: {}", rate);
+            }
+        }
+
+        let start = Instant::now();
+        let mut origin = None;
+        let mut published = 0;
+        while let Some(record) = self.next_record()? {
+            match mode {
+                PlaybackMode::Rate(rate) => {
+                    let origin = *origin.get_or_insert(record.timestamp);
+                    let due = Duration::from_secs_f64((record.timestamp - origin).max(0.0) / rate);
+                    if let Some(wait) = due.checked_sub(start.elapsed()) {
+                        tokio::time::sleep(wait).await;
+                    }
+                }
+                // Deja correr a los suscriptores para que no se retrasen
+                PlaybackMode::AsFastAsPossible => tokio::task::yield_now().await,
+            }
+            self.publish(&record);
+            published += 1;
+        }
+        log::info!("⏏️ Reproducción terminada: {} mensajes", published);
+        Ok(published)
+    }
+
+    fn next_record(&mut self) -> Result<Option<LogRecord>> {
+        while self.pending.is_empty() {
+            if self.next_chunk >= self.source.chunks().len() {
+                return Ok(None);
+            }
+            self.pending = self.source.read_chunk(self.next_chunk)?.into();
+            self.next_chunk += 1;
+        }
+        Ok(self.pending.pop_front())
+    }
+
+    fn publish(&mut self, record: &LogRecord) {
+        match &record.payload {
+            LogPayload::Sensor(reading) => {
+                if let Some(publisher) = self.publishers.get_mut(&record.channel) {
+                    publisher.publish(reading.clone());
+                }
+            }
+            LogPayload::Command(command) => {
+                let _ = self.commands.send(command.clone());
+            }
+            LogPayload::Pose(pose) => {
+                let _ = self.poses.send(pose.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recording::{LogWriter, COMMAND_CHANNEL};
+    use crate::sensors::{OdometryData, SensorReading};
+    use std::io::Cursor;
+
+    fn log() -> LogReader<Cursor<Vec<u8>>> {
+        let mut writer = LogWriter::new(Cursor::new(Vec::new()), 300).unwrap();
+        for i in 0..30 {
+            let t = 10.0 + i as f64 * 0.01;
+            writer
+                .write(&LogRecord {
+                    channel: Arc::from("odom"),
+                    sequence: i + 1,
+                    timestamp: t,
+                    payload: LogPayload::Sensor(SensorReading::Odometry(OdometryData {
+                        x: i as f64,
+                        timestamp: t,
+                        ..Default::default()
+                    })),
+                })
+                .unwrap();
+            if i % 10 == 0 {
+                writer
+                    .write(&LogRecord {
+                        channel: Arc::from(COMMAND_CHANNEL),
+                        sequence: i / 10 + 1,
+                        timestamp: t,
+                        payload: LogPayload::Command(ControlInput::new(0.1 * i as f64, 0.0)),
+                    })
+                    .unwrap();
+            }
+        }
+        let (cursor, _) = writer.finish().unwrap();
+        LogReader::new(Cursor::new(cursor.into_inner())).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_replay_through_bus_stepwise_and_timed() {
+        let mut bus = SensorBus::new(100);
+        let mut player = Player::new(log());
+        player.attach(&mut bus).unwrap();
+        let mut odometry = bus.subscribe("odom").unwrap();
+        let mut commands = player.subscribe_commands();
+
+        let first = player.step().unwrap().unwrap();
+        assert_eq!(first.timestamp, 10.0);
+        let message = odometry.recv().await.unwrap();
+        assert_eq!((message.sequence, message.timestamp), (1, 10.0));
+        player.step().unwrap();
+        assert_eq!(commands.recv().await.unwrap().linear_x, 0.0);
+
+        // A 2x, los 0.29 s grabados duran unos 0.145 s
+        player.seek(10.0).unwrap();
+        let start = Instant::now();
+        assert_eq!(player.run(PlaybackMode::Rate(2.0)).await.unwrap(), 33);
+        let elapsed = start.elapsed().as_secs_f64();
+        assert!((0.13..0.5).contains(&elapsed), "{}", elapsed);
+        assert_eq!(bus.history("odom", 100).len(), 31);
+
+        player.seek(10.195).unwrap();
+        let next = player.step().unwrap().unwrap();
+        assert!((next.timestamp - 10.2).abs() < 1e-9);
+        assert_eq!(
+            player.run(PlaybackMode::AsFastAsPossible).await.unwrap(),
+            10
+        );
+        assert!(player.step().unwrap().is_none());
+        assert!(player.run(PlaybackMode::Rate(0.0)).await.is_err());
+    }
+}
@@ -0,0 +1,265 @@
+//! Grabación en segundo plano: los productores encolan sin bloquearse y una
+//! tarea dedicada escribe en disco
+use super::format::{LogIndex, LogWriter};
+use super::{LogPayload, LogRecord, RecordingConfig, COMMAND_CHANNEL, POSE_CHANNEL};
+use crate::control::{ControlInput, RobotState};
+use crate::sensors::SensorBus;
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+#[derive(Debug)]
+enum Entry {
+    Record(LogRecord),
+    Flush,
+    Finish,
+}
+
+/// Resultado de una grabación
+#[derive(Debug, Clone, Serialize)]
+pub struct LogSummary {
+    pub path: PathBuf,
+    pub records: u64,
+    pub chunks: usize,
+    /// Mensajes perdidos por cola llena o suscripción retrasada
+    pub dropped: u64,
+    pub time_range: Option<(f64, f64)>,
+}
+
+/// Extremo para grabar desde cualquier tarea; clonarlo es barato
+#[derive(Debug, Clone)]
+pub struct RecorderHandle {
+    sender: mpsc::Sender<Entry>,
+    exclude: Arc<[String]>,
+    dropped: Arc<AtomicU64>,
+    commands: Arc<AtomicU64>,
+    poses: Arc<AtomicU64>,
+}
+
+impl RecorderHandle {
+    /// Encola un mensaje; si la cola está llena se descarta y se cuenta
+    pub fn record(&self, record: LogRecord) {
+        if self
+            .exclude
+            .iter()
+            .any(|channel| **channel == *record.channel)
+        {
+            return;
+        }
+        if self.sender.try_send(Entry::Record(record)).is_err() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Comando de velocidad enviado a los motores
+    pub fn record_command(&self, command: &ControlInput) {
+        self.record(LogRecord {
+            channel: Arc::from(COMMAND_CHANNEL),
+            sequence: self.commands.fetch_add(1, Ordering::Relaxed) + 1,
+            timestamp: chrono::Utc::now().timestamp_micros() as f64 * 1e-6,
+            payload: LogPayload::Command(command.clone()),
+        });
+    }
+
+    /// Pose estimada por la localización
+    pub fn record_pose(&self, pose: &RobotState) {
+        self.record(LogRecord {
+            channel: Arc::from(POSE_CHANNEL),
+            sequence: self.poses.fetch_add(1, Ordering::Relaxed) + 1,
+            timestamp: pose.timestamp,
+            payload: LogPayload::Pose(pose.clone()),
+        });
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+pub struct Recorder {
+    path: PathBuf,
+    handle: RecorderHandle,
+    writer: JoinHandle<Result<LogIndex>>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Recorder {
+    /// Crea el registro en `path` y arranca la tarea de escritura
+    pub fn start(path: &Path, config: &RecordingConfig) -> Result<Self> {
+        let mut writer = LogWriter::create(path, config.chunk_size)?;
+        let (sender, mut receiver) = mpsc::channel(config.queue_capacity.max(1));
+
+        let writer = tokio::task::spawn_blocking(move || {
+            while let Some(entry) = receiver.blocking_recv() {
+                let result = match entry {
+                    Entry::Record(record) => writer.write(&record),
+                    Entry::Flush => writer.flush_chunk(),
+                    Entry::Finish => break,
+                };
+                if let Err(e) = result {
+                    log::error!("❌ Error grabando el registro: {}", e);
+                }
+            }
+            writer.finish().map(|(_, index)| index)
+        });
+
+        // Un bloque a medias se escribe igualmente cada cierto tiempo para no
+        // perderlo si el proceso muere
+        let flusher = {
+            let sender = sender.clone();
+            let period = Duration::from_secs_f64(config.flush_interval.max(0.01));
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(period);
+                interval.tick().await;
+                loop {
+                    interval.tick().await;
+                    if sender.send(Entry::Flush).await.is_err() {
+                        break;
+                    }
+                }
+            })
+        };
+
+        log::info!("⏺️ Grabando en {}", path.display());
+        Ok(Self {
+            path: path.to_path_buf(),
+            handle: RecorderHandle {
+                sender,
+                exclude: config.exclude.clone().into(),
+                dropped: Arc::new(AtomicU64::new(0)),
+                commands: Arc::new(AtomicU64::new(0)),
+                poses: Arc::new(AtomicU64::new(0)),
+            },
+            writer,
+            tasks: vec![flusher],
+        })
+    }
+
+    pub fn handle(&self) -> RecorderHandle {
+        self.handle.clone()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Graba todos los tópicos del bus, incluidos los que se creen después
+    pub fn record_bus(&mut self, bus: &SensorBus) {
+        let mut messages = bus.subscribe_all();
+        let handle = self.handle.clone();
+
+        self.tasks.push(tokio::spawn(async move {
+            loop {
+                match messages.recv().await {
+                    Ok(message) => {
+                        if handle.exclude.iter().any(|c| **c == *message.sensor) {
+                            continue;
+                        }
+                        let record = LogRecord {
+                            channel: message.sensor.clone(),
+                            sequence: message.sequence,
+                            timestamp: message.timestamp,
+                            payload: LogPayload::Sensor((*message.reading).clone()),
+                        };
+                        // Aquí sí se espera: el retraso lo absorbe el canal del bus
+                        if handle.sender.send(Entry::Record(record)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(RecvError::Lagged(lost)) => {
+                        log::warn!("⚠️ Grabación retrasada: {} mensajes perdidos", lost);
+                        handle.dropped.fetch_add(lost, Ordering::Relaxed);
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        }));
+    }
+
+    /// Vacía la cola, escribe el índice y cierra el fichero
+    pub async fn finish(self) -> Result<LogSummary> {
+        for task in &self.tasks {
+            task.abort();
+        }
+        // Los mensajes ya encolados van antes que la marca de fin
+        self.handle
+            .sender
+            .send(Entry::Finish)
+            .await
+            .map_err(|_| anyhow!("La tarea de grabación terminó antes de tiempo"))?;
+        let index = self.writer.await??;
+
+        let summary = LogSummary {
+            path: self.path,
+            records: index.records(),
+            chunks: index.chunks.len(),
+            dropped: self.handle.dropped(),
+            time_range: index.time_range(),
+        };
+        log::info!(
+            "⏹️ Grabación cerrada: {} mensajes en {} bloques ({} perdidos)",
+            summary.records,
+            summary.chunks,
+            summary.dropped
+        );
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recording::{LogReader, RecordSource};
+    use crate::sensors::{OdometryData, SensorKind, SensorReading};
+
+    #[tokio::test]
+    async fn test_recorder_captures_bus_commands_and_poses() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = RecordingConfig {
+            chunk_size: 256,
+            exclude: vec!["camera".to_string()],
+            ..Default::default()
+        };
+        let mut bus = SensorBus::new(16);
+        let mut odometry = bus.publisher("odom", SensorKind::Odometry).unwrap();
+
+        let mut recorder = Recorder::start(&dir.path().join("run.mblog"), &config).unwrap();
+        recorder.record_bus(&bus);
+        let handle = recorder.handle();
+
+        for i in 0..20 {
+            odometry.publish(SensorReading::Odometry(OdometryData {
+                x: i as f64 * 0.1,
+                timestamp: 100.0 + i as f64 * 0.1,
+                ..Default::default()
+            }));
+            handle.record_command(&ControlInput::new(0.2, 0.0));
+            tokio::task::yield_now().await;
+        }
+        handle.record_pose(&RobotState::new(1.0, 0.0, 0.0));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let summary = recorder.finish().await.unwrap();
+        assert_eq!(summary.records, 41);
+        assert_eq!(summary.dropped, 0);
+        assert!(summary.chunks > 1);
+
+        let mut reader = LogReader::open(&summary.path).unwrap();
+        let mut names: Vec<_> = reader.channels().iter().map(|c| c.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, [COMMAND_CHANNEL, "odom", POSE_CHANNEL]);
+        let odometry: Vec<_> = (0..summary.chunks)
+            .flat_map(|chunk| reader.read_chunk(chunk).unwrap())
+            .filter(|record| &*record.channel == "odom")
+            .collect();
+        assert_eq!(odometry.len(), 20);
+        assert_eq!(odometry[0].timestamp, 100.0);
+        assert_eq!(odometry[19].sequence, 20);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::sensors::Sensor; // Importar el trait Sensor
+use crate::sensors::SensorDriver; // Importar el trait SensorDriver
 use crate::{ApiServer, Camera, Config, Lidar, NavigationController, VisionProcessor, IMU};
 use anyhow::Result;
 
@@ -35,9 +35,9 @@ impl Robot {
     /// Inicia todos los sensores del robot
     pub async fn start_sensors(&mut self) -> Result<()> {
         println!("🔧 Iniciando sensores...");
-        self.lidar.initialize()?;
-        self.camera.initialize()?;
-        self.imu.initialize()?;
+        self.lidar.connect().await.map_err(anyhow::Error::msg)?;
+        self.camera.connect().await.map_err(anyhow::Error::msg)?;
+        self.imu.connect().await.map_err(anyhow::Error::msg)?;
         println!("✅ Todos los sensores iniciados");
         Ok(())
     }
@@ -0,0 +1,346 @@
+//! Bus de sensores: una tarea tokio por sensor publicando en canales broadcast
+use super::drivers::{SensorKind, SensorReading};
+use super::registry::{SensorRegistry, SharedSensor};
+use super::ring_buffer::RingBuffer;
+use crate::metrics;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+
+/// Mensajes en vuelo por canal antes de que un suscriptor lento pierda datos
+const CHANNEL_CAPACITY: usize = 64;
+
+/// Lectura publicada en el bus. La lectura va en `Arc` para que clonar el
+/// mensaje por cada suscriptor no copie frames de cámara ni escaneos.
+#[derive(Debug, Clone)]
+pub struct SensorMessage {
+    pub sensor: Arc<str>,
+    pub sequence: u64,
+    pub timestamp: f64, // segundos Unix
+    pub reading: Arc<SensorReading>,
+}
+
+impl SensorMessage {
+    pub fn kind(&self) -> SensorKind {
+        self.reading.kind()
+    }
+}
+
+/// Error de lectura de un driver, para el monitor de salud
+#[derive(Debug, Clone)]
+pub struct SensorFault {
+    pub sensor: Arc<str>,
+    pub error: String,
+}
+
+type History = Arc<Mutex<RingBuffer<SensorMessage>>>;
+
+#[derive(Debug)]
+struct Topic {
+    kind: SensorKind,
+    sender: broadcast::Sender<SensorMessage>,
+    history: History,
+    task: Option<JoinHandle<()>>,
+}
+
+/// Extremo de publicación de un tópico. Lo usan las tareas de los drivers y
+/// fuentes externas como la odometría del control de motores.
+#[derive(Debug, Clone)]
+pub struct SensorPublisher {
+    sensor: Arc<str>,
+    sequence: u64,
+    sender: broadcast::Sender<SensorMessage>,
+    all: broadcast::Sender<SensorMessage>,
+    history: History,
+    published: prometheus::IntCounter,
+}
+
+impl SensorPublisher {
+    pub fn publish(&mut self, reading: SensorReading) -> SensorMessage {
+        self.sequence += 1;
+        self.published.inc();
+        let message = SensorMessage {
+            sensor: self.sensor.clone(),
+            sequence: self.sequence,
+            timestamp: reading.timestamp(),
+            reading: Arc::new(reading),
+        };
+
+        self.history
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(message.clone());
+
+        // Sin suscriptores el envío falla; no es un error para el productor
+        let _ = self.sender.send(message.clone());
+        let _ = self.all.send(message.clone());
+        message
+    }
+}
+
+#[derive(Debug)]
+pub struct SensorBus {
+    topics: HashMap<String, Topic>,
+    all: broadcast::Sender<SensorMessage>,
+    faults: broadcast::Sender<SensorFault>,
+    history_capacity: usize,
+}
+
+impl SensorBus {
+    pub fn new(history_capacity: usize) -> Self {
+        let (all, _) = broadcast::channel(CHANNEL_CAPACITY * 4);
+        let (faults, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            topics: HashMap::new(),
+            all,
+            faults,
+            history_capacity,
+        }
+    }
+
+    /// Crea (o reutiliza) el tópico `name` y devuelve un publicador para él
+    pub fn publisher(&mut self, name: &str, kind: SensorKind) -> Result<SensorPublisher> {
+        let capacity = self.history_capacity;
+        let topic = self.topics.entry(name.to_string()).or_insert_with(|| {
+            let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+            Topic {
+                kind,
+                sender,
+                history: Arc::new(Mutex::new(RingBuffer::new(capacity))),
+                task: None,
+            }
+        });
+
+        if topic.kind != kind {
+            return Err(anyhow::anyhow!(
+                "Tópico '{}' ya existe con tipo {:?}",
+                name,
+                topic.kind
+            ));
+        }
+
+        let kind = format!("{:?}", kind).to_lowercase();
+        Ok(SensorPublisher {
+            sensor: Arc::from(name),
+            sequence: 0,
+            sender: topic.sender.clone(),
+            all: self.all.clone(),
+            history: topic.history.clone(),
+            published: metrics::sensor_counter(name, &kind),
+        })
+    }
+
+    /// Lanza la tarea de adquisición de un sensor a su frecuencia nominal
+    pub async fn spawn(&mut self, name: &str, driver: SharedSensor) -> Result<()> {
+        let (kind, period) = {
+            let driver = driver.lock().await;
+            (driver.kind(), driver.sample_period())
+        };
+
+        self.stop(name);
+        let mut publisher = self.publisher(name, kind)?;
+        let faults = self.faults.clone();
+        let sensor: Arc<str> = Arc::from(name);
+        let metrics = metrics::metrics();
+        let read_time = metrics.sensor_read.with_label_values(&[name]);
+        let latency = metrics.sensor_latency.with_label_values(&[name]);
+        let errors = metrics.sensor_errors.with_label_values(&[name]);
+
+        let task = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+            loop {
+                interval.tick().await;
+
+                let result = {
+                    let mut driver = driver.lock().await;
+                    if !driver.is_connected() {
+                        continue;
+                    }
+                    let start = Instant::now();
+                    let result = driver.read().await;
+                    read_time.observe(start.elapsed().as_secs_f64());
+                    result
+                };
+
+                match result {
+                    Ok(reading) => {
+                        latency.observe((metrics::unix_now() - reading.timestamp()).max(0.0));
+                        publisher.publish(reading);
+                    }
+                    Err(error) => {
+                        errors.inc();
+                        log::warn!("⚠️ Error leyendo sensor '{}': {}", sensor, error);
+                        let _ = faults.send(SensorFault {
+                            sensor: sensor.clone(),
+                            error,
+                        });
+                    }
+                }
+            }
+        });
+
+        if let Some(topic) = self.topics.get_mut(name) {
+            topic.task = Some(task);
+        }
+        log::info!("📡 Tarea de adquisición de '{}' cada {:?}", name, period);
+        Ok(())
+    }
+
+    /// Lanza una tarea por cada sensor del registro
+    pub async fn spawn_registry(&mut self, registry: &SensorRegistry) -> Result<()> {
+        for (name, _, driver) in registry.iter() {
+            self.spawn(name, driver.clone()).await?;
+        }
+        Ok(())
+    }
+
+    /// Detiene la tarea de un sensor; el tópico y su histórico se conservan
+    pub fn stop(&mut self, name: &str) {
+        if let Some(task) = self.topics.get_mut(name).and_then(|t| t.task.take()) {
+            task.abort();
+            log::info!("⏹️ Tarea de adquisición de '{}' detenida", name);
+        }
+    }
+
+    /// Detiene la tarea y elimina el tópico
+    pub fn remove(&mut self, name: &str) {
+        self.stop(name);
+        self.topics.remove(name);
+    }
+
+    pub fn shutdown(&mut self) {
+        let names: Vec<String> = self.topics.keys().cloned().collect();
+        for name in names {
+            self.stop(&name);
+        }
+    }
+
+    pub fn subscribe(&self, name: &str) -> Option<broadcast::Receiver<SensorMessage>> {
+        self.topics.get(name).map(|topic| topic.sender.subscribe())
+    }
+
+    /// Recibe los mensajes de todos los tópicos, incluidos los creados después
+    pub fn subscribe_all(&self) -> broadcast::Receiver<SensorMessage> {
+        self.all.subscribe()
+    }
+
+    pub fn subscribe_faults(&self) -> broadcast::Receiver<SensorFault> {
+        self.faults.subscribe()
+    }
+
+    pub fn latest(&self, name: &str) -> Option<SensorMessage> {
+        let topic = self.topics.get(name)?;
+        let history = topic.history.lock().unwrap_or_else(|e| e.into_inner());
+        history.latest().cloned()
+    }
+
+    /// Los `count` mensajes más recientes de un tópico, en orden cronológico
+    pub fn history(&self, name: &str, count: usize) -> Vec<SensorMessage> {
+        self.topics
+            .get(name)
+            .map(|topic| {
+                let history = topic.history.lock().unwrap_or_else(|e| e.into_inner());
+                history.recent(count).cloned().collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn topics(&self) -> Vec<(String, SensorKind)> {
+        self.topics
+            .iter()
+            .map(|(name, topic)| (name.clone(), topic.kind))
+            .collect()
+    }
+
+    pub fn is_running(&self, name: &str) -> bool {
+        self.topics
+            .get(name)
+            .and_then(|topic| topic.task.as_ref())
+            .map(|task| !task.is_finished())
+            .unwrap_or(false)
+    }
+}
+
+impl Default for SensorBus {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+impl Drop for SensorBus {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::drivers::{IMUConfig, Lidar, LidarConfig, IMU};
+    use crate::sensors::OdometryData;
+
+    #[tokio::test]
+    async fn test_publisher_fills_history_and_channels() {
+        let mut bus = SensorBus::new(2);
+        let mut odometry = bus.publisher("odom", SensorKind::Odometry).unwrap();
+        let mut receiver = bus.subscribe("odom").unwrap();
+        let mut all = bus.subscribe_all();
+
+        for i in 0..3 {
+            odometry.publish(SensorReading::Odometry(OdometryData {
+                x: i as f64,
+                timestamp: i as f64,
+                ..Default::default()
+            }));
+        }
+
+        assert_eq!(receiver.recv().await.unwrap().sequence, 1);
+        assert_eq!(&*all.recv().await.unwrap().sensor, "odom");
+        let history = bus.history("odom", 10);
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].timestamp, 2.0);
+        assert!(bus.publisher("odom", SensorKind::Imu).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sensors_run_at_independent_rates() {
+        let mut imu = IMU::simulated(IMUConfig {
+            sample_rate: 200,
+            ..Default::default()
+        });
+        imu.connect().await.unwrap();
+        let mut lidar = Lidar::new(LidarConfig {
+            sample_rate: 10,
+            ..Default::default()
+        });
+        lidar.connect().await.unwrap();
+
+        let mut registry = SensorRegistry::new();
+        registry.add("imu", Box::new(imu)).unwrap();
+        registry.add("lidar", Box::new(lidar)).unwrap();
+
+        let mut bus = SensorBus::new(1000);
+        bus.spawn_registry(&registry).await.unwrap();
+        assert!(bus.is_running("imu"));
+
+        tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+        bus.shutdown();
+
+        let imu_count = bus.history("imu", 1000).len();
+        let lidar_count = bus.history("lidar", 1000).len();
+        assert!(lidar_count >= 2, "lidar publicó {}", lidar_count);
+        assert!(
+            imu_count > lidar_count * 4,
+            "imu {} lidar {}",
+            imu_count,
+            lidar_count
+        );
+        assert!(!bus.is_running("imu"));
+    }
+}
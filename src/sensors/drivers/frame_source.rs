@@ -0,0 +1,404 @@
+//! Fuentes de frames para `Camera`: V4L2, reproducción desde disco y patrón sintético
+use super::pixel_format::{PixelFormat, RawFrame};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use tokio::time::{Duration, Instant};
+
+/// Origen de los frames de la cámara
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum CameraSource {
+    /// Dispositivo Video4Linux2 en `CameraConfig::device_path`
+    V4l2,
+    /// Directorio de imágenes JPEG/PNG reproducidas en orden alfabético
+    ImageDirectory { path: PathBuf, loop_playback: bool },
+    /// Fichero con frames crudos en `CameraConfig::format` (MJPEG concatenado, YUYV o RGB24)
+    RawVideo { path: PathBuf, loop_playback: bool },
+    /// Patrón de prueba generado en memoria
+    Synthetic,
+}
+
+#[derive(Debug)]
+pub enum FrameSource {
+    #[cfg(target_os = "linux")]
+    V4l2(super::v4l2::V4l2Capture),
+    ImageDirectory(ImageDirectorySource),
+    RawVideo(RawVideoSource),
+    Synthetic(SyntheticSource),
+}
+
+impl FrameSource {
+    pub fn open(config: &super::CameraConfig) -> Result<Self> {
+        let format = PixelFormat::from_fourcc_str(&config.format)
+            .ok_or_else(|| anyhow::anyhow!("Formato de cámara desconocido: {}", config.format))?;
+
+        match &config.source {
+            #[cfg(target_os = "linux")]
+            CameraSource::V4l2 => Ok(Self::V4l2(super::v4l2::V4l2Capture::open(
+                &config.device_path,
+                format,
+                config.width,
+                config.height,
+                config.framerate,
+            )?)),
+            #[cfg(not(target_os = "linux"))]
+            CameraSource::V4l2 => Err(anyhow::anyhow!("V4L2 solo está disponible en Linux")),
+            CameraSource::ImageDirectory {
+                path,
+                loop_playback,
+            } => Ok(Self::ImageDirectory(ImageDirectorySource::open(
+                path,
+                config.framerate,
+                *loop_playback,
+            )?)),
+            CameraSource::RawVideo {
+                path,
+                loop_playback,
+            } => Ok(Self::RawVideo(RawVideoSource::open(
+                path,
+                format,
+                config.width,
+                config.height,
+                config.framerate,
+                *loop_playback,
+            )?)),
+            CameraSource::Synthetic => Ok(Self::Synthetic(SyntheticSource::new(
+                config.width,
+                config.height,
+                config.framerate,
+            ))),
+        }
+    }
+
+    pub async fn next_frame(&mut self) -> Result<RawFrame> {
+        match self {
+            #[cfg(target_os = "linux")]
+            Self::V4l2(capture) => capture.next_frame().await,
+            Self::ImageDirectory(source) => source.next_frame().await,
+            Self::RawVideo(source) => source.next_frame().await,
+            Self::Synthetic(source) => source.next_frame().await,
+        }
+    }
+}
+
+/// Entrega frames a la cadencia configurada a partir del primer frame
+#[derive(Debug)]
+struct FramePacer {
+    period: Duration,
+    next_due: Option<Instant>,
+}
+
+impl FramePacer {
+    fn new(framerate: u32) -> Self {
+        Self {
+            period: Duration::from_secs_f64(1.0 / framerate.max(1) as f64),
+            next_due: None,
+        }
+    }
+
+    async fn wait(&mut self) {
+        let now = Instant::now();
+        let due = self.next_due.unwrap_or(now);
+        if due > now {
+            tokio::time::sleep_until(due).await;
+        }
+        // Si vamos con retraso no se acumula ráfaga de frames
+        self.next_due = Some(due.max(now) + self.period);
+    }
+}
+
+fn now_seconds() -> f64 {
+    chrono::Utc::now().timestamp_micros() as f64 * 1e-6
+}
+
+#[derive(Debug)]
+pub struct ImageDirectorySource {
+    files: Vec<PathBuf>,
+    position: usize,
+    loop_playback: bool,
+    sequence: u64,
+    pacer: FramePacer,
+}
+
+impl ImageDirectorySource {
+    pub fn open(path: &Path, framerate: u32, loop_playback: bool) -> Result<Self> {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(path)
+            .map_err(|e| anyhow::anyhow!("No se pudo leer {}: {}", path.display(), e))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|file| image_format(file).is_some())
+            .collect();
+        files.sort();
+
+        if files.is_empty() {
+            return Err(anyhow::anyhow!(
+                "No hay imágenes JPEG/PNG en {}",
+                path.display()
+            ));
+        }
+
+        Ok(Self {
+            files,
+            position: 0,
+            loop_playback,
+            sequence: 0,
+            pacer: FramePacer::new(framerate),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub async fn next_frame(&mut self) -> Result<RawFrame> {
+        if self.position >= self.files.len() {
+            if !self.loop_playback {
+                return Err(anyhow::anyhow!("Fin de la secuencia de imágenes"));
+            }
+            self.position = 0;
+        }
+
+        let file = &self.files[self.position];
+        let format = image_format(file).unwrap_or(PixelFormat::Mjpeg);
+        let data = tokio::fs::read(file).await?;
+        self.position += 1;
+
+        self.pacer.wait().await;
+        self.sequence += 1;
+
+        // Las dimensiones reales se conocen al decodificar
+        Ok(RawFrame {
+            format,
+            width: 0,
+            height: 0,
+            data,
+            sequence: self.sequence,
+            timestamp: now_seconds(),
+        })
+    }
+}
+
+fn image_format(path: &Path) -> Option<PixelFormat> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+        "jpg" | "jpeg" => Some(PixelFormat::Mjpeg),
+        "png" => Some(PixelFormat::Png),
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+pub struct RawVideoSource {
+    path: PathBuf,
+    reader: BufReader<std::fs::File>,
+    format: PixelFormat,
+    width: u32,
+    height: u32,
+    loop_playback: bool,
+    sequence: u64,
+    pacer: FramePacer,
+}
+
+impl RawVideoSource {
+    pub fn open(
+        path: &Path,
+        format: PixelFormat,
+        width: u32,
+        height: u32,
+        framerate: u32,
+        loop_playback: bool,
+    ) -> Result<Self> {
+        let file = std::fs::File::open(path)
+            .map_err(|e| anyhow::anyhow!("No se pudo abrir {}: {}", path.display(), e))?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            reader: BufReader::new(file),
+            format,
+            width,
+            height,
+            loop_playback,
+            sequence: 0,
+            pacer: FramePacer::new(framerate),
+        })
+    }
+
+    pub async fn next_frame(&mut self) -> Result<RawFrame> {
+        let data = match self.read_frame()? {
+            Some(data) => data,
+            None if self.loop_playback && self.sequence > 0 => {
+                self.rewind()?;
+                self.read_frame()?
+                    .ok_or_else(|| anyhow::anyhow!("Vídeo vacío: {}", self.path.display()))?
+            }
+            None => return Err(anyhow::anyhow!("Fin del vídeo {}", self.path.display())),
+        };
+
+        self.pacer.wait().await;
+        self.sequence += 1;
+
+        Ok(RawFrame {
+            format: self.format,
+            width: self.width,
+            height: self.height,
+            data,
+            sequence: self.sequence,
+            timestamp: now_seconds(),
+        })
+    }
+
+    fn rewind(&mut self) -> Result<()> {
+        let file = std::fs::File::open(&self.path)?;
+        self.reader = BufReader::new(file);
+        Ok(())
+    }
+
+    fn read_frame(&mut self) -> Result<Option<Vec<u8>>> {
+        match self.format.frame_size(self.width, self.height) {
+            Some(size) => {
+                let mut data = vec![0u8; size];
+                match self.reader.read_exact(&mut data) {
+                    Ok(()) => Ok(Some(data)),
+                    Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(None),
+                    Err(e) => Err(e.into()),
+                }
+            }
+            None => read_jpeg(&mut self.reader),
+        }
+    }
+}
+
+/// Extrae el siguiente JPEG (SOI..EOI) de un flujo MJPEG concatenado
+fn read_jpeg<R: Read>(reader: &mut R) -> Result<Option<Vec<u8>>> {
+    let mut byte = [0u8; 1];
+    let mut previous = 0u8;
+    let mut frame = Vec::new();
+
+    // Buscar el marcador SOI (FF D8)
+    loop {
+        if reader.read(&mut byte)? == 0 {
+            return Ok(None);
+        }
+        if previous == 0xFF && byte[0] == 0xD8 {
+            frame.extend_from_slice(&[0xFF, 0xD8]);
+            break;
+        }
+        previous = byte[0];
+    }
+
+    // Copiar hasta EOI (FF D9); en datos entrópicos 0xFF siempre va seguido de 0x00
+    previous = 0;
+    loop {
+        if reader.read(&mut byte)? == 0 {
+            return Err(anyhow::anyhow!("JPEG truncado en el flujo MJPEG"));
+        }
+        frame.push(byte[0]);
+        if previous == 0xFF && byte[0] == 0xD9 {
+            return Ok(Some(frame));
+        }
+        previous = byte[0];
+    }
+}
+
+/// Gradiente de prueba (el comportamiento anterior de `Camera`)
+#[derive(Debug)]
+pub struct SyntheticSource {
+    width: u32,
+    height: u32,
+    sequence: u64,
+    pacer: FramePacer,
+}
+
+impl SyntheticSource {
+    pub fn new(width: u32, height: u32, framerate: u32) -> Self {
+        Self {
+            width,
+            height,
+            sequence: 0,
+            pacer: FramePacer::new(framerate),
+        }
+    }
+
+    pub async fn next_frame(&mut self) -> Result<RawFrame> {
+        self.pacer.wait().await;
+        self.sequence += 1;
+
+        let mut data = Vec::with_capacity((self.width * self.height * 3) as usize);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                data.push(((x as f32 / self.width as f32) * 255.0) as u8);
+                data.push(((y as f32 / self.height as f32) * 255.0) as u8);
+                data.push(128u8);
+            }
+        }
+
+        Ok(RawFrame {
+            format: PixelFormat::Rgb24,
+            width: self.width,
+            height: self.height,
+            data,
+            sequence: self.sequence,
+            timestamp: now_seconds(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_jpeg_splits_concatenated_stream() {
+        let stream = [
+            0x00, 0xFF, 0xD8, 0x01, 0xFF, 0x00, 0xFF, 0xD9, 0xFF, 0xD8, 0x02, 0xFF, 0xD9,
+        ];
+        let mut reader = &stream[..];
+
+        let first = read_jpeg(&mut reader).unwrap().unwrap();
+        assert_eq!(first, vec![0xFF, 0xD8, 0x01, 0xFF, 0x00, 0xFF, 0xD9]);
+        let second = read_jpeg(&mut reader).unwrap().unwrap();
+        assert_eq!(second, vec![0xFF, 0xD8, 0x02, 0xFF, 0xD9]);
+        assert!(read_jpeg(&mut reader).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_raw_video_replays_and_loops() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("video.yuyv");
+        // Dos frames YUYV de 2x1
+        std::fs::write(&path, [16, 128, 16, 128, 235, 128, 235, 128]).unwrap();
+
+        let mut source = RawVideoSource::open(&path, PixelFormat::Yuyv, 2, 1, 1000, true).unwrap();
+        let frames = [
+            source.next_frame().await.unwrap(),
+            source.next_frame().await.unwrap(),
+            source.next_frame().await.unwrap(),
+        ];
+
+        assert_eq!(frames[0].data, vec![16, 128, 16, 128]);
+        assert_eq!(frames[1].data, vec![235, 128, 235, 128]);
+        assert_eq!(frames[2].data, frames[0].data);
+        assert_eq!(frames[2].sequence, 3);
+    }
+
+    #[tokio::test]
+    async fn test_image_directory_ignores_other_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("b.png"), b"png").unwrap();
+        std::fs::write(dir.path().join("a.jpg"), b"jpg").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), b"txt").unwrap();
+
+        let mut source = ImageDirectorySource::open(dir.path(), 1000, false).unwrap();
+        assert_eq!(source.len(), 2);
+        assert_eq!(
+            source.next_frame().await.unwrap().format,
+            PixelFormat::Mjpeg
+        );
+        assert_eq!(source.next_frame().await.unwrap().format, PixelFormat::Png);
+        assert!(source.next_frame().await.is_err());
+    }
+}
@@ -0,0 +1,323 @@
+//! Abstracción de GPIO y ADC usada por los sensores de distancia
+use super::i2c::I2cBus;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Pines digitales numerados según el controlador (BCM en la Raspberry Pi)
+pub trait Gpio: Send + std::fmt::Debug {
+    fn write(&mut self, pin: u32, high: bool) -> Result<()>;
+
+    fn read(&mut self, pin: u32) -> Result<bool>;
+
+    /// Espera activamente a que `pin` alcance el nivel `high` y devuelve el
+    /// instante en que lo hizo, o `None` si vence `timeout`
+    fn wait_for(&mut self, pin: u32, high: bool, timeout: Duration) -> Result<Option<Instant>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if self.read(pin)? == high {
+                return Ok(Some(Instant::now()));
+            }
+            if Instant::now() >= deadline {
+                return Ok(None);
+            }
+            std::hint::spin_loop();
+        }
+    }
+}
+
+/// Convertidor analógico-digital de varios canales
+pub trait Adc: Send + std::fmt::Debug {
+    /// Tensión del canal en voltios
+    fn read_voltage(&mut self, channel: u8) -> Result<f64>;
+}
+
+/// GPIO por la interfaz sysfs del kernel (`/sys/class/gpio`)
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+pub struct SysfsGpio {
+    values: HashMap<u32, std::fs::File>,
+}
+
+#[cfg(target_os = "linux")]
+impl SysfsGpio {
+    const ROOT: &'static str = "/sys/class/gpio";
+
+    /// Exporta los pines y fija su dirección
+    pub fn open(outputs: &[u32], inputs: &[u32]) -> Result<Self> {
+        let mut values = HashMap::new();
+        for (pins, direction) in [(outputs, "out"), (inputs, "in")] {
+            for &pin in pins {
+                values.insert(pin, Self::export(pin, direction)?);
+            }
+        }
+        Ok(Self { values })
+    }
+
+    fn export(pin: u32, direction: &str) -> Result<std::fs::File> {
+        let dir = format!("{}/gpio{}", Self::ROOT, pin);
+        if !std::path::Path::new(&dir).exists() {
+            std::fs::write(format!("{}/export", Self::ROOT), pin.to_string())
+                .map_err(|e| anyhow::anyhow!("No se pudo exportar GPIO {}: {}", pin, e))?;
+        }
+        std::fs::write(format!("{}/direction", dir), direction)
+            .map_err(|e| anyhow::anyhow!("No se pudo configurar GPIO {}: {}", pin, e))?;
+
+        std::fs::OpenOptions::new()
+            .read(true)
+            .write(direction == "out")
+            .open(format!("{}/value", dir))
+            .map_err(|e| anyhow::anyhow!("No se pudo abrir GPIO {}: {}", pin, e))
+    }
+
+    fn file(&mut self, pin: u32) -> Result<&mut std::fs::File> {
+        self.values
+            .get_mut(&pin)
+            .ok_or_else(|| anyhow::anyhow!("GPIO {} no configurado", pin))
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Gpio for SysfsGpio {
+    fn write(&mut self, pin: u32, high: bool) -> Result<()> {
+        use std::io::Write;
+        let file = self.file(pin)?;
+        file.write_all(if high { b"1" } else { b"0" })?;
+        Ok(())
+    }
+
+    fn read(&mut self, pin: u32) -> Result<bool> {
+        use std::io::{Read, Seek, SeekFrom};
+        let file = self.file(pin)?;
+        let mut value = [0u8; 1];
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut value)?;
+        Ok(value[0] == b'1')
+    }
+}
+
+/// ADC ADS1115 de 16 bits sobre I2C, en modo de conversión única
+#[derive(Debug)]
+pub struct Ads1115<B: I2cBus> {
+    bus: B,
+    address: u8,
+}
+
+impl<B: I2cBus> Ads1115<B> {
+    pub const DEFAULT_ADDRESS: u8 = 0x48;
+
+    const REG_CONVERSION: u8 = 0x00;
+    const REG_CONFIG: u8 = 0x01;
+    /// Fondo de escala con PGA = ±4.096 V
+    const FULL_SCALE: f64 = 4.096;
+
+    pub fn new(bus: B, address: u8) -> Self {
+        Self { bus, address }
+    }
+}
+
+impl<B: I2cBus> Adc for Ads1115<B> {
+    fn read_voltage(&mut self, channel: u8) -> Result<f64> {
+        if channel > 3 {
+            return Err(anyhow::anyhow!("Canal ADS1115 {} fuera de rango", channel));
+        }
+
+        // OS=1 (iniciar), MUX=AINx/GND, PGA=±4.096 V, conversión única,
+        // 128 SPS y comparador deshabilitado
+        let mux = (4 + channel as u16) << 12;
+        let config: u16 = 0x8000 | mux | 0x0200 | 0x0100 | 0x0080 | 0x0003;
+        let [high, low] = config.to_be_bytes();
+        self.bus
+            .write(self.address, &[Self::REG_CONFIG, high, low])?;
+
+        // A 128 SPS una conversión tarda ~8 ms
+        let mut status = [0u8; 2];
+        for _ in 0..20 {
+            std::thread::sleep(Duration::from_millis(1));
+            self.bus
+                .read_registers(self.address, Self::REG_CONFIG, &mut status)?;
+            if status[0] & 0x80 != 0 {
+                let mut raw = [0u8; 2];
+                self.bus
+                    .read_registers(self.address, Self::REG_CONVERSION, &mut raw)?;
+                return Ok(i16::from_be_bytes(raw) as f64 * Self::FULL_SCALE / 32768.0);
+            }
+        }
+
+        Err(anyhow::anyhow!("ADS1115 no completó la conversión"))
+    }
+}
+
+/// Distancia al obstáculo simulado, compartida entre el test y el backend.
+/// `f64::INFINITY` significa que no hay nada delante del sensor.
+#[derive(Debug, Clone)]
+pub struct SimulatedTarget(Arc<Mutex<f64>>);
+
+impl SimulatedTarget {
+    pub fn new(distance: f64) -> Self {
+        Self(Arc::new(Mutex::new(distance)))
+    }
+
+    pub fn set(&self, distance: f64) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = distance;
+    }
+
+    pub fn get(&self) -> f64 {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for SimulatedTarget {
+    fn default() -> Self {
+        Self::new(f64::INFINITY)
+    }
+}
+
+/// HC-SR04 simulado: tras el pulso de disparo, el pin de eco se mantiene
+/// alto el tiempo de vuelo correspondiente a la distancia del objetivo.
+/// Las esperas no duermen; devuelven los instantes calculados.
+#[derive(Debug)]
+pub struct SimulatedHcSr04 {
+    trigger_pin: u32,
+    echo_pin: u32,
+    speed_of_sound: f64,
+    target: SimulatedTarget,
+    trigger_high: bool,
+    echo: Option<(Instant, Instant)>,
+}
+
+impl SimulatedHcSr04 {
+    /// Alcance físico del módulo; más allá no vuelve eco
+    const MAX_ECHO_RANGE: f64 = 4.5;
+
+    pub fn new(
+        trigger_pin: u32,
+        echo_pin: u32,
+        speed_of_sound: f64,
+        target: SimulatedTarget,
+    ) -> Self {
+        Self {
+            trigger_pin,
+            echo_pin,
+            speed_of_sound,
+            target,
+            trigger_high: false,
+            echo: None,
+        }
+    }
+}
+
+impl Gpio for SimulatedHcSr04 {
+    fn write(&mut self, pin: u32, high: bool) -> Result<()> {
+        if pin != self.trigger_pin {
+            return Err(anyhow::anyhow!("GPIO {} no es salida", pin));
+        }
+
+        // El flanco de bajada del disparo emite la ráfaga ultrasónica
+        if self.trigger_high && !high {
+            let distance = self.target.get();
+            self.echo = (distance.is_finite() && distance <= Self::MAX_ECHO_RANGE).then(|| {
+                let start = Instant::now() + Duration::from_micros(200);
+                let flight = Duration::from_secs_f64(2.0 * distance / self.speed_of_sound);
+                (start, start + flight)
+            });
+        }
+        self.trigger_high = high;
+        Ok(())
+    }
+
+    fn read(&mut self, pin: u32) -> Result<bool> {
+        if pin != self.echo_pin {
+            return Err(anyhow::anyhow!("GPIO {} no es entrada", pin));
+        }
+        let now = Instant::now();
+        Ok(self
+            .echo
+            .map(|(start, end)| now >= start && now < end)
+            .unwrap_or(false))
+    }
+
+    fn wait_for(&mut self, pin: u32, high: bool, _timeout: Duration) -> Result<Option<Instant>> {
+        if pin != self.echo_pin {
+            return Err(anyhow::anyhow!("GPIO {} no es entrada", pin));
+        }
+        Ok(self.echo.map(|(start, end)| if high { start } else { end }))
+    }
+}
+
+/// GP2Y0A21YK simulado sobre un ADC: la tensión sigue la curva del
+/// fabricante para la distancia del objetivo
+#[derive(Debug)]
+pub struct SimulatedGp2y0a21 {
+    channel: u8,
+    target: SimulatedTarget,
+}
+
+impl SimulatedGp2y0a21 {
+    /// Salida sin objetivo dentro del alcance
+    const IDLE_VOLTAGE: f64 = 0.25;
+
+    pub fn new(channel: u8, target: SimulatedTarget) -> Self {
+        Self { channel, target }
+    }
+}
+
+impl Adc for SimulatedGp2y0a21 {
+    fn read_voltage(&mut self, channel: u8) -> Result<f64> {
+        if channel != self.channel {
+            return Ok(0.0);
+        }
+        let distance = self.target.get();
+        if !distance.is_finite() {
+            return Ok(Self::IDLE_VOLTAGE);
+        }
+        Ok(gp2y0a21_voltage(distance).max(Self::IDLE_VOLTAGE))
+    }
+}
+
+/// Ajuste potencial de la curva del GP2Y0A21YK: `d[cm] = 27.86·V^-1.15`
+pub fn gp2y0a21_distance(voltage: f64) -> f64 {
+    if voltage <= 0.0 {
+        return f64::INFINITY;
+    }
+    0.2786 * voltage.powf(-1.15)
+}
+
+/// Inversa de `gp2y0a21_distance`
+pub fn gp2y0a21_voltage(distance: f64) -> f64 {
+    (0.2786 / distance.max(0.1)).powf(1.0 / 1.15)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::drivers::i2c::MockI2cBus;
+
+    #[test]
+    fn test_gp2y0a21_curve_round_trip() {
+        for distance in [0.1, 0.25, 0.5, 0.8] {
+            let voltage = gp2y0a21_voltage(distance);
+            assert!((gp2y0a21_distance(voltage) - distance).abs() < 1e-9);
+        }
+        // ~2.3 V a 10 cm y ~0.4 V a 80 cm según la hoja de datos
+        assert!((gp2y0a21_voltage(0.1) - 2.3).abs() < 0.2);
+        assert!((gp2y0a21_voltage(0.8) - 0.4).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_ads1115_single_shot() {
+        let bus = MockI2cBus::new();
+        bus.add_device(0x48);
+        // El registro de conversión se sirve como cola: 1.5 V
+        let raw = (1.5f64 / 4.096 * 32768.0).round() as i16;
+        bus.push_stream(0x48, 0x00, &raw.to_be_bytes());
+
+        let mut adc = Ads1115::new(bus.clone(), Ads1115::<MockI2cBus>::DEFAULT_ADDRESS);
+        let voltage = adc.read_voltage(2).unwrap();
+        assert!((voltage - 1.5).abs() < 1e-3);
+        // AIN2 frente a GND, PGA ±4.096 V, conversión única a 128 SPS
+        assert_eq!(bus.writes()[..2], [(0x48, 0x01, 0xE3), (0x48, 0x02, 0x83)]);
+        assert!(adc.read_voltage(4).is_err());
+    }
+}
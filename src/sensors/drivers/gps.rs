@@ -0,0 +1,793 @@
+//! Receptor GNSS (u-blox NEO-6M y compatibles) sobre un flujo de bytes genérico
+use super::nmea::{self, Gsa, NmeaSentence, Rmc, Vtg};
+use super::ubx::{self, UbxMessage};
+use super::{period_from_rate, SensorDriver, SensorKind, SensorReading};
+use crate::geometry::Covariance3;
+use crate::sensors::{GpsData, GpsFix, SensorHealth};
+use crate::transforms::frames;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Sentencia NMEA más larga que se acepta antes de descartar el buffer
+const MAX_SENTENCE_LEN: usize = 256;
+/// Payload UBX más largo que se acepta
+const MAX_UBX_LEN: usize = 1024;
+
+/// Flujo de bytes de un receptor: puerto serie, registro o simulación
+pub trait GpsStream: Read + Send + std::fmt::Debug {}
+
+impl<T: Read + Send + std::fmt::Debug> GpsStream for T {}
+
+/// Origen de los bytes del receptor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GpsSource {
+    /// Puerto serie en `GpsConfig::port`
+    Serial,
+    /// Registro NMEA/UBX capturado, reproducido a `sample_rate` soluciones por segundo
+    Replay { path: String },
+    /// Receptor en memoria que emite NMEA en la posición indicada
+    Simulated {
+        latitude: f64,
+        longitude: f64,
+        altitude: f64,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GpsConfig {
+    pub port: String,
+    pub baudrate: u32,
+    pub sample_rate: u32,
+    /// Error equivalente de pseudodistancia (m): σ horizontal = HDOP·UERE
+    pub uere: f64,
+    pub source: GpsSource,
+    pub frame_id: String,
+}
+
+impl Default for GpsConfig {
+    fn default() -> Self {
+        Self {
+            port: "/dev/serial0".to_string(),
+            baudrate: 9600, // valor de fábrica del NEO-6M
+            sample_rate: 1,
+            uere: 4.0,
+            source: GpsSource::Serial,
+            frame_id: frames::GPS.to_string(),
+        }
+    }
+}
+
+/// Mensaje decodificado del flujo
+#[derive(Debug, Clone, PartialEq)]
+pub enum GpsMessage {
+    Nmea(NmeaSentence),
+    Ubx(UbxMessage),
+}
+
+/// Separa sentencias NMEA y tramas UBX entrelazadas en un flujo de bytes,
+/// descartando la basura entre ellas
+#[derive(Debug, Default)]
+pub struct GpsStreamParser {
+    buffer: Vec<u8>,
+    errors: u64,
+}
+
+impl GpsStreamParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<GpsMessage> {
+        self.buffer.extend_from_slice(bytes);
+        let mut messages = Vec::new();
+
+        loop {
+            let Some(start) = self
+                .buffer
+                .iter()
+                .position(|&b| b == b'$' || b == ubx::SYNC[0])
+            else {
+                self.buffer.clear();
+                break;
+            };
+            self.buffer.drain(..start);
+
+            if self.buffer[0] == b'$' {
+                let Some(end) = self.buffer.iter().position(|&b| b == b'\n') else {
+                    if self.buffer.len() > MAX_SENTENCE_LEN {
+                        self.errors += 1;
+                        self.buffer.drain(..1);
+                        continue;
+                    }
+                    break;
+                };
+                let line: Vec<u8> = self.buffer.drain(..=end).collect();
+                match std::str::from_utf8(&line).map(nmea::parse_sentence) {
+                    Ok(Ok(Some(sentence))) => messages.push(GpsMessage::Nmea(sentence)),
+                    Ok(Ok(None)) => {}
+                    _ => self.errors += 1,
+                }
+            } else {
+                if self.buffer.len() < 2 {
+                    break;
+                }
+                if self.buffer[1] != ubx::SYNC[1] {
+                    self.buffer.drain(..1);
+                    continue;
+                }
+                let Some(len) = ubx::frame_len(&self.buffer) else {
+                    break;
+                };
+                if len > MAX_UBX_LEN + 8 {
+                    self.errors += 1;
+                    self.buffer.drain(..1);
+                    continue;
+                }
+                if self.buffer.len() < len {
+                    break;
+                }
+                match ubx::parse_frame(&self.buffer[..len]) {
+                    Ok(message) => {
+                        messages.push(GpsMessage::Ubx(message));
+                        self.buffer.drain(..len);
+                    }
+                    Err(_) => {
+                        self.errors += 1;
+                        self.buffer.drain(..1);
+                    }
+                }
+            }
+        }
+
+        messages
+    }
+
+    /// Sentencias o tramas descartadas por checksum o formato
+    pub fn errors(&self) -> u64 {
+        self.errors
+    }
+}
+
+/// Combina los mensajes de una época en una `GpsData`. NMEA emite la solución
+/// al llegar GGA (con velocidad de RMC/VTG y DOP de GSA); UBX con NAV-PVT o
+/// NAV-POSLLH.
+#[derive(Debug)]
+pub struct GpsEpoch {
+    uere: f64,
+    rmc: Option<Rmc>,
+    gsa: Option<Gsa>,
+    vtg: Option<Vtg>,
+}
+
+impl GpsEpoch {
+    pub fn new(uere: f64) -> Self {
+        Self {
+            uere,
+            rmc: None,
+            gsa: None,
+            vtg: None,
+        }
+    }
+
+    pub fn apply(&mut self, message: GpsMessage) -> Option<GpsData> {
+        match message {
+            GpsMessage::Nmea(NmeaSentence::Rmc(rmc)) => self.rmc = Some(rmc),
+            GpsMessage::Nmea(NmeaSentence::Gsa(gsa)) => self.gsa = Some(gsa),
+            GpsMessage::Nmea(NmeaSentence::Vtg(vtg)) => self.vtg = Some(vtg),
+            GpsMessage::Nmea(NmeaSentence::Gga(gga)) => {
+                let fix = match gga.quality {
+                    0 | 6 => GpsFix::NoFix, // 6: estimado por navegación a estima
+                    1 if self.gsa.as_ref().is_some_and(|gsa| gsa.fix_type == 2) => GpsFix::Fix2D,
+                    2 => GpsFix::Dgps,
+                    4 => GpsFix::RtkFixed,
+                    5 => GpsFix::RtkFloat,
+                    _ => GpsFix::Fix3D,
+                };
+                let gsa = self.gsa.as_ref();
+                let hdop = gga.hdop.or(gsa.and_then(|gsa| gsa.hdop)).unwrap_or(99.99);
+                let vdop = gsa.and_then(|gsa| gsa.vdop).unwrap_or(hdop);
+                let horizontal = hdop * self.uere * fix_scale(fix);
+                let vertical = vdop * self.uere * fix_scale(fix);
+                let date = self.rmc.as_ref().and_then(|rmc| rmc.date);
+
+                return Some(GpsData {
+                    frame_id: frames::GPS.to_string(),
+                    latitude: gga.latitude.unwrap_or(f64::NAN),
+                    longitude: gga.longitude.unwrap_or(f64::NAN),
+                    altitude: gga.altitude.unwrap_or(0.0) + gga.geoid_separation.unwrap_or(0.0),
+                    fix,
+                    satellites: gga.satellites,
+                    hdop,
+                    vdop,
+                    speed: self.speed(),
+                    course: self.course(),
+                    covariance: diagonal(horizontal, vertical),
+                    timestamp: utc_timestamp(date, gga.time),
+                });
+            }
+            GpsMessage::Ubx(UbxMessage::NavPvt(pvt)) => {
+                let fix = match (pvt.fix_ok, pvt.fix_type, pvt.carrier_solution) {
+                    (false, _, _) | (_, 0 | 5, _) => GpsFix::NoFix,
+                    (_, _, 2) => GpsFix::RtkFixed,
+                    (_, _, 1) => GpsFix::RtkFloat,
+                    (_, 2, _) => GpsFix::Fix2D,
+                    _ => GpsFix::Fix3D,
+                };
+                let timestamp = match pvt.utc {
+                    Some((year, month, day, hour, minute, second)) => utc_timestamp(
+                        Some((day, month, year)),
+                        Some((hour * 3600 + minute * 60) as f64 + second),
+                    ),
+                    None => now(),
+                };
+
+                return Some(GpsData {
+                    frame_id: frames::GPS.to_string(),
+                    latitude: pvt.latitude,
+                    longitude: pvt.longitude,
+                    altitude: pvt.height,
+                    fix,
+                    satellites: pvt.satellites,
+                    hdop: pvt.pdop,
+                    vdop: pvt.pdop,
+                    speed: pvt.ground_speed,
+                    course: pvt.heading,
+                    covariance: diagonal(pvt.horizontal_accuracy, pvt.vertical_accuracy),
+                    timestamp,
+                });
+            }
+            GpsMessage::Ubx(UbxMessage::NavPosllh(posllh)) => {
+                // NAV-POSLLH no trae el tipo de fix: se toma de GSA si lo hay
+                let fix = match self.gsa.as_ref().map(|gsa| gsa.fix_type) {
+                    Some(2) => GpsFix::Fix2D,
+                    Some(3) => GpsFix::Fix3D,
+                    Some(_) => GpsFix::NoFix,
+                    None if posllh.horizontal_accuracy < 50.0 => GpsFix::Fix3D,
+                    None => GpsFix::NoFix,
+                };
+                let gsa = self.gsa.as_ref();
+
+                return Some(GpsData {
+                    frame_id: frames::GPS.to_string(),
+                    latitude: posllh.latitude,
+                    longitude: posllh.longitude,
+                    altitude: posllh.height,
+                    fix,
+                    satellites: gsa.map(|gsa| gsa.satellites.len() as u8).unwrap_or(0),
+                    hdop: gsa.and_then(|gsa| gsa.hdop).unwrap_or(99.99),
+                    vdop: gsa.and_then(|gsa| gsa.vdop).unwrap_or(99.99),
+                    speed: self.speed(),
+                    course: self.course(),
+                    covariance: diagonal(posllh.horizontal_accuracy, posllh.vertical_accuracy),
+                    timestamp: now(),
+                });
+            }
+            GpsMessage::Ubx(UbxMessage::Other { .. }) => {}
+        }
+        None
+    }
+
+    fn speed(&self) -> f64 {
+        let vtg = self.vtg.as_ref().and_then(|vtg| vtg.speed);
+        vtg.or(self.rmc.as_ref().and_then(|rmc| rmc.speed))
+            .unwrap_or(0.0)
+    }
+
+    fn course(&self) -> f64 {
+        let vtg = self.vtg.as_ref().and_then(|vtg| vtg.course);
+        vtg.or(self.rmc.as_ref().and_then(|rmc| rmc.course))
+            .unwrap_or(0.0)
+    }
+}
+
+/// Las correcciones diferenciales reducen el error de pseudodistancia
+fn fix_scale(fix: GpsFix) -> f64 {
+    match fix {
+        GpsFix::RtkFixed => 0.005,
+        GpsFix::RtkFloat => 0.1,
+        GpsFix::Dgps => 0.25,
+        _ => 1.0,
+    }
+}
+
+fn diagonal(horizontal: f64, vertical: f64) -> Covariance3 {
+    let (h, v) = (horizontal * horizontal, vertical * vertical);
+    Covariance3::diagonal(h, h, v)
+}
+
+fn now() -> f64 {
+    chrono::Utc::now().timestamp_millis() as f64 / 1000.0
+}
+
+/// Fecha de RMC más hora del día; sin fecha se usa el reloj del sistema
+fn utc_timestamp(date: Option<(u32, u32, i32)>, time: Option<f64>) -> f64 {
+    let (Some((day, month, year)), Some(time)) = (date, time) else {
+        return now();
+    };
+    chrono::NaiveDate::from_ymd_opt(year, month, day)
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|midnight| midnight.and_utc().timestamp() as f64 + time)
+        .unwrap_or_else(now)
+}
+
+/// Grados decimales a `ddmm.mmmm` (o `dddmm.mmmm`) y hemisferio
+fn format_coordinate(value: f64, latitude: bool) -> (String, char) {
+    let hemisphere = match (latitude, value >= 0.0) {
+        (true, true) => 'N',
+        (true, false) => 'S',
+        (false, true) => 'E',
+        (false, false) => 'W',
+    };
+    let value = value.abs();
+    let degrees = value.trunc();
+    let minutes = (value - degrees) * 60.0;
+    let text = if latitude {
+        format!("{:02}{:07.4}", degrees as u32, minutes)
+    } else {
+        format!("{:03}{:07.4}", degrees as u32, minutes)
+    };
+    (text, hemisphere)
+}
+
+/// Posición del receptor simulado, compartida con quien lo mueve
+#[derive(Debug, Clone)]
+pub struct SimulatedPosition(Arc<Mutex<(f64, f64, f64)>>);
+
+impl SimulatedPosition {
+    pub fn new(latitude: f64, longitude: f64, altitude: f64) -> Self {
+        Self(Arc::new(Mutex::new((latitude, longitude, altitude))))
+    }
+
+    pub fn set(&self, latitude: f64, longitude: f64, altitude: f64) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = (latitude, longitude, altitude);
+    }
+
+    pub fn get(&self) -> (f64, f64, f64) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Receptor simulado: cada periodo emite RMC, GSA y GGA con un fix 3D
+#[derive(Debug)]
+pub struct SimulatedReceiver {
+    position: SimulatedPosition,
+    period: Duration,
+    next_epoch: Instant,
+    pending: Vec<u8>,
+}
+
+impl SimulatedReceiver {
+    pub fn new(position: SimulatedPosition, period: Duration) -> Self {
+        Self {
+            position,
+            period,
+            next_epoch: Instant::now(),
+            pending: Vec::new(),
+        }
+    }
+
+    fn epoch(&self) -> Vec<u8> {
+        let (latitude, longitude, altitude) = self.position.get();
+        let (lat, ns) = format_coordinate(latitude, true);
+        let (lon, ew) = format_coordinate(longitude, false);
+        let utc = chrono::Utc::now();
+        let time = utc.format("%H%M%S%.3f").to_string();
+        let date = utc.format("%d%m%y").to_string();
+
+        let sentences = [
+            format!(
+                "GPRMC,{},A,{},{},{},{},0.0,0.0,{},,,A",
+                time, lat, ns, lon, ew, date
+            ),
+            "GPGSA,A,3,02,05,12,15,18,21,25,29,,,,,1.6,0.9,1.3".to_string(),
+            format!(
+                "GPGGA,{},{},{},{},{},1,08,0.9,{:.1},M,0.0,M,,",
+                time, lat, ns, lon, ew, altitude
+            ),
+        ];
+        sentences
+            .iter()
+            .flat_map(|body| nmea::with_checksum(body).into_bytes())
+            .collect()
+    }
+}
+
+impl Read for SimulatedReceiver {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.pending.is_empty() {
+            let wait = self.next_epoch.saturating_duration_since(Instant::now());
+            // Espera acotada para que el hilo lector pueda comprobar si debe parar
+            if !wait.is_zero() {
+                std::thread::sleep(wait.min(Duration::from_millis(100)));
+                return Ok(0);
+            }
+            self.pending = self.epoch();
+            self.next_epoch += self.period;
+        }
+        let count = buf.len().min(self.pending.len());
+        buf[..count].copy_from_slice(&self.pending[..count]);
+        self.pending.drain(..count);
+        Ok(count)
+    }
+}
+
+/// Estado compartido entre el driver y su hilo lector
+#[derive(Debug, Default)]
+struct GpsShared {
+    latest: Option<GpsData>,
+    received: Option<Instant>,
+    stream_error: Option<String>,
+}
+
+// Driver GPS
+#[derive(Debug)]
+pub struct Gps {
+    config: GpsConfig,
+    reader: Option<Box<dyn GpsStream>>,
+    shared: Arc<Mutex<GpsShared>>,
+    running: Arc<AtomicBool>,
+    is_connected: bool,
+    last_returned: Option<Instant>,
+}
+
+impl Gps {
+    pub fn new(config: GpsConfig) -> Self {
+        Self {
+            config,
+            reader: None,
+            shared: Arc::new(Mutex::new(GpsShared::default())),
+            running: Arc::new(AtomicBool::new(false)),
+            is_connected: false,
+            last_returned: None,
+        }
+    }
+
+    /// Lee NMEA/UBX de un flujo ya abierto en lugar de `config.source`
+    pub fn with_reader(config: GpsConfig, reader: Box<dyn GpsStream>) -> Self {
+        Self {
+            reader: Some(reader),
+            ..Self::new(config)
+        }
+    }
+
+    /// Receptor simulado; la posición devuelta permite moverlo
+    pub fn simulated(config: GpsConfig) -> (Self, SimulatedPosition) {
+        let position = match config.source {
+            GpsSource::Simulated {
+                latitude,
+                longitude,
+                altitude,
+            } => SimulatedPosition::new(latitude, longitude, altitude),
+            _ => SimulatedPosition::new(0.0, 0.0, 0.0),
+        };
+        let receiver =
+            SimulatedReceiver::new(position.clone(), period_from_rate(config.sample_rate));
+        (Self::with_reader(config, Box::new(receiver)), position)
+    }
+
+    pub async fn connect(&mut self) -> Result<(), String> {
+        log::info!("🔌 Conectando GPS {:?}...", self.config.source);
+
+        let reader = match self.reader.take() {
+            Some(reader) => reader,
+            None => self.open_source()?,
+        };
+        // Al reproducir un registro se marca el ritmo tras cada solución
+        let pace = matches!(self.config.source, GpsSource::Replay { .. })
+            .then(|| period_from_rate(self.config.sample_rate));
+
+        *self.shared.lock().unwrap_or_else(|e| e.into_inner()) = GpsShared::default();
+        self.running.store(true, Ordering::SeqCst);
+        let shared = Arc::clone(&self.shared);
+        let running = Arc::clone(&self.running);
+        let uere = self.config.uere;
+        std::thread::Builder::new()
+            .name("gps-reader".to_string())
+            .spawn(move || read_stream(reader, uere, pace, shared, running))
+            .map_err(|e| format!("No se pudo lanzar el lector GPS: {}", e))?;
+
+        self.is_connected = true;
+        log::info!("✅ GPS conectado");
+        Ok(())
+    }
+
+    pub async fn disconnect(&mut self) -> Result<(), String> {
+        log::info!("🔌 Desconectando GPS...");
+        // El hilo lector termina en su próxima lectura
+        self.running.store(false, Ordering::SeqCst);
+        self.is_connected = false;
+        Ok(())
+    }
+
+    /// Última solución del receptor. Espera hasta dos periodos a que llegue
+    /// una nueva antes de dar el flujo por detenido.
+    pub async fn read_fix(&mut self) -> Result<GpsData, String> {
+        if !self.is_connected {
+            return Err("GPS no conectado".to_string());
+        }
+
+        let deadline = Instant::now() + 2 * period_from_rate(self.config.sample_rate);
+        loop {
+            {
+                let shared = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+                if let Some(error) = &shared.stream_error {
+                    return Err(error.clone());
+                }
+                if let (Some(fix), Some(received)) = (&shared.latest, shared.received) {
+                    if self.last_returned.is_none_or(|last| received > last) {
+                        self.last_returned = Some(received);
+                        return Ok(GpsData {
+                            frame_id: self.config.frame_id.clone(),
+                            ..fix.clone()
+                        });
+                    }
+                }
+            }
+            if Instant::now() >= deadline {
+                return Err("Sin solución GNSS nueva".to_string());
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    fn open_source(&self) -> Result<Box<dyn GpsStream>, String> {
+        match &self.config.source {
+            GpsSource::Serial => open_serial(&self.config.port, self.config.baudrate),
+            GpsSource::Replay { path } => std::fs::File::open(path)
+                .map(|file| Box::new(file) as Box<dyn GpsStream>)
+                .map_err(|e| format!("No se pudo abrir el registro GPS {}: {}", path, e)),
+            GpsSource::Simulated {
+                latitude,
+                longitude,
+                altitude,
+            } => Ok(Box::new(SimulatedReceiver::new(
+                SimulatedPosition::new(*latitude, *longitude, *altitude),
+                period_from_rate(self.config.sample_rate),
+            ))),
+        }
+    }
+
+    pub fn get_config(&self) -> &GpsConfig {
+        &self.config
+    }
+
+    pub fn get_health(&self) -> SensorHealth {
+        if !self.is_connected {
+            return SensorHealth::Disconnected;
+        }
+
+        let shared = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(error) = &shared.stream_error {
+            return SensorHealth::Error(error.clone());
+        }
+        if let Some(received) = shared.received {
+            if received.elapsed().as_secs() > 5 {
+                return SensorHealth::Error("No data received for 5 seconds".to_string());
+            }
+        }
+        match &shared.latest {
+            Some(fix) if !fix.has_fix() => SensorHealth::Warning("Sin fix GNSS".to_string()),
+            _ => SensorHealth::Healthy,
+        }
+    }
+}
+
+/// Bucle del hilo lector: decodifica el flujo y publica cada solución
+fn read_stream(
+    mut reader: Box<dyn GpsStream>,
+    uere: f64,
+    pace: Option<Duration>,
+    shared: Arc<Mutex<GpsShared>>,
+    running: Arc<AtomicBool>,
+) {
+    let mut parser = GpsStreamParser::new();
+    let mut epoch = GpsEpoch::new(uere);
+    let mut buf = [0u8; 512];
+
+    while running.load(Ordering::SeqCst) {
+        let count = match reader.read(&mut buf) {
+            // El puerto serie devuelve 0 al vencer VTIME
+            Ok(0) if pace.is_none() => continue,
+            Ok(0) => {
+                log::info!("📼 Fin del registro GPS");
+                break;
+            }
+            Ok(count) => count,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => {
+                log::error!("❌ Error leyendo GPS: {}", e);
+                shared
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .stream_error = Some(format!("Error leyendo GPS: {}", e));
+                break;
+            }
+        };
+
+        for message in parser.push(&buf[..count]) {
+            if let Some(fix) = epoch.apply(message) {
+                {
+                    let mut shared = shared.lock().unwrap_or_else(|e| e.into_inner());
+                    shared.latest = Some(fix);
+                    shared.received = Some(Instant::now());
+                }
+                if let Some(pace) = pace {
+                    std::thread::sleep(pace);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn open_serial(port: &str, baudrate: u32) -> Result<Box<dyn GpsStream>, String> {
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::os::unix::io::AsRawFd;
+
+    let speed = match baudrate {
+        4800 => libc::B4800,
+        9600 => libc::B9600,
+        19200 => libc::B19200,
+        38400 => libc::B38400,
+        57600 => libc::B57600,
+        115200 => libc::B115200,
+        230400 => libc::B230400,
+        other => return Err(format!("Baudrate GPS {} no soportado", other)),
+    };
+
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_NOCTTY)
+        .open(port)
+        .map_err(|e| format!("No se pudo abrir {}: {}", port, e))?;
+
+    // Modo crudo 8N1; read() vuelve tras 100 ms sin datos
+    let fd = file.as_raw_fd();
+    // SAFETY: `fd` es un descriptor abierto y `termios` se inicializa con tcgetattr
+    unsafe {
+        let mut termios: libc::termios = std::mem::zeroed();
+        if libc::tcgetattr(fd, &mut termios) != 0 {
+            return Err(format!(
+                "{} no es un puerto serie: {}",
+                port,
+                std::io::Error::last_os_error()
+            ));
+        }
+        libc::cfmakeraw(&mut termios);
+        libc::cfsetispeed(&mut termios, speed);
+        libc::cfsetospeed(&mut termios, speed);
+        termios.c_cflag |= libc::CLOCAL | libc::CREAD;
+        termios.c_cc[libc::VMIN] = 0;
+        termios.c_cc[libc::VTIME] = 1;
+        if libc::tcsetattr(fd, libc::TCSANOW, &termios) != 0 {
+            return Err(format!(
+                "No se pudo configurar {}: {}",
+                port,
+                std::io::Error::last_os_error()
+            ));
+        }
+    }
+
+    Ok(Box::new(file))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn open_serial(port: &str, _baudrate: u32) -> Result<Box<dyn GpsStream>, String> {
+    Err(format!(
+        "Puerto serie {} no soportado en esta plataforma",
+        port
+    ))
+}
+
+#[async_trait]
+impl SensorDriver for Gps {
+    fn kind(&self) -> SensorKind {
+        SensorKind::Gps
+    }
+
+    async fn connect(&mut self) -> Result<(), String> {
+        Gps::connect(self).await
+    }
+
+    async fn disconnect(&mut self) -> Result<(), String> {
+        Gps::disconnect(self).await
+    }
+
+    async fn read(&mut self) -> Result<SensorReading, String> {
+        self.read_fix().await.map(SensorReading::Gps)
+    }
+
+    fn is_connected(&self) -> bool {
+        self.is_connected
+    }
+
+    fn health(&self) -> SensorHealth {
+        self.get_health()
+    }
+
+    fn sample_period(&self) -> Duration {
+        period_from_rate(self.config.sample_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_parser_interleaved_nmea_and_ubx() {
+        let mut stream = b"\x00\xffbasura".to_vec();
+        stream.extend_from_slice(
+            nmea::with_checksum("GPGSA,A,3,04,05,,09,12,,,24,,,,,2.5,1.3,2.1").as_bytes(),
+        );
+        stream.extend_from_slice(&ubx::encode_frame(0x05, 0x01, &[0x06, 0x00]));
+        stream.extend_from_slice(
+            b"$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47\r\n",
+        );
+
+        // Entregado en trozos arbitrarios, como llega de un UART
+        let mut parser = GpsStreamParser::new();
+        let mut epoch = GpsEpoch::new(4.0);
+        let mut fixes = Vec::new();
+        for chunk in stream.chunks(7) {
+            for message in parser.push(chunk) {
+                fixes.extend(epoch.apply(message));
+            }
+        }
+
+        assert_eq!(parser.errors(), 0);
+        assert_eq!(fixes.len(), 1);
+        let fix = &fixes[0];
+        assert_eq!(fix.fix, GpsFix::Fix3D);
+        assert!((fix.latitude - 48.1173).abs() < 1e-6);
+        assert!((fix.altitude - (545.4 + 46.9)).abs() < 1e-9);
+        // σ horizontal = HDOP de GGA · UERE, σ vertical = VDOP de GSA · UERE
+        assert!((fix.covariance[0][0] - (0.9f64 * 4.0).powi(2)).abs() < 1e-9);
+        assert!((fix.covariance[2][2] - (2.1f64 * 4.0).powi(2)).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_simulated_receiver_and_spec() {
+        let spec: super::super::SensorSpec = toml::from_str(
+            r#"
+            name = "gps"
+            type = "gps"
+            sample_rate = 10
+
+            [source.simulated]
+            latitude = 40.4168
+            longitude = -3.7038
+            altitude = 650.0
+            "#,
+        )
+        .unwrap();
+        assert_eq!(spec.kind(), SensorKind::Gps);
+        let super::super::DriverConfig::Gps(config) = spec.driver else {
+            panic!("no es GPS");
+        };
+
+        let (mut gps, position) = Gps::simulated(config);
+        gps.connect().await.unwrap();
+        let fix = gps.read_fix().await.unwrap();
+        assert!(fix.has_fix());
+        assert!((fix.latitude - 40.4168).abs() < 1e-6);
+        assert!((fix.longitude + 3.7038).abs() < 1e-6);
+
+        position.set(40.4170, -3.7040, 650.0);
+        let mut fix = gps.read_fix().await.unwrap();
+        // Puede quedar una época ya emitida con la posición anterior
+        if (fix.latitude - 40.4170).abs() > 1e-6 {
+            fix = gps.read_fix().await.unwrap();
+        }
+        assert!((fix.latitude - 40.4170).abs() < 1e-6);
+        assert_eq!(gps.get_health(), SensorHealth::Healthy);
+        gps.disconnect().await.unwrap();
+    }
+}
@@ -0,0 +1,274 @@
+//! Abstracción del bus I2C usada por los drivers de registro (IMU, potencia...)
+use anyhow::Result;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// Bus I2C maestro. Las direcciones son de 7 bits.
+pub trait I2cBus: Send + std::fmt::Debug {
+    fn write(&mut self, address: u8, data: &[u8]) -> Result<()>;
+
+    /// Escribe `data` y lee `buffer.len()` bytes en una sola transacción (repeated start)
+    fn write_read(&mut self, address: u8, data: &[u8], buffer: &mut [u8]) -> Result<()>;
+
+    fn write_register(&mut self, address: u8, register: u8, value: u8) -> Result<()> {
+        self.write(address, &[register, value])
+    }
+
+    fn read_register(&mut self, address: u8, register: u8) -> Result<u8> {
+        let mut buffer = [0u8; 1];
+        self.write_read(address, &[register], &mut buffer)?;
+        Ok(buffer[0])
+    }
+
+    /// Lectura en ráfaga a partir de `register` (el dispositivo auto-incrementa)
+    fn read_registers(&mut self, address: u8, register: u8, buffer: &mut [u8]) -> Result<()> {
+        self.write_read(address, &[register], buffer)
+    }
+}
+
+impl<T: I2cBus + ?Sized> I2cBus for Box<T> {
+    fn write(&mut self, address: u8, data: &[u8]) -> Result<()> {
+        (**self).write(address, data)
+    }
+
+    fn write_read(&mut self, address: u8, data: &[u8], buffer: &mut [u8]) -> Result<()> {
+        (**self).write_read(address, data, buffer)
+    }
+}
+
+/// Bus I2C real sobre `/dev/i2c-N` (módulo i2c-dev del kernel)
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+pub struct LinuxI2cBus {
+    file: std::fs::File,
+    path: String,
+}
+
+#[cfg(target_os = "linux")]
+impl LinuxI2cBus {
+    const I2C_RDWR: libc::c_ulong = 0x0707;
+    const I2C_M_RD: u16 = 0x0001;
+
+    pub fn open(path: &str) -> Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)
+            .map_err(|e| anyhow::anyhow!("No se pudo abrir el bus I2C {}: {}", path, e))?;
+
+        Ok(Self {
+            file,
+            path: path.to_string(),
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn transfer(&mut self, messages: &mut [I2cMsg]) -> Result<()> {
+        use std::os::unix::io::AsRawFd;
+
+        let mut request = I2cRdwrData {
+            msgs: messages.as_mut_ptr(),
+            nmsgs: messages.len() as u32,
+        };
+
+        // SAFETY: `request` apunta a mensajes y buffers válidos durante toda la llamada
+        let result = unsafe {
+            libc::ioctl(
+                self.file.as_raw_fd(),
+                Self::I2C_RDWR as _,
+                &mut request as *mut I2cRdwrData,
+            )
+        };
+
+        if result < 0 {
+            return Err(anyhow::anyhow!(
+                "Transferencia I2C fallida en {}: {}",
+                self.path,
+                std::io::Error::last_os_error()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[repr(C)]
+struct I2cMsg {
+    addr: u16,
+    flags: u16,
+    len: u16,
+    buf: *mut u8,
+}
+
+#[cfg(target_os = "linux")]
+#[repr(C)]
+struct I2cRdwrData {
+    msgs: *mut I2cMsg,
+    nmsgs: u32,
+}
+
+#[cfg(target_os = "linux")]
+impl I2cBus for LinuxI2cBus {
+    fn write(&mut self, address: u8, data: &[u8]) -> Result<()> {
+        let mut data = data.to_vec();
+        let mut messages = [I2cMsg {
+            addr: address as u16,
+            flags: 0,
+            len: data.len() as u16,
+            buf: data.as_mut_ptr(),
+        }];
+        self.transfer(&mut messages)
+    }
+
+    fn write_read(&mut self, address: u8, data: &[u8], buffer: &mut [u8]) -> Result<()> {
+        let mut data = data.to_vec();
+        let mut messages = [
+            I2cMsg {
+                addr: address as u16,
+                flags: 0,
+                len: data.len() as u16,
+                buf: data.as_mut_ptr(),
+            },
+            I2cMsg {
+                addr: address as u16,
+                flags: Self::I2C_M_RD,
+                len: buffer.len() as u16,
+                buf: buffer.as_mut_ptr(),
+            },
+        ];
+        self.transfer(&mut messages)
+    }
+}
+
+/// Bus I2C en memoria: cada dispositivo es un mapa de 256 registros.
+///
+/// Los clones comparten el mismo estado, de modo que un test puede conservar
+/// una copia para inspeccionar o modificar registros mientras el driver usa otra.
+#[derive(Debug, Clone, Default)]
+pub struct MockI2cBus {
+    state: Arc<Mutex<MockState>>,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    devices: HashMap<u8, [u8; 256]>,
+    streams: HashMap<(u8, u8), VecDeque<u8>>,
+    stream_registers: HashSet<(u8, u8)>,
+    writes: Vec<(u8, u8, u8)>,
+}
+
+impl MockI2cBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registra un dispositivo en `address` con todos los registros a cero
+    pub fn add_device(&self, address: u8) {
+        self.lock().devices.entry(address).or_insert([0u8; 256]);
+    }
+
+    pub fn set_register(&self, address: u8, register: u8, value: u8) {
+        let mut state = self.lock();
+        state.devices.entry(address).or_insert([0u8; 256])[register as usize] = value;
+    }
+
+    pub fn register(&self, address: u8, register: u8) -> Option<u8> {
+        self.lock()
+            .devices
+            .get(&address)
+            .map(|registers| registers[register as usize])
+    }
+
+    /// Copia `values` en registros consecutivos a partir de `register`
+    pub fn set_registers(&self, address: u8, register: u8, values: &[u8]) {
+        let mut state = self.lock();
+        let registers = state.devices.entry(address).or_insert([0u8; 256]);
+        for (offset, value) in values.iter().enumerate() {
+            registers[(register as usize + offset) % 256] = *value;
+        }
+    }
+
+    /// Marca `register` como puerto FIFO: las lecturas consumen bytes de la cola
+    /// en lugar de auto-incrementar la dirección.
+    pub fn push_stream(&self, address: u8, register: u8, bytes: &[u8]) {
+        let mut state = self.lock();
+        state.stream_registers.insert((address, register));
+        state
+            .streams
+            .entry((address, register))
+            .or_default()
+            .extend(bytes.iter().copied());
+    }
+
+    pub fn stream_len(&self, address: u8, register: u8) -> usize {
+        self.lock()
+            .streams
+            .get(&(address, register))
+            .map(|queue| queue.len())
+            .unwrap_or(0)
+    }
+
+    /// Historial de escrituras de registro como `(dirección, registro, valor)`
+    pub fn writes(&self) -> Vec<(u8, u8, u8)> {
+        self.lock().writes.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl I2cBus for MockI2cBus {
+    fn write(&mut self, address: u8, data: &[u8]) -> Result<()> {
+        let mut state = self.lock();
+        if !state.devices.contains_key(&address) {
+            return Err(anyhow::anyhow!(
+                "NACK: no hay dispositivo en 0x{:02X}",
+                address
+            ));
+        }
+
+        if let Some((&register, values)) = data.split_first() {
+            for (offset, value) in values.iter().enumerate() {
+                let register = register.wrapping_add(offset as u8);
+                state.devices.get_mut(&address).unwrap()[register as usize] = *value;
+                state.writes.push((address, register, *value));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_read(&mut self, address: u8, data: &[u8], buffer: &mut [u8]) -> Result<()> {
+        let mut state = self.lock();
+        let registers = match state.devices.get(&address) {
+            Some(registers) => *registers,
+            None => {
+                return Err(anyhow::anyhow!(
+                    "NACK: no hay dispositivo en 0x{:02X}",
+                    address
+                ))
+            }
+        };
+
+        let register = data.first().copied().unwrap_or(0);
+
+        if state.stream_registers.contains(&(address, register)) {
+            let queue = state.streams.entry((address, register)).or_default();
+            for byte in buffer.iter_mut() {
+                *byte = queue.pop_front().unwrap_or(0);
+            }
+            return Ok(());
+        }
+
+        for (offset, byte) in buffer.iter_mut().enumerate() {
+            *byte = registers[(register as usize + offset) % 256];
+        }
+
+        Ok(())
+    }
+}
@@ -1,9 +1,155 @@
-use super::{CameraData, IMUData, LidarData, LidarPoint, Vector3};
+pub mod frame_source;
+pub mod gpio;
+pub mod gps;
+pub mod i2c;
+pub mod mpu6050;
+pub mod nmea;
+pub mod pixel_format;
+pub mod range;
+pub mod ubx;
+#[cfg(target_os = "linux")]
+pub mod v4l2;
+
+use super::{
+    CameraData, GpsData, IMUData, LidarData, LidarPoint, OdometryData, RangeData, SensorHealth,
+    Vector3,
+};
+use async_trait::async_trait;
+use frame_source::{CameraSource, FrameSource};
+pub use gps::{Gps, GpsConfig, GpsSource};
+use i2c::I2cBus;
+use mpu6050::{AccelRange, DlpfBandwidth, GyroRange, Mpu6050, Mpu6050Settings};
+pub use range::{RangeConfig, RangeModel, RangeSensor};
+use crate::transforms::frames;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::time::Duration;
+
+/// Muestras usadas por `SensorDriver::calibrate` en el IMU
+const IMU_CALIBRATION_SAMPLES: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SensorKind {
+    Lidar,
+    Imu,
+    Camera,
+    Odometry,
+    Range,
+    Gps,
+}
+
+/// Lectura tipada de cualquier sensor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SensorReading {
+    Lidar(LidarData),
+    Imu(IMUData),
+    Camera(CameraData),
+    Odometry(OdometryData),
+    Range(RangeData),
+    Gps(GpsData),
+}
+
+impl SensorReading {
+    pub fn kind(&self) -> SensorKind {
+        match self {
+            Self::Lidar(_) => SensorKind::Lidar,
+            Self::Imu(_) => SensorKind::Imu,
+            Self::Camera(_) => SensorKind::Camera,
+            Self::Odometry(_) => SensorKind::Odometry,
+            Self::Range(_) => SensorKind::Range,
+            Self::Gps(_) => SensorKind::Gps,
+        }
+    }
+
+    /// Marca de tiempo de la lectura en segundos Unix
+    pub fn timestamp(&self) -> f64 {
+        match self {
+            Self::Lidar(scan) => scan.points.first().map(|p| p.timestamp).unwrap_or(0.0),
+            Self::Imu(imu) => imu.timestamp,
+            Self::Camera(frame) => frame.timestamp,
+            Self::Odometry(odometry) => odometry.timestamp,
+            Self::Range(range) => range.timestamp,
+            Self::Gps(gps) => gps.timestamp,
+        }
+    }
+}
+
+/// Interfaz común de todos los drivers de sensores
+#[async_trait]
+pub trait SensorDriver: Send + std::fmt::Debug {
+    fn kind(&self) -> SensorKind;
+
+    async fn connect(&mut self) -> Result<(), String>;
+
+    async fn disconnect(&mut self) -> Result<(), String>;
+
+    async fn read(&mut self) -> Result<SensorReading, String>;
+
+    /// Calibración tras conectar; por defecto no hace nada
+    async fn calibrate(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool;
+
+    fn health(&self) -> SensorHealth;
+
+    /// Periodo nominal entre lecturas
+    fn sample_period(&self) -> Duration;
+}
+
+/// Configuración de un driver, etiquetada por tipo de sensor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DriverConfig {
+    Lidar(LidarConfig),
+    Imu(IMUConfig),
+    Camera(CameraConfig),
+    Range(RangeConfig),
+    Gps(GpsConfig),
+}
+
+/// Sensor declarado en la configuración: nombre único más su driver
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SensorSpec {
+    pub name: String,
+    #[serde(flatten)]
+    pub driver: DriverConfig,
+}
+
+impl SensorSpec {
+    pub fn new(name: impl Into<String>, driver: DriverConfig) -> Self {
+        Self {
+            name: name.into(),
+            driver,
+        }
+    }
+
+    pub fn kind(&self) -> SensorKind {
+        match self.driver {
+            DriverConfig::Lidar(_) => SensorKind::Lidar,
+            DriverConfig::Imu(_) => SensorKind::Imu,
+            DriverConfig::Camera(_) => SensorKind::Camera,
+            DriverConfig::Range(_) => SensorKind::Range,
+            DriverConfig::Gps(_) => SensorKind::Gps,
+        }
+    }
+
+    /// Instancia el driver sin conectarlo
+    pub fn build(&self) -> Box<dyn SensorDriver> {
+        match &self.driver {
+            DriverConfig::Lidar(config) => Box::new(Lidar::new(config.clone())),
+            DriverConfig::Imu(config) => Box::new(IMU::new(config.clone())),
+            DriverConfig::Camera(config) => Box::new(Camera::new(config.clone())),
+            DriverConfig::Range(config) => Box::new(RangeSensor::new(config.clone())),
+            DriverConfig::Gps(config) => Box::new(Gps::new(config.clone())),
+        }
+    }
+}
 
 // Configuraciones
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct LidarConfig {
     pub port: String,
     pub baudrate: u32,
@@ -12,6 +158,7 @@ pub struct LidarConfig {
     pub min_range: f64,
     pub max_range: f64,
     pub sample_rate: u32,
+    pub frame_id: String,
 }
 
 impl Default for LidarConfig {
@@ -24,38 +171,53 @@ impl Default for LidarConfig {
             min_range: 0.05, // 5cm
             max_range: 12.0, // 12m
             sample_rate: 10, // 10Hz
+            frame_id: frames::LASER.to_string(),
         }
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct IMUConfig {
+    pub i2c_bus: String,
     pub i2c_address: u8,
     pub sample_rate: u32,
     pub acceleration_scale: f64,
     pub gyroscope_scale: f64,
     pub magnetometer_scale: f64,
+    pub dlpf: DlpfBandwidth,
+    pub use_fifo: bool,
+    pub simulated: bool, // MPU-6050 en memoria en lugar de `i2c_bus`
+    pub frame_id: String,
 }
 
 impl Default for IMUConfig {
     fn default() -> Self {
         Self {
+            i2c_bus: "/dev/i2c-1".to_string(),
             i2c_address: 0x68,
             sample_rate: 100,            // 100Hz
             acceleration_scale: 16384.0, // LSB/g
             gyroscope_scale: 131.0,      // LSB/°/s
             magnetometer_scale: 0.15,    // μT/LSB
+            dlpf: DlpfBandwidth::Hz44,
+            use_fifo: false,
+            simulated: false,
+            frame_id: frames::IMU.to_string(),
         }
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CameraConfig {
     pub device_path: String,
     pub width: u32,
     pub height: u32,
     pub framerate: u32,
     pub format: String,
+    pub source: CameraSource,
+    pub frame_id: String,
 }
 
 impl Default for CameraConfig {
@@ -66,6 +228,8 @@ impl Default for CameraConfig {
             height: 480,
             framerate: 30,
             format: "MJPG".to_string(),
+            source: CameraSource::V4l2,
+            frame_id: frames::CAMERA.to_string(),
         }
     }
 }
@@ -138,6 +302,7 @@ impl Lidar {
         self.last_scan_time = Some(now);
 
         Ok(LidarData {
+            frame_id: self.config.frame_id.clone(),
             points,
             scan_time,
             min_angle: self.config.min_angle,
@@ -155,18 +320,18 @@ impl Lidar {
         &self.config
     }
 
-    pub fn get_health(&self) -> super::SensorHealth {
+    pub fn get_health(&self) -> SensorHealth {
         if !self.is_connected {
-            return super::SensorHealth::Disconnected;
+            return SensorHealth::Disconnected;
         }
 
         if let Some(last_scan) = self.last_scan_time {
             if last_scan.elapsed().as_secs() > 5 {
-                return super::SensorHealth::Error("No data received for 5 seconds".to_string());
+                return SensorHealth::Error("No data received for 5 seconds".to_string());
             }
         }
 
-        super::SensorHealth::Healthy
+        SensorHealth::Healthy
     }
 }
 
@@ -174,6 +339,7 @@ impl Lidar {
 #[derive(Debug)]
 pub struct IMU {
     config: IMUConfig,
+    device: Option<Mpu6050<Box<dyn I2cBus>>>,
     is_connected: bool,
     calibration_data: IMUCalibration,
 }
@@ -201,27 +367,70 @@ impl IMU {
     pub fn new(config: IMUConfig) -> Self {
         Self {
             config,
+            device: None,
             is_connected: false,
             calibration_data: IMUCalibration::default(),
         }
     }
 
+    /// Usa un bus ya abierto (p. ej. `i2c::MockI2cBus`) en lugar de `config.i2c_bus`
+    pub fn with_bus(config: IMUConfig, bus: Box<dyn I2cBus>) -> Self {
+        let address = config.i2c_address;
+        Self {
+            device: Some(Mpu6050::new(bus, address)),
+            ..Self::new(config)
+        }
+    }
+
+    /// IMU sobre un MPU-6050 simulado en memoria
+    pub fn simulated(config: IMUConfig) -> Self {
+        let bus = mpu6050::simulated_bus(config.i2c_address);
+        Self::with_bus(config, Box::new(bus))
+    }
+
     pub async fn connect(&mut self) -> Result<(), String> {
         log::info!(
-            "🔌 Conectando IMU en dirección 0x{:02X}...",
+            "🔌 Conectando IMU en {} dirección 0x{:02X}...",
+            self.config.i2c_bus,
             self.config.i2c_address
         );
 
-        // Simular conexión I2C
-        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+        let settings = self.settings()?;
+
+        if self.device.is_none() {
+            let bus: Box<dyn I2cBus> = if self.config.simulated {
+                Box::new(mpu6050::simulated_bus(self.config.i2c_address))
+            } else {
+                Self::open_bus(&self.config.i2c_bus)?
+            };
+            self.device = Some(Mpu6050::new(bus, self.config.i2c_address));
+        }
+        let device = self.device.as_mut().unwrap();
+
+        let model = device.probe().map_err(|e| e.to_string())?;
+        device.reset().map_err(|e| e.to_string())?;
+
+        // El reset tarda ~100 ms en completarse
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let device = self.device.as_mut().unwrap();
+        device.configure(settings).map_err(|e| e.to_string())?;
+        if self.config.use_fifo {
+            device.enable_fifo().map_err(|e| e.to_string())?;
+        }
 
         self.is_connected = true;
-        log::info!("✅ IMU conectado exitosamente");
+        log::info!("✅ IMU conectado exitosamente ({:?})", model);
         Ok(())
     }
 
     pub async fn disconnect(&mut self) -> Result<(), String> {
         log::info!("🔌 Desconectando IMU...");
+        if let Some(device) = self.device.as_mut() {
+            if let Err(e) = device.sleep() {
+                log::warn!("⚠️ No se pudo poner el IMU en reposo: {}", e);
+            }
+        }
         self.is_connected = false;
         Ok(())
     }
@@ -231,49 +440,114 @@ impl IMU {
             return Err("IMU no conectado".to_string());
         }
 
+        let device = self.device.as_mut().ok_or("IMU sin dispositivo")?;
+        let sample = device.read_sample().map_err(|e| e.to_string())?;
         let timestamp = chrono::Utc::now().timestamp_millis() as f64 / 1000.0;
 
-        // Generar datos simulados del IMU
-        let acceleration = Vector3::new(
-            (rand::random::<f64>() - 0.5) * 0.2, // Pequeñas variaciones
-            (rand::random::<f64>() - 0.5) * 0.2,
-            9.81 + (rand::random::<f64>() - 0.5) * 0.1, // Gravedad + ruido
-        );
-
-        let gyroscope = Vector3::new(
-            (rand::random::<f64>() - 0.5) * 0.1,
-            (rand::random::<f64>() - 0.5) * 0.1,
-            (rand::random::<f64>() - 0.5) * 0.05,
-        );
+        Ok(self.to_imu_data(
+            sample.acceleration,
+            sample.gyroscope,
+            sample.temperature.unwrap_or(f64::NAN),
+            timestamp,
+        ))
+    }
 
-        let magnetometer = Vector3::new(
-            25.0 + (rand::random::<f64>() - 0.5) * 5.0,
-            5.0 + (rand::random::<f64>() - 0.5) * 2.0,
-            -45.0 + (rand::random::<f64>() - 0.5) * 5.0,
-        );
+    /// Vacía la FIFO del sensor. Las marcas de tiempo se reconstruyen hacia atrás
+    /// desde el instante de lectura usando la frecuencia de muestreo configurada.
+    pub async fn read_fifo(&mut self) -> Result<Vec<IMUData>, String> {
+        if !self.is_connected {
+            return Err("IMU no conectado".to_string());
+        }
 
-        let temperature = 23.0 + (rand::random::<f64>() - 0.5) * 5.0;
+        let device = self.device.as_mut().ok_or("IMU sin dispositivo")?;
+        let samples = device.read_fifo().map_err(|e| e.to_string())?;
+        let now = chrono::Utc::now().timestamp_millis() as f64 / 1000.0;
+        let period = 1.0 / self.config.sample_rate.max(1) as f64;
+        let count = samples.len();
+
+        Ok(samples
+            .into_iter()
+            .enumerate()
+            .map(|(i, sample)| {
+                let timestamp = now - (count - 1 - i) as f64 * period;
+                self.to_imu_data(sample.acceleration, sample.gyroscope, f64::NAN, timestamp)
+            })
+            .collect())
+    }
 
+    fn to_imu_data(
+        &self,
+        acceleration: Vector3,
+        gyroscope: Vector3,
+        temperature: f64,
+        timestamp: f64,
+    ) -> IMUData {
         // Aplicar calibración si está disponible
-        let calibrated_acceleration = if self.calibration_data.is_calibrated {
-            Vector3::new(
-                acceleration.x - self.calibration_data.acceleration_bias.x,
-                acceleration.y - self.calibration_data.acceleration_bias.y,
-                acceleration.z - self.calibration_data.acceleration_bias.z,
+        let (acceleration, gyroscope) = if self.calibration_data.is_calibrated {
+            let accel_bias = &self.calibration_data.acceleration_bias;
+            let gyro_bias = &self.calibration_data.gyroscope_bias;
+            (
+                Vector3::new(
+                    acceleration.x - accel_bias.x,
+                    acceleration.y - accel_bias.y,
+                    acceleration.z - accel_bias.z,
+                ),
+                Vector3::new(
+                    gyroscope.x - gyro_bias.x,
+                    gyroscope.y - gyro_bias.y,
+                    gyroscope.z - gyro_bias.z,
+                ),
             )
         } else {
-            acceleration
+            (acceleration, gyroscope)
         };
 
-        Ok(IMUData {
-            acceleration: calibrated_acceleration,
+        IMUData {
+            frame_id: self.config.frame_id.clone(),
+            acceleration,
             gyroscope,
-            magnetometer,
+            // El magnetómetro AK8963 del MPU-9250 no se lee todavía
+            magnetometer: Vector3::zero(),
             temperature,
             timestamp,
+        }
+    }
+
+    fn settings(&self) -> Result<Mpu6050Settings, String> {
+        let accel_range =
+            AccelRange::from_sensitivity(self.config.acceleration_scale).ok_or_else(|| {
+                format!(
+                    "acceleration_scale {} no corresponde a ningún rango del MPU",
+                    self.config.acceleration_scale
+                )
+            })?;
+        let gyro_range =
+            GyroRange::from_sensitivity(self.config.gyroscope_scale).ok_or_else(|| {
+                format!(
+                    "gyroscope_scale {} no corresponde a ningún rango del MPU",
+                    self.config.gyroscope_scale
+                )
+            })?;
+
+        Ok(Mpu6050Settings {
+            accel_range,
+            gyro_range,
+            dlpf: self.config.dlpf,
+            sample_rate_hz: self.config.sample_rate,
         })
     }
 
+    #[cfg(target_os = "linux")]
+    fn open_bus(path: &str) -> Result<Box<dyn I2cBus>, String> {
+        let bus = i2c::LinuxI2cBus::open(path).map_err(|e| e.to_string())?;
+        Ok(Box::new(bus))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn open_bus(path: &str) -> Result<Box<dyn I2cBus>, String> {
+        Err(format!("Bus I2C {} no soportado en esta plataforma", path))
+    }
+
     pub async fn calibrate(&mut self, samples: usize) -> Result<(), String> {
         log::info!("🔧 Calibrando IMU ({} muestras)...", samples);
 
@@ -305,7 +579,7 @@ impl IMU {
         self.calibration_data.acceleration_bias = Vector3::new(
             accel_sum.x / samples as f64,
             accel_sum.y / samples as f64,
-            accel_sum.z / samples as f64 - 9.81, // Restar gravedad
+            accel_sum.z / samples as f64 - mpu6050::STANDARD_GRAVITY, // Restar gravedad
         );
 
         self.calibration_data.gyroscope_bias = Vector3::new(
@@ -343,16 +617,16 @@ impl IMU {
         self.calibration_data.is_calibrated
     }
 
-    pub fn get_health(&self) -> super::SensorHealth {
+    pub fn get_health(&self) -> SensorHealth {
         if !self.is_connected {
-            return super::SensorHealth::Disconnected;
+            return SensorHealth::Disconnected;
         }
 
         if !self.is_calibrated() {
-            return super::SensorHealth::Warning("IMU no calibrado".to_string());
+            return SensorHealth::Warning("IMU no calibrado".to_string());
         }
 
-        super::SensorHealth::Healthy
+        SensorHealth::Healthy
     }
 }
 
@@ -360,6 +634,7 @@ impl IMU {
 #[derive(Debug)]
 pub struct Camera {
     config: CameraConfig,
+    source: Option<FrameSource>,
     is_connected: bool,
     frame_count: u64,
 }
@@ -368,16 +643,20 @@ impl Camera {
     pub fn new(config: CameraConfig) -> Self {
         Self {
             config,
+            source: None,
             is_connected: false,
             frame_count: 0,
         }
     }
 
     pub async fn connect(&mut self) -> Result<(), String> {
-        log::info!("🔌 Conectando cámara en {}...", self.config.device_path);
+        log::info!(
+            "🔌 Conectando cámara {:?} en {}...",
+            self.config.source,
+            self.config.device_path
+        );
 
-        // Simular conexión a cámara
-        tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+        self.source = Some(FrameSource::open(&self.config).map_err(|e| e.to_string())?);
 
         self.is_connected = true;
         log::info!(
@@ -391,6 +670,7 @@ impl Camera {
 
     pub async fn disconnect(&mut self) -> Result<(), String> {
         log::info!("🔌 Desconectando cámara...");
+        self.source = None;
         self.is_connected = false;
         Ok(())
     }
@@ -400,32 +680,25 @@ impl Camera {
             return Err("Cámara no conectada".to_string());
         }
 
-        self.frame_count += 1;
-        let timestamp = chrono::Utc::now().timestamp_millis() as f64 / 1000.0;
+        let source = self.source.as_mut().ok_or("Cámara sin fuente de frames")?;
+        let frame = source.next_frame().await.map_err(|e| e.to_string())?;
 
-        // Generar frame simulado (en implementación real usaría v4l-rs o similar)
-        let frame_size = (self.config.width * self.config.height * 3) as usize;
-        let mut frame_data = Vec::with_capacity(frame_size);
+        // La decodificación JPEG es costosa: fuera del hilo del runtime
+        let timestamp = frame.timestamp;
+        let image = tokio::task::spawn_blocking(move || pixel_format::decode_to_rgb(&frame))
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| e.to_string())?;
 
-        // Generar patrón de prueba (gradiente + ruido)
-        for y in 0..self.config.height {
-            for x in 0..self.config.width {
-                let r = ((x as f32 / self.config.width as f32) * 255.0) as u8;
-                let g = ((y as f32 / self.config.height as f32) * 255.0) as u8;
-                let b = 128u8;
-
-                frame_data.push(r);
-                frame_data.push(g);
-                frame_data.push(b);
-            }
-        }
+        self.frame_count += 1;
 
         Ok(CameraData {
-            frame_id: format!("frame_{}", self.frame_count),
-            width: self.config.width,
-            height: self.config.height,
+            frame_id: self.config.frame_id.clone(),
+            sequence: self.frame_count,
+            width: image.width,
+            height: image.height,
             channels: 3,
-            data: frame_data,
+            data: image.data,
             timestamp,
         })
     }
@@ -438,15 +711,116 @@ impl Camera {
         self.frame_count
     }
 
-    pub fn get_health(&self) -> super::SensorHealth {
+    pub fn get_health(&self) -> SensorHealth {
         if !self.is_connected {
-            return super::SensorHealth::Disconnected;
+            return SensorHealth::Disconnected;
         }
 
         if self.frame_count == 0 {
-            return super::SensorHealth::Warning("No frames captured".to_string());
+            return SensorHealth::Warning("No frames captured".to_string());
         }
 
-        super::SensorHealth::Healthy
+        SensorHealth::Healthy
+    }
+}
+
+pub(crate) fn period_from_rate(rate: u32) -> Duration {
+    Duration::from_secs_f64(1.0 / rate.max(1) as f64)
+}
+
+#[async_trait]
+impl SensorDriver for Lidar {
+    fn kind(&self) -> SensorKind {
+        SensorKind::Lidar
+    }
+
+    async fn connect(&mut self) -> Result<(), String> {
+        Lidar::connect(self).await
+    }
+
+    async fn disconnect(&mut self) -> Result<(), String> {
+        Lidar::disconnect(self).await
+    }
+
+    async fn read(&mut self) -> Result<SensorReading, String> {
+        self.read_scan().await.map(SensorReading::Lidar)
+    }
+
+    fn is_connected(&self) -> bool {
+        self.is_connected
+    }
+
+    fn health(&self) -> SensorHealth {
+        self.get_health()
+    }
+
+    fn sample_period(&self) -> Duration {
+        period_from_rate(self.config.sample_rate)
+    }
+}
+
+#[async_trait]
+impl SensorDriver for IMU {
+    fn kind(&self) -> SensorKind {
+        SensorKind::Imu
+    }
+
+    async fn connect(&mut self) -> Result<(), String> {
+        IMU::connect(self).await
+    }
+
+    async fn disconnect(&mut self) -> Result<(), String> {
+        IMU::disconnect(self).await
+    }
+
+    async fn read(&mut self) -> Result<SensorReading, String> {
+        self.read_data().await.map(SensorReading::Imu)
+    }
+
+    async fn calibrate(&mut self) -> Result<(), String> {
+        IMU::calibrate(self, IMU_CALIBRATION_SAMPLES).await
+    }
+
+    fn is_connected(&self) -> bool {
+        self.is_connected
+    }
+
+    fn health(&self) -> SensorHealth {
+        self.get_health()
+    }
+
+    fn sample_period(&self) -> Duration {
+        period_from_rate(self.config.sample_rate)
+    }
+}
+
+#[async_trait]
+impl SensorDriver for Camera {
+    fn kind(&self) -> SensorKind {
+        SensorKind::Camera
+    }
+
+    async fn connect(&mut self) -> Result<(), String> {
+        Camera::connect(self).await
+    }
+
+    async fn disconnect(&mut self) -> Result<(), String> {
+        Camera::disconnect(self).await
+    }
+
+    async fn read(&mut self) -> Result<SensorReading, String> {
+        self.capture_frame().await.map(SensorReading::Camera)
+    }
+
+    fn is_connected(&self) -> bool {
+        self.is_connected
+    }
+
+    fn health(&self) -> SensorHealth {
+        self.get_health()
+    }
+
+    fn sample_period(&self) -> Duration {
+        period_from_rate(self.config.framerate)
     }
 }
@@ -0,0 +1,501 @@
+//! Driver a nivel de registro para IMUs InvenSense MPU-6050 / MPU-6500 / MPU-9250
+use super::i2c::I2cBus;
+use crate::sensors::Vector3;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+pub const STANDARD_GRAVITY: f64 = 9.80665; // m/s²
+
+// Mapa de registros (comunes a toda la familia)
+pub const SMPLRT_DIV: u8 = 0x19;
+pub const CONFIG: u8 = 0x1A;
+pub const GYRO_CONFIG: u8 = 0x1B;
+pub const ACCEL_CONFIG: u8 = 0x1C;
+pub const ACCEL_CONFIG_2: u8 = 0x1D; // Solo MPU-6500/9250
+pub const FIFO_EN: u8 = 0x23;
+pub const INT_STATUS: u8 = 0x3A;
+pub const ACCEL_XOUT_H: u8 = 0x3B;
+pub const USER_CTRL: u8 = 0x6A;
+pub const PWR_MGMT_1: u8 = 0x6B;
+pub const FIFO_COUNT_H: u8 = 0x72;
+pub const FIFO_R_W: u8 = 0x74;
+pub const WHO_AM_I: u8 = 0x75;
+
+// Bits
+const PWR_DEVICE_RESET: u8 = 0x80;
+const PWR_SLEEP: u8 = 0x40;
+const PWR_CLKSEL_PLL_X: u8 = 0x01;
+const USER_CTRL_FIFO_EN: u8 = 0x40;
+const USER_CTRL_FIFO_RESET: u8 = 0x04;
+const FIFO_EN_GYRO_ACCEL: u8 = 0x78; // XG | YG | ZG | ACCEL
+const INT_STATUS_FIFO_OFLOW: u8 = 0x10;
+
+/// Bytes por muestra en la FIFO: acelerómetro (6) + giroscopio (6)
+pub const FIFO_SAMPLE_SIZE: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mpu6050Model {
+    Mpu6050,
+    Mpu6500,
+    Mpu9250,
+    Mpu9255,
+}
+
+impl Mpu6050Model {
+    pub fn from_who_am_i(value: u8) -> Option<Self> {
+        match value {
+            0x68 => Some(Self::Mpu6050),
+            0x70 => Some(Self::Mpu6500),
+            0x71 => Some(Self::Mpu9250),
+            0x73 => Some(Self::Mpu9255),
+            _ => None,
+        }
+    }
+
+    fn fifo_capacity(&self) -> usize {
+        match self {
+            Self::Mpu6050 => 1024,
+            _ => 512,
+        }
+    }
+
+    fn temperature_celsius(&self, raw: i16) -> f64 {
+        match self {
+            Self::Mpu6050 => raw as f64 / 340.0 + 36.53,
+            _ => raw as f64 / 333.87 + 21.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AccelRange {
+    G2,
+    G4,
+    G8,
+    G16,
+}
+
+impl AccelRange {
+    /// Sensibilidad en LSB/g
+    pub fn sensitivity(&self) -> f64 {
+        match self {
+            Self::G2 => 16384.0,
+            Self::G4 => 8192.0,
+            Self::G8 => 4096.0,
+            Self::G16 => 2048.0,
+        }
+    }
+
+    /// Rango cuya sensibilidad coincide con `scale` (LSB/g)
+    pub fn from_sensitivity(scale: f64) -> Option<Self> {
+        [Self::G2, Self::G4, Self::G8, Self::G16]
+            .into_iter()
+            .find(|range| (range.sensitivity() - scale).abs() < 1e-6)
+    }
+
+    fn bits(&self) -> u8 {
+        (*self as u8) << 3
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GyroRange {
+    Dps250,
+    Dps500,
+    Dps1000,
+    Dps2000,
+}
+
+impl GyroRange {
+    /// Sensibilidad en LSB/(°/s)
+    pub fn sensitivity(&self) -> f64 {
+        match self {
+            Self::Dps250 => 131.0,
+            Self::Dps500 => 65.5,
+            Self::Dps1000 => 32.8,
+            Self::Dps2000 => 16.4,
+        }
+    }
+
+    /// Rango cuya sensibilidad coincide con `scale` (LSB/°/s)
+    pub fn from_sensitivity(scale: f64) -> Option<Self> {
+        [Self::Dps250, Self::Dps500, Self::Dps1000, Self::Dps2000]
+            .into_iter()
+            .find(|range| (range.sensitivity() - scale).abs() < 1e-6)
+    }
+
+    fn bits(&self) -> u8 {
+        (*self as u8) << 3
+    }
+}
+
+/// Ancho de banda del filtro paso bajo digital (DLPF_CFG)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DlpfBandwidth {
+    Hz260,
+    Hz184,
+    Hz94,
+    Hz44,
+    Hz21,
+    Hz10,
+    Hz5,
+}
+
+impl DlpfBandwidth {
+    fn bits(&self) -> u8 {
+        *self as u8
+    }
+
+    /// Frecuencia interna del giroscopio antes de SMPLRT_DIV
+    fn internal_rate_hz(&self) -> f64 {
+        match self {
+            Self::Hz260 => 8000.0,
+            _ => 1000.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Mpu6050Settings {
+    pub accel_range: AccelRange,
+    pub gyro_range: GyroRange,
+    pub dlpf: DlpfBandwidth,
+    pub sample_rate_hz: u32,
+}
+
+impl Default for Mpu6050Settings {
+    fn default() -> Self {
+        Self {
+            accel_range: AccelRange::G2,
+            gyro_range: GyroRange::Dps250,
+            dlpf: DlpfBandwidth::Hz44,
+            sample_rate_hz: 100,
+        }
+    }
+}
+
+/// Muestra convertida a unidades físicas
+#[derive(Debug, Clone)]
+pub struct Mpu6050Sample {
+    pub acceleration: Vector3, // m/s²
+    pub gyroscope: Vector3,    // rad/s
+    pub temperature: Option<f64>,
+}
+
+#[derive(Debug)]
+pub struct Mpu6050<B: I2cBus> {
+    bus: B,
+    address: u8,
+    model: Option<Mpu6050Model>,
+    settings: Mpu6050Settings,
+    fifo_enabled: bool,
+}
+
+impl<B: I2cBus> Mpu6050<B> {
+    pub fn new(bus: B, address: u8) -> Self {
+        Self {
+            bus,
+            address,
+            model: None,
+            settings: Mpu6050Settings::default(),
+            fifo_enabled: false,
+        }
+    }
+
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    pub fn model(&self) -> Option<Mpu6050Model> {
+        self.model
+    }
+
+    pub fn settings(&self) -> &Mpu6050Settings {
+        &self.settings
+    }
+
+    pub fn is_fifo_enabled(&self) -> bool {
+        self.fifo_enabled
+    }
+
+    /// Lee WHO_AM_I y verifica que el dispositivo pertenece a la familia MPU
+    pub fn probe(&mut self) -> Result<Mpu6050Model> {
+        let who_am_i = self.bus.read_register(self.address, WHO_AM_I)?;
+        let model = Mpu6050Model::from_who_am_i(who_am_i).ok_or_else(|| {
+            anyhow::anyhow!(
+                "WHO_AM_I inesperado en 0x{:02X}: 0x{:02X}",
+                self.address,
+                who_am_i
+            )
+        })?;
+
+        self.model = Some(model);
+        Ok(model)
+    }
+
+    /// Reinicia todos los registros. El llamador debe esperar ~100 ms antes de `configure`.
+    pub fn reset(&mut self) -> Result<()> {
+        self.fifo_enabled = false;
+        self.bus
+            .write_register(self.address, PWR_MGMT_1, PWR_DEVICE_RESET)
+    }
+
+    /// Sale de reposo usando el PLL del giroscopio X como reloj
+    pub fn wake(&mut self) -> Result<()> {
+        self.bus
+            .write_register(self.address, PWR_MGMT_1, PWR_CLKSEL_PLL_X)
+    }
+
+    pub fn sleep(&mut self) -> Result<()> {
+        self.bus
+            .write_register(self.address, PWR_MGMT_1, PWR_SLEEP | PWR_CLKSEL_PLL_X)
+    }
+
+    pub fn configure(&mut self, settings: Mpu6050Settings) -> Result<()> {
+        if settings.sample_rate_hz == 0 {
+            return Err(anyhow::anyhow!(
+                "La frecuencia de muestreo debe ser positiva"
+            ));
+        }
+
+        self.wake()?;
+
+        let divider = (settings.dlpf.internal_rate_hz() / settings.sample_rate_hz as f64 - 1.0)
+            .round()
+            .clamp(0.0, 255.0) as u8;
+
+        self.bus
+            .write_register(self.address, CONFIG, settings.dlpf.bits())?;
+        self.bus.write_register(self.address, SMPLRT_DIV, divider)?;
+        self.bus
+            .write_register(self.address, GYRO_CONFIG, settings.gyro_range.bits())?;
+        self.bus
+            .write_register(self.address, ACCEL_CONFIG, settings.accel_range.bits())?;
+
+        if !matches!(self.model, Some(Mpu6050Model::Mpu6050) | None) {
+            // En la familia 6500 el DLPF del acelerómetro es independiente
+            self.bus
+                .write_register(self.address, ACCEL_CONFIG_2, settings.dlpf.bits())?;
+        }
+
+        self.settings = settings;
+        Ok(())
+    }
+
+    /// Frecuencia de muestreo efectiva tras aplicar SMPLRT_DIV
+    pub fn effective_sample_rate(&mut self) -> Result<f64> {
+        let divider = self.bus.read_register(self.address, SMPLRT_DIV)?;
+        Ok(self.settings.dlpf.internal_rate_hz() / (1.0 + divider as f64))
+    }
+
+    /// Lectura en ráfaga de ACCEL_XOUT_H..GYRO_ZOUT_L (14 bytes)
+    pub fn read_sample(&mut self) -> Result<Mpu6050Sample> {
+        let mut buffer = [0u8; 14];
+        self.bus
+            .read_registers(self.address, ACCEL_XOUT_H, &mut buffer)?;
+
+        let raw = |i: usize| i16::from_be_bytes([buffer[i], buffer[i + 1]]);
+        let model = self.model.unwrap_or(Mpu6050Model::Mpu6050);
+
+        Ok(Mpu6050Sample {
+            acceleration: self.scale_acceleration(raw(0), raw(2), raw(4)),
+            temperature: Some(model.temperature_celsius(raw(6))),
+            gyroscope: self.scale_gyroscope(raw(8), raw(10), raw(12)),
+        })
+    }
+
+    /// Activa la FIFO con acelerómetro y giroscopio
+    pub fn enable_fifo(&mut self) -> Result<()> {
+        self.bus.write_register(self.address, USER_CTRL, 0)?;
+        self.bus.write_register(self.address, FIFO_EN, 0)?;
+        self.bus
+            .write_register(self.address, USER_CTRL, USER_CTRL_FIFO_RESET)?;
+        self.bus
+            .write_register(self.address, FIFO_EN, FIFO_EN_GYRO_ACCEL)?;
+        self.bus
+            .write_register(self.address, USER_CTRL, USER_CTRL_FIFO_EN)?;
+        self.fifo_enabled = true;
+        Ok(())
+    }
+
+    pub fn disable_fifo(&mut self) -> Result<()> {
+        self.bus.write_register(self.address, FIFO_EN, 0)?;
+        self.bus.write_register(self.address, USER_CTRL, 0)?;
+        self.fifo_enabled = false;
+        Ok(())
+    }
+
+    pub fn fifo_count(&mut self) -> Result<usize> {
+        let mut buffer = [0u8; 2];
+        self.bus
+            .read_registers(self.address, FIFO_COUNT_H, &mut buffer)?;
+        Ok(u16::from_be_bytes(buffer) as usize)
+    }
+
+    /// Vacía la FIFO y devuelve todas las muestras completas pendientes.
+    ///
+    /// Si la FIFO desbordó, los datos ya no están alineados: se reinicia y se
+    /// devuelve un error para que el llamador lo contabilice.
+    pub fn read_fifo(&mut self) -> Result<Vec<Mpu6050Sample>> {
+        if !self.fifo_enabled {
+            return Err(anyhow::anyhow!("FIFO no habilitada"));
+        }
+
+        let status = self.bus.read_register(self.address, INT_STATUS)?;
+        let count = self.fifo_count()?;
+        let capacity = self.model.unwrap_or(Mpu6050Model::Mpu6050).fifo_capacity();
+
+        if status & INT_STATUS_FIFO_OFLOW != 0 || count >= capacity {
+            self.bus.write_register(
+                self.address,
+                USER_CTRL,
+                USER_CTRL_FIFO_EN | USER_CTRL_FIFO_RESET,
+            )?;
+            return Err(anyhow::anyhow!("Desbordamiento de FIFO ({} bytes)", count));
+        }
+
+        let complete = count / FIFO_SAMPLE_SIZE;
+        let mut samples = Vec::with_capacity(complete);
+        let mut buffer = [0u8; FIFO_SAMPLE_SIZE];
+
+        for _ in 0..complete {
+            self.bus
+                .read_registers(self.address, FIFO_R_W, &mut buffer)?;
+            let raw = |i: usize| i16::from_be_bytes([buffer[i], buffer[i + 1]]);
+
+            samples.push(Mpu6050Sample {
+                acceleration: self.scale_acceleration(raw(0), raw(2), raw(4)),
+                gyroscope: self.scale_gyroscope(raw(6), raw(8), raw(10)),
+                temperature: None,
+            });
+        }
+
+        Ok(samples)
+    }
+
+    fn scale_acceleration(&self, x: i16, y: i16, z: i16) -> Vector3 {
+        let factor = STANDARD_GRAVITY / self.settings.accel_range.sensitivity();
+        Vector3::new(x as f64 * factor, y as f64 * factor, z as f64 * factor)
+    }
+
+    fn scale_gyroscope(&self, x: i16, y: i16, z: i16) -> Vector3 {
+        let factor = 1.0_f64.to_radians() / self.settings.gyro_range.sensitivity();
+        Vector3::new(x as f64 * factor, y as f64 * factor, z as f64 * factor)
+    }
+}
+
+/// Bus simulado con un MPU-6050 en reposo (1 g en Z, 25 °C)
+pub fn simulated_bus(address: u8) -> super::i2c::MockI2cBus {
+    let bus = super::i2c::MockI2cBus::new();
+    bus.set_register(address, WHO_AM_I, 0x68);
+
+    let temperature_raw = ((25.0 - 36.53) * 340.0) as i16;
+    let mut burst = [0u8; 14];
+    burst[4..6].copy_from_slice(&16384i16.to_be_bytes());
+    burst[6..8].copy_from_slice(&temperature_raw.to_be_bytes());
+    bus.set_registers(address, ACCEL_XOUT_H, &burst);
+
+    bus
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::i2c::MockI2cBus;
+    use super::*;
+
+    const ADDRESS: u8 = 0x68;
+
+    fn queue_fifo_samples(bus: &MockI2cBus, samples: &[[i16; 6]]) {
+        let mut bytes = Vec::new();
+        for sample in samples {
+            for value in sample {
+                bytes.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+        bus.push_stream(ADDRESS, FIFO_R_W, &bytes);
+        let count = bus.stream_len(ADDRESS, FIFO_R_W) as u16;
+        bus.set_registers(ADDRESS, FIFO_COUNT_H, &count.to_be_bytes());
+    }
+
+    #[test]
+    fn test_probe_rejects_unknown_device() {
+        let bus = MockI2cBus::new();
+        bus.set_register(ADDRESS, WHO_AM_I, 0x12);
+
+        let mut imu = Mpu6050::new(bus, ADDRESS);
+        assert!(imu.probe().is_err());
+    }
+
+    #[test]
+    fn test_configure_writes_range_and_dlpf() {
+        let bus = simulated_bus(ADDRESS);
+        let mut imu = Mpu6050::new(bus.clone(), ADDRESS);
+        assert_eq!(imu.probe().unwrap(), Mpu6050Model::Mpu6050);
+
+        imu.configure(Mpu6050Settings {
+            accel_range: AccelRange::G8,
+            gyro_range: GyroRange::Dps1000,
+            dlpf: DlpfBandwidth::Hz44,
+            sample_rate_hz: 200,
+        })
+        .unwrap();
+
+        assert_eq!(bus.register(ADDRESS, PWR_MGMT_1), Some(0x01));
+        assert_eq!(bus.register(ADDRESS, CONFIG), Some(0x03));
+        assert_eq!(bus.register(ADDRESS, SMPLRT_DIV), Some(4));
+        assert_eq!(bus.register(ADDRESS, GYRO_CONFIG), Some(0x10));
+        assert_eq!(bus.register(ADDRESS, ACCEL_CONFIG), Some(0x10));
+        assert!((imu.effective_sample_rate().unwrap() - 200.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_burst_read_scales_to_si_units() {
+        let bus = simulated_bus(ADDRESS);
+        // 250 °/s a fondo de escala en Z
+        bus.set_registers(ADDRESS, ACCEL_XOUT_H + 12, &32750i16.to_be_bytes());
+
+        let mut imu = Mpu6050::new(bus, ADDRESS);
+        imu.probe().unwrap();
+        imu.configure(Mpu6050Settings::default()).unwrap();
+
+        let sample = imu.read_sample().unwrap();
+        assert!((sample.acceleration.z - STANDARD_GRAVITY).abs() < 1e-9);
+        assert!((sample.gyroscope.z - 250.0_f64.to_radians()).abs() < 1e-3);
+        assert!((sample.temperature.unwrap() - 25.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_fifo_returns_complete_samples() {
+        let bus = simulated_bus(ADDRESS);
+        let mut imu = Mpu6050::new(bus.clone(), ADDRESS);
+        imu.probe().unwrap();
+        imu.configure(Mpu6050Settings::default()).unwrap();
+        imu.enable_fifo().unwrap();
+
+        queue_fifo_samples(
+            &bus,
+            &[[0, 0, 16384, 0, 0, 131], [0, 0, -16384, 0, 0, -131]],
+        );
+
+        let samples = imu.read_fifo().unwrap();
+        assert_eq!(samples.len(), 2);
+        assert!((samples[0].acceleration.z - STANDARD_GRAVITY).abs() < 1e-9);
+        assert!((samples[1].gyroscope.z + 1.0_f64.to_radians()).abs() < 1e-9);
+        assert_eq!(bus.stream_len(ADDRESS, FIFO_R_W), 0);
+    }
+
+    #[test]
+    fn test_fifo_overflow_resets_fifo() {
+        let bus = simulated_bus(ADDRESS);
+        let mut imu = Mpu6050::new(bus.clone(), ADDRESS);
+        imu.probe().unwrap();
+        imu.enable_fifo().unwrap();
+        bus.set_register(ADDRESS, INT_STATUS, INT_STATUS_FIFO_OFLOW);
+
+        assert!(imu.read_fifo().is_err());
+        assert_eq!(
+            bus.register(ADDRESS, USER_CTRL),
+            Some(USER_CTRL_FIFO_EN | USER_CTRL_FIFO_RESET)
+        );
+    }
+}
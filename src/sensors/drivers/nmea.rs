@@ -0,0 +1,226 @@
+//! Parser de sentencias NMEA 0183 (GGA, RMC, GSA, VTG) de cualquier talker
+use anyhow::Result;
+
+/// Nudos a metros por segundo
+const KNOTS_TO_MS: f64 = 0.514_444;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NmeaSentence {
+    Gga(Gga),
+    Rmc(Rmc),
+    Gsa(Gsa),
+    Vtg(Vtg),
+}
+
+/// Posición y calidad del fix
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Gga {
+    pub time: Option<f64>, // segundos desde medianoche UTC
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// 0 sin fix, 1 GPS, 2 DGPS, 4 RTK fijo, 5 RTK flotante
+    pub quality: u8,
+    pub satellites: u8,
+    pub hdop: Option<f64>,
+    pub altitude: Option<f64>, // sobre el nivel del mar
+    pub geoid_separation: Option<f64>,
+}
+
+/// Mínimo recomendado: posición, velocidad, rumbo y fecha
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Rmc {
+    pub time: Option<f64>,
+    pub valid: bool,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub speed: Option<f64>,            // m/s
+    pub course: Option<f64>,           // grados desde el norte verdadero
+    pub date: Option<(u32, u32, i32)>, // (día, mes, año)
+}
+
+/// Dilución de precisión y satélites usados
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Gsa {
+    /// 1 sin fix, 2 fix 2D, 3 fix 3D
+    pub fix_type: u8,
+    pub satellites: Vec<u16>,
+    pub pdop: Option<f64>,
+    pub hdop: Option<f64>,
+    pub vdop: Option<f64>,
+}
+
+/// Rumbo y velocidad sobre el suelo
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Vtg {
+    pub course: Option<f64>, // grados desde el norte verdadero
+    pub speed: Option<f64>,  // m/s
+}
+
+/// Interpreta una línea `$..*hh`. Devuelve `Ok(None)` para tipos de
+/// sentencia no soportados (GSV, GLL, ...).
+pub fn parse_sentence(line: &str) -> Result<Option<NmeaSentence>> {
+    let line = line.trim();
+    let body = line
+        .strip_prefix('$')
+        .ok_or_else(|| anyhow::anyhow!("Sentencia NMEA sin '$': {}", line))?;
+
+    let body = match body.split_once('*') {
+        Some((body, checksum)) => {
+            let expected = u8::from_str_radix(checksum, 16)
+                .map_err(|_| anyhow::anyhow!("Checksum NMEA ilegible: {}", checksum))?;
+            let actual = body.bytes().fold(0u8, |acc, b| acc ^ b);
+            if actual != expected {
+                return Err(anyhow::anyhow!(
+                    "Checksum NMEA incorrecto: {:02X} != {:02X}",
+                    actual,
+                    expected
+                ));
+            }
+            body
+        }
+        None => body,
+    };
+
+    let fields: Vec<&str> = body.split(',').collect();
+    let address = fields[0];
+    if address.len() < 5 {
+        return Err(anyhow::anyhow!("Dirección NMEA inválida: {}", address));
+    }
+    let field = |index: usize| fields.get(index).copied().unwrap_or("");
+
+    // Los dos primeros caracteres son el talker (GP, GN, GL, GA, BD...)
+    let sentence = match &address[address.len() - 3..] {
+        "GGA" => NmeaSentence::Gga(Gga {
+            time: parse_time(field(1)),
+            latitude: parse_coordinate(field(2), field(3)),
+            longitude: parse_coordinate(field(4), field(5)),
+            quality: field(6).parse().unwrap_or(0),
+            satellites: field(7).parse().unwrap_or(0),
+            hdop: field(8).parse().ok(),
+            altitude: field(9).parse().ok(),
+            geoid_separation: field(11).parse().ok(),
+        }),
+        "RMC" => NmeaSentence::Rmc(Rmc {
+            time: parse_time(field(1)),
+            valid: field(2) == "A",
+            latitude: parse_coordinate(field(3), field(4)),
+            longitude: parse_coordinate(field(5), field(6)),
+            speed: field(7)
+                .parse::<f64>()
+                .ok()
+                .map(|knots| knots * KNOTS_TO_MS),
+            course: field(8).parse().ok(),
+            date: parse_date(field(9)),
+        }),
+        "GSA" => NmeaSentence::Gsa(Gsa {
+            fix_type: field(2).parse().unwrap_or(1),
+            satellites: (3..15).filter_map(|i| field(i).parse().ok()).collect(),
+            pdop: field(15).parse().ok(),
+            hdop: field(16).parse().ok(),
+            vdop: field(17).parse().ok(),
+        }),
+        "VTG" => NmeaSentence::Vtg(Vtg {
+            course: field(1).parse().ok(),
+            // Preferir km/h (campo 7) si está; si no, nudos (campo 5)
+            speed: field(7)
+                .parse::<f64>()
+                .ok()
+                .map(|kmh| kmh / 3.6)
+                .or_else(|| field(5).parse::<f64>().ok().map(|k| k * KNOTS_TO_MS)),
+        }),
+        _ => return Ok(None),
+    };
+
+    Ok(Some(sentence))
+}
+
+/// `ddmm.mmmm` + hemisferio a grados decimales
+fn parse_coordinate(value: &str, hemisphere: &str) -> Option<f64> {
+    let raw: f64 = value.parse().ok()?;
+    let degrees = (raw / 100.0).trunc();
+    let decimal = degrees + (raw - degrees * 100.0) / 60.0;
+    match hemisphere {
+        "N" | "E" => Some(decimal),
+        "S" | "W" => Some(-decimal),
+        _ => None,
+    }
+}
+
+/// `hhmmss.ss` a segundos desde medianoche
+fn parse_time(value: &str) -> Option<f64> {
+    if value.len() < 6 {
+        return None;
+    }
+    let hours: f64 = value.get(0..2)?.parse().ok()?;
+    let minutes: f64 = value.get(2..4)?.parse().ok()?;
+    let seconds: f64 = value.get(4..)?.parse().ok()?;
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
+/// `ddmmyy` a (día, mes, año)
+fn parse_date(value: &str) -> Option<(u32, u32, i32)> {
+    if value.len() != 6 {
+        return None;
+    }
+    let day = value.get(0..2)?.parse().ok()?;
+    let month = value.get(2..4)?.parse().ok()?;
+    let year: i32 = value.get(4..6)?.parse().ok()?;
+    Some((day, month, 2000 + year))
+}
+
+/// Añade `$` y el checksum a un cuerpo de sentencia
+pub fn with_checksum(body: &str) -> String {
+    let checksum = body.bytes().fold(0u8, |acc, b| acc ^ b);
+    format!("${}*{:02X}\r\n", body, checksum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_neo6m_epoch() {
+        let gga = "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47";
+        let Some(NmeaSentence::Gga(gga)) = parse_sentence(gga).unwrap() else {
+            panic!("no es GGA");
+        };
+        assert!((gga.latitude.unwrap() - 48.1173).abs() < 1e-6);
+        assert!((gga.longitude.unwrap() - 11.516_666).abs() < 1e-6);
+        assert_eq!(gga.quality, 1);
+        assert_eq!(gga.satellites, 8);
+        assert_eq!(gga.time, Some(12.0 * 3600.0 + 35.0 * 60.0 + 19.0));
+
+        let rmc = "$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W*6A";
+        let Some(NmeaSentence::Rmc(rmc)) = parse_sentence(rmc).unwrap() else {
+            panic!("no es RMC");
+        };
+        assert!(rmc.valid);
+        assert!((rmc.speed.unwrap() - 22.4 * KNOTS_TO_MS).abs() < 1e-9);
+        assert_eq!(rmc.date, Some((23, 3, 2094)));
+
+        let gsa = "$GPGSA,A,3,04,05,,09,12,,,24,,,,,2.5,1.3,2.1*39";
+        let Some(NmeaSentence::Gsa(gsa)) = parse_sentence(gsa).unwrap() else {
+            panic!("no es GSA");
+        };
+        assert_eq!(gsa.fix_type, 3);
+        assert_eq!(gsa.satellites, vec![4, 5, 9, 12, 24]);
+        assert_eq!(gsa.vdop, Some(2.1));
+
+        let vtg = with_checksum("GNVTG,054.7,T,034.4,M,005.5,N,010.2,K,A");
+        let Some(NmeaSentence::Vtg(vtg)) = parse_sentence(&vtg).unwrap() else {
+            panic!("no es VTG");
+        };
+        assert_eq!(vtg.course, Some(54.7));
+        assert!((vtg.speed.unwrap() - 10.2 / 3.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rejects_bad_checksum_and_skips_unknown() {
+        assert!(parse_sentence(
+            "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*48"
+        )
+        .is_err());
+        let gsv = with_checksum("GPGSV,1,1,01,04,77,210,45");
+        assert_eq!(parse_sentence(&gsv).unwrap(), None);
+    }
+}
@@ -0,0 +1,245 @@
+//! Formatos de píxel de cámara y conversión a RGB24
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PixelFormat {
+    Mjpeg,
+    Yuyv,
+    Rgb24,
+    Png,
+}
+
+impl PixelFormat {
+    /// Interpreta el código FourCC de `CameraConfig::format` ("MJPG", "YUYV", "RGB3")
+    pub fn from_fourcc_str(fourcc: &str) -> Option<Self> {
+        match fourcc.to_ascii_uppercase().as_str() {
+            "MJPG" | "MJPEG" | "JPEG" => Some(Self::Mjpeg),
+            "YUYV" | "YUY2" => Some(Self::Yuyv),
+            "RGB3" | "RGB24" => Some(Self::Rgb24),
+            _ => None,
+        }
+    }
+
+    pub fn fourcc(&self) -> u32 {
+        let code = match self {
+            Self::Mjpeg => b"MJPG",
+            Self::Yuyv => b"YUYV",
+            Self::Rgb24 => b"RGB3",
+            Self::Png => b"PNG ",
+        };
+        u32::from_le_bytes(*code)
+    }
+
+    pub fn from_fourcc(fourcc: u32) -> Option<Self> {
+        [Self::Mjpeg, Self::Yuyv, Self::Rgb24]
+            .into_iter()
+            .find(|format| format.fourcc() == fourcc)
+    }
+
+    /// Tamaño de un frame sin comprimir, `None` para formatos comprimidos
+    pub fn frame_size(&self, width: u32, height: u32) -> Option<usize> {
+        let pixels = width as usize * height as usize;
+        match self {
+            Self::Yuyv => Some(pixels * 2),
+            Self::Rgb24 => Some(pixels * 3),
+            Self::Mjpeg | Self::Png => None,
+        }
+    }
+}
+
+/// Frame tal y como lo entrega la fuente, antes de decodificar
+#[derive(Debug, Clone)]
+pub struct RawFrame {
+    pub format: PixelFormat,
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+    pub sequence: u64,
+    pub timestamp: f64, // segundos Unix
+}
+
+/// Imagen RGB24 decodificada
+#[derive(Debug, Clone)]
+pub struct RgbImage {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+pub fn decode_to_rgb(frame: &RawFrame) -> Result<RgbImage> {
+    match frame.format {
+        PixelFormat::Mjpeg => decode_jpeg(&frame.data),
+        PixelFormat::Png => decode_png(&frame.data),
+        PixelFormat::Yuyv => Ok(RgbImage {
+            width: frame.width,
+            height: frame.height,
+            data: yuyv_to_rgb(&frame.data, frame.width, frame.height)?,
+        }),
+        PixelFormat::Rgb24 => {
+            let expected = frame.width as usize * frame.height as usize * 3;
+            if frame.data.len() < expected {
+                return Err(anyhow::anyhow!(
+                    "Frame RGB24 incompleto: {} de {} bytes",
+                    frame.data.len(),
+                    expected
+                ));
+            }
+            Ok(RgbImage {
+                width: frame.width,
+                height: frame.height,
+                data: frame.data[..expected].to_vec(),
+            })
+        }
+    }
+}
+
+/// Conversión YUV 4:2:2 (Y0 U Y1 V) a RGB24 con coeficientes BT.601
+pub fn yuyv_to_rgb(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>> {
+    let pixels = width as usize * height as usize;
+    if data.len() < pixels * 2 {
+        return Err(anyhow::anyhow!(
+            "Frame YUYV incompleto: {} de {} bytes",
+            data.len(),
+            pixels * 2
+        ));
+    }
+
+    let mut rgb = Vec::with_capacity(pixels * 3);
+    for chunk in data[..pixels * 2].chunks_exact(4) {
+        let (y0, u, y1, v) = (chunk[0], chunk[1], chunk[2], chunk[3]);
+        rgb.extend_from_slice(&yuv_to_rgb(y0, u, v));
+        rgb.extend_from_slice(&yuv_to_rgb(y1, u, v));
+    }
+
+    Ok(rgb)
+}
+
+fn yuv_to_rgb(y: u8, u: u8, v: u8) -> [u8; 3] {
+    let c = y as i32 - 16;
+    let d = u as i32 - 128;
+    let e = v as i32 - 128;
+
+    let clamp = |value: i32| ((value + 128) >> 8).clamp(0, 255) as u8;
+    [
+        clamp(298 * c + 409 * e),
+        clamp(298 * c - 100 * d - 208 * e),
+        clamp(298 * c + 516 * d),
+    ]
+}
+
+/// Decodifica un JPEG (incluido MJPEG sin tablas Huffman) a RGB24
+pub fn decode_jpeg(data: &[u8]) -> Result<RgbImage> {
+    let mut decoder = jpeg_decoder::Decoder::new(data);
+    let pixels = decoder
+        .decode()
+        .map_err(|e| anyhow::anyhow!("Error decodificando JPEG: {}", e))?;
+    let info = decoder
+        .info()
+        .ok_or_else(|| anyhow::anyhow!("JPEG sin cabecera"))?;
+
+    let data = match info.pixel_format {
+        jpeg_decoder::PixelFormat::RGB24 => pixels,
+        jpeg_decoder::PixelFormat::L8 => pixels.iter().flat_map(|&l| [l, l, l]).collect(),
+        jpeg_decoder::PixelFormat::L16 => pixels
+            .chunks_exact(2)
+            .flat_map(|l| [l[0], l[0], l[0]])
+            .collect(),
+        jpeg_decoder::PixelFormat::CMYK32 => pixels
+            .chunks_exact(4)
+            .flat_map(|p| {
+                let k = p[3] as u16;
+                [
+                    (p[0] as u16 * k / 255) as u8,
+                    (p[1] as u16 * k / 255) as u8,
+                    (p[2] as u16 * k / 255) as u8,
+                ]
+            })
+            .collect(),
+    };
+
+    Ok(RgbImage {
+        width: info.width as u32,
+        height: info.height as u32,
+        data,
+    })
+}
+
+pub fn decode_png(data: &[u8]) -> Result<RgbImage> {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder
+        .read_info()
+        .map_err(|e| anyhow::anyhow!("Error leyendo PNG: {}", e))?;
+
+    let mut buffer = vec![0u8; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buffer)
+        .map_err(|e| anyhow::anyhow!("Error decodificando PNG: {}", e))?;
+    buffer.truncate(info.buffer_size());
+
+    let data = match info.color_type {
+        png::ColorType::Rgb => buffer,
+        png::ColorType::Rgba => buffer
+            .chunks_exact(4)
+            .flat_map(|p| [p[0], p[1], p[2]])
+            .collect(),
+        png::ColorType::Grayscale => buffer.iter().flat_map(|&l| [l, l, l]).collect(),
+        png::ColorType::GrayscaleAlpha => buffer
+            .chunks_exact(2)
+            .flat_map(|p| [p[0], p[0], p[0]])
+            .collect(),
+        png::ColorType::Indexed => {
+            return Err(anyhow::anyhow!("PNG indexado no expandido"));
+        }
+    };
+
+    Ok(RgbImage {
+        width: info.width,
+        height: info.height,
+        data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fourcc_round_trip() {
+        let format = PixelFormat::from_fourcc_str("MJPG").unwrap();
+        assert_eq!(format, PixelFormat::Mjpeg);
+        assert_eq!(PixelFormat::from_fourcc(format.fourcc()), Some(format));
+        assert_eq!(PixelFormat::Yuyv.fourcc(), 0x5659_5559);
+    }
+
+    #[test]
+    fn test_yuyv_to_rgb_gray_levels() {
+        // Negro (Y=16) y blanco (Y=235) sin croma
+        let rgb = yuyv_to_rgb(&[16, 128, 235, 128], 2, 1).unwrap();
+        assert_eq!(rgb, vec![0, 0, 0, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_yuyv_rejects_short_frame() {
+        assert!(yuyv_to_rgb(&[0; 6], 2, 2).is_err());
+    }
+
+    #[test]
+    fn test_png_decoding() {
+        let mut encoded = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut encoded, 2, 1);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header().unwrap();
+            writer
+                .write_image_data(&[255, 0, 0, 255, 0, 0, 255, 128])
+                .unwrap();
+        }
+
+        let image = decode_png(&encoded).unwrap();
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.data, vec![255, 0, 0, 0, 0, 255]);
+    }
+}
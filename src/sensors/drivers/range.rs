@@ -0,0 +1,407 @@
+//! Sensores de distancia de haz único: ultrasonido HC-SR04 e infrarrojo GP2Y0A21YK
+use super::gpio::{Adc, Gpio, SimulatedGp2y0a21, SimulatedHcSr04, SimulatedTarget};
+use super::{period_from_rate, SensorDriver, SensorKind, SensorReading};
+use crate::sensors::{MountingPose, RangeData, RangeRadiation, SensorHealth};
+use crate::transforms::frames;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Eco más largo que devuelve el HC-SR04 antes de rendirse (~38 ms)
+const ECHO_TIMEOUT: Duration = Duration::from_millis(38);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RangeModel {
+    /// Ultrasonido: pulso en `trigger_pin` y anchura del pulso en `echo_pin`
+    HcSr04 { trigger_pin: u32, echo_pin: u32 },
+    /// Infrarrojo analógico leído por un ADS1115 en `i2c_bus`
+    Gp2y0a21 {
+        i2c_bus: String,
+        adc_address: u8,
+        channel: u8,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RangeConfig {
+    pub model: RangeModel,
+    pub min_range: f64,
+    pub max_range: f64,
+    pub field_of_view: f64, // radianes
+    pub mounting: MountingPose,
+    pub sample_rate: u32,
+    pub temperature: f64, // °C, para la velocidad del sonido
+    pub simulated: bool,  // backend en memoria en lugar de GPIO/ADC
+    pub frame_id: String,
+}
+
+impl Default for RangeConfig {
+    fn default() -> Self {
+        Self::hc_sr04(23, 24)
+    }
+}
+
+impl RangeConfig {
+    pub fn hc_sr04(trigger_pin: u32, echo_pin: u32) -> Self {
+        Self {
+            model: RangeModel::HcSr04 {
+                trigger_pin,
+                echo_pin,
+            },
+            min_range: 0.02,
+            max_range: 4.0,
+            field_of_view: 15f64.to_radians(),
+            mounting: MountingPose::default(),
+            sample_rate: 10, // el fabricante pide ≥60 ms entre disparos
+            temperature: 20.0,
+            simulated: false,
+            frame_id: frames::RANGE.to_string(),
+        }
+    }
+
+    pub fn gp2y0a21(i2c_bus: &str, adc_address: u8, channel: u8) -> Self {
+        Self {
+            model: RangeModel::Gp2y0a21 {
+                i2c_bus: i2c_bus.to_string(),
+                adc_address,
+                channel,
+            },
+            min_range: 0.10,
+            max_range: 0.80,
+            field_of_view: 5f64.to_radians(),
+            sample_rate: 25, // una medida cada ~39 ms
+            ..Self::hc_sr04(0, 0)
+        }
+    }
+
+    pub fn radiation(&self) -> RangeRadiation {
+        match self.model {
+            RangeModel::HcSr04 { .. } => RangeRadiation::Ultrasound,
+            RangeModel::Gp2y0a21 { .. } => RangeRadiation::Infrared,
+        }
+    }
+
+    /// Velocidad del sonido en el aire (m/s)
+    pub fn speed_of_sound(&self) -> f64 {
+        331.3 + 0.606 * self.temperature
+    }
+}
+
+#[derive(Debug)]
+enum RangeBackend {
+    Ultrasonic(Box<dyn Gpio>),
+    Infrared(Box<dyn Adc>),
+}
+
+// Driver de distancia
+#[derive(Debug)]
+pub struct RangeSensor {
+    config: RangeConfig,
+    backend: Option<RangeBackend>,
+    is_connected: bool,
+    last_reading: Option<Instant>,
+}
+
+impl RangeSensor {
+    pub fn new(config: RangeConfig) -> Self {
+        Self {
+            config,
+            backend: None,
+            is_connected: false,
+            last_reading: None,
+        }
+    }
+
+    /// Usa un GPIO ya abierto para un HC-SR04
+    pub fn with_gpio(config: RangeConfig, gpio: Box<dyn Gpio>) -> Self {
+        Self {
+            backend: Some(RangeBackend::Ultrasonic(gpio)),
+            ..Self::new(config)
+        }
+    }
+
+    /// Usa un ADC ya abierto para un GP2Y0A21YK
+    pub fn with_adc(config: RangeConfig, adc: Box<dyn Adc>) -> Self {
+        Self {
+            backend: Some(RangeBackend::Infrared(adc)),
+            ..Self::new(config)
+        }
+    }
+
+    /// Sensor sobre un backend simulado; el objetivo devuelto fija la
+    /// distancia del obstáculo
+    pub fn simulated(config: RangeConfig) -> (Self, SimulatedTarget) {
+        let target = SimulatedTarget::default();
+        let backend = Self::simulated_backend(&config, target.clone());
+        let sensor = Self {
+            backend: Some(backend),
+            ..Self::new(config)
+        };
+        (sensor, target)
+    }
+
+    fn simulated_backend(config: &RangeConfig, target: SimulatedTarget) -> RangeBackend {
+        match config.model {
+            RangeModel::HcSr04 {
+                trigger_pin,
+                echo_pin,
+            } => RangeBackend::Ultrasonic(Box::new(SimulatedHcSr04::new(
+                trigger_pin,
+                echo_pin,
+                config.speed_of_sound(),
+                target,
+            ))),
+            RangeModel::Gp2y0a21 { channel, .. } => {
+                RangeBackend::Infrared(Box::new(SimulatedGp2y0a21::new(channel, target)))
+            }
+        }
+    }
+
+    pub async fn connect(&mut self) -> Result<(), String> {
+        log::info!(
+            "🔌 Conectando sensor de distancia {:?}...",
+            self.config.model
+        );
+
+        if self.backend.is_none() {
+            let backend = if self.config.simulated {
+                Self::simulated_backend(&self.config, SimulatedTarget::default())
+            } else {
+                Self::open_backend(&self.config.model)?
+            };
+            self.backend = Some(backend);
+        }
+
+        self.is_connected = true;
+        log::info!("✅ Sensor de distancia conectado");
+        Ok(())
+    }
+
+    pub async fn disconnect(&mut self) -> Result<(), String> {
+        log::info!("🔌 Desconectando sensor de distancia...");
+        self.is_connected = false;
+        Ok(())
+    }
+
+    pub async fn read_range(&mut self) -> Result<RangeData, String> {
+        if !self.is_connected {
+            return Err("Sensor de distancia no conectado".to_string());
+        }
+
+        let measured = match self.backend.take() {
+            Some(RangeBackend::Ultrasonic(mut gpio)) => {
+                let (trigger, echo) = match self.config.model {
+                    RangeModel::HcSr04 {
+                        trigger_pin,
+                        echo_pin,
+                    } => (trigger_pin, echo_pin),
+                    RangeModel::Gp2y0a21 { .. } => {
+                        self.backend = Some(RangeBackend::Ultrasonic(gpio));
+                        return Err("GP2Y0A21 configurado sobre un GPIO".to_string());
+                    }
+                };
+
+                // La medida espera activamente el eco: fuera del runtime
+                let (gpio, result) = tokio::task::spawn_blocking(move || {
+                    let result = measure_echo(gpio.as_mut(), trigger, echo);
+                    (gpio, result)
+                })
+                .await
+                .map_err(|e| e.to_string())?;
+                self.backend = Some(RangeBackend::Ultrasonic(gpio));
+
+                result
+                    .map_err(|e| e.to_string())?
+                    .map(|flight| flight.as_secs_f64() * self.config.speed_of_sound() / 2.0)
+            }
+            Some(RangeBackend::Infrared(mut adc)) => {
+                let channel = match self.config.model {
+                    RangeModel::Gp2y0a21 { channel, .. } => channel,
+                    _ => 0,
+                };
+                let result = adc.read_voltage(channel);
+                self.backend = Some(RangeBackend::Infrared(adc));
+                Some(super::gpio::gp2y0a21_distance(
+                    result.map_err(|e| e.to_string())?,
+                ))
+            }
+            None => return Err("Sensor de distancia sin dispositivo".to_string()),
+        };
+
+        self.last_reading = Some(Instant::now());
+        Ok(self.to_range_data(measured))
+    }
+
+    fn to_range_data(&self, measured: Option<f64>) -> RangeData {
+        let range = match measured {
+            Some(distance) if distance > self.config.max_range => f64::INFINITY,
+            // Por debajo del mínimo la lectura no es fiable, pero hay algo muy cerca
+            Some(distance) => distance.max(self.config.min_range),
+            None => f64::INFINITY,
+        };
+
+        RangeData {
+            frame_id: self.config.frame_id.clone(),
+            radiation: self.config.radiation(),
+            range,
+            min_range: self.config.min_range,
+            max_range: self.config.max_range,
+            field_of_view: self.config.field_of_view,
+            mounting: self.config.mounting,
+            timestamp: chrono::Utc::now().timestamp_millis() as f64 / 1000.0,
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn open_backend(model: &RangeModel) -> Result<RangeBackend, String> {
+        match model {
+            RangeModel::HcSr04 {
+                trigger_pin,
+                echo_pin,
+            } => {
+                let gpio = super::gpio::SysfsGpio::open(&[*trigger_pin], &[*echo_pin])
+                    .map_err(|e| e.to_string())?;
+                Ok(RangeBackend::Ultrasonic(Box::new(gpio)))
+            }
+            RangeModel::Gp2y0a21 {
+                i2c_bus,
+                adc_address,
+                ..
+            } => {
+                let bus = super::i2c::LinuxI2cBus::open(i2c_bus).map_err(|e| e.to_string())?;
+                let adc = super::gpio::Ads1115::new(bus, *adc_address);
+                Ok(RangeBackend::Infrared(Box::new(adc)))
+            }
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn open_backend(model: &RangeModel) -> Result<RangeBackend, String> {
+        Err(format!("{:?} no soportado en esta plataforma", model))
+    }
+
+    pub fn get_config(&self) -> &RangeConfig {
+        &self.config
+    }
+
+    pub fn get_health(&self) -> SensorHealth {
+        if !self.is_connected {
+            return SensorHealth::Disconnected;
+        }
+
+        if let Some(last_reading) = self.last_reading {
+            if last_reading.elapsed().as_secs() > 5 {
+                return SensorHealth::Error("No data received for 5 seconds".to_string());
+            }
+        }
+
+        SensorHealth::Healthy
+    }
+}
+
+/// Pulso de 10 µs en `trigger` y tiempo de vuelo medido en `echo`; `None`
+/// si no vuelve eco
+fn measure_echo(gpio: &mut dyn Gpio, trigger: u32, echo: u32) -> anyhow::Result<Option<Duration>> {
+    gpio.write(trigger, false)?;
+    spin_for(Duration::from_micros(2));
+    gpio.write(trigger, true)?;
+    spin_for(Duration::from_micros(10));
+    gpio.write(trigger, false)?;
+
+    let Some(start) = gpio.wait_for(echo, true, ECHO_TIMEOUT)? else {
+        return Ok(None);
+    };
+    let Some(end) = gpio.wait_for(echo, false, ECHO_TIMEOUT)? else {
+        return Ok(None);
+    };
+    Ok(Some(end.saturating_duration_since(start)))
+}
+
+fn spin_for(duration: Duration) {
+    let deadline = Instant::now() + duration;
+    while Instant::now() < deadline {
+        std::hint::spin_loop();
+    }
+}
+
+#[async_trait]
+impl SensorDriver for RangeSensor {
+    fn kind(&self) -> SensorKind {
+        SensorKind::Range
+    }
+
+    async fn connect(&mut self) -> Result<(), String> {
+        RangeSensor::connect(self).await
+    }
+
+    async fn disconnect(&mut self) -> Result<(), String> {
+        RangeSensor::disconnect(self).await
+    }
+
+    async fn read(&mut self) -> Result<SensorReading, String> {
+        self.read_range().await.map(SensorReading::Range)
+    }
+
+    fn is_connected(&self) -> bool {
+        self.is_connected
+    }
+
+    fn health(&self) -> SensorHealth {
+        self.get_health()
+    }
+
+    fn sample_period(&self) -> Duration {
+        period_from_rate(self.config.sample_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::drivers::SensorSpec;
+
+    #[tokio::test]
+    async fn test_simulated_ultrasonic_and_infrared() {
+        let (mut sonar, target) = RangeSensor::simulated(RangeConfig::hc_sr04(23, 24));
+        sonar.connect().await.unwrap();
+
+        target.set(1.2);
+        let reading = sonar.read_range().await.unwrap();
+        assert_eq!(reading.radiation, RangeRadiation::Ultrasound);
+        assert!((reading.range - 1.2).abs() < 0.01, "{}", reading.range);
+
+        target.set(f64::INFINITY);
+        assert!(!sonar.read_range().await.unwrap().is_detection());
+
+        let (mut ir, target) = RangeSensor::simulated(RangeConfig::gp2y0a21("/dev/i2c-1", 0x48, 0));
+        ir.connect().await.unwrap();
+        target.set(0.3);
+        assert!((ir.read_range().await.unwrap().range - 0.3).abs() < 1e-6);
+        // Cristal a 1.5 m queda fuera del alcance del infrarrojo
+        target.set(1.5);
+        assert_eq!(ir.read_range().await.unwrap().range, f64::INFINITY);
+    }
+
+    #[test]
+    fn test_range_spec_from_toml() {
+        let spec: SensorSpec = toml::from_str(
+            r#"
+            name = "sonar_front"
+            type = "range"
+            simulated = true
+            mounting = { x = 0.2, theta = 0.0 }
+
+            [model.hc_sr04]
+            trigger_pin = 17
+            echo_pin = 27
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(spec.kind(), SensorKind::Range);
+        let driver = spec.build();
+        assert_eq!(driver.sample_period(), Duration::from_millis(100));
+    }
+}
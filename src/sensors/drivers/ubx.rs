@@ -0,0 +1,187 @@
+//! Protocolo binario UBX de u-blox: tramas y mensajes de navegación
+use anyhow::Result;
+
+pub const SYNC: [u8; 2] = [0xB5, 0x62];
+const CLASS_NAV: u8 = 0x01;
+const NAV_POSLLH: u8 = 0x02;
+const NAV_PVT: u8 = 0x07;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UbxMessage {
+    NavPosllh(NavPosllh),
+    NavPvt(NavPvt),
+    /// Cualquier otro mensaje con checksum válido
+    Other {
+        class: u8,
+        id: u8,
+    },
+}
+
+/// NAV-POSLLH: posición geodésica (NEO-6 y posteriores)
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavPosllh {
+    pub itow: u32, // ms de la semana GPS
+    pub latitude: f64,
+    pub longitude: f64,
+    pub height: f64,              // sobre el elipsoide (m)
+    pub height_msl: f64,          // sobre el nivel del mar (m)
+    pub horizontal_accuracy: f64, // 1σ (m)
+    pub vertical_accuracy: f64,
+}
+
+/// NAV-PVT: solución completa (u-blox 7 y posteriores)
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavPvt {
+    pub itow: u32,
+    /// Fecha y hora UTC si el receptor la marca como válida
+    pub utc: Option<(i32, u32, u32, u32, u32, f64)>,
+    /// 0 sin fix, 2 2D, 3 3D, 4 GNSS+inercial, 5 solo tiempo
+    pub fix_type: u8,
+    pub fix_ok: bool,
+    /// 0 sin RTK, 1 flotante, 2 fijo
+    pub carrier_solution: u8,
+    pub satellites: u8,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub height: f64,
+    pub height_msl: f64,
+    pub horizontal_accuracy: f64,
+    pub vertical_accuracy: f64,
+    pub ground_speed: f64, // m/s
+    pub heading: f64,      // grados de movimiento desde el norte
+    pub pdop: f64,
+}
+
+/// Longitud total de la trama cuya cabecera empieza en `bytes`, si ya se
+/// conoce la longitud del payload
+pub fn frame_len(bytes: &[u8]) -> Option<usize> {
+    if bytes.len() < 6 {
+        return None;
+    }
+    Some(8 + u16::from_le_bytes([bytes[4], bytes[5]]) as usize)
+}
+
+/// Decodifica una trama completa `B5 62 clase id len payload ck_a ck_b`
+pub fn parse_frame(frame: &[u8]) -> Result<UbxMessage> {
+    if frame.len() < 8 || frame[..2] != SYNC {
+        return Err(anyhow::anyhow!("Trama UBX inválida"));
+    }
+    let len = u16::from_le_bytes([frame[4], frame[5]]) as usize;
+    if frame.len() != 8 + len {
+        return Err(anyhow::anyhow!(
+            "Trama UBX truncada: {} bytes de {}",
+            frame.len(),
+            8 + len
+        ));
+    }
+    let (ck_a, ck_b) = checksum(&frame[2..6 + len]);
+    if [ck_a, ck_b] != frame[6 + len..] {
+        return Err(anyhow::anyhow!("Checksum UBX incorrecto"));
+    }
+
+    let (class, id, payload) = (frame[2], frame[3], &frame[6..6 + len]);
+    Ok(match (class, id) {
+        (CLASS_NAV, NAV_POSLLH) if len == 28 => UbxMessage::NavPosllh(NavPosllh {
+            itow: u32_at(payload, 0),
+            longitude: i32_at(payload, 4) as f64 * 1e-7,
+            latitude: i32_at(payload, 8) as f64 * 1e-7,
+            height: i32_at(payload, 12) as f64 / 1000.0,
+            height_msl: i32_at(payload, 16) as f64 / 1000.0,
+            horizontal_accuracy: u32_at(payload, 20) as f64 / 1000.0,
+            vertical_accuracy: u32_at(payload, 24) as f64 / 1000.0,
+        }),
+        (CLASS_NAV, NAV_PVT) if len == 92 => {
+            let valid = payload[11];
+            let flags = payload[21];
+            // validDate y validTime
+            let utc = (valid & 0x03 == 0x03).then(|| {
+                (
+                    u16::from_le_bytes([payload[4], payload[5]]) as i32,
+                    payload[6] as u32,
+                    payload[7] as u32,
+                    payload[8] as u32,
+                    payload[9] as u32,
+                    payload[10] as f64 + i32_at(payload, 16) as f64 * 1e-9,
+                )
+            });
+            UbxMessage::NavPvt(NavPvt {
+                itow: u32_at(payload, 0),
+                utc,
+                fix_type: payload[20],
+                fix_ok: flags & 0x01 != 0,
+                carrier_solution: flags >> 6,
+                satellites: payload[23],
+                longitude: i32_at(payload, 24) as f64 * 1e-7,
+                latitude: i32_at(payload, 28) as f64 * 1e-7,
+                height: i32_at(payload, 32) as f64 / 1000.0,
+                height_msl: i32_at(payload, 36) as f64 / 1000.0,
+                horizontal_accuracy: u32_at(payload, 40) as f64 / 1000.0,
+                vertical_accuracy: u32_at(payload, 44) as f64 / 1000.0,
+                ground_speed: i32_at(payload, 60) as f64 / 1000.0,
+                heading: i32_at(payload, 64) as f64 * 1e-5,
+                pdop: u16::from_le_bytes([payload[76], payload[77]]) as f64 * 0.01,
+            })
+        }
+        _ => UbxMessage::Other { class, id },
+    })
+}
+
+/// Construye una trama con su checksum
+pub fn encode_frame(class: u8, id: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(8 + payload.len());
+    frame.extend_from_slice(&SYNC);
+    frame.extend_from_slice(&[class, id]);
+    frame.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+    frame.extend_from_slice(payload);
+    let (ck_a, ck_b) = checksum(&frame[2..]);
+    frame.extend_from_slice(&[ck_a, ck_b]);
+    frame
+}
+
+/// Checksum de Fletcher de 8 bits sobre clase, id, longitud y payload
+fn checksum(bytes: &[u8]) -> (u8, u8) {
+    bytes.iter().fold((0u8, 0u8), |(a, b), &byte| {
+        let a = a.wrapping_add(byte);
+        (a, b.wrapping_add(a))
+    })
+}
+
+fn u32_at(payload: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(payload[offset..offset + 4].try_into().unwrap())
+}
+
+fn i32_at(payload: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes(payload[offset..offset + 4].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nav_posllh_round_trip() {
+        let mut payload = Vec::new();
+        payload.extend_from_slice(&123_456u32.to_le_bytes());
+        payload.extend_from_slice(&(-37_123_457i32).to_le_bytes()); // lon
+        payload.extend_from_slice(&(404_167_754i32).to_le_bytes()); // lat
+        payload.extend_from_slice(&705_123i32.to_le_bytes());
+        payload.extend_from_slice(&655_000i32.to_le_bytes());
+        payload.extend_from_slice(&2_500u32.to_le_bytes());
+        payload.extend_from_slice(&4_000u32.to_le_bytes());
+
+        let mut frame = encode_frame(CLASS_NAV, NAV_POSLLH, &payload);
+        assert_eq!(frame_len(&frame), Some(frame.len()));
+
+        let UbxMessage::NavPosllh(posllh) = parse_frame(&frame).unwrap() else {
+            panic!("no es NAV-POSLLH");
+        };
+        assert!((posllh.latitude - 40.416_775_4).abs() < 1e-9);
+        assert!((posllh.longitude + 3.712_345_7).abs() < 1e-9);
+        assert!((posllh.height - 705.123).abs() < 1e-9);
+        assert_eq!(posllh.horizontal_accuracy, 2.5);
+
+        let last = frame.len() - 1;
+        frame[last] ^= 0xFF;
+        assert!(parse_frame(&frame).is_err());
+    }
+}
@@ -0,0 +1,568 @@
+//! Captura de vídeo Video4Linux2 con streaming por buffers mmap
+use super::pixel_format::{PixelFormat, RawFrame};
+use anyhow::Result;
+use std::os::unix::io::{AsRawFd, RawFd};
+use tokio::io::unix::AsyncFd;
+
+const BUF_TYPE_VIDEO_CAPTURE: u32 = 1;
+const MEMORY_MMAP: u32 = 1;
+const FIELD_ANY: u32 = 0;
+
+const CAP_VIDEO_CAPTURE: u32 = 0x0000_0001;
+const CAP_STREAMING: u32 = 0x0400_0000;
+const CAP_DEVICE_CAPS: u32 = 0x8000_0000;
+
+const BUF_FLAG_TIMESTAMP_MASK: u32 = 0xe000;
+const BUF_FLAG_TIMESTAMP_MONOTONIC: u32 = 0x2000;
+const BUF_FLAG_ERROR: u32 = 0x0040;
+
+const BUFFER_COUNT: u32 = 4;
+
+const fn ioc(dir: u64, nr: u64, size: usize) -> u64 {
+    (dir << 30) | ((size as u64) << 16) | ((b'V' as u64) << 8) | nr
+}
+const IOC_WRITE: u64 = 1;
+const IOC_READ: u64 = 2;
+
+const VIDIOC_QUERYCAP: u64 = ioc(IOC_READ, 0, std::mem::size_of::<Capability>());
+const VIDIOC_S_FMT: u64 = ioc(IOC_READ | IOC_WRITE, 5, std::mem::size_of::<Format>());
+const VIDIOC_REQBUFS: u64 = ioc(
+    IOC_READ | IOC_WRITE,
+    8,
+    std::mem::size_of::<RequestBuffers>(),
+);
+const VIDIOC_QUERYBUF: u64 = ioc(IOC_READ | IOC_WRITE, 9, std::mem::size_of::<Buffer>());
+const VIDIOC_QBUF: u64 = ioc(IOC_READ | IOC_WRITE, 15, std::mem::size_of::<Buffer>());
+const VIDIOC_DQBUF: u64 = ioc(IOC_READ | IOC_WRITE, 17, std::mem::size_of::<Buffer>());
+const VIDIOC_STREAMON: u64 = ioc(IOC_WRITE, 18, std::mem::size_of::<libc::c_int>());
+const VIDIOC_STREAMOFF: u64 = ioc(IOC_WRITE, 19, std::mem::size_of::<libc::c_int>());
+const VIDIOC_S_PARM: u64 = ioc(IOC_READ | IOC_WRITE, 22, std::mem::size_of::<StreamParm>());
+
+// Estructuras del ABI de <linux/videodev2.h>
+#[repr(C)]
+#[derive(Default)]
+struct Capability {
+    driver: [u8; 16],
+    card: [u8; 32],
+    bus_info: [u8; 32],
+    version: u32,
+    capabilities: u32,
+    device_caps: u32,
+    reserved: [u32; 3],
+}
+
+#[repr(C)]
+#[derive(Default, Clone, Copy)]
+struct PixFormat {
+    width: u32,
+    height: u32,
+    pixelformat: u32,
+    field: u32,
+    bytesperline: u32,
+    sizeimage: u32,
+    colorspace: u32,
+    private: u32,
+    flags: u32,
+    ycbcr_enc: u32,
+    quantization: u32,
+    xfer_func: u32,
+}
+
+#[repr(C)]
+union FormatUnion {
+    pix: PixFormat,
+    raw: [u8; 200],
+    _align: [u64; 25],
+}
+
+#[repr(C)]
+struct Format {
+    type_: u32,
+    fmt: FormatUnion,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct RequestBuffers {
+    count: u32,
+    type_: u32,
+    memory: u32,
+    capabilities: u32,
+    reserved: u32,
+}
+
+#[repr(C)]
+#[derive(Default, Clone, Copy)]
+struct Timecode {
+    type_: u32,
+    flags: u32,
+    frames: u8,
+    seconds: u8,
+    minutes: u8,
+    hours: u8,
+    userbits: [u8; 4],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+union BufferMemory {
+    offset: u32,
+    userptr: libc::c_ulong,
+    fd: i32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Buffer {
+    index: u32,
+    type_: u32,
+    bytesused: u32,
+    flags: u32,
+    field: u32,
+    timestamp: libc::timeval,
+    timecode: Timecode,
+    sequence: u32,
+    memory: u32,
+    m: BufferMemory,
+    length: u32,
+    reserved2: u32,
+    request_fd: i32,
+}
+
+impl Buffer {
+    fn new(index: u32) -> Self {
+        // SAFETY: la estructura es POD; todo a cero es un valor válido
+        let mut buffer: Self = unsafe { std::mem::zeroed() };
+        buffer.index = index;
+        buffer.type_ = BUF_TYPE_VIDEO_CAPTURE;
+        buffer.memory = MEMORY_MMAP;
+        buffer
+    }
+}
+
+#[repr(C)]
+#[derive(Default, Clone, Copy)]
+struct Fract {
+    numerator: u32,
+    denominator: u32,
+}
+
+#[repr(C)]
+#[derive(Default, Clone, Copy)]
+struct CaptureParm {
+    capability: u32,
+    capturemode: u32,
+    timeperframe: Fract,
+    extendedmode: u32,
+    readbuffers: u32,
+    reserved: [u32; 4],
+}
+
+#[repr(C)]
+union StreamParmUnion {
+    capture: CaptureParm,
+    raw: [u8; 200],
+}
+
+#[repr(C)]
+struct StreamParm {
+    type_: u32,
+    parm: StreamParmUnion,
+}
+
+struct MappedBuffer {
+    ptr: *mut libc::c_void,
+    length: usize,
+}
+
+/// Descriptor del dispositivo; se cierra al soltarse
+struct DeviceFd(RawFd);
+
+impl AsRawFd for DeviceFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0
+    }
+}
+
+impl Drop for DeviceFd {
+    fn drop(&mut self) {
+        // SAFETY: el descriptor pertenece en exclusiva a esta estructura
+        unsafe {
+            libc::close(self.0);
+        }
+    }
+}
+
+/// Formato acordado con el driver tras VIDIOC_S_FMT
+#[derive(Debug, Clone)]
+pub struct NegotiatedFormat {
+    pub format: PixelFormat,
+    pub width: u32,
+    pub height: u32,
+    pub bytes_per_line: u32,
+    pub frame_size: u32,
+}
+
+pub struct V4l2Capture {
+    device: AsyncFd<DeviceFd>,
+    path: String,
+    card: String,
+    format: NegotiatedFormat,
+    buffers: Vec<MappedBuffer>,
+    streaming: bool,
+}
+
+// SAFETY: los buffers mmap solo se acceden a través de `&mut self`
+unsafe impl Send for V4l2Capture {}
+
+impl std::fmt::Debug for V4l2Capture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("V4l2Capture")
+            .field("path", &self.path)
+            .field("card", &self.card)
+            .field("format", &self.format)
+            .field("buffers", &self.buffers.len())
+            .field("streaming", &self.streaming)
+            .finish()
+    }
+}
+
+impl V4l2Capture {
+    /// Abre el dispositivo, negocia formato y framerate y arranca el streaming.
+    ///
+    /// `preferred` se intenta primero; si el driver lo rechaza se prueban el resto
+    /// de formatos soportados (MJPEG, YUYV, RGB24).
+    pub fn open(
+        path: &str,
+        preferred: PixelFormat,
+        width: u32,
+        height: u32,
+        framerate: u32,
+    ) -> Result<Self> {
+        let c_path = std::ffi::CString::new(path)?;
+        // SAFETY: `c_path` es una cadena C válida
+        let fd = unsafe { libc::open(c_path.as_ptr(), libc::O_RDWR | libc::O_NONBLOCK) };
+        if fd < 0 {
+            return Err(anyhow::anyhow!(
+                "No se pudo abrir {}: {}",
+                path,
+                std::io::Error::last_os_error()
+            ));
+        }
+        let device = DeviceFd(fd);
+
+        let mut capability = Capability::default();
+        xioctl(fd, VIDIOC_QUERYCAP, &mut capability)?;
+        let caps = if capability.capabilities & CAP_DEVICE_CAPS != 0 {
+            capability.device_caps
+        } else {
+            capability.capabilities
+        };
+        if caps & CAP_VIDEO_CAPTURE == 0 || caps & CAP_STREAMING == 0 {
+            return Err(anyhow::anyhow!("{} no soporta captura por streaming", path));
+        }
+        let card = c_string(&capability.card);
+
+        let format = negotiate_format(fd, preferred, width, height)?;
+        set_framerate(fd, framerate)?;
+
+        // `DeviceFd` es dueño exclusivo del descriptor, que es lo que pide la
+        // seguridad de E/S de `AsyncFd::register` (solo en tokio >= 1.53)
+        #[allow(deprecated)]
+        let device = AsyncFd::new(device)?;
+        let mut capture = Self {
+            device,
+            path: path.to_string(),
+            card,
+            format,
+            buffers: Vec::new(),
+            streaming: false,
+        };
+        capture.start_streaming()?;
+
+        log::info!(
+            "📷 V4L2 {} ({}): {:?} {}x{}",
+            capture.path,
+            capture.card,
+            capture.format.format,
+            capture.format.width,
+            capture.format.height
+        );
+
+        Ok(capture)
+    }
+
+    pub fn format(&self) -> &NegotiatedFormat {
+        &self.format
+    }
+
+    pub fn card(&self) -> &str {
+        &self.card
+    }
+
+    /// Espera al siguiente buffer lleno, copia su contenido y lo devuelve al driver
+    pub async fn next_frame(&mut self) -> Result<RawFrame> {
+        loop {
+            let mut guard = self.device.readable().await?;
+            let fd = guard.get_ref().as_raw_fd();
+            match try_dequeue(fd, &self.buffers, &self.format) {
+                Ok(Some(frame)) => return Ok(frame),
+                Ok(None) => guard.clear_ready(),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn start_streaming(&mut self) -> Result<()> {
+        let fd = self.device.get_ref().as_raw_fd();
+
+        let mut request = RequestBuffers {
+            count: BUFFER_COUNT,
+            type_: BUF_TYPE_VIDEO_CAPTURE,
+            memory: MEMORY_MMAP,
+            ..Default::default()
+        };
+        xioctl(fd, VIDIOC_REQBUFS, &mut request)?;
+        if request.count < 2 {
+            return Err(anyhow::anyhow!(
+                "Memoria insuficiente para buffers de captura"
+            ));
+        }
+
+        for index in 0..request.count {
+            let mut buffer = Buffer::new(index);
+            xioctl(fd, VIDIOC_QUERYBUF, &mut buffer)?;
+
+            // SAFETY: offset y longitud provienen de VIDIOC_QUERYBUF
+            let ptr = unsafe {
+                libc::mmap(
+                    std::ptr::null_mut(),
+                    buffer.length as usize,
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_SHARED,
+                    fd,
+                    buffer.m.offset as libc::off_t,
+                )
+            };
+            if ptr == libc::MAP_FAILED {
+                return Err(anyhow::anyhow!(
+                    "mmap del buffer {} falló: {}",
+                    index,
+                    std::io::Error::last_os_error()
+                ));
+            }
+            self.buffers.push(MappedBuffer {
+                ptr,
+                length: buffer.length as usize,
+            });
+
+            xioctl(fd, VIDIOC_QBUF, &mut buffer)?;
+        }
+
+        let mut buffer_type = BUF_TYPE_VIDEO_CAPTURE as libc::c_int;
+        xioctl(fd, VIDIOC_STREAMON, &mut buffer_type)?;
+        self.streaming = true;
+        Ok(())
+    }
+}
+
+impl Drop for V4l2Capture {
+    fn drop(&mut self) {
+        let fd = self.device.get_ref().as_raw_fd();
+        if self.streaming {
+            let mut buffer_type = BUF_TYPE_VIDEO_CAPTURE as libc::c_int;
+            let _ = xioctl(fd, VIDIOC_STREAMOFF, &mut buffer_type);
+        }
+        for buffer in self.buffers.drain(..) {
+            // SAFETY: cada región se mapeó en `start_streaming` y no se usa más
+            unsafe {
+                libc::munmap(buffer.ptr, buffer.length);
+            }
+        }
+    }
+}
+
+fn try_dequeue(
+    fd: RawFd,
+    buffers: &[MappedBuffer],
+    format: &NegotiatedFormat,
+) -> Result<Option<RawFrame>> {
+    let mut buffer = Buffer::new(0);
+
+    if let Err(e) = xioctl(fd, VIDIOC_DQBUF, &mut buffer) {
+        if let Some(io) = e.downcast_ref::<std::io::Error>() {
+            if io.kind() == std::io::ErrorKind::WouldBlock {
+                return Ok(None);
+            }
+        }
+        return Err(e);
+    }
+
+    let mapped = &buffers[buffer.index as usize];
+    let used = (buffer.bytesused as usize).min(mapped.length);
+    // SAFETY: el buffer está mapeado y desencolado; el driver no lo escribe hasta QBUF
+    let data = unsafe { std::slice::from_raw_parts(mapped.ptr as *const u8, used) }.to_vec();
+
+    let timestamp = buffer_timestamp(&buffer);
+    let sequence = buffer.sequence as u64;
+    let corrupted = buffer.flags & BUF_FLAG_ERROR != 0;
+
+    xioctl(fd, VIDIOC_QBUF, &mut buffer)?;
+
+    if corrupted {
+        log::warn!("⚠️ Frame {} marcado como corrupto por el driver", sequence);
+        return Ok(None);
+    }
+
+    Ok(Some(RawFrame {
+        format: format.format,
+        width: format.width,
+        height: format.height,
+        data,
+        sequence,
+        timestamp,
+    }))
+}
+
+fn negotiate_format(
+    fd: RawFd,
+    preferred: PixelFormat,
+    width: u32,
+    height: u32,
+) -> Result<NegotiatedFormat> {
+    let mut candidates = vec![preferred];
+    for format in [PixelFormat::Mjpeg, PixelFormat::Yuyv, PixelFormat::Rgb24] {
+        if !candidates.contains(&format) {
+            candidates.push(format);
+        }
+    }
+
+    for candidate in candidates {
+        let mut format = Format {
+            type_: BUF_TYPE_VIDEO_CAPTURE,
+            fmt: FormatUnion { raw: [0; 200] },
+        };
+        format.fmt.pix = PixFormat {
+            width,
+            height,
+            pixelformat: candidate.fourcc(),
+            field: FIELD_ANY,
+            ..Default::default()
+        };
+
+        if xioctl(fd, VIDIOC_S_FMT, &mut format).is_err() {
+            continue;
+        }
+
+        // SAFETY: el driver rellena `pix` para buffers de captura
+        let pix = unsafe { format.fmt.pix };
+        if let Some(accepted) = PixelFormat::from_fourcc(pix.pixelformat) {
+            if accepted != preferred {
+                log::warn!(
+                    "⚠️ Formato {:?} no disponible, usando {:?}",
+                    preferred,
+                    accepted
+                );
+            }
+            return Ok(NegotiatedFormat {
+                format: accepted,
+                width: pix.width,
+                height: pix.height,
+                bytes_per_line: pix.bytesperline,
+                frame_size: pix.sizeimage,
+            });
+        }
+    }
+
+    Err(anyhow::anyhow!(
+        "El dispositivo no ofrece ningún formato soportado (MJPG, YUYV, RGB3)"
+    ))
+}
+
+fn set_framerate(fd: RawFd, framerate: u32) -> Result<()> {
+    if framerate == 0 {
+        return Ok(());
+    }
+
+    let mut parm = StreamParm {
+        type_: BUF_TYPE_VIDEO_CAPTURE,
+        parm: StreamParmUnion { raw: [0; 200] },
+    };
+    parm.parm.capture = CaptureParm {
+        timeperframe: Fract {
+            numerator: 1,
+            denominator: framerate,
+        },
+        ..Default::default()
+    };
+
+    // No todos los drivers permiten fijar el framerate
+    if let Err(e) = xioctl(fd, VIDIOC_S_PARM, &mut parm) {
+        log::warn!("⚠️ No se pudo fijar {} fps: {}", framerate, e);
+    }
+    Ok(())
+}
+
+/// Convierte la marca de tiempo del buffer a segundos Unix.
+///
+/// La mayoría de drivers usan CLOCK_MONOTONIC; se traslada al reloj de pared
+/// con el desfase actual entre ambos relojes.
+fn buffer_timestamp(buffer: &Buffer) -> f64 {
+    let seconds = buffer.timestamp.tv_sec as f64 + buffer.timestamp.tv_usec as f64 * 1e-6;
+    let wall_clock = chrono::Utc::now().timestamp_micros() as f64 * 1e-6;
+
+    if seconds == 0.0 {
+        return wall_clock;
+    }
+
+    if buffer.flags & BUF_FLAG_TIMESTAMP_MASK == BUF_FLAG_TIMESTAMP_MONOTONIC {
+        let mut now = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        // SAFETY: `now` es un puntero válido
+        unsafe {
+            libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now);
+        }
+        let monotonic = now.tv_sec as f64 + now.tv_nsec as f64 * 1e-9;
+        wall_clock - (monotonic - seconds)
+    } else {
+        seconds
+    }
+}
+
+fn xioctl<T>(fd: RawFd, request: u64, arg: &mut T) -> Result<()> {
+    loop {
+        // SAFETY: `arg` es la estructura que espera `request` según videodev2.h
+        let result = unsafe { libc::ioctl(fd, request as _, arg as *mut T) };
+        if result >= 0 {
+            return Ok(());
+        }
+        let error = std::io::Error::last_os_error();
+        if error.kind() != std::io::ErrorKind::Interrupted {
+            return Err(error.into());
+        }
+    }
+}
+
+fn c_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_abi_sizes_match_videodev2() {
+        assert_eq!(std::mem::size_of::<Capability>(), 104);
+        assert_eq!(std::mem::size_of::<Format>(), 208);
+        assert_eq!(std::mem::size_of::<RequestBuffers>(), 20);
+        assert_eq!(std::mem::size_of::<Buffer>(), 88);
+        assert_eq!(std::mem::size_of::<StreamParm>(), 204);
+        assert_eq!(VIDIOC_DQBUF, 0xC058_5611);
+        assert_eq!(VIDIOC_S_FMT, 0xC0D0_5605);
+    }
+}
@@ -0,0 +1,855 @@
+//! Monitor de salud de sensores: estadísticas, reconexión y modo degradado
+use super::bus::{SensorBus, SensorFault, SensorMessage};
+use super::drivers::{SensorKind, SensorReading};
+use super::registry::SharedSensor;
+use super::ring_buffer::RingBuffer;
+use super::{SensorHealth, SensorStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{broadcast, Mutex};
+use tokio::task::JoinHandle;
+
+/// Aceleración y velocidad angular máximas representables por el MPU (±16 g, ±2000 °/s)
+const MAX_ACCELERATION: f64 = 16.0 * 9.80665;
+const MAX_ANGULAR_RATE: f64 = 2000.0 * std::f64::consts::PI / 180.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HealthConfig {
+    pub check_interval_ms: u64,
+    /// Fracción mínima de la frecuencia nominal antes de avisar
+    pub min_rate_ratio: f64,
+    /// Retraso máximo entre la marca de tiempo y la recepción (s)
+    pub max_latency: f64,
+    /// Silencio tolerado, en periodos nominales, antes de declarar el sensor caído
+    pub dropout_periods: f64,
+    /// Silencio mínimo tolerado (s), para sensores muy rápidos
+    pub min_dropout: f64,
+    /// Lecturas idénticas consecutivas que se consideran valor congelado
+    pub stuck_threshold: u32,
+    /// Tipos de sensor en los que se busca valor congelado
+    pub stuck_kinds: Vec<SensorKind>,
+    /// Fracción (media móvil) de lecturas fuera de rango antes de avisar
+    pub max_out_of_range_ratio: f64,
+    pub backoff_initial_ms: u64,
+    pub backoff_max_ms: u64,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            check_interval_ms: 100,
+            min_rate_ratio: 0.5,
+            max_latency: 0.5,
+            dropout_periods: 10.0,
+            min_dropout: 0.5,
+            stuck_threshold: 20,
+            // Los IMU en reposo y las cámaras fijas repiten lecturas legítimamente
+            stuck_kinds: vec![SensorKind::Lidar],
+            max_out_of_range_ratio: 0.3,
+            backoff_initial_ms: 500,
+            backoff_max_ms: 30_000,
+        }
+    }
+}
+
+/// Nivel de operación que el control impone a las órdenes de velocidad
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum OperatingMode {
+    Normal,
+    Degraded { speed_factor: f64 },
+    Stopped,
+}
+
+impl OperatingMode {
+    /// Factor a aplicar sobre la velocidad máxima
+    pub fn speed_factor(&self) -> f64 {
+        match self {
+            Self::Normal => 1.0,
+            Self::Degraded { speed_factor } => *speed_factor,
+            Self::Stopped => 0.0,
+        }
+    }
+
+    pub fn allows_motion(&self) -> bool {
+        !matches!(self, Self::Stopped)
+    }
+
+    /// El más restrictivo de los dos
+    fn min(self, other: Self) -> Self {
+        if other.speed_factor() < self.speed_factor() {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum HealthEvent {
+    HealthChanged {
+        sensor: String,
+        previous: SensorHealth,
+        current: SensorHealth,
+    },
+    Reconnected {
+        sensor: String,
+        attempts: u32,
+    },
+    ReconnectFailed {
+        sensor: String,
+        error: String,
+        retry_in_ms: u64,
+    },
+    ModeChanged {
+        mode: OperatingMode,
+        reason: String,
+    },
+}
+
+/// Estadísticas de un sensor calculadas a partir de sus mensajes
+#[derive(Debug, Clone, Serialize)]
+pub struct SensorStats {
+    pub kind: SensorKind,
+    pub expected_rate: f64,
+    pub rate: f64,
+    pub latency: f64,
+    pub silence: f64,
+    pub repeated: u32,
+    pub out_of_range_ratio: f64,
+    pub messages: u64,
+    pub read_errors: u64,
+}
+
+#[derive(Debug)]
+struct WatchedSensor {
+    driver: Option<SharedSensor>,
+    kind: SensorKind,
+    period: Duration,
+    arrivals: RingBuffer<Instant>,
+    last_message: Option<Instant>,
+    last_update: SystemTime,
+    watched_since: Instant,
+    latency: f64,
+    fingerprint: Option<u64>,
+    repeated: u32,
+    out_of_range_ratio: f64,
+    messages: u64,
+    read_errors: u64,
+    error_count: u32,
+    connected: bool,
+    health: SensorHealth,
+    backoff: Duration,
+    next_attempt: Option<Instant>,
+    attempts: u32,
+}
+
+impl WatchedSensor {
+    fn new(kind: SensorKind, period: Duration, driver: Option<SharedSensor>, now: Instant) -> Self {
+        Self {
+            driver,
+            kind,
+            period,
+            arrivals: RingBuffer::new(32),
+            last_message: None,
+            last_update: SystemTime::now(),
+            watched_since: now,
+            latency: 0.0,
+            fingerprint: None,
+            repeated: 0,
+            out_of_range_ratio: 0.0,
+            messages: 0,
+            read_errors: 0,
+            error_count: 0,
+            connected: true,
+            health: SensorHealth::Healthy,
+            backoff: Duration::ZERO,
+            next_attempt: None,
+            attempts: 0,
+        }
+    }
+
+    fn reset_statistics(&mut self, now: Instant) {
+        self.arrivals.clear();
+        self.last_message = None;
+        self.watched_since = now;
+        self.fingerprint = None;
+        self.repeated = 0;
+        self.out_of_range_ratio = 0.0;
+    }
+
+    fn rate(&self) -> f64 {
+        match (self.arrivals.oldest(), self.arrivals.latest()) {
+            (Some(first), Some(last)) if self.arrivals.len() > 1 => {
+                let span = last.duration_since(*first).as_secs_f64();
+                if span > 0.0 {
+                    (self.arrivals.len() - 1) as f64 / span
+                } else {
+                    0.0
+                }
+            }
+            _ => 0.0,
+        }
+    }
+
+    fn silence(&self, now: Instant) -> f64 {
+        let since = self.last_message.unwrap_or(self.watched_since);
+        now.saturating_duration_since(since).as_secs_f64()
+    }
+
+    fn stats(&self, now: Instant) -> SensorStats {
+        SensorStats {
+            kind: self.kind,
+            expected_rate: 1.0 / self.period.as_secs_f64().max(1e-6),
+            rate: self.rate(),
+            latency: self.latency,
+            silence: self.silence(now),
+            repeated: self.repeated,
+            out_of_range_ratio: self.out_of_range_ratio,
+            messages: self.messages,
+            read_errors: self.read_errors,
+        }
+    }
+
+    fn status(&self) -> SensorStatus {
+        SensorStatus {
+            connected: self.connected,
+            last_update: self.last_update,
+            error_count: self.error_count,
+            health: self.health.clone(),
+        }
+    }
+}
+
+/// Reconexión pendiente de un driver caído
+pub struct Reconnect {
+    pub sensor: String,
+    driver: SharedSensor,
+}
+
+impl Reconnect {
+    /// Desconecta, conecta y calibra el driver; puede tardar segundos
+    pub async fn run(&self) -> Result<(), String> {
+        let mut driver = self.driver.lock().await;
+        if driver.is_connected() {
+            let _ = driver.disconnect().await;
+        }
+        driver.connect().await?;
+        driver.calibrate().await
+    }
+}
+
+pub struct HealthMonitor {
+    config: HealthConfig,
+    sensors: HashMap<String, WatchedSensor>,
+    mode: OperatingMode,
+    events: broadcast::Sender<HealthEvent>,
+}
+
+impl HealthMonitor {
+    pub fn new(config: HealthConfig) -> Self {
+        let (events, _) = broadcast::channel(64);
+        Self {
+            config,
+            sensors: HashMap::new(),
+            mode: OperatingMode::Normal,
+            events,
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<HealthEvent> {
+        self.events.subscribe()
+    }
+
+    /// Empieza a vigilar un sensor. Sin `driver` no se intentan reconexiones
+    /// (fuentes externas como la odometría).
+    pub fn watch(
+        &mut self,
+        name: &str,
+        kind: SensorKind,
+        period: Duration,
+        driver: Option<SharedSensor>,
+    ) {
+        self.sensors.insert(
+            name.to_string(),
+            WatchedSensor::new(kind, period, driver, Instant::now()),
+        );
+    }
+
+    pub fn unwatch(&mut self, name: &str) {
+        self.sensors.remove(name);
+    }
+
+    pub fn mode(&self) -> OperatingMode {
+        self.mode
+    }
+
+    pub fn status(&self, name: &str) -> Option<SensorStatus> {
+        self.sensors.get(name).map(|sensor| sensor.status())
+    }
+
+    pub fn statuses(&self) -> HashMap<String, SensorStatus> {
+        self.sensors
+            .iter()
+            .map(|(name, sensor)| (name.clone(), sensor.status()))
+            .collect()
+    }
+
+    pub fn stats(&self, name: &str) -> Option<SensorStats> {
+        let now = Instant::now();
+        self.sensors.get(name).map(|sensor| sensor.stats(now))
+    }
+
+    /// Registra un mensaje recibido en el instante `now`
+    pub fn observe(&mut self, message: &SensorMessage, now: Instant) {
+        let Some(sensor) = self.sensors.get_mut(&*message.sensor) else {
+            return;
+        };
+
+        sensor.messages += 1;
+        sensor.arrivals.push(now);
+        sensor.last_message = Some(now);
+        sensor.last_update = SystemTime::now();
+
+        let wall_clock = chrono::Utc::now().timestamp_millis() as f64 / 1000.0;
+        let latency = (wall_clock - message.timestamp).max(0.0);
+        sensor.latency = if sensor.messages == 1 {
+            latency
+        } else {
+            0.9 * sensor.latency + 0.1 * latency
+        };
+
+        let out_of_range = if out_of_range(&message.reading) {
+            1.0
+        } else {
+            0.0
+        };
+        sensor.out_of_range_ratio = 0.9 * sensor.out_of_range_ratio + 0.1 * out_of_range;
+
+        if self.config.stuck_kinds.contains(&sensor.kind) {
+            let fingerprint = fingerprint(&message.reading);
+            if sensor.fingerprint == Some(fingerprint) {
+                sensor.repeated += 1;
+            } else {
+                sensor.repeated = 0;
+                sensor.fingerprint = Some(fingerprint);
+            }
+        }
+    }
+
+    /// Cuenta un error de lectura del driver
+    pub fn record_error(&mut self, name: &str) {
+        if let Some(sensor) = self.sensors.get_mut(name) {
+            sensor.read_errors += 1;
+            sensor.error_count += 1;
+        }
+    }
+
+    /// Evalúa la salud de todos los sensores, reintenta reconexiones pendientes
+    /// y publica los cambios de estado y de modo de operación.
+    pub async fn check(&mut self, now: Instant) {
+        for reconnect in self.assess(now) {
+            let result = reconnect.run().await;
+            self.finish_reconnect(&reconnect.sensor, result, now);
+        }
+    }
+
+    /// Parte de `check` que no espera a ningún driver: evalúa la salud,
+    /// actualiza el modo y devuelve las reconexiones que tocan ahora, para
+    /// hacerlas sin tener el monitor bloqueado.
+    pub fn assess(&mut self, now: Instant) -> Vec<Reconnect> {
+        let names: Vec<String> = self.sensors.keys().cloned().collect();
+        let mut reconnects = Vec::new();
+
+        for name in &names {
+            self.refresh_connection(name);
+            let health = self.evaluate(name, now);
+            self.set_health(name, health);
+            if let Some(reconnect) = self.schedule_reconnect(name, now) {
+                reconnects.push(reconnect);
+            }
+        }
+
+        self.update_mode();
+        reconnects
+    }
+
+    fn refresh_connection(&mut self, name: &str) {
+        let Some(sensor) = self.sensors.get_mut(name) else {
+            return;
+        };
+        // Si la tarea de adquisición tiene el driver ocupado, conservar el último estado
+        if let Some(driver) = &sensor.driver {
+            if let Ok(driver) = driver.try_lock() {
+                sensor.connected = driver.is_connected();
+            }
+        }
+    }
+
+    fn evaluate(&self, name: &str, now: Instant) -> SensorHealth {
+        let sensor = &self.sensors[name];
+        let config = &self.config;
+
+        if !sensor.connected {
+            return SensorHealth::Disconnected;
+        }
+
+        let period = sensor.period.as_secs_f64();
+        let dropout = (period * config.dropout_periods).max(config.min_dropout);
+        let silence = sensor.silence(now);
+        if silence > dropout {
+            return SensorHealth::Error(format!("Sin datos desde hace {:.1} s", silence));
+        }
+
+        if sensor.repeated >= config.stuck_threshold {
+            return SensorHealth::Error(format!(
+                "Valor congelado ({} lecturas idénticas)",
+                sensor.repeated
+            ));
+        }
+
+        let expected = 1.0 / period.max(1e-6);
+        let rate = sensor.rate();
+        if sensor.arrivals.len() == sensor.arrivals.capacity()
+            && rate < expected * config.min_rate_ratio
+        {
+            return SensorHealth::Warning(format!(
+                "Frecuencia baja: {:.1} Hz de {:.1} Hz",
+                rate, expected
+            ));
+        }
+
+        if sensor.latency > config.max_latency {
+            return SensorHealth::Warning(format!("Latencia alta: {:.3} s", sensor.latency));
+        }
+
+        if sensor.out_of_range_ratio > config.max_out_of_range_ratio {
+            return SensorHealth::Warning(format!(
+                "{:.0}% de lecturas fuera de rango",
+                sensor.out_of_range_ratio * 100.0
+            ));
+        }
+
+        SensorHealth::Healthy
+    }
+
+    fn set_health(&mut self, name: &str, health: SensorHealth) {
+        let sensor = self.sensors.get_mut(name).unwrap();
+        if sensor.health == health {
+            return;
+        }
+
+        if matches!(health, SensorHealth::Error(_)) {
+            sensor.error_count += 1;
+        }
+
+        let previous = std::mem::replace(&mut sensor.health, health.clone());
+        match &health {
+            SensorHealth::Healthy => log::info!("💚 Sensor '{}' recuperado", name),
+            other => log::warn!("⚠️ Sensor '{}': {:?}", name, other),
+        }
+
+        let _ = self.events.send(HealthEvent::HealthChanged {
+            sensor: name.to_string(),
+            previous,
+            current: health,
+        });
+    }
+
+    fn schedule_reconnect(&mut self, name: &str, now: Instant) -> Option<Reconnect> {
+        let sensor = self.sensors.get_mut(name).unwrap();
+
+        let failed = matches!(
+            sensor.health,
+            SensorHealth::Disconnected | SensorHealth::Error(_)
+        );
+        let driver = sensor.driver.clone()?;
+        if !failed {
+            sensor.backoff = Duration::ZERO;
+            sensor.next_attempt = None;
+            sensor.attempts = 0;
+            return None;
+        }
+
+        if let Some(next_attempt) = sensor.next_attempt {
+            if now < next_attempt {
+                return None;
+            }
+        }
+
+        sensor.attempts += 1;
+        log::info!(
+            "🔄 Reconectando sensor '{}' (intento {})...",
+            name,
+            sensor.attempts
+        );
+        Some(Reconnect {
+            sensor: name.to_string(),
+            driver,
+        })
+    }
+
+    /// Anota el resultado de una reconexión devuelta por `assess`
+    pub fn finish_reconnect(&mut self, name: &str, result: Result<(), String>, now: Instant) {
+        let config = &self.config;
+        // El sensor puede haber dejado de vigilarse mientras se reconectaba
+        let Some(sensor) = self.sensors.get_mut(name) else {
+            return;
+        };
+
+        match result {
+            Ok(()) => {
+                let attempts = sensor.attempts;
+                sensor.connected = true;
+                sensor.backoff = Duration::ZERO;
+                sensor.next_attempt = None;
+                sensor.attempts = 0;
+                sensor.reset_statistics(now);
+                log::info!("✅ Sensor '{}' reconectado", name);
+                let _ = self.events.send(HealthEvent::Reconnected {
+                    sensor: name.to_string(),
+                    attempts,
+                });
+            }
+            Err(error) => {
+                sensor.connected = false;
+                sensor.error_count += 1;
+                sensor.backoff = if sensor.backoff.is_zero() {
+                    Duration::from_millis(config.backoff_initial_ms)
+                } else {
+                    (sensor.backoff * 2).min(Duration::from_millis(config.backoff_max_ms))
+                };
+                sensor.next_attempt = Some(now + sensor.backoff);
+                log::warn!(
+                    "❌ Reconexión de '{}' fallida: {} (reintento en {:?})",
+                    name,
+                    error,
+                    sensor.backoff
+                );
+                let _ = self.events.send(HealthEvent::ReconnectFailed {
+                    sensor: name.to_string(),
+                    error,
+                    retry_in_ms: sensor.backoff.as_millis() as u64,
+                });
+            }
+        }
+    }
+
+    fn update_mode(&mut self) {
+        let mut mode = OperatingMode::Normal;
+        let mut reasons = Vec::new();
+
+        for (name, sensor) in &self.sensors {
+            let sensor_mode = degraded_mode(sensor.kind, &sensor.health);
+            if sensor_mode != OperatingMode::Normal {
+                reasons.push(format!("{}: {:?}", name, sensor.health));
+            }
+            mode = mode.min(sensor_mode);
+        }
+
+        if mode != self.mode {
+            self.mode = mode;
+            let reason = if reasons.is_empty() {
+                "Todos los sensores operativos".to_string()
+            } else {
+                reasons.join(", ")
+            };
+            log::warn!("🚦 Modo de operación: {:?} ({})", mode, reason);
+            let _ = self.events.send(HealthEvent::ModeChanged { mode, reason });
+        }
+    }
+
+    /// Lanza el monitor sobre los mensajes y fallos del bus
+    pub fn spawn(monitor: Arc<Mutex<HealthMonitor>>, bus: &SensorBus) -> JoinHandle<()> {
+        let mut messages = bus.subscribe_all();
+        let mut faults = bus.subscribe_faults();
+
+        tokio::spawn(async move {
+            let interval_ms = monitor.lock().await.config.check_interval_ms;
+            let mut interval = tokio::time::interval(Duration::from_millis(interval_ms));
+
+            loop {
+                tokio::select! {
+                    message = messages.recv() => match message {
+                        Ok(message) => monitor.lock().await.observe(&message, Instant::now()),
+                        Err(broadcast::error::RecvError::Lagged(_)) => {}
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    fault = faults.recv() => match fault {
+                        Ok(SensorFault { sensor, .. }) => monitor.lock().await.record_error(&sensor),
+                        Err(broadcast::error::RecvError::Lagged(_)) => {}
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    _ = interval.tick() => {
+                        // Sin el cerrojo mientras se reconecta: observe() y los
+                        // consumidores de estado no esperan a connect/calibrate
+                        let reconnects = monitor.lock().await.assess(Instant::now());
+                        for reconnect in reconnects {
+                            let result = reconnect.run().await;
+                            monitor
+                                .lock()
+                                .await
+                                .finish_reconnect(&reconnect.sensor, result, Instant::now());
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Modo de operación que impone un sensor según su tipo y estado
+fn degraded_mode(kind: SensorKind, health: &SensorHealth) -> OperatingMode {
+    let failed = matches!(health, SensorHealth::Error(_) | SensorHealth::Disconnected);
+    let warning = matches!(health, SensorHealth::Warning(_));
+
+    match kind {
+        // Sin lidar no hay detección de obstáculos
+        SensorKind::Lidar if failed => OperatingMode::Stopped,
+        SensorKind::Lidar if warning => OperatingMode::Degraded { speed_factor: 0.5 },
+        SensorKind::Imu | SensorKind::Odometry if failed => {
+            OperatingMode::Degraded { speed_factor: 0.5 }
+        }
+        SensorKind::Imu | SensorKind::Odometry if warning => {
+            OperatingMode::Degraded { speed_factor: 0.8 }
+        }
+        SensorKind::Camera if failed => OperatingMode::Degraded { speed_factor: 0.8 },
+        // Sin ultrasonidos/IR el vidrio y los obstáculos bajos quedan sin cubrir
+        SensorKind::Range if failed => OperatingMode::Degraded { speed_factor: 0.7 },
+        _ => OperatingMode::Normal,
+    }
+}
+
+fn out_of_range(reading: &SensorReading) -> bool {
+    match reading {
+        SensorReading::Lidar(scan) => {
+            if scan.points.is_empty() {
+                return true;
+            }
+            let invalid = scan
+                .points
+                .iter()
+                .filter(|p| {
+                    !p.distance.is_finite()
+                        || p.distance < scan.min_range
+                        || p.distance > scan.max_range
+                })
+                .count();
+            invalid * 2 > scan.points.len()
+        }
+        SensorReading::Imu(imu) => {
+            let accel = imu.acceleration.magnitude();
+            let gyro = imu.gyroscope.magnitude();
+            !accel.is_finite()
+                || !gyro.is_finite()
+                || accel > MAX_ACCELERATION * 1.8 // módulo de tres ejes saturados
+                || gyro > MAX_ANGULAR_RATE * 1.8
+        }
+        SensorReading::Camera(frame) => {
+            frame.data.len() != (frame.width * frame.height * frame.channels) as usize
+        }
+        SensorReading::Odometry(odometry) => {
+            !odometry.x.is_finite() || !odometry.y.is_finite() || !odometry.theta.is_finite()
+        }
+        // Infinito es válido (nada en el alcance); NaN o negativo no
+        SensorReading::Range(range) => range.range.is_nan() || range.range < 0.0,
+        // Sin fix las coordenadas no significan nada
+        SensorReading::Gps(gps) => {
+            gps.has_fix() && (gps.latitude.abs() > 90.0 || gps.longitude.abs() > 180.0)
+        }
+    }
+}
+
+/// Huella de los valores de una lectura para detectar datos congelados
+fn fingerprint(reading: &SensorReading) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    match reading {
+        SensorReading::Lidar(scan) => {
+            for point in &scan.points {
+                point.distance.to_bits().hash(&mut hasher);
+            }
+        }
+        SensorReading::Imu(imu) => {
+            for v in [imu.acceleration, imu.gyroscope] {
+                v.x.to_bits().hash(&mut hasher);
+                v.y.to_bits().hash(&mut hasher);
+                v.z.to_bits().hash(&mut hasher);
+            }
+        }
+        SensorReading::Camera(frame) => frame.data.hash(&mut hasher),
+        SensorReading::Odometry(odometry) => {
+            odometry.x.to_bits().hash(&mut hasher);
+            odometry.y.to_bits().hash(&mut hasher);
+            odometry.theta.to_bits().hash(&mut hasher);
+        }
+        SensorReading::Range(range) => range.range.to_bits().hash(&mut hasher),
+        // Un robot parado repite posición; la hora del receptor no
+        SensorReading::Gps(gps) => {
+            gps.latitude.to_bits().hash(&mut hasher);
+            gps.longitude.to_bits().hash(&mut hasher);
+            gps.timestamp.to_bits().hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::drivers::{Lidar, LidarConfig};
+    use crate::sensors::{LidarData, LidarPoint};
+
+    fn scan_message(distance: f64) -> SensorMessage {
+        let timestamp = chrono::Utc::now().timestamp_millis() as f64 / 1000.0;
+        SensorMessage {
+            sensor: Arc::from("lidar"),
+            sequence: 0,
+            timestamp,
+            reading: Arc::new(SensorReading::Lidar(LidarData {
+                frame_id: "laser".to_string(),
+                points: vec![LidarPoint {
+                    angle: 0.0,
+                    distance,
+                    quality: 100,
+                    timestamp,
+                }],
+                scan_time: 0.1,
+                min_angle: 0.0,
+                max_angle: 0.0,
+                min_range: 0.1,
+                max_range: 10.0,
+            })),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_silent_lidar_stops_robot() {
+        let mut monitor = HealthMonitor::new(HealthConfig::default());
+        let mut events = monitor.subscribe();
+        monitor.watch("lidar", SensorKind::Lidar, Duration::from_millis(100), None);
+
+        let start = Instant::now();
+        for i in 0..5 {
+            monitor.observe(&scan_message(1.0 + i as f64), start);
+        }
+        monitor.check(start).await;
+        assert_eq!(monitor.mode(), OperatingMode::Normal);
+
+        // 2 s sin mensajes con un periodo nominal de 100 ms
+        monitor.check(start + Duration::from_secs(2)).await;
+        assert!(matches!(
+            monitor.status("lidar").unwrap().health,
+            SensorHealth::Error(_)
+        ));
+        assert_eq!(monitor.mode(), OperatingMode::Stopped);
+        assert!(!monitor.mode().allows_motion());
+
+        let mut saw_mode_change = false;
+        while let Ok(event) = events.try_recv() {
+            if let HealthEvent::ModeChanged { mode, .. } = event {
+                saw_mode_change = mode == OperatingMode::Stopped;
+            }
+        }
+        assert!(saw_mode_change);
+    }
+
+    #[tokio::test]
+    async fn test_stuck_and_out_of_range_detection() {
+        let mut monitor = HealthMonitor::new(HealthConfig::default());
+        monitor.watch("lidar", SensorKind::Lidar, Duration::from_millis(100), None);
+
+        let now = Instant::now();
+        for _ in 0..25 {
+            monitor.observe(&scan_message(2.0), now);
+        }
+        monitor.check(now).await;
+        let status = monitor.status("lidar").unwrap();
+        assert!(matches!(status.health, SensorHealth::Error(ref e) if e.contains("congelado")));
+        assert_eq!(status.error_count, 1);
+
+        for i in 0..25 {
+            monitor.observe(&scan_message(50.0 + i as f64), now);
+        }
+        monitor.check(now).await;
+        assert!(matches!(
+            monitor.status("lidar").unwrap().health,
+            SensorHealth::Warning(_)
+        ));
+        assert_eq!(
+            monitor.mode(),
+            OperatingMode::Degraded { speed_factor: 0.5 }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reconnects_disconnected_driver() {
+        let lidar = Lidar::new(LidarConfig::default());
+        let driver: SharedSensor = Arc::new(Mutex::new(Box::new(lidar)));
+
+        let mut monitor = HealthMonitor::new(HealthConfig::default());
+        let mut events = monitor.subscribe();
+        monitor.watch(
+            "lidar",
+            SensorKind::Lidar,
+            Duration::from_millis(100),
+            Some(driver.clone()),
+        );
+
+        monitor.check(Instant::now()).await;
+        assert!(driver.lock().await.is_connected());
+
+        let mut reconnected = false;
+        while let Ok(event) = events.try_recv() {
+            reconnected |= matches!(event, HealthEvent::Reconnected { attempts: 1, .. });
+        }
+        assert!(reconnected);
+        assert!(monitor.status("lidar").unwrap().connected);
+    }
+
+    #[tokio::test]
+    async fn test_spawned_monitor_is_not_locked_during_reconnect() {
+        let lidar = Lidar::new(LidarConfig::default());
+        let driver: SharedSensor = Arc::new(Mutex::new(Box::new(lidar)));
+        let config = HealthConfig {
+            check_interval_ms: 10,
+            min_dropout: 0.02,
+            ..HealthConfig::default()
+        };
+        let monitor = Arc::new(Mutex::new(HealthMonitor::new(config)));
+        let mut events = monitor.lock().await.subscribe();
+        monitor.lock().await.watch(
+            "lidar",
+            SensorKind::Lidar,
+            Duration::from_millis(1),
+            Some(driver.clone()),
+        );
+
+        // La adquisición tiene el driver ocupado: la reconexión se queda esperando
+        let busy = driver.lock().await;
+        let bus = SensorBus::new(8);
+        let task = HealthMonitor::spawn(monitor.clone(), &bus);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let guard = tokio::time::timeout(Duration::from_millis(50), monitor.lock())
+            .await
+            .expect("el monitor no debe quedar bloqueado durante la reconexión");
+        assert_eq!(guard.mode(), OperatingMode::Stopped);
+        drop(guard);
+
+        drop(busy);
+        let reconnected = tokio::time::timeout(Duration::from_secs(1), async {
+            loop {
+                if let Ok(HealthEvent::Reconnected { .. }) = events.recv().await {
+                    break;
+                }
+            }
+        })
+        .await;
+        assert!(reconnected.is_ok());
+        task.abort();
+    }
+}
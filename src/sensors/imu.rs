@@ -1,98 +0,0 @@
-use super::{Sensor, SensorStatus};
-use anyhow::Result;
-
-#[derive(Debug)]
-pub struct IMU {
-    i2c_address: u8,
-    status: SensorStatus,
-    calibration: CalibrationData,
-}
-
-impl IMU {
-    pub fn new(i2c_address: u8) -> Self {
-        Self {
-            i2c_address,
-            status: SensorStatus {
-                connected: false,
-                last_update: std::time::SystemTime::now(),
-                error_count: 0,
-            },
-            calibration: CalibrationData::default(),
-        }
-    }
-
-    pub fn read_gyroscope(&mut self) -> Result<(f64, f64, f64)> {
-        // Simular lectura del giroscopio (radianes/segundo)
-        Ok((0.1, -0.05, 0.02)) // pitch, roll, yaw
-    }
-
-    pub fn read_accelerometer(&mut self) -> Result<(f64, f64, f64)> {
-        // Simular lectura del acelerómetro (m/s²)
-        Ok((0.0, 0.0, 9.8)) // x, y, z (gravedad)
-    }
-
-    pub fn read_magnetometer(&mut self) -> Result<(f64, f64, f64)> {
-        // Simular lectura del magnetómetro (microteslas)
-        Ok((25.0, 5.0, -45.0)) // x, y, z
-    }
-
-    pub fn get_orientation(&mut self) -> Result<Orientation> {
-        let (pitch, roll, yaw) = self.read_gyroscope()?;
-        Ok(Orientation { pitch, roll, yaw })
-    }
-
-    pub fn calibrate(&mut self) -> Result<()> {
-        println!(
-            "🎯 Calibrando IMU en dirección 0x{:02X}...",
-            self.i2c_address
-        );
-        // Simular calibración
-        self.calibration.is_calibrated = true;
-        Ok(())
-    }
-}
-
-impl Sensor for IMU {
-    fn initialize(&mut self) -> Result<()> {
-        self.status.connected = true;
-        println!(
-            "🧭 IMU inicializado en dirección I2C: 0x{:02X}",
-            self.i2c_address
-        );
-        Ok(())
-    }
-
-    fn read_data(&mut self) -> Result<Vec<u8>> {
-        // Combinar datos de todos los sensores IMU
-        let mut data = Vec::new();
-        let (ax, ay, az) = self.read_accelerometer()?;
-        let (_gx, _gy, _gz) = self.read_gyroscope()?;
-        let (_mx, _my, _mz) = self.read_magnetometer()?;
-
-        // Convertir a bytes (simplificado)
-        data.extend_from_slice(&ax.to_le_bytes());
-        data.extend_from_slice(&ay.to_le_bytes());
-        data.extend_from_slice(&az.to_le_bytes());
-        // ... agregar más datos
-
-        Ok(data)
-    }
-
-    fn get_status(&self) -> SensorStatus {
-        self.status.clone()
-    }
-}
-
-#[derive(Debug, Clone)]
-pub struct Orientation {
-    pub pitch: f64, // inclinación frontal (radianes)
-    pub roll: f64,  // inclinación lateral (radianes)
-    pub yaw: f64,   // orientación (radianes)
-}
-
-#[derive(Debug, Default)]
-struct CalibrationData {
-    is_calibrated: bool,
-    accelerometer_bias: (f64, f64, f64),
-    gyroscope_bias: (f64, f64, f64),
-}
@@ -1,128 +1,249 @@
-use super::drivers::{Camera, Lidar, SensorDriver, IMU};
-use super::{CameraData, IMUData, LidarData, SensorData};
+use super::bus::SensorBus;
+use super::drivers::{SensorDriver, SensorKind, SensorReading, SensorSpec};
+use super::health::{HealthConfig, HealthEvent, HealthMonitor, OperatingMode};
+use super::registry::{SensorRegistry, SharedSensor};
+use super::ring_buffer::RingBuffer;
+use super::{SensorData, SensorHealth, SensorStatus};
+use crate::recording::{LogSummary, Recorder, RecorderHandle, RecordingConfig};
 use anyhow::Result;
 use std::collections::HashMap;
-use std::time::{Duration, Instant};
-use tokio::sync::mpsc;
+use std::sync::Arc;
+use tokio::sync::{broadcast, Mutex};
+use tokio::task::JoinHandle;
 
 pub struct SensorManager {
-    lidar: Option<Lidar>,
-    imu: Option<IMU>,
-    camera: Option<Camera>,
-    data_buffer: Vec<SensorData>,
-    max_buffer_size: usize,
+    registry: SensorRegistry,
+    bus: SensorBus,
+    streaming: bool,
+    health: Option<(Arc<Mutex<HealthMonitor>>, JoinHandle<()>)>,
+    data_buffer: RingBuffer<SensorData>,
+    recorder: Option<Recorder>,
 }
 
 impl SensorManager {
     pub fn new() -> Self {
         Self {
-            lidar: None,
-            imu: None,
-            camera: None,
-            data_buffer: Vec::new(),
-            max_buffer_size: 1000,
+            registry: SensorRegistry::new(),
+            bus: SensorBus::default(),
+            streaming: false,
+            health: None,
+            data_buffer: RingBuffer::new(1000),
+            recorder: None,
         }
     }
 
+    /// Crea el gestor con los sensores declarados en la configuración
+    pub fn from_specs(specs: &[SensorSpec]) -> Result<Self> {
+        Ok(Self {
+            registry: SensorRegistry::from_specs(specs)?,
+            ..Self::new()
+        })
+    }
+
     pub async fn initialize_all(&mut self) -> Result<()> {
-        // Inicializar LIDAR si está configurado
-        if let Some(lidar) = &mut self.lidar {
-            lidar.connect().await?;
+        let failures = self.registry.connect_all().await;
+
+        if !failures.is_empty() && failures.len() == self.registry.len() {
+            return Err(anyhow::anyhow!(
+                "Ningún sensor disponible: {}",
+                failures
+                    .iter()
+                    .map(|(name, error)| format!("{}: {}", name, error))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
         }
 
-        // Inicializar IMU si está configurado
-        if let Some(imu) = &mut self.imu {
-            imu.connect().await?;
-            imu.calibrate().await?;
+        Ok(())
+    }
+
+    /// Lanza una tarea de adquisición por sensor; las lecturas se publican en el bus
+    pub async fn start_streaming(&mut self) -> Result<()> {
+        self.bus.spawn_registry(&self.registry).await?;
+        self.streaming = true;
+        Ok(())
+    }
+
+    /// Vigila los sensores del registro sobre los mensajes del bus; requiere
+    /// `start_streaming` para recibir datos
+    pub async fn start_health_monitor(
+        &mut self,
+        config: HealthConfig,
+    ) -> broadcast::Receiver<HealthEvent> {
+        if let Some((monitor, _)) = &self.health {
+            return monitor.lock().await.subscribe();
         }
 
-        // Inicializar cámara si está configurado
-        if let Some(camera) = &mut self.camera {
-            camera.connect().await?;
+        let mut monitor = HealthMonitor::new(config);
+        for (name, kind, driver) in self.registry.iter() {
+            let period = driver.lock().await.sample_period();
+            monitor.watch(name, kind, period, Some(driver.clone()));
         }
 
-        Ok(())
+        let events = monitor.subscribe();
+        let monitor = Arc::new(Mutex::new(monitor));
+        let task = HealthMonitor::spawn(monitor.clone(), &self.bus);
+        self.health = Some((monitor, task));
+        events
+    }
+
+    pub fn health_monitor(&self) -> Option<Arc<Mutex<HealthMonitor>>> {
+        self.health.as_ref().map(|(monitor, _)| monitor.clone())
+    }
+
+    pub async fn operating_mode(&self) -> OperatingMode {
+        match &self.health {
+            Some((monitor, _)) => monitor.lock().await.mode(),
+            None => OperatingMode::Normal,
+        }
+    }
+
+    pub async fn shutdown(&mut self) {
+        if let Some((_, task)) = self.health.take() {
+            task.abort();
+        }
+        if let Err(e) = self.stop_recording().await {
+            log::error!("❌ Error cerrando la grabación: {}", e);
+        }
+        self.bus.shutdown();
+        self.streaming = false;
+        self.registry.disconnect_all().await;
     }
 
     pub async fn read_all_sensors(&mut self) -> Result<SensorData> {
         let mut sensor_data = SensorData::default();
 
-        // Leer LIDAR
-        if let Some(lidar) = &mut self.lidar {
-            if let Ok(lidar_data) = lidar.scan().await {
-                sensor_data.lidar = Some(lidar_data);
+        for (name, reading) in self.registry.read_all().await {
+            match reading {
+                Ok(reading) => sensor_data.insert(name, reading),
+                Err(e) => log::warn!("⚠️ Error leyendo sensor '{}': {}", name, e),
             }
         }
 
-        // Leer IMU
-        if let Some(imu) = &mut self.imu {
-            if let Ok(imu_data) = imu.read_data().await {
-                sensor_data.imu = Some(imu_data);
+        // Guardar en buffer
+        self.data_buffer.push(sensor_data.clone());
+
+        Ok(sensor_data)
+    }
+
+    /// Última lectura publicada de cada sensor, sin bloquear a los drivers
+    pub fn snapshot(&self) -> SensorData {
+        let mut sensor_data = SensorData::default();
+        for name in self.registry.names() {
+            if let Some(message) = self.bus.latest(&name) {
+                sensor_data.insert(name, (*message.reading).clone());
             }
         }
+        sensor_data
+    }
 
-        // Leer cámara
-        if let Some(camera) = &mut self.camera {
-            if let Ok(camera_data) = camera.capture_frame().await {
-                sensor_data.camera = Some(camera_data);
-            }
+    /// Añade un sensor sin conectarlo (se conectará en `initialize_all`)
+    pub fn add_sensor(&mut self, name: &str, driver: Box<dyn SensorDriver>) -> Result<()> {
+        self.registry.add(name, driver)
+    }
+
+    /// Conecta y añade un sensor con el sistema en marcha
+    pub async fn plug_sensor(&mut self, name: &str, driver: Box<dyn SensorDriver>) -> Result<()> {
+        self.registry.plug(name, driver).await?;
+        let Some(driver) = self.registry.get(name) else {
+            return Ok(());
+        };
+
+        if self.streaming {
+            self.bus.spawn(name, driver.clone()).await?;
+        }
+        if let Some((monitor, _)) = &self.health {
+            let (kind, period) = {
+                let driver = driver.lock().await;
+                (driver.kind(), driver.sample_period())
+            };
+            monitor.lock().await.watch(name, kind, period, Some(driver));
         }
+        Ok(())
+    }
 
-        // Guardar en buffer
-        self.data_buffer.push(sensor_data.clone());
-        if self.data_buffer.len() > self.max_buffer_size {
-            self.data_buffer.remove(0);
+    pub async fn remove_sensor(&mut self, name: &str) -> Result<SharedSensor> {
+        if let Some((monitor, _)) = &self.health {
+            monitor.lock().await.unwatch(name);
         }
+        self.bus.remove(name);
+        self.registry.remove(name).await
+    }
 
-        Ok(sensor_data)
+    /// Graba todo lo que pase por el bus en un registro nuevo de
+    /// `config.directory`. El handle sirve para añadir comandos y poses.
+    pub fn start_recording(&mut self, config: &RecordingConfig) -> Result<RecorderHandle> {
+        if let Some(recorder) = &self.recorder {
+            return Ok(recorder.handle());
+        }
+        let mut recorder = Recorder::start(&config.new_log_path(), config)?;
+        recorder.record_bus(&self.bus);
+        let handle = recorder.handle();
+        self.recorder = Some(recorder);
+        Ok(handle)
     }
 
-    // Getters
-    pub fn set_lidar(&mut self, lidar: Lidar) {
-        self.lidar = Some(lidar);
+    pub fn recording(&self) -> Option<RecorderHandle> {
+        self.recorder.as_ref().map(|recorder| recorder.handle())
     }
 
-    pub fn set_imu(&mut self, imu: IMU) {
-        self.imu = Some(imu);
+    pub async fn stop_recording(&mut self) -> Result<Option<LogSummary>> {
+        match self.recorder.take() {
+            Some(recorder) => Ok(Some(recorder.finish().await?)),
+            None => Ok(None),
+        }
     }
 
-    pub fn set_camera(&mut self, camera: Camera) {
-        self.camera = Some(camera);
+    pub fn registry(&self) -> &SensorRegistry {
+        &self.registry
     }
 
-    pub fn get_recent_data(&self, count: usize) -> &[SensorData] {
-        let start = if self.data_buffer.len() > count {
-            self.data_buffer.len() - count
-        } else {
-            0
-        };
-        &self.data_buffer[start..]
+    pub fn bus(&self) -> &SensorBus {
+        &self.bus
     }
 
-    pub fn get_sensor_status(&self) -> HashMap<String, bool> {
-        let mut status = HashMap::new();
+    /// Para registrar publicadores externos (p. ej. odometría) en el bus
+    pub fn bus_mut(&mut self) -> &mut SensorBus {
+        &mut self.bus
+    }
 
-        status.insert(
-            "lidar".to_string(),
-            self.lidar
-                .as_ref()
-                .map(|l| l.is_connected())
-                .unwrap_or(false),
-        );
-        status.insert(
-            "imu".to_string(),
-            self.imu.as_ref().map(|i| i.is_connected()).unwrap_or(false),
-        );
-        status.insert(
-            "camera".to_string(),
-            self.camera
-                .as_ref()
-                .map(|c| c.is_connected())
-                .unwrap_or(false),
-        );
+    pub fn sensors_of_kind(&self, kind: SensorKind) -> Vec<String> {
+        self.registry.names_of_kind(kind)
+    }
 
+    // Getters
+    pub fn get_recent_data(&self, count: usize) -> Vec<SensorData> {
+        self.data_buffer.recent(count).cloned().collect()
+    }
+
+    pub async fn get_sensor_status(&self) -> HashMap<String, bool> {
+        let mut status = HashMap::new();
+        for (name, _, driver) in self.registry.iter() {
+            status.insert(name.to_string(), driver.lock().await.is_connected());
+        }
         status
     }
+
+    /// Salud según el monitor si está activo; si no, la que informa cada driver
+    pub async fn get_sensor_health(&self) -> HashMap<String, SensorHealth> {
+        match &self.health {
+            Some((monitor, _)) => monitor
+                .lock()
+                .await
+                .statuses()
+                .into_iter()
+                .map(|(name, status)| (name, status.health))
+                .collect(),
+            None => self.registry.health().await.into_iter().collect(),
+        }
+    }
+
+    pub async fn get_sensor_statuses(&self) -> HashMap<String, SensorStatus> {
+        match &self.health {
+            Some((monitor, _)) => monitor.lock().await.statuses(),
+            None => HashMap::new(),
+        }
+    }
 }
 
 impl Default for SensorManager {
@@ -130,3 +251,58 @@ impl Default for SensorManager {
         Self::new()
     }
 }
+
+impl SensorData {
+    /// Guarda la lectura en el campo del tipo correspondiente si está libre;
+    /// los sensores adicionales del mismo tipo van a `additional`.
+    pub fn insert(&mut self, name: String, reading: SensorReading) {
+        match reading {
+            SensorReading::Lidar(scan) if self.lidar.is_none() => self.lidar = Some(scan),
+            SensorReading::Imu(imu) if self.imu.is_none() => self.imu = Some(imu),
+            SensorReading::Camera(frame) if self.camera.is_none() => self.camera = Some(frame),
+            SensorReading::Odometry(odometry) if self.odometry.is_none() => {
+                self.odometry = Some(odometry)
+            }
+            SensorReading::Range(range) => self.ranges.push(range),
+            SensorReading::Gps(gps) if self.gps.is_none() => self.gps = Some(gps),
+            reading => {
+                self.additional.insert(name, reading);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::drivers::{DriverConfig, Lidar, LidarConfig};
+
+    #[tokio::test]
+    async fn test_manager_with_multiple_lidars() {
+        let specs = vec![
+            SensorSpec::new("front", DriverConfig::Lidar(LidarConfig::default())),
+            SensorSpec::new("rear", DriverConfig::Lidar(LidarConfig::default())),
+        ];
+        let mut manager = SensorManager::from_specs(&specs).unwrap();
+        manager.initialize_all().await.unwrap();
+
+        let data = manager.read_all_sensors().await.unwrap();
+        assert!(data.lidar.is_some());
+        assert!(data.imu.is_none());
+        assert!(matches!(
+            data.additional.get("rear"),
+            Some(SensorReading::Lidar(_))
+        ));
+
+        manager
+            .plug_sensor("side", Box::new(Lidar::new(LidarConfig::default())))
+            .await
+            .unwrap();
+        manager.remove_sensor("front").await.unwrap();
+        assert_eq!(
+            manager.sensors_of_kind(SensorKind::Lidar),
+            vec!["rear", "side"]
+        );
+        assert_eq!(manager.get_recent_data(10).len(), 1);
+    }
+}
@@ -1,81 +1,193 @@
 pub mod drivers;
+pub mod bus;
+pub mod health;
 pub mod manager;
+pub mod obstacles;
+pub mod processor;
+pub mod registry;
+pub mod ring_buffer;
+pub mod scan_filter;
+pub mod sync;
+
+pub use drivers::{
+    Camera, CameraConfig, DriverConfig, Gps, GpsConfig, GpsSource, IMUConfig, Lidar, LidarConfig,
+    RangeConfig, RangeModel, RangeSensor, SensorDriver, SensorKind, SensorReading, SensorSpec, IMU,
+};
+pub use bus::{SensorBus, SensorMessage};
+pub use health::{HealthConfig, HealthEvent, HealthMonitor, OperatingMode, Reconnect};
+pub use manager::SensorManager;
+pub use registry::SensorRegistry;
+pub use scan_filter::{ScanFilter, ScanFilterChain};
+pub use sync::{ApproximateTimeSync, SyncedFrame};
+pub use crate::geometry::Vector3;
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Instantánea de todos los sensores: el primero de cada tipo ocupa su campo,
+/// el resto se guarda por nombre en `additional`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SensorData {
+    pub lidar: Option<LidarData>,
+    pub imu: Option<IMUData>,
+    pub camera: Option<CameraData>,
+    pub odometry: Option<OdometryData>,
+    /// Todos los sensores de distancia, que suelen montarse en grupo
+    pub ranges: Vec<RangeData>,
+    pub gps: Option<GpsData>,
+    pub additional: HashMap<String, SensorReading>,
+}
 
-// Estructuras básicas para compilación
+// Tipos de datos producidos por los drivers
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SensorData;
+pub struct LidarPoint {
+    pub angle: f64,    // radianes
+    pub distance: f64, // metros
+    pub quality: u16,
+    pub timestamp: f64, // segundos
+}
 
-#[derive(Debug, Clone)]
-pub struct SensorStatus {
-    pub connected: bool,
-    pub last_update: std::time::SystemTime,
-    pub error_count: u32,
-    pub health: SensorHealth,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LidarData {
+    /// Marco de coordenadas en que se expresan los datos
+    #[serde(default)]
+    pub frame_id: String,
+    pub points: Vec<LidarPoint>,
+    pub scan_time: f64,
+    pub min_angle: f64,
+    pub max_angle: f64,
+    pub min_range: f64,
+    pub max_range: f64,
 }
 
-#[derive(Debug, Clone)]
-pub enum SensorHealth {
-    Healthy,
-    Warning(String),
-    Error(String),
-    Disconnected,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IMUData {
+    #[serde(default)]
+    pub frame_id: String,
+    pub acceleration: Vector3, // m/s²
+    pub gyroscope: Vector3,    // rad/s
+    pub magnetometer: Vector3, // μT
+    pub temperature: f64,      // °C
+    pub timestamp: f64,        // segundos
 }
 
-// Configuraciones básicas
-#[derive(Debug, Clone, Default)]
-pub struct LidarConfig {
-    pub port: String,
-    pub baudrate: u32,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraData {
+    /// Marco de coordenadas de la cámara
+    pub frame_id: String,
+    /// Número de imagen desde la conexión
+    #[serde(default)]
+    pub sequence: u64,
+    pub width: u32,
+    pub height: u32,
+    pub channels: u32,
+    pub data: Vec<u8>,
+    pub timestamp: f64,
 }
 
-#[derive(Debug, Clone, Default)]
-pub struct CameraConfig {
-    pub device_path: String,
+/// Odometría de ruedas en el marco de odometría
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OdometryData {
+    #[serde(default)]
+    pub frame_id: String,
+    pub x: f64,
+    pub y: f64,
+    pub theta: f64,
+    pub linear_velocity: f64,  // m/s
+    pub angular_velocity: f64, // rad/s
+    pub timestamp: f64,        // segundos
 }
 
-#[derive(Debug, Clone, Default)]
-pub struct IMUConfig {
-    pub i2c_address: u8,
+impl OdometryData {
+    pub fn pose(&self) -> crate::geometry::Pose2D {
+        crate::geometry::Pose2D::new(self.x, self.y, self.theta)
+    }
 }
 
-// Placeholder implementations
-pub struct Lidar;
-pub struct Camera;
-pub struct IMU;
-pub struct SensorManager;
+/// Pose de montaje de un sensor respecto a la base del robot
+pub type MountingPose = crate::geometry::Pose2D;
 
-impl Lidar {
-    pub fn new(_config: LidarConfig) -> Self {
-        Self
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RangeRadiation {
+    Ultrasound,
+    Infrared,
+}
 
-    pub async fn connect(&mut self) -> anyhow::Result<()> {
-        Ok(())
-    }
+/// Medida de un sensor de distancia de haz único (ultrasonido o infrarrojo)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RangeData {
+    #[serde(default)]
+    pub frame_id: String,
+    pub radiation: RangeRadiation,
+    pub range: f64, // metros; infinito si no hay nada dentro del alcance
+    pub min_range: f64,
+    pub max_range: f64,
+    pub field_of_view: f64, // apertura total del cono, radianes
+    pub mounting: MountingPose,
+    pub timestamp: f64, // segundos
 }
 
-impl Camera {
-    pub fn new(_config: CameraConfig) -> Self {
-        Self
+impl RangeData {
+    /// La medida corresponde a un objeto dentro del alcance
+    pub fn is_detection(&self) -> bool {
+        self.range.is_finite() && self.range <= self.max_range
     }
+}
 
-    pub async fn connect(&mut self) -> anyhow::Result<()> {
-        Ok(())
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GpsFix {
+    #[default]
+    NoFix,
+    Fix2D,
+    Fix3D,
+    Dgps,
+    RtkFloat,
+    RtkFixed,
 }
 
-impl IMU {
-    pub fn new(_config: IMUConfig) -> Self {
-        Self
-    }
+/// Solución GNSS en WGS84
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpsData {
+    #[serde(default)]
+    pub frame_id: String,
+    pub latitude: f64,  // grados
+    pub longitude: f64, // grados
+    pub altitude: f64,  // metros sobre el elipsoide
+    pub fix: GpsFix,
+    pub satellites: u8,
+    pub hdop: f64,
+    pub vdop: f64,
+    pub speed: f64,  // m/s sobre el suelo
+    pub course: f64, // grados desde el norte verdadero
+    /// Covarianza de la posición en el plano tangente este-norte-arriba (m²)
+    pub covariance: crate::geometry::Covariance3,
+    pub timestamp: f64, // segundos
+}
 
-    pub async fn connect(&mut self) -> anyhow::Result<()> {
-        Ok(())
+impl GpsData {
+    /// Hay posición horizontal utilizable
+    pub fn has_fix(&self) -> bool {
+        self.fix != GpsFix::NoFix && self.latitude.is_finite() && self.longitude.is_finite()
     }
 }
-// ... tu código existente ...
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SensorStatus {
+    pub connected: bool,
+    pub last_update: std::time::SystemTime,
+    pub error_count: u32,
+    pub health: SensorHealth,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SensorHealth {
+    Healthy,
+    Warning(String),
+    Error(String),
+    Disconnected,
+}
 
 #[cfg(test)]
 mod tests {
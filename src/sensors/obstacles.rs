@@ -0,0 +1,791 @@
+//! Segmentación de escaneos lidar en obstáculos y seguimiento multi-objetivo
+use super::{LidarData, RangeData};
+use crate::geometry::{Point2, Pose2D};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SegmentationConfig {
+    /// Ángulo λ del umbral adaptativo de Borges-Aldon (rad)
+    pub breakpoint_lambda: f64,
+    /// Ruido de distancia del lidar (m)
+    pub range_sigma: f64,
+    pub min_points: usize,
+    /// Residuo máximo para aceptar un ajuste de recta o círculo (m)
+    pub fit_tolerance: f64,
+    /// Radio máximo aceptado para un ajuste circular (m)
+    pub max_circle_radius: f64,
+}
+
+impl Default for SegmentationConfig {
+    fn default() -> Self {
+        Self {
+            breakpoint_lambda: 10f64.to_radians(),
+            range_sigma: 0.03,
+            min_points: 3,
+            fit_tolerance: 0.05,
+            max_circle_radius: 1.0,
+        }
+    }
+}
+
+/// Geometría ajustada a un cluster, en el marco del lidar
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ObstacleShape {
+    Segment {
+        start: Point2,
+        end: Point2,
+    },
+    Circle {
+        center: Point2,
+        radius: f64,
+    },
+    /// Envolvente convexa, en sentido antihorario
+    Polygon {
+        vertices: Vec<Point2>,
+    },
+}
+
+impl ObstacleShape {
+    /// Transforma la geometría con la pose `pose`
+    pub fn transformed(&self, pose: &Pose2D) -> Self {
+        let t = |p: &Point2| pose.transform_point(*p);
+        match self {
+            Self::Segment { start, end } => Self::Segment {
+                start: t(start),
+                end: t(end),
+            },
+            Self::Circle { center, radius } => Self::Circle {
+                center: t(center),
+                radius: *radius,
+            },
+            Self::Polygon { vertices } => Self::Polygon {
+                vertices: vertices.iter().map(t).collect(),
+            },
+        }
+    }
+
+    /// Distancia de `point` al contorno de la geometría
+    pub fn distance_to(&self, point: Point2) -> f64 {
+        match self {
+            Self::Segment { start, end } => distance_to_segment(point, *start, *end),
+            Self::Circle { center, radius } => (point.distance_to(center) - radius).abs(),
+            Self::Polygon { vertices } => vertices
+                .iter()
+                .zip(vertices.iter().cycle().skip(1))
+                .map(|(a, b)| distance_to_segment(point, *a, *b))
+                .fold(f64::INFINITY, f64::min),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectedObstacle {
+    pub center: Point2,
+    pub shape: ObstacleShape,
+    /// Mayor dimensión del obstáculo (m)
+    pub size: f64,
+    pub point_count: usize,
+}
+
+/// Obstáculo visto por un sensor de distancia: el arco del cono a la
+/// distancia medida, aproximado por su cuerda en el marco del robot
+pub fn range_obstacle(reading: &RangeData) -> Option<DetectedObstacle> {
+    if !reading.is_detection() {
+        return None;
+    }
+
+    let half = reading.field_of_view / 2.0;
+    let range = reading.range;
+    let mounting = &reading.mounting;
+    let start = mounting.transform_point(Point2::from_polar(range, -half));
+    let end = mounting.transform_point(Point2::from_polar(range, half));
+
+    Some(DetectedObstacle {
+        center: mounting.transform_point(Point2::new(range, 0.0)),
+        size: start.distance_to(&end),
+        shape: ObstacleShape::Segment { start, end },
+        point_count: 1,
+    })
+}
+
+/// Añade a las detecciones del lidar lo que ven los sensores de distancia y
+/// el lidar no (cristal, obstáculos por debajo del plano de barrido).
+/// Supone el lidar en el origen del marco del robot; devuelve cuántas se
+/// añadieron.
+pub fn fuse_ranges(
+    detections: &mut Vec<DetectedObstacle>,
+    ranges: &[RangeData],
+    tolerance: f64,
+) -> usize {
+    let before = detections.len();
+
+    for obstacle in ranges.iter().filter_map(range_obstacle) {
+        let seen = detections[..before]
+            .iter()
+            .any(|detection| detection.shape.distance_to(obstacle.center) <= tolerance);
+        if !seen {
+            detections.push(obstacle);
+        }
+    }
+
+    detections.len() - before
+}
+
+/// Divide el escaneo en clusters con el umbral adaptativo de Borges-Aldon:
+/// dos puntos consecutivos se separan si distan más de
+/// `r·sin(Δφ)/sin(λ-Δφ) + 3σ`.
+pub fn segment(scan: &LidarData, config: &SegmentationConfig) -> Vec<Vec<Point2>> {
+    let mut clusters = Vec::new();
+    let mut current: Vec<Point2> = Vec::new();
+    let mut first: Option<(f64, Point2)> = None; // (ángulo, xy)
+    let mut previous: Option<(f64, f64, Point2)> = None; // (ángulo, distancia, xy)
+
+    for point in &scan.points {
+        if !point.distance.is_finite() || point.distance <= 0.0 {
+            continue;
+        }
+        let xy = Point2::from_polar(point.distance, point.angle);
+
+        if let Some((angle, distance, last)) = previous {
+            let threshold = breakpoint_threshold(distance, (point.angle - angle).abs(), config);
+            if xy.distance_to(&last) > threshold {
+                clusters.push(std::mem::take(&mut current));
+            }
+        }
+
+        first.get_or_insert((point.angle, xy));
+        current.push(xy);
+        previous = Some((point.angle, point.distance, xy));
+    }
+    clusters.push(current);
+
+    // Un barrido de 360° cierra sobre sí mismo: unir primer y último cluster
+    let full_turn = scan.max_angle - scan.min_angle >= 2.0 * std::f64::consts::PI - 0.1;
+    if let (true, Some((first_angle, first_xy)), Some((last_angle, last_distance, last_xy))) =
+        (full_turn && clusters.len() > 1, first, previous)
+    {
+        let dphi = 2.0 * std::f64::consts::PI - (last_angle - first_angle).abs();
+        if first_xy.distance_to(&last_xy) <= breakpoint_threshold(last_distance, dphi, config) {
+            let tail = clusters.pop().unwrap();
+            clusters[0].splice(0..0, tail);
+        }
+    }
+
+    clusters.retain(|c| c.len() >= config.min_points);
+    clusters
+}
+
+fn breakpoint_threshold(distance: f64, dphi: f64, config: &SegmentationConfig) -> f64 {
+    if dphi >= config.breakpoint_lambda {
+        return 0.0;
+    }
+    distance * dphi.sin() / (config.breakpoint_lambda - dphi).sin() + 3.0 * config.range_sigma
+}
+
+/// Segmenta el escaneo y ajusta una geometría a cada cluster
+pub fn extract_obstacles(scan: &LidarData, config: &SegmentationConfig) -> Vec<DetectedObstacle> {
+    segment(scan, config)
+        .iter()
+        .map(|cluster| fit_cluster(cluster, config))
+        .collect()
+}
+
+/// Recta si el cluster es alineado, círculo si encaja en un arco y, si no,
+/// la envolvente convexa
+pub fn fit_cluster(points: &[Point2], config: &SegmentationConfig) -> DetectedObstacle {
+    let centroid = centroid(points);
+
+    if let Some((start, end, residual)) = fit_line(points) {
+        if residual <= config.fit_tolerance {
+            return DetectedObstacle {
+                center: start.lerp(&end, 0.5),
+                size: start.distance_to(&end),
+                shape: ObstacleShape::Segment { start, end },
+                point_count: points.len(),
+            };
+        }
+    }
+
+    if let Some((center, radius, residual)) = fit_circle(points) {
+        if residual <= config.fit_tolerance && radius <= config.max_circle_radius {
+            return DetectedObstacle {
+                center,
+                size: 2.0 * radius,
+                shape: ObstacleShape::Circle { center, radius },
+                point_count: points.len(),
+            };
+        }
+    }
+
+    let vertices = convex_hull(points);
+    let size = vertices
+        .iter()
+        .flat_map(|a| vertices.iter().map(move |b| a.distance_to(b)))
+        .fold(0.0, f64::max);
+
+    DetectedObstacle {
+        center: centroid,
+        shape: ObstacleShape::Polygon { vertices },
+        size,
+        point_count: points.len(),
+    }
+}
+
+/// Recta por mínimos cuadrados totales. Devuelve los extremos proyectados
+/// y el residuo perpendicular máximo.
+pub fn fit_line(points: &[Point2]) -> Option<(Point2, Point2, f64)> {
+    if points.len() < 2 {
+        return None;
+    }
+
+    let center = centroid(points);
+    let (mut sxx, mut syy, mut sxy) = (0.0, 0.0, 0.0);
+    for point in points {
+        let d = *point - center;
+        sxx += d.x * d.x;
+        syy += d.y * d.y;
+        sxy += d.x * d.y;
+    }
+
+    // Dirección principal de la matriz de covarianza
+    let direction = Point2::from_polar(1.0, 0.5 * (2.0 * sxy).atan2(sxx - syy));
+
+    let mut min_t = f64::INFINITY;
+    let mut max_t = f64::NEG_INFINITY;
+    let mut residual: f64 = 0.0;
+    for point in points {
+        let d = *point - center;
+        let t = d.dot(&direction);
+        min_t = min_t.min(t);
+        max_t = max_t.max(t);
+        residual = residual.max(d.cross(&direction).abs());
+    }
+
+    Some((
+        center + direction * min_t,
+        center + direction * max_t,
+        residual,
+    ))
+}
+
+/// Círculo algebraico de Kåsa. Devuelve centro, radio y residuo RMS.
+pub fn fit_circle(points: &[Point2]) -> Option<(Point2, f64, f64)> {
+    if points.len() < 3 {
+        return None;
+    }
+
+    // Centrar para mejorar el condicionamiento
+    let mean = centroid(points);
+    let (mut suu, mut svv, mut suv) = (0.0, 0.0, 0.0);
+    let (mut suuu, mut svvv, mut suvv, mut svuu) = (0.0, 0.0, 0.0, 0.0);
+    for point in points {
+        let Point2 { x: u, y: v } = *point - mean;
+        suu += u * u;
+        svv += v * v;
+        suv += u * v;
+        suuu += u * u * u;
+        svvv += v * v * v;
+        suvv += u * v * v;
+        svuu += v * u * u;
+    }
+
+    let det = suu * svv - suv * suv;
+    if det.abs() < 1e-12 {
+        return None;
+    }
+
+    let bu = 0.5 * (suuu + suvv);
+    let bv = 0.5 * (svvv + svuu);
+    let uc = (bu * svv - bv * suv) / det;
+    let vc = (suu * bv - suv * bu) / det;
+    let n = points.len() as f64;
+    let radius = (uc * uc + vc * vc + (suu + svv) / n).sqrt();
+    let center = mean + Point2::new(uc, vc);
+
+    let rms = (points
+        .iter()
+        .map(|p| (p.distance_to(&center) - radius).powi(2))
+        .sum::<f64>()
+        / n)
+        .sqrt();
+
+    Some((center, radius, rms))
+}
+
+/// Envolvente convexa por cadena monótona de Andrew
+pub fn convex_hull(points: &[Point2]) -> Vec<Point2> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+
+    let cross = |o: Point2, a: Point2, b: Point2| (a - o).cross(&(b - o));
+
+    let mut hull: Vec<Point2> = Vec::with_capacity(sorted.len() * 2);
+    for pass in 0..2 {
+        let start = hull.len();
+        let iter: Box<dyn Iterator<Item = &Point2>> = if pass == 0 {
+            Box::new(sorted.iter())
+        } else {
+            Box::new(sorted.iter().rev())
+        };
+        for &p in iter {
+            while hull.len() >= start + 2
+                && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        hull.pop();
+    }
+
+    hull
+}
+
+fn centroid(points: &[Point2]) -> Point2 {
+    let n = points.len().max(1) as f64;
+    points.iter().fold(Point2::origin(), |sum, p| sum + *p) * (1.0 / n)
+}
+
+fn distance_to_segment(point: Point2, a: Point2, b: Point2) -> f64 {
+    let direction = b - a;
+    let length_sq = direction.dot(&direction);
+    if length_sq <= f64::EPSILON {
+        return point.distance_to(&a);
+    }
+    let t = ((point - a).dot(&direction) / length_sq).clamp(0.0, 1.0);
+    point.distance_to(&a.lerp(&b, t))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrackerConfig {
+    /// Distancia máxima para asociar una detección a un track (m)
+    pub gate_distance: f64,
+    /// Obstáculos mayores (paredes) no se siguen: se consideran estáticos
+    pub max_track_size: f64,
+    /// Detecciones necesarias para confirmar un track
+    pub confirm_hits: u32,
+    /// Ciclos sin detección antes de eliminar un track
+    pub max_misses: u32,
+    /// Velocidad a partir de la cual un obstáculo se considera dinámico (m/s)
+    pub dynamic_speed: f64,
+    /// Ruido de aceleración del modelo de velocidad constante (m/s²)
+    pub process_noise: f64,
+    /// Ruido de medida de la posición del centro (m)
+    pub measurement_noise: f64,
+}
+
+impl Default for TrackerConfig {
+    fn default() -> Self {
+        Self {
+            gate_distance: 0.8,
+            max_track_size: 1.5,
+            confirm_hits: 3,
+            max_misses: 5,
+            dynamic_speed: 0.25,
+            process_noise: 1.0,
+            measurement_noise: 0.05,
+        }
+    }
+}
+
+/// Filtro de Kalman de velocidad constante para un eje
+#[derive(Debug, Clone)]
+struct AxisFilter {
+    position: f64,
+    velocity: f64,
+    covariance: [[f64; 2]; 2],
+}
+
+impl AxisFilter {
+    fn new(position: f64, measurement_noise: f64) -> Self {
+        Self {
+            position,
+            velocity: 0.0,
+            covariance: [[measurement_noise.powi(2), 0.0], [0.0, 1.0]],
+        }
+    }
+
+    fn predict(&mut self, dt: f64, process_noise: f64) {
+        self.position += self.velocity * dt;
+
+        let [[p00, p01], [p10, p11]] = self.covariance;
+        let q = process_noise.powi(2);
+        self.covariance = [
+            [
+                p00 + dt * (p10 + p01) + dt * dt * p11 + q * dt.powi(4) / 4.0,
+                p01 + dt * p11 + q * dt.powi(3) / 2.0,
+            ],
+            [p10 + dt * p11 + q * dt.powi(3) / 2.0, p11 + q * dt * dt],
+        ];
+    }
+
+    fn update(&mut self, measurement: f64, measurement_noise: f64) {
+        let [[p00, p01], [p10, p11]] = self.covariance;
+        let s = p00 + measurement_noise.powi(2);
+        let (k0, k1) = (p00 / s, p10 / s);
+        let innovation = measurement - self.position;
+
+        self.position += k0 * innovation;
+        self.velocity += k1 * innovation;
+        self.covariance = [
+            [(1.0 - k0) * p00, (1.0 - k0) * p01],
+            [p10 - k1 * p00, p11 - k1 * p01],
+        ];
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Track {
+    id: u64,
+    x: AxisFilter,
+    y: AxisFilter,
+    shape: ObstacleShape,
+    size: f64,
+    hits: u32,
+    misses: u32,
+    age: u32,
+}
+
+impl Track {
+    fn position(&self) -> Point2 {
+        Point2::new(self.x.position, self.y.position)
+    }
+}
+
+/// Obstáculo con identidad estable, en el marco fijo (odometría/mapa)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackedObstacle {
+    /// `None` para obstáculos grandes no seguidos (paredes)
+    pub id: Option<u64>,
+    pub position: Point2,
+    /// Velocidad estimada (m/s)
+    pub velocity: Point2,
+    pub shape: ObstacleShape,
+    pub size: f64,
+    pub is_dynamic: bool,
+    pub age: u32,
+}
+
+impl TrackedObstacle {
+    pub fn speed(&self) -> f64 {
+        self.velocity.norm()
+    }
+}
+
+/// Seguidor multi-objetivo: asociación voraz por vecino más cercano dentro
+/// de una puerta y un filtro de Kalman de velocidad constante por track
+#[derive(Debug)]
+pub struct ObstacleTracker {
+    config: TrackerConfig,
+    tracks: Vec<Track>,
+    next_id: u64,
+    last_timestamp: Option<f64>,
+}
+
+impl ObstacleTracker {
+    pub fn new(config: TrackerConfig) -> Self {
+        Self {
+            config,
+            tracks: Vec::new(),
+            next_id: 1,
+            last_timestamp: None,
+        }
+    }
+
+    /// Incorpora las detecciones de un escaneo tomado en `timestamp` desde la
+    /// pose del robot `robot_pose` en el marco fijo
+    pub fn update(
+        &mut self,
+        detections: &[DetectedObstacle],
+        robot_pose: &Pose2D,
+        timestamp: f64,
+    ) -> Vec<TrackedObstacle> {
+        let dt = self
+            .last_timestamp
+            .map(|last| (timestamp - last).max(0.0))
+            .unwrap_or(0.0);
+        self.last_timestamp = Some(timestamp);
+
+        for track in &mut self.tracks {
+            track.x.predict(dt, self.config.process_noise);
+            track.y.predict(dt, self.config.process_noise);
+            track.age += 1;
+        }
+
+        let mut output = Vec::new();
+        let mut trackable = Vec::new();
+        for detection in detections {
+            let center = robot_pose.transform_point(detection.center);
+            let shape = detection.shape.transformed(robot_pose);
+            if detection.size > self.config.max_track_size {
+                output.push(TrackedObstacle {
+                    id: None,
+                    position: center,
+                    velocity: Point2::origin(),
+                    shape,
+                    size: detection.size,
+                    is_dynamic: false,
+                    age: 0,
+                });
+            } else {
+                trackable.push((center, shape, detection.size));
+            }
+        }
+
+        // Pares (track, detección) ordenados por distancia
+        let mut pairs: Vec<(f64, usize, usize)> = Vec::new();
+        for (t, track) in self.tracks.iter().enumerate() {
+            for (d, (center, _, _)) in trackable.iter().enumerate() {
+                let distance = track.position().distance_to(center);
+                if distance <= self.config.gate_distance {
+                    pairs.push((distance, t, d));
+                }
+            }
+        }
+        pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut track_matched = vec![false; self.tracks.len()];
+        let mut detection_matched = vec![false; trackable.len()];
+        for (_, t, d) in pairs {
+            if track_matched[t] || detection_matched[d] {
+                continue;
+            }
+            track_matched[t] = true;
+            detection_matched[d] = true;
+
+            let (center, shape, size) = &trackable[d];
+            let track = &mut self.tracks[t];
+            track.x.update(center.x, self.config.measurement_noise);
+            track.y.update(center.y, self.config.measurement_noise);
+            track.shape = shape.clone();
+            track.size = *size;
+            track.hits += 1;
+            track.misses = 0;
+        }
+
+        for (t, matched) in track_matched.iter().enumerate() {
+            if !matched {
+                self.tracks[t].misses += 1;
+            }
+        }
+        self.tracks
+            .retain(|track| track.misses <= self.config.max_misses);
+
+        for (d, (center, shape, size)) in trackable.into_iter().enumerate() {
+            if detection_matched[d] {
+                continue;
+            }
+            self.tracks.push(Track {
+                id: self.next_id,
+                x: AxisFilter::new(center.x, self.config.measurement_noise),
+                y: AxisFilter::new(center.y, self.config.measurement_noise),
+                shape,
+                size,
+                hits: 1,
+                misses: 0,
+                age: 0,
+            });
+            self.next_id += 1;
+        }
+
+        output.extend(
+            self.tracks
+                .iter()
+                .filter(|track| track.hits >= self.config.confirm_hits && track.misses == 0)
+                .map(|track| {
+                    let velocity = Point2::new(track.x.velocity, track.y.velocity);
+                    TrackedObstacle {
+                        id: Some(track.id),
+                        position: track.position(),
+                        velocity,
+                        shape: track.shape.clone(),
+                        size: track.size,
+                        is_dynamic: velocity.norm() >= self.config.dynamic_speed,
+                        age: track.age,
+                    }
+                }),
+        );
+
+        output
+    }
+
+    pub fn track_count(&self) -> usize {
+        self.tracks.len()
+    }
+}
+
+impl Default for ObstacleTracker {
+    fn default() -> Self {
+        Self::new(TrackerConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::LidarPoint;
+    use std::f64::consts::PI;
+
+    /// Escaneo sintético de 1° de resolución sobre `world(angle) -> distancia`
+    fn scan(world: impl Fn(f64) -> f64) -> LidarData {
+        let points = (0..360)
+            .map(|i| {
+                let angle = -PI + i as f64 * PI / 180.0;
+                LidarPoint {
+                    angle,
+                    distance: world(angle),
+                    quality: 100,
+                    timestamp: 0.0,
+                }
+            })
+            .collect();
+        LidarData {
+            frame_id: "laser".to_string(),
+            points,
+            scan_time: 0.1,
+            min_angle: -PI,
+            max_angle: PI,
+            min_range: 0.05,
+            max_range: 12.0,
+        }
+    }
+
+    /// Distancia a lo largo del rayo `angle` hasta un círculo, si lo corta
+    fn ray_circle(angle: f64, center: Point2, radius: f64) -> Option<f64> {
+        let b = Point2::from_polar(1.0, angle).dot(&center);
+        let c = center.dot(&center) - radius * radius;
+        let disc = b * b - c;
+        (disc >= 0.0 && b - disc.sqrt() > 0.0).then(|| b - disc.sqrt())
+    }
+
+    #[test]
+    fn test_wall_and_post_are_separated_and_fitted() {
+        // Pared en x = 3 delante y poste de 0.3 m de radio a la izquierda
+        let data = scan(|angle| {
+            if let Some(d) = ray_circle(angle, Point2::new(0.0, 2.0), 0.3) {
+                return d;
+            }
+            if angle.abs() < 0.6 {
+                return 3.0 / angle.cos();
+            }
+            f64::NAN
+        });
+
+        let obstacles = extract_obstacles(&data, &SegmentationConfig::default());
+        assert_eq!(obstacles.len(), 2, "{:?}", obstacles);
+
+        let wall = obstacles
+            .iter()
+            .find(|o| matches!(o.shape, ObstacleShape::Segment { .. }))
+            .unwrap();
+        assert!((wall.center.x - 3.0).abs() < 0.05);
+        assert!(wall.size > 3.0);
+
+        let post = obstacles
+            .iter()
+            .find(|o| matches!(o.shape, ObstacleShape::Circle { .. }))
+            .unwrap();
+        assert!((post.size - 0.6).abs() < 0.05, "tamaño {}", post.size);
+        assert!((post.center.y - 2.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_convex_hull() {
+        let points = [(0.0, 0.0), (1.0, 0.0), (0.5, 0.2), (1.0, 1.0), (0.0, 1.0)].map(Point2::from);
+        let hull = convex_hull(&points);
+        assert_eq!(hull.len(), 4);
+        assert!(!hull.contains(&Point2::new(0.5, 0.2)));
+    }
+
+    #[test]
+    fn test_tracker_estimates_velocity_of_moving_person() {
+        let config = SegmentationConfig::default();
+        let mut tracker = ObstacleTracker::default();
+        let mut last = Vec::new();
+
+        for step in 0..20 {
+            let t = step as f64 * 0.1;
+            // Persona cruzando a 1 m/s en y, más una pared estática en x = 4
+            let person = Point2::new(2.0, -1.0 + t);
+            let data = scan(|angle| {
+                if let Some(d) = ray_circle(angle, person, 0.2) {
+                    return d;
+                }
+                if angle.abs() < 0.7 {
+                    return 4.0 / angle.cos();
+                }
+                f64::NAN
+            });
+            last = tracker.update(&extract_obstacles(&data, &config), &Pose2D::identity(), t);
+        }
+
+        let person = last
+            .iter()
+            .find(|o| o.position.distance_to(&Point2::new(2.0, 0.9)) < 0.3)
+            .unwrap();
+        assert!(person.id.is_some());
+        assert!(person.is_dynamic);
+        assert!(
+            (person.velocity.y - 1.0).abs() < 0.15,
+            "{:?}",
+            person.velocity
+        );
+        assert!(person.velocity.x.abs() < 0.15);
+
+        let wall = last.iter().find(|o| o.id.is_none()).unwrap();
+        assert!(!wall.is_dynamic);
+    }
+
+    #[test]
+    fn test_ranges_fill_lidar_blind_spots() {
+        use crate::sensors::{MountingPose, RangeData, RangeRadiation};
+
+        let sonar = |y: f64, range: f64| RangeData {
+            frame_id: "range".to_string(),
+            radiation: RangeRadiation::Ultrasound,
+            range,
+            min_range: 0.02,
+            max_range: 4.0,
+            field_of_view: 15f64.to_radians(),
+            mounting: MountingPose {
+                x: 0.2,
+                y,
+                theta: 0.0,
+            },
+            timestamp: 0.0,
+        };
+
+        // El lidar ve una pared a 2 m delante del robot
+        let mut detections = vec![DetectedObstacle {
+            center: Point2::new(2.0, 0.0),
+            shape: ObstacleShape::Segment {
+                start: Point2::new(2.0, -1.0),
+                end: Point2::new(2.0, 1.0),
+            },
+            size: 2.0,
+            point_count: 40,
+        }];
+
+        let added = fuse_ranges(
+            &mut detections,
+            &[
+                sonar(0.0, 1.8),            // la misma pared: ya la ve el lidar
+                sonar(0.1, 0.5),            // cristal que el lidar atraviesa
+                sonar(-0.1, f64::INFINITY), // nada delante
+            ],
+            0.1,
+        );
+
+        assert_eq!(added, 1);
+        let glass = &detections[1];
+        assert!((glass.center.x - 0.7).abs() < 1e-9);
+        assert!((glass.center.y - 0.1).abs() < 1e-9);
+        assert!((glass.size - 2.0 * 0.5 * 7.5f64.to_radians().sin()).abs() < 1e-9);
+    }
+}
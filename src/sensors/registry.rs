@@ -0,0 +1,301 @@
+//! Registro de sensores conectables en caliente, indexados por nombre
+use super::drivers::{SensorDriver, SensorKind, SensorReading, SensorSpec};
+use super::SensorHealth;
+use anyhow::Result;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Driver compartido entre el registro y las tareas que lo leen
+pub type SharedSensor = Arc<Mutex<Box<dyn SensorDriver>>>;
+
+#[derive(Debug, Clone)]
+struct RegistryEntry {
+    name: String,
+    kind: SensorKind,
+    driver: SharedSensor,
+}
+
+/// Conjunto de sensores de cualquier tipo. Conserva el orden de registro,
+/// de modo que el primer sensor de cada tipo actúa como principal.
+#[derive(Debug, Default)]
+pub struct SensorRegistry {
+    entries: Vec<RegistryEntry>,
+}
+
+impl SensorRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Construye el registro a partir de la configuración, sin conectar los sensores
+    pub fn from_specs(specs: &[SensorSpec]) -> Result<Self> {
+        let mut registry = Self::new();
+        for spec in specs {
+            registry.add_spec(spec)?;
+        }
+        Ok(registry)
+    }
+
+    pub fn add(&mut self, name: impl Into<String>, driver: Box<dyn SensorDriver>) -> Result<()> {
+        let name = name.into();
+        if self.contains(&name) {
+            return Err(anyhow::anyhow!("Sensor '{}' ya registrado", name));
+        }
+
+        log::info!("➕ Sensor '{}' ({:?}) registrado", name, driver.kind());
+        self.entries.push(RegistryEntry {
+            name,
+            kind: driver.kind(),
+            driver: Arc::new(Mutex::new(driver)),
+        });
+        Ok(())
+    }
+
+    pub fn add_spec(&mut self, spec: &SensorSpec) -> Result<()> {
+        self.add(spec.name.clone(), spec.build())
+    }
+
+    /// Registra y conecta un sensor en caliente. Si la conexión falla no se registra.
+    pub async fn plug(
+        &mut self,
+        name: impl Into<String>,
+        driver: Box<dyn SensorDriver>,
+    ) -> Result<()> {
+        let name = name.into();
+        if self.contains(&name) {
+            return Err(anyhow::anyhow!("Sensor '{}' ya registrado", name));
+        }
+
+        let mut driver = driver;
+        driver.connect().await.map_err(anyhow::Error::msg)?;
+        driver.calibrate().await.map_err(anyhow::Error::msg)?;
+        self.add(name, driver)
+    }
+
+    /// Retira un sensor del registro y lo desconecta
+    pub async fn remove(&mut self, name: &str) -> Result<SharedSensor> {
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| entry.name == name)
+            .ok_or_else(|| anyhow::anyhow!("Sensor '{}' no registrado", name))?;
+        let entry = self.entries.remove(index);
+
+        let mut driver = entry.driver.lock().await;
+        if driver.is_connected() {
+            if let Err(e) = driver.disconnect().await {
+                log::warn!("⚠️ Error desconectando sensor '{}': {}", name, e);
+            }
+        }
+        drop(driver);
+
+        log::info!("➖ Sensor '{}' retirado", name);
+        Ok(entry.driver)
+    }
+
+    pub fn get(&self, name: &str) -> Option<SharedSensor> {
+        self.entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .map(|entry| entry.driver.clone())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.iter().any(|entry| entry.name == name)
+    }
+
+    pub fn kind_of(&self, name: &str) -> Option<SensorKind> {
+        self.entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .map(|entry| entry.kind)
+    }
+
+    /// Nombres en orden de registro
+    pub fn names(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .map(|entry| entry.name.clone())
+            .collect()
+    }
+
+    pub fn names_of_kind(&self, kind: SensorKind) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.kind == kind)
+            .map(|entry| entry.name.clone())
+            .collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, SensorKind, &SharedSensor)> {
+        self.entries
+            .iter()
+            .map(|entry| (entry.name.as_str(), entry.kind, &entry.driver))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Conecta y calibra todos los sensores. Devuelve los que fallaron.
+    pub async fn connect_all(&self) -> Vec<(String, String)> {
+        let mut failures = Vec::new();
+
+        for entry in &self.entries {
+            let mut driver = entry.driver.lock().await;
+            if driver.is_connected() {
+                continue;
+            }
+
+            let result = match driver.connect().await {
+                Ok(()) => driver.calibrate().await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                log::error!("❌ Sensor '{}' no disponible: {}", entry.name, e);
+                failures.push((entry.name.clone(), e));
+            }
+        }
+
+        failures
+    }
+
+    pub async fn disconnect_all(&self) {
+        for entry in &self.entries {
+            let mut driver = entry.driver.lock().await;
+            if let Err(e) = driver.disconnect().await {
+                log::warn!("⚠️ Error desconectando sensor '{}': {}", entry.name, e);
+            }
+        }
+    }
+
+    /// Lee una vez cada sensor conectado, en orden de registro
+    pub async fn read_all(&self) -> Vec<(String, Result<SensorReading, String>)> {
+        let mut readings = Vec::with_capacity(self.entries.len());
+
+        for entry in &self.entries {
+            let mut driver = entry.driver.lock().await;
+            if !driver.is_connected() {
+                continue;
+            }
+            readings.push((entry.name.clone(), driver.read().await));
+        }
+
+        readings
+    }
+
+    pub async fn health(&self) -> Vec<(String, SensorHealth)> {
+        let mut health = Vec::with_capacity(self.entries.len());
+        for entry in &self.entries {
+            health.push((entry.name.clone(), entry.driver.lock().await.health()));
+        }
+        health
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::drivers::frame_source::CameraSource;
+    use crate::sensors::drivers::{CameraConfig, DriverConfig, IMUConfig, LidarConfig};
+
+    fn specs() -> Vec<SensorSpec> {
+        vec![
+            SensorSpec::new("lidar_front", DriverConfig::Lidar(LidarConfig::default())),
+            SensorSpec::new("lidar_rear", DriverConfig::Lidar(LidarConfig::default())),
+            SensorSpec::new(
+                "imu",
+                DriverConfig::Imu(IMUConfig {
+                    simulated: true,
+                    ..Default::default()
+                }),
+            ),
+            SensorSpec::new(
+                "camera",
+                DriverConfig::Camera(CameraConfig {
+                    width: 8,
+                    height: 4,
+                    source: CameraSource::Synthetic,
+                    ..Default::default()
+                }),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_registry_rejects_duplicate_names() {
+        let mut registry = SensorRegistry::from_specs(&specs()).unwrap();
+        assert_eq!(registry.len(), 4);
+        assert_eq!(
+            registry.names_of_kind(SensorKind::Lidar),
+            vec!["lidar_front", "lidar_rear"]
+        );
+        assert!(registry.add_spec(&specs()[0]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_registry_reads_every_sensor() {
+        let registry = SensorRegistry::from_specs(&specs()).unwrap();
+        assert!(registry.connect_all().await.is_empty());
+
+        let readings = registry.read_all().await;
+        let kinds: Vec<_> = readings
+            .iter()
+            .map(|(_, reading)| reading.as_ref().unwrap().kind())
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                SensorKind::Lidar,
+                SensorKind::Lidar,
+                SensorKind::Imu,
+                SensorKind::Camera
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hot_plug_and_remove() {
+        let mut registry = SensorRegistry::new();
+        let spec = &specs()[0];
+        registry.plug(&spec.name, spec.build()).await.unwrap();
+        assert!(registry
+            .get("lidar_front")
+            .unwrap()
+            .lock()
+            .await
+            .is_connected());
+
+        let removed = registry.remove("lidar_front").await.unwrap();
+        assert!(!removed.lock().await.is_connected());
+        assert!(registry.is_empty());
+        assert!(registry.remove("lidar_front").await.is_err());
+    }
+
+    #[test]
+    fn test_spec_from_toml() {
+        let spec: SensorSpec = toml::from_str(
+            r#"
+            name = "imu_base"
+            type = "imu"
+            i2c_address = 105
+            simulated = true
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(spec.kind(), SensorKind::Imu);
+        match spec.driver {
+            DriverConfig::Imu(config) => {
+                assert_eq!(config.i2c_address, 0x69);
+                assert!(config.simulated);
+                assert_eq!(config.sample_rate, 100);
+            }
+            other => panic!("driver inesperado: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! Buffer circular de capacidad fija para el histórico de mensajes
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone)]
+pub struct RingBuffer<T> {
+    items: VecDeque<T>,
+    capacity: usize,
+}
+
+impl<T> RingBuffer<T> {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            items: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Inserta al final; si está lleno descarta y devuelve el elemento más antiguo
+    pub fn push(&mut self, item: T) -> Option<T> {
+        let evicted = if self.items.len() == self.capacity {
+            self.items.pop_front()
+        } else {
+            None
+        };
+        self.items.push_back(item);
+        evicted
+    }
+
+    pub fn latest(&self) -> Option<&T> {
+        self.items.back()
+    }
+
+    pub fn oldest(&self) -> Option<&T> {
+        self.items.front()
+    }
+
+    /// Del más antiguo al más reciente
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator {
+        self.items.iter()
+    }
+
+    /// Los `count` elementos más recientes, en orden cronológico
+    pub fn recent(&self, count: usize) -> impl Iterator<Item = &T> {
+        self.items
+            .iter()
+            .skip(self.items.len().saturating_sub(count))
+    }
+
+    /// Descarta los elementos más antiguos mientras cumplan `predicate`
+    pub fn drain_while(&mut self, mut predicate: impl FnMut(&T) -> bool) {
+        while self.items.front().map(&mut predicate).unwrap_or(false) {
+            self.items.pop_front();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_buffer_evicts_oldest() {
+        let mut buffer = RingBuffer::new(3);
+        assert_eq!(buffer.push(1), None);
+        buffer.push(2);
+        buffer.push(3);
+        assert_eq!(buffer.push(4), Some(1));
+
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), vec![2, 3, 4]);
+        assert_eq!(buffer.recent(2).copied().collect::<Vec<_>>(), vec![3, 4]);
+        assert_eq!(buffer.latest(), Some(&4));
+
+        buffer.drain_while(|&x| x < 4);
+        assert_eq!(buffer.len(), 1);
+    }
+}
@@ -0,0 +1,383 @@
+//! Cadena configurable de filtros de preprocesado para escaneos lidar
+use super::bus::{SensorBus, SensorMessage};
+use super::drivers::{SensorKind, SensorReading};
+use super::ring_buffer::RingBuffer;
+use super::{LidarData, LidarPoint, OdometryData};
+use crate::geometry::{Point2, Pose2D};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+/// Filtro individual de la cadena. Las coordenadas son del marco del lidar
+/// (x hacia delante, y a la izquierda) y los ángulos en radianes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScanFilter {
+    /// Descarta distancias fuera de `[min, max]` y valores no finitos
+    Range { min: f64, max: f64 },
+    /// Conserva sólo los puntos con ángulo en `[min, max]`
+    Angle { min: f64, max: f64 },
+    /// Descarta puntos con `quality` inferior al umbral
+    Quality { min_quality: u16 },
+    /// Sustituye cada distancia por la mediana de su vecindad angular
+    Median { window: usize },
+    /// Elimina puntos fantasma en bordes de objetos (veiling points)
+    Shadow {
+        min_angle: f64,
+        max_angle: f64,
+        window: usize,
+    },
+    /// Elimina los puntos dentro de la caja (p. ej. el propio chasis)
+    Box {
+        min_x: f64,
+        max_x: f64,
+        min_y: f64,
+        max_y: f64,
+    },
+    /// Corrige la distorsión por movimiento llevando cada punto a la pose
+    /// del robot al final del barrido, usando odometría interpolada
+    Deskew,
+}
+
+impl ScanFilter {
+    pub fn apply(&self, scan: LidarData, odometry: &RingBuffer<OdometryData>) -> LidarData {
+        match self {
+            Self::Range { min, max } => retain(scan, |p| {
+                p.distance.is_finite() && p.distance >= *min && p.distance <= *max
+            }),
+            Self::Angle { min, max } => {
+                let mut scan = retain(scan, |p| p.angle >= *min && p.angle <= *max);
+                scan.min_angle = scan.min_angle.max(*min);
+                scan.max_angle = scan.max_angle.min(*max);
+                scan
+            }
+            Self::Quality { min_quality } => retain(scan, |p| p.quality >= *min_quality),
+            Self::Median { window } => median(scan, *window),
+            Self::Shadow {
+                min_angle,
+                max_angle,
+                window,
+            } => shadow(scan, *min_angle, *max_angle, *window),
+            Self::Box {
+                min_x,
+                max_x,
+                min_y,
+                max_y,
+            } => retain(scan, |p| {
+                let (x, y) = (p.distance * p.angle.cos(), p.distance * p.angle.sin());
+                !(x >= *min_x && x <= *max_x && y >= *min_y && y <= *max_y)
+            }),
+            Self::Deskew => deskew(scan, odometry),
+        }
+    }
+}
+
+/// Secuencia de filtros aplicada en orden
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ScanFilterChain {
+    pub filters: Vec<ScanFilter>,
+}
+
+impl ScanFilterChain {
+    pub fn new(filters: Vec<ScanFilter>) -> Self {
+        Self { filters }
+    }
+
+    pub fn push(&mut self, filter: ScanFilter) -> &mut Self {
+        self.filters.push(filter);
+        self
+    }
+
+    pub fn needs_odometry(&self) -> bool {
+        self.filters.contains(&ScanFilter::Deskew)
+    }
+
+    pub fn apply(&self, scan: LidarData, odometry: &RingBuffer<OdometryData>) -> LidarData {
+        self.filters
+            .iter()
+            .fold(scan, |scan, filter| filter.apply(scan, odometry))
+    }
+
+    /// Filtra el tópico `input` y publica el resultado como `output`.
+    /// Si se indica `odometry`, se usa para el filtro `Deskew`.
+    pub fn spawn(
+        self,
+        bus: &mut SensorBus,
+        input: &str,
+        output: &str,
+        odometry: Option<&str>,
+    ) -> Result<JoinHandle<()>> {
+        let mut scans = bus
+            .subscribe(input)
+            .ok_or_else(|| anyhow::anyhow!("Tópico '{}' no existe", input))?;
+        let mut publisher = bus.publisher(output, SensorKind::Lidar)?;
+        let mut odometry_rx = match odometry {
+            Some(name) => Some(
+                bus.subscribe(name)
+                    .ok_or_else(|| anyhow::anyhow!("Tópico '{}' no existe", name))?,
+            ),
+            None => None,
+        };
+        let input = input.to_string();
+
+        Ok(tokio::spawn(async move {
+            let mut history = RingBuffer::new(200);
+
+            loop {
+                let message = tokio::select! {
+                    odometry = recv_optional(&mut odometry_rx) => {
+                        match odometry {
+                            Ok(SensorMessage { reading, .. }) => {
+                                if let SensorReading::Odometry(odometry) = &*reading {
+                                    history.push(odometry.clone());
+                                }
+                            }
+                            Err(broadcast::error::RecvError::Lagged(_)) => {}
+                            Err(broadcast::error::RecvError::Closed) => odometry_rx = None,
+                        }
+                        continue;
+                    }
+                    scan = scans.recv() => match scan {
+                        Ok(message) => message,
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            log::warn!("⚠️ Filtro de '{}' retrasado, {} escaneos perdidos", input, skipped);
+                            continue;
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                };
+
+                if let SensorReading::Lidar(scan) = &*message.reading {
+                    publisher.publish(SensorReading::Lidar(self.apply(scan.clone(), &history)));
+                }
+            }
+        }))
+    }
+}
+
+/// Recibe del canal si existe; si no, espera indefinidamente
+async fn recv_optional(
+    receiver: &mut Option<broadcast::Receiver<SensorMessage>>,
+) -> Result<SensorMessage, broadcast::error::RecvError> {
+    match receiver {
+        Some(receiver) => receiver.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+fn retain(mut scan: LidarData, mut keep: impl FnMut(&LidarPoint) -> bool) -> LidarData {
+    scan.points.retain(|p| keep(p));
+    scan
+}
+
+fn median(mut scan: LidarData, window: usize) -> LidarData {
+    let half = window / 2;
+    if half == 0 || scan.points.len() < 3 {
+        return scan;
+    }
+
+    let distances: Vec<f64> = scan.points.iter().map(|p| p.distance).collect();
+    let mut neighbourhood = Vec::with_capacity(2 * half + 1);
+
+    for (i, point) in scan.points.iter_mut().enumerate() {
+        let start = i.saturating_sub(half);
+        let end = (i + half + 1).min(distances.len());
+        neighbourhood.clear();
+        neighbourhood.extend_from_slice(&distances[start..end]);
+        neighbourhood.sort_by(|a, b| a.total_cmp(b));
+        point.distance = neighbourhood[neighbourhood.len() / 2];
+    }
+
+    scan
+}
+
+/// Dos puntos vecinos forman sombra si el segmento que los une es casi
+/// paralelo al rayo láser: el ángulo de incidencia queda fuera de
+/// `[min_angle, max_angle]`. Se descarta el más lejano, como en ROS.
+fn shadow(scan: LidarData, min_angle: f64, max_angle: f64, window: usize) -> LidarData {
+    let points = &scan.points;
+    let mut remove = vec![false; points.len()];
+
+    for i in 0..points.len() {
+        for j in (i + 1)..(i + window + 1).min(points.len()) {
+            let (ri, rj) = (points[i].distance, points[j].distance);
+            let dtheta = (points[j].angle - points[i].angle).abs();
+            let incidence = (rj * dtheta.sin()).atan2(ri - rj * dtheta.cos());
+            if !(min_angle..=max_angle).contains(&incidence) {
+                remove[if ri > rj { i } else { j }] = true;
+            }
+        }
+    }
+
+    let mut remove = remove.into_iter();
+    retain(scan, |_| !remove.next().unwrap_or(false))
+}
+
+fn deskew(mut scan: LidarData, odometry: &RingBuffer<OdometryData>) -> LidarData {
+    let Some(reference_time) = scan
+        .points
+        .iter()
+        .map(|p| p.timestamp)
+        .max_by(|a, b| a.total_cmp(b))
+    else {
+        return scan;
+    };
+    let Some(reference) = interpolate_pose(odometry, reference_time) else {
+        log::debug!(
+            "Sin odometría para corregir el escaneo de {:.3}",
+            reference_time
+        );
+        return scan;
+    };
+
+    let to_reference = reference.inverse();
+    for point in &mut scan.points {
+        let Some(pose) = interpolate_pose(odometry, point.timestamp) else {
+            continue;
+        };
+
+        // Punto en el marco de odometría desde la pose en que se midió y de
+        // vuelta al marco del robot al final del barrido
+        let local = to_reference * (pose * Point2::from_polar(point.distance, point.angle));
+        point.distance = local.norm();
+        point.angle = local.angle();
+    }
+
+    scan
+}
+
+/// Pose interpolada entre las dos muestras que rodean `t`
+fn interpolate_pose(odometry: &RingBuffer<OdometryData>, t: f64) -> Option<Pose2D> {
+    let after = odometry.iter().position(|o| o.timestamp >= t)?;
+    let b = odometry.iter().nth(after)?;
+    if after == 0 {
+        // Se tolera una muestra exacta al inicio, pero no extrapolar hacia atrás
+        return (b.timestamp == t).then_some(b.pose());
+    }
+    let a = odometry.iter().nth(after - 1)?;
+
+    let span = b.timestamp - a.timestamp;
+    let alpha = if span > 0.0 {
+        (t - a.timestamp) / span
+    } else {
+        0.0
+    };
+    Some(a.pose().interpolate(&b.pose(), alpha))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn scan(points: &[(f64, f64)]) -> LidarData {
+        LidarData {
+            frame_id: "laser".to_string(),
+            points: points
+                .iter()
+                .enumerate()
+                .map(|(i, &(angle, distance))| LidarPoint {
+                    angle,
+                    distance,
+                    quality: (i * 10) as u16,
+                    timestamp: i as f64 * 0.01,
+                })
+                .collect(),
+            scan_time: 0.1,
+            min_angle: -PI,
+            max_angle: PI,
+            min_range: 0.05,
+            max_range: 12.0,
+        }
+    }
+
+    fn distances(scan: &LidarData) -> Vec<f64> {
+        scan.points.iter().map(|p| p.distance).collect()
+    }
+
+    #[test]
+    fn test_crop_quality_and_box_filters() {
+        let chain: ScanFilterChain = serde_json::from_str(
+            r#"[
+                {"type": "range", "min": 0.1, "max": 5.0},
+                {"type": "angle", "min": -1.0, "max": 1.0},
+                {"type": "quality", "min_quality": 10},
+                {"type": "box", "min_x": -0.2, "max_x": 0.3, "min_y": -0.2, "max_y": 0.2}
+            ]"#,
+        )
+        .unwrap();
+
+        let input = scan(&[
+            (0.0, 1.0),  // quality 0: descartado
+            (0.0, 0.25), // dentro de la caja del chasis
+            (0.5, 9.0),  // fuera de rango
+            (2.0, 1.0),  // fuera de ángulo
+            (0.2, 2.0),
+            (-0.2, f64::NAN),
+        ]);
+        let output = chain.apply(input, &RingBuffer::new(1));
+
+        assert_eq!(distances(&output), vec![2.0]);
+        assert_eq!(output.min_angle, -1.0);
+    }
+
+    #[test]
+    fn test_median_removes_spike() {
+        let step = 0.01;
+        let input = scan(&[
+            (0.0, 2.0),
+            (step, 2.0),
+            (2.0 * step, 7.0),
+            (3.0 * step, 2.0),
+            (4.0 * step, 2.0),
+        ]);
+        let output = ScanFilter::Median { window: 3 }.apply(input, &RingBuffer::new(1));
+        assert_eq!(distances(&output), vec![2.0; 5]);
+    }
+
+    #[test]
+    fn test_shadow_filter_removes_veiling_points() {
+        let step = 0.5f64.to_radians();
+        // Borde de un objeto a 1 m con fondo a 3 m: el punto intermedio es fantasma
+        let input = scan(&[
+            (0.0, 1.0),
+            (step, 1.0),
+            (2.0 * step, 2.0),
+            (3.0 * step, 3.0),
+            (4.0 * step, 3.0),
+        ]);
+        let filter = ScanFilter::Shadow {
+            min_angle: 10f64.to_radians(),
+            max_angle: 170f64.to_radians(),
+            window: 1,
+        };
+        let output = filter.apply(input, &RingBuffer::new(1));
+
+        assert!(!distances(&output).contains(&2.0));
+        assert!(distances(&output).contains(&1.0));
+    }
+
+    #[test]
+    fn test_deskew_compensates_forward_motion() {
+        // El robot avanza a 1 m/s hacia una pared frontal a 2 m del final del barrido
+        let mut odometry = RingBuffer::new(10);
+        for i in 0..=2 {
+            odometry.push(OdometryData {
+                x: i as f64 * 0.05,
+                timestamp: i as f64 * 0.05,
+                ..Default::default()
+            });
+        }
+
+        let mut input = scan(&[(0.0, 2.1), (0.0, 2.0)]);
+        input.points[0].timestamp = 0.0;
+        input.points[1].timestamp = 0.1;
+
+        let output = ScanFilter::Deskew.apply(input, &odometry);
+        for d in distances(&output) {
+            assert!((d - 2.0).abs() < 1e-9, "distancia {}", d);
+        }
+    }
+}
@@ -0,0 +1,269 @@
+//! Sincronizador de tiempo aproximado lidar + odometría + IMU
+use super::bus::{SensorBus, SensorMessage};
+use super::ring_buffer::RingBuffer;
+use std::collections::VecDeque;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+
+/// Tupla de mensajes con marcas de tiempo dentro de la tolerancia. El SLAM
+/// la recibe con `NavigationRuntime::with_synced_frames`.
+#[derive(Debug, Clone)]
+pub struct SyncedFrame {
+    pub timestamp: f64, // la del escaneo lidar
+    pub lidar: SensorMessage,
+    pub odometry: SensorMessage,
+    pub imu: SensorMessage,
+}
+
+/// Nombres de los tópicos a emparejar
+#[derive(Debug, Clone)]
+pub struct SyncTopics {
+    pub lidar: String,
+    pub odometry: String,
+    pub imu: String,
+}
+
+impl Default for SyncTopics {
+    fn default() -> Self {
+        Self {
+            lidar: "lidar".to_string(),
+            odometry: "odometry".to_string(),
+            imu: "imu".to_string(),
+        }
+    }
+}
+
+enum Match {
+    Found(SensorMessage),
+    Missing,
+    Pending,
+}
+
+/// Empareja cada escaneo lidar con la odometría y la muestra IMU más
+/// cercanas en el tiempo, siempre que disten menos de `slop` segundos.
+///
+/// Un escaneo se resuelve cuando ambos flujos rápidos ya han publicado
+/// algún mensaje posterior a él, o cuando el reloj del bus lo ha dejado
+/// atrás más de `slop` (en ese caso se usa lo que haya o se descarta).
+#[derive(Debug)]
+pub struct ApproximateTimeSync {
+    topics: SyncTopics,
+    slop: f64,
+    pending_lidar: VecDeque<SensorMessage>,
+    odometry: RingBuffer<SensorMessage>,
+    imu: RingBuffer<SensorMessage>,
+    newest: f64,
+    dropped: u64,
+}
+
+impl ApproximateTimeSync {
+    pub fn new(topics: SyncTopics, slop: f64, history: usize) -> Self {
+        Self {
+            topics,
+            slop,
+            pending_lidar: VecDeque::new(),
+            odometry: RingBuffer::new(history),
+            imu: RingBuffer::new(history),
+            newest: f64::NEG_INFINITY,
+            dropped: 0,
+        }
+    }
+
+    /// Incorpora un mensaje y devuelve las tuplas que quedan resueltas
+    pub fn push(&mut self, message: SensorMessage) -> Vec<SyncedFrame> {
+        let sensor = &*message.sensor;
+        if sensor == self.topics.lidar {
+            self.pending_lidar.push_back(message.clone());
+        } else if sensor == self.topics.odometry {
+            self.odometry.push(message.clone());
+        } else if sensor == self.topics.imu {
+            self.imu.push(message.clone());
+        } else {
+            return Vec::new();
+        }
+
+        self.newest = self.newest.max(message.timestamp);
+        self.resolve()
+    }
+
+    /// Escaneos descartados por no tener pareja dentro de la tolerancia
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending_lidar.len()
+    }
+
+    fn resolve(&mut self) -> Vec<SyncedFrame> {
+        let mut frames = Vec::new();
+
+        while let Some(lidar) = self.pending_lidar.front() {
+            let timestamp = lidar.timestamp;
+            let expired = self.newest > timestamp + self.slop;
+            let odometry = Self::find(&self.odometry, timestamp, self.slop, expired);
+            let imu = Self::find(&self.imu, timestamp, self.slop, expired);
+
+            match (odometry, imu) {
+                (Match::Found(odometry), Match::Found(imu)) => {
+                    let lidar = self.pending_lidar.pop_front().unwrap();
+                    frames.push(SyncedFrame {
+                        timestamp,
+                        lidar,
+                        odometry,
+                        imu,
+                    });
+                }
+                (Match::Missing, _) | (_, Match::Missing) => {
+                    self.pending_lidar.pop_front();
+                    self.dropped += 1;
+                    log::debug!("Escaneo lidar {:.3} sin pareja, descartado", timestamp);
+                }
+                _ => break,
+            }
+        }
+
+        // Lo anterior al escaneo pendiente más antiguo ya no puede emparejarse
+        if let Some(oldest) = self.pending_lidar.front().map(|m| m.timestamp) {
+            let horizon = oldest - self.slop;
+            self.odometry.drain_while(|m| m.timestamp < horizon);
+            self.imu.drain_while(|m| m.timestamp < horizon);
+        }
+
+        frames
+    }
+
+    fn find(buffer: &RingBuffer<SensorMessage>, timestamp: f64, slop: f64, expired: bool) -> Match {
+        let has_later = buffer
+            .latest()
+            .map(|m| m.timestamp >= timestamp)
+            .unwrap_or(false);
+        if !has_later && !expired {
+            return Match::Pending;
+        }
+
+        buffer
+            .iter()
+            .filter(|m| (m.timestamp - timestamp).abs() <= slop)
+            .min_by(|a, b| {
+                let da = (a.timestamp - timestamp).abs();
+                let db = (b.timestamp - timestamp).abs();
+                da.total_cmp(&db)
+            })
+            .cloned()
+            .map(Match::Found)
+            .unwrap_or(Match::Missing)
+    }
+
+    /// Lanza el sincronizador sobre el bus y entrega las tuplas por un canal
+    pub fn spawn(
+        bus: &SensorBus,
+        topics: SyncTopics,
+        slop: f64,
+    ) -> (mpsc::Receiver<SyncedFrame>, JoinHandle<()>) {
+        let mut receiver = bus.subscribe_all();
+        let (sender, frames) = mpsc::channel(16);
+        let mut sync = Self::new(topics, slop, 512);
+
+        let task = tokio::spawn(async move {
+            loop {
+                let message = match receiver.recv().await {
+                    Ok(message) => message,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        log::warn!("⚠️ Sincronizador retrasado, {} mensajes perdidos", skipped);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+
+                for frame in sync.push(message) {
+                    if sender.send(frame).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        (frames, task)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::drivers::SensorReading;
+    use crate::sensors::{IMUData, LidarData, LidarPoint, OdometryData, Vector3};
+    use std::sync::Arc;
+
+    fn message(sensor: &str, timestamp: f64) -> SensorMessage {
+        let reading = match sensor {
+            "lidar" => SensorReading::Lidar(LidarData {
+                frame_id: "laser".to_string(),
+                points: vec![LidarPoint {
+                    angle: 0.0,
+                    distance: 1.0,
+                    quality: 100,
+                    timestamp,
+                }],
+                scan_time: 0.1,
+                min_angle: 0.0,
+                max_angle: 0.0,
+                min_range: 0.1,
+                max_range: 10.0,
+            }),
+            "imu" => SensorReading::Imu(IMUData {
+                frame_id: "imu_link".to_string(),
+                acceleration: Vector3::zero(),
+                gyroscope: Vector3::zero(),
+                magnetometer: Vector3::zero(),
+                temperature: 25.0,
+                timestamp,
+            }),
+            _ => SensorReading::Odometry(OdometryData {
+                timestamp,
+                ..Default::default()
+            }),
+        };
+
+        SensorMessage {
+            sensor: Arc::from(sensor),
+            sequence: 0,
+            timestamp,
+            reading: Arc::new(reading),
+        }
+    }
+
+    #[test]
+    fn test_matches_nearest_messages() {
+        let mut sync = ApproximateTimeSync::new(SyncTopics::default(), 0.02, 100);
+
+        for i in 0..7 {
+            let t = 1.0 + i as f64 * 0.01;
+            assert!(sync.push(message("imu", t)).is_empty());
+        }
+        assert!(sync.push(message("odometry", 1.0)).is_empty());
+        assert!(sync.push(message("lidar", 1.052)).is_empty());
+
+        // Falta odometría posterior al escaneo: sigue pendiente
+        assert_eq!(sync.pending(), 1);
+
+        let frames = sync.push(message("odometry", 1.06));
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].imu.timestamp, 1.05);
+        assert_eq!(frames[0].odometry.timestamp, 1.06);
+    }
+
+    #[test]
+    fn test_drops_scan_without_match() {
+        let mut sync = ApproximateTimeSync::new(SyncTopics::default(), 0.01, 100);
+
+        sync.push(message("lidar", 2.0));
+        sync.push(message("odometry", 2.0));
+        // La IMU salta por encima del escaneo sin muestras cercanas
+        let frames = sync.push(message("imu", 2.5));
+
+        assert!(frames.is_empty());
+        assert_eq!(sync.dropped(), 1);
+        assert_eq!(sync.pending(), 0);
+    }
+}
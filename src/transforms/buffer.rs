@@ -0,0 +1,365 @@
+//! Almacén de transformaciones con historial e interpolación
+use super::{StampedTransform, TransformsConfig};
+use crate::geometry::{Point2, Pose2D};
+use std::collections::{HashMap, VecDeque};
+
+/// Enlace padre → hijo del árbol. Los estáticos guardan una sola muestra
+/// válida en cualquier instante.
+#[derive(Debug, Clone)]
+struct Link {
+    parent: String,
+    is_static: bool,
+    history: VecDeque<(f64, Pose2D)>,
+}
+
+impl Link {
+    fn latest(&self) -> Option<f64> {
+        if self.is_static {
+            return None;
+        }
+        self.history.back().map(|&(t, _)| t)
+    }
+
+    fn sample(&self, child: &str, time: f64) -> Result<Pose2D, String> {
+        if self.is_static {
+            return self
+                .history
+                .back()
+                .map(|&(_, transform)| transform)
+                .ok_or_else(|| format!("Transformación {} → {} vacía", self.parent, child));
+        }
+
+        let (Some(&(first, _)), Some(&(last, _))) = (self.history.front(), self.history.back())
+        else {
+            return Err(format!("Sin datos para {} → {}", self.parent, child));
+        };
+        if time < first || time > last {
+            return Err(format!(
+                "Extrapolación en {} → {}: t={:.3} fuera de [{:.3}, {:.3}]",
+                self.parent, child, time, first, last
+            ));
+        }
+
+        // Primera muestra con marca de tiempo >= time
+        let upper = self.history.partition_point(|&(t, _)| t < time);
+        let (t1, after) = self.history[upper];
+        if upper == 0 || t1 == time {
+            return Ok(after);
+        }
+        let (t0, before) = self.history[upper - 1];
+        Ok(before.interpolate(&after, (time - t0) / (t1 - t0)))
+    }
+}
+
+/// Árbol de marcos indexado por el hijo: cada marco tiene como mucho un padre
+#[derive(Debug, Clone)]
+pub struct TransformBuffer {
+    links: HashMap<String, Link>,
+    cache_duration: f64,
+}
+
+impl TransformBuffer {
+    pub fn new(cache_duration: f64) -> Self {
+        Self {
+            links: HashMap::new(),
+            cache_duration,
+        }
+    }
+
+    /// Buffer con las transformaciones estáticas de la configuración
+    pub fn from_config(config: &TransformsConfig) -> Result<Self, String> {
+        let mut buffer = Self::new(config.cache_duration);
+        for link in &config.static_transforms {
+            buffer.set_static(StampedTransform::new(
+                &link.parent,
+                &link.child,
+                0.0,
+                Pose2D::new(link.x, link.y, link.theta),
+            ))?;
+        }
+        Ok(buffer)
+    }
+
+    /// Transformación fija, p. ej. el montaje de un sensor
+    pub fn set_static(&mut self, transform: StampedTransform) -> Result<(), String> {
+        self.check_link(&transform)?;
+        self.links.insert(
+            transform.child,
+            Link {
+                parent: transform.parent,
+                is_static: true,
+                history: VecDeque::from([(transform.timestamp, transform.transform)]),
+            },
+        );
+        Ok(())
+    }
+
+    /// Añade una muestra de una transformación que cambia con el tiempo
+    pub fn set_transform(&mut self, transform: StampedTransform) -> Result<(), String> {
+        self.check_link(&transform)?;
+        if !transform.timestamp.is_finite() {
+            return Err(format!(
+                "Marca de tiempo no válida en {} → {}",
+                transform.parent, transform.child
+            ));
+        }
+
+        let link = self
+            .links
+            .entry(transform.child.clone())
+            .or_insert_with(|| Link {
+                parent: transform.parent.clone(),
+                is_static: false,
+                history: VecDeque::new(),
+            });
+        if link.parent != transform.parent || link.is_static {
+            if link.parent != transform.parent {
+                log::warn!(
+                    "⚠️ {} pasa a depender de {} (antes {})",
+                    transform.child,
+                    transform.parent,
+                    link.parent
+                );
+            }
+            link.parent = transform.parent;
+            link.is_static = false;
+            link.history.clear();
+        }
+
+        // Las muestras pueden llegar desordenadas entre sensores
+        let index = link
+            .history
+            .partition_point(|&(t, _)| t <= transform.timestamp);
+        if index > 0 && link.history[index - 1].0 == transform.timestamp {
+            link.history[index - 1].1 = transform.transform;
+        } else {
+            link.history
+                .insert(index, (transform.timestamp, transform.transform));
+        }
+
+        let newest = link.history.back().map_or(transform.timestamp, |&(t, _)| t);
+        while link
+            .history
+            .front()
+            .is_some_and(|&(t, _)| t < newest - self.cache_duration)
+        {
+            link.history.pop_front();
+        }
+        Ok(())
+    }
+
+    /// Marcos conocidos, padres e hijos
+    pub fn frames(&self) -> Vec<String> {
+        let mut frames: Vec<String> = self
+            .links
+            .iter()
+            .flat_map(|(child, link)| [child.clone(), link.parent.clone()])
+            .collect();
+        frames.sort();
+        frames.dedup();
+        frames
+    }
+
+    pub fn has_frame(&self, frame: &str) -> bool {
+        self.links.contains_key(frame) || self.links.values().any(|link| link.parent == frame)
+    }
+
+    pub fn parent(&self, frame: &str) -> Option<&str> {
+        self.links.get(frame).map(|link| link.parent.as_str())
+    }
+
+    pub fn can_transform(&self, target: &str, source: &str, time: Option<f64>) -> bool {
+        self.lookup(target, source, time).is_ok()
+    }
+
+    /// Transformación que lleva datos de `source` a `target`
+    /// (`p_target = T · p_source`). Con `time = None` se usa el instante más
+    /// reciente en que todos los enlaces de la cadena tienen datos.
+    pub fn lookup(
+        &self,
+        target: &str,
+        source: &str,
+        time: Option<f64>,
+    ) -> Result<StampedTransform, String> {
+        for frame in [target, source] {
+            if !self.has_frame(frame) {
+                return Err(format!("Marco desconocido: {}", frame));
+            }
+        }
+
+        let source_chain = self.chain(source);
+        let target_chain = self.chain(target);
+        let common = source_chain
+            .iter()
+            .position(|frame| target_chain.contains(frame))
+            .ok_or_else(|| format!("{} y {} no están conectados", target, source))?;
+        let ancestor = &source_chain[common];
+        let target_depth = target_chain
+            .iter()
+            .position(|frame| frame == ancestor)
+            .unwrap_or(0);
+        let source_links = &source_chain[..common];
+        let target_links = &target_chain[..target_depth];
+
+        let time = match time {
+            Some(time) => time,
+            None => source_links
+                .iter()
+                .chain(target_links)
+                .filter_map(|child| self.links[child].latest())
+                .reduce(f64::min)
+                .unwrap_or(0.0),
+        };
+
+        // Pose de cada extremo en el marco del ancestro común
+        let to_ancestor = |links: &[String]| -> Result<Pose2D, String> {
+            links.iter().try_fold(Pose2D::identity(), |acc, child| {
+                Ok(self.links[child].sample(child, time)?.compose(&acc))
+            })
+        };
+        let ancestor_source = to_ancestor(source_links)?;
+        let ancestor_target = to_ancestor(target_links)?;
+
+        Ok(StampedTransform::new(
+            target,
+            source,
+            time,
+            ancestor_target.inverse().compose(&ancestor_source),
+        ))
+    }
+
+    /// Lleva un punto de `source` a `target`
+    pub fn transform_point(
+        &self,
+        target: &str,
+        source: &str,
+        time: Option<f64>,
+        point: Point2,
+    ) -> Result<Point2, String> {
+        Ok(self.lookup(target, source, time)?.transform * point)
+    }
+
+    /// El marco y sus antecesores hasta la raíz
+    fn chain(&self, frame: &str) -> Vec<String> {
+        let mut chain = vec![frame.to_string()];
+        while let Some(link) = chain.last().and_then(|frame| self.links.get(frame)) {
+            // `check_link` impide ciclos, pero se acota por seguridad
+            if chain.len() > self.links.len() {
+                break;
+            }
+            chain.push(link.parent.clone());
+        }
+        chain
+    }
+
+    fn check_link(&self, transform: &StampedTransform) -> Result<(), String> {
+        if transform.parent.is_empty() || transform.child.is_empty() {
+            return Err("Los marcos de una transformación no pueden estar vacíos".to_string());
+        }
+        if self.chain(&transform.parent).contains(&transform.child) {
+            return Err(format!(
+                "{} → {} crearía un ciclo en el árbol de marcos",
+                transform.parent, transform.child
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for TransformBuffer {
+    fn default() -> Self {
+        Self::new(TransformsConfig::default().cache_duration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transforms::frames::{BASE_LINK, LASER, MAP, ODOM};
+
+    fn buffer() -> TransformBuffer {
+        let mut buffer = TransformBuffer::new(5.0);
+        buffer
+            .set_static(StampedTransform::new(
+                BASE_LINK,
+                LASER,
+                0.0,
+                Pose2D::new(0.2, 0.0, 0.0),
+            ))
+            .unwrap();
+        buffer
+            .set_transform(StampedTransform::new(MAP, ODOM, 0.0, Pose2D::identity()))
+            .unwrap();
+        buffer
+            .set_transform(StampedTransform::new(
+                MAP,
+                ODOM,
+                2.0,
+                Pose2D::new(0.0, 1.0, 0.0),
+            ))
+            .unwrap();
+        for (t, x) in [(0.0, 0.0), (1.0, 1.0), (2.0, 2.0), (3.0, 3.0)] {
+            buffer
+                .set_transform(StampedTransform::new(
+                    ODOM,
+                    BASE_LINK,
+                    t,
+                    Pose2D::new(x, 0.0, 0.0),
+                ))
+                .unwrap();
+        }
+        buffer
+    }
+
+    #[test]
+    fn test_lookup_interpolates_through_chain() {
+        let buffer = buffer();
+
+        // A t=1: base en (1, 0) de odom, odom en (0, 0.5) del mapa
+        let laser = buffer.lookup(MAP, LASER, Some(1.0)).unwrap();
+        assert!((laser.transform.x - 1.2).abs() < 1e-9);
+        assert!((laser.transform.y - 0.5).abs() < 1e-9);
+
+        // Sentido inverso y entre hermanos
+        let point = buffer
+            .transform_point(LASER, MAP, Some(1.0), Point2::new(1.2, 0.5))
+            .unwrap();
+        assert!(point.norm() < 1e-9);
+        let base = buffer.lookup(LASER, BASE_LINK, Some(2.5)).unwrap();
+        assert!((base.transform.x + 0.2).abs() < 1e-9);
+
+        // Sin tiempo: el último común es t=2, limitado por map → odom
+        let latest = buffer.lookup(MAP, BASE_LINK, None).unwrap();
+        assert_eq!(latest.timestamp, 2.0);
+        assert!((latest.transform.x - 2.0).abs() < 1e-9);
+        assert!((latest.transform.y - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_lookup_rejects_extrapolation_and_cycles() {
+        let mut buffer = buffer();
+
+        assert!(buffer.lookup(MAP, LASER, Some(2.5)).is_err());
+        assert!(buffer.lookup(MAP, "camera", None).is_err());
+        assert!(buffer
+            .set_static(StampedTransform::new(
+                LASER,
+                MAP,
+                0.0,
+                Pose2D::identity()
+            ))
+            .is_err());
+
+        // Muestras fuera de la ventana de historial se descartan
+        buffer
+            .set_transform(StampedTransform::new(
+                ODOM,
+                BASE_LINK,
+                10.0,
+                Pose2D::identity(),
+            ))
+            .unwrap();
+        assert!(buffer.lookup(ODOM, BASE_LINK, Some(3.0)).is_err());
+        assert!(buffer.can_transform(ODOM, LASER, Some(10.0)));
+    }
+}
@@ -0,0 +1,78 @@
+//! Árbol de marcos de coordenadas al estilo de tf: map → odom → base_link →
+//! sensores, con historial por enlace e interpolación en el tiempo
+pub mod buffer;
+
+pub use buffer::TransformBuffer;
+
+use crate::geometry::Pose2D;
+use serde::{Deserialize, Serialize};
+
+/// Nombres de los marcos habituales
+pub mod frames {
+    pub const MAP: &str = "map";
+    pub const ODOM: &str = "odom";
+    pub const BASE_LINK: &str = "base_link";
+    pub const LASER: &str = "laser";
+    pub const IMU: &str = "imu_link";
+    pub const CAMERA: &str = "camera";
+    pub const RANGE: &str = "range";
+    pub const GPS: &str = "gps";
+}
+
+/// Transformación entre dos marcos válida en un instante: pose del marco
+/// hijo expresada en el marco padre, de modo que `p_padre = T · p_hijo`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StampedTransform {
+    pub parent: String,
+    pub child: String,
+    pub timestamp: f64, // segundos
+    pub transform: Pose2D,
+}
+
+impl StampedTransform {
+    pub fn new(parent: &str, child: &str, timestamp: f64, transform: Pose2D) -> Self {
+        Self {
+            parent: parent.to_string(),
+            child: child.to_string(),
+            timestamp,
+            transform,
+        }
+    }
+}
+
+/// Transformación fija declarada en la configuración (montaje de sensores)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StaticTransformConfig {
+    pub parent: String,
+    pub child: String,
+    #[serde(default)]
+    pub x: f64,
+    #[serde(default)]
+    pub y: f64,
+    #[serde(default)]
+    pub theta: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TransformsConfig {
+    /// Historial que se guarda de cada transformación dinámica (s)
+    pub cache_duration: f64,
+    pub static_transforms: Vec<StaticTransformConfig>,
+}
+
+impl Default for TransformsConfig {
+    fn default() -> Self {
+        Self {
+            cache_duration: 10.0,
+            // LIDAR centrado sobre la base hasta que se mida el montaje real
+            static_transforms: vec![StaticTransformConfig {
+                parent: frames::BASE_LINK.to_string(),
+                child: frames::LASER.to_string(),
+                x: 0.0,
+                y: 0.0,
+                theta: 0.0,
+            }],
+        }
+    }
+}